@@ -0,0 +1,138 @@
+//! Throughput benchmarks for the parser and writer.
+//!
+//! These use the built-in bencher, rather than criterion, so that the crate
+//! keeps having no dependencies, not even development ones. Because of that
+//! they need a nightly toolchain:
+//!
+//! ```text
+//! cargo +nightly bench
+//! cargo +nightly bench -- lex
+//! ```
+//!
+//! Each benchmark sets the number of bytes it processes per iteration, so the
+//! results are reported in MB/s. The inputs are either generated on the spot,
+//! or loaded from the fixtures directory, so nothing is fetched over network.
+
+#![feature(test)]
+#![allow(clippy::needless_return)]
+
+extern crate test;
+
+use pdf::*;
+use test::{black_box, Bencher};
+
+const FIXTURE: &[u8] = include_bytes!("fixtures/sample.pdf");
+
+/// Content stream with a mix of text and path operators, about 1 MB long.
+fn large_content_stream() -> Vec<u8> {
+	let mut content = ContentStream::new();
+	for index in 0..12_000 {
+		let y = (index % 50) as f64 * 14.0 + 72.0;
+		content.begin_text().set_font("F1", 12.0).move_text(72.0, y).show_text(b"The quick brown fox jumps over the lazy dog.").end_text();
+		content.rect(Rectangle::new(72.0, y, 468.0, 0.5)).fill();
+	}
+	return content.finish();
+}
+
+/// Writes a document with the given number of pages, each with some text.
+fn write_document(pages: usize) -> Vec<u8> {
+	let mut doc = Document::new();
+	let font = doc.add_base_font("Helvetica");
+	for number in 1..=pages {
+		let mut content = ContentStream::new();
+		let name = content.use_font(font);
+		for line in 0..40 {
+			content.begin_text().set_font(&name, 12.0).move_text(72.0, 720.0 - line as f64 * 14.0).show_text(std::format!("Page {}, line {}", number, line).as_bytes()).end_text();
+		}
+		doc.add_page(612.0, 792.0).set_contents(content);
+	}
+
+	let mut data = Vec::new();
+	doc.write(&mut Writer::new(&mut data)).unwrap();
+	return data;
+}
+
+#[bench]
+fn lex_content_stream(b: &mut Bencher) {
+	let data = large_content_stream();
+	b.bytes = data.len() as u64;
+	b.iter(|| {
+		return parse_operations(black_box(&data)).unwrap();
+	});
+}
+
+#[bench]
+fn parse_xref_table(b: &mut Bencher) {
+	let data = write_document(500);
+	b.bytes = data.len() as u64;
+	b.iter(|| {
+		let reader = Reader::new(black_box(&data)).unwrap();
+		return reader.object_ids().count();
+	});
+}
+
+#[bench]
+fn read_fixture(b: &mut Bencher) {
+	b.bytes = FIXTURE.len() as u64;
+	b.iter(|| {
+		let reader = Reader::new(black_box(FIXTURE)).unwrap();
+		return (0..reader.page_count().unwrap()).map(|index| reader.page(index).unwrap().id()).collect::<Vec<_>>();
+	});
+}
+
+#[bench]
+fn inflate_stream(b: &mut Bencher) {
+	let data = large_content_stream();
+	let compressed = filters::flate::encode(&data);
+	b.bytes = data.len() as u64;
+	b.iter(|| {
+		return filters::flate::decode(black_box(&compressed)).unwrap();
+	});
+}
+
+#[bench]
+fn write_many_pages(b: &mut Bencher) {
+	b.bytes = write_document(300).len() as u64;
+	b.iter(|| {
+		return write_document(black_box(300));
+	});
+}
+
+/// Baseline for [`write_large_xref`]: a table of as many entries, written
+/// with a `write!` call per entry, as the writer used to before the entries
+/// were buffered. Only the table is written, so this is the part of the
+/// document the buffering speeds up.
+#[bench]
+fn write_large_xref_per_entry(b: &mut Bencher) {
+	let size = 200_005;
+	let mut data = Vec::new();
+	b.bytes = size as u64 * 20;
+	b.iter(|| {
+		data.clear();
+		let mut writer = Writer::new(black_box(&mut data));
+		std::write!(writer, "xref\n0 {}\n", size).unwrap();
+		std::write!(writer, "0000000000 65535 f\r\n").unwrap();
+		for number in 1..size {
+			std::write!(writer, "{:0>10} {:0>5} n\r\n", number * 24 + 15, 0).unwrap();
+		}
+		return writer.pos();
+	});
+}
+
+#[bench]
+fn write_large_xref(b: &mut Bencher) {
+	// Tiny objects, so that writing the cross-reference table dominates.
+	let mut doc = Document::new();
+	doc.add_page(612.0, 792.0);
+	for number in 0..200_000 {
+		doc.add_object(PdfObject::Integer(number));
+	}
+	let mut data = Vec::new();
+	doc.write(&mut Writer::new(&mut data)).unwrap();
+	b.bytes = data.len() as u64;
+	b.iter(|| {
+		data.clear();
+		doc.write(&mut Writer::new(black_box(&mut data))).unwrap();
+		return data.len();
+	});
+}
@@ -0,0 +1,257 @@
+//! Interactive forms, i.e. the `/AcroForm` dictionary of the catalog.
+
+use crate::annotation::default_appearance_name;
+use crate::javascript::javascript_action;
+use crate::{parse_operations, Color, ContentStream, Dictionary, Document, FontRef, ObjectId, PdfError, PdfObject};
+
+/// Font and colour selected by a default appearance string (`/DA`), which
+/// the appearance streams of the form fields start with.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DefaultAppearance {
+	/// Name of the font in the default resources, without the leading slash.
+	pub font: Option<String>,
+	/// Font size, where zero means that the text is sized automatically to
+	/// fit the field.
+	pub size: f64,
+	/// Colour of the text.
+	pub color: Option<Color>,
+}
+
+impl DefaultAppearance {
+	/// Checks whether the text is sized automatically to fit the field.
+	#[inline]
+	pub fn is_auto_size(&self) -> bool {
+		return self.size == 0.0;
+	}
+}
+
+/// Parses the default appearance string, e.g. `/Helv 12 Tf 0 g`. The last
+/// `Tf` and colour operators win, and anything which cannot be parsed is
+/// left out of the result.
+pub fn parse_default_appearance(da: &[u8]) -> DefaultAppearance {
+	let mut appearance = DefaultAppearance::default();
+	for operation in parse_operations(da).unwrap_or_default() {
+		let numbers = operation.operands.iter().map(PdfObject::as_number).collect::<Option<Vec<_>>>().unwrap_or_default();
+		match (operation.operator.as_slice(), operation.operands.as_slice(), numbers.as_slice()) {
+			(b"Tf", [font, size], _) => {
+				if let (Some(font), Some(size)) = (font.as_name(), size.as_number()) {
+					appearance.font = Some(String::from_utf8_lossy(font).into_owned());
+					appearance.size = size;
+				}
+			},
+			(b"g", _, &[gray]) => appearance.color = Some(Color::Gray(gray)),
+			(b"rg", _, &[r, g, b]) => appearance.color = Some(Color::Rgb(r, g, b)),
+			(b"k", _, &[c, m, y, k]) => appearance.color = Some(Color::Cmyk(c, m, y, k)),
+			_ => {},
+		}
+	}
+	return appearance;
+}
+
+impl Document {
+	/// Gets the interactive form dictionary, creating it with no fields if
+	/// the document doesn't have one yet.
+	pub(crate) fn acroform_mut(&mut self) -> &mut Dictionary {
+		if !self.catalog.get("AcroForm").is_some_and(|acroform| acroform.as_dict().is_some()) {
+			let mut acroform = Dictionary::new();
+			acroform.set("Fields", Vec::<PdfObject>::new());
+			self.catalog.set("AcroForm", acroform);
+		}
+		return self.catalog.get_mut("AcroForm").and_then(PdfObject::as_dict_mut).expect("interactive form is not a dictionary");
+	}
+
+	/// Attempts to set the default appearance of the form fields, used by the
+	/// fields without their own `/DA`. The font is added to the default
+	/// resources of the form, under the name conventionally used for it in
+	/// default appearance strings, e.g. `/Helv` for Helvetica, which is
+	/// returned.
+	pub fn set_form_default_appearance(&mut self, font: FontRef, size: f64, color: Color) -> Result<String, PdfError> {
+		let Some(base_font) = self.object(font.0).and_then(PdfObject::as_dict).filter(|dict| dict.get_name("Type") == Some(b"Font")).map(|dict| dict.get_name("BaseFont").unwrap_or_default()) else {
+			return Err(PdfError::InvalidArgument(std::format!("object {} {} R is not a font", font.0.number, font.0.generation)));
+		};
+		let name = match std::str::from_utf8(base_font).ok().and_then(default_appearance_name) {
+			Some(name) => name.to_string(),
+			None => std::format!("F{}", font.0.number),
+		};
+
+		let mut da = ContentStream::new();
+		da.set_font(&name, size).set_fill_color(color);
+		let da = String::from_utf8_lossy(da.as_bytes()).split_ascii_whitespace().collect::<Vec<_>>().join(" ");
+
+		let acroform = self.acroform_mut();
+		if !acroform.get("DR").is_some_and(|dr| dr.as_dict().is_some()) {
+			acroform.set("DR", Dictionary::new());
+		}
+		let dr = acroform.get_mut("DR").and_then(PdfObject::as_dict_mut).expect("default resources are not a dictionary");
+		if !dr.get("Font").is_some_and(|fonts| fonts.as_dict().is_some()) {
+			dr.set("Font", Dictionary::new());
+		}
+		dr.get_mut("Font").and_then(PdfObject::as_dict_mut).expect("default fonts are not a dictionary").set(&name, font.0);
+		acroform.set("DA", PdfObject::string(da.into_bytes()));
+		return Ok(name);
+	}
+
+	/// Adds the text field with the given partial name to the form.
+	pub fn add_text_field(&mut self, name: &str) -> FormField<'_> {
+		let mut dict = Dictionary::new();
+		dict.set("FT", PdfObject::name("Tx"));
+		dict.set("T", PdfObject::text_string(name));
+		let id = self.add_object(dict);
+		if let Some(PdfObject::Array(fields)) = self.acroform_mut().get_mut("Fields") {
+			fields.push(id.into());
+		}
+		return FormField {
+			doc: self,
+			id,
+		};
+	}
+
+	/// Gets the form field, if the object is a field dictionary.
+	pub fn form_field(&mut self, id: ObjectId) -> Option<FormField<'_>> {
+		let dict = self.object(id)?.as_dict()?;
+		if !dict.contains_key("FT") && !dict.contains_key("T") {
+			return None;
+		}
+		return Some(FormField {
+			doc: self,
+			id,
+		});
+	}
+}
+
+/// Handle to a field of the interactive form of a [`Document`], which allows
+/// modifying it.
+pub struct FormField<'a> {
+	doc: &'a mut Document,
+	id: ObjectId,
+}
+
+impl FormField<'_> {
+	/// Gets the identifier of the field object.
+	#[inline]
+	pub fn id(&self) -> ObjectId {
+		return self.id;
+	}
+
+	/// Gets the field dictionary.
+	pub fn dict(&self) -> &Dictionary {
+		return self.doc.objects[&self.id].as_dict().expect("field object is not a dictionary");
+	}
+
+	/// Gets the field dictionary for modification.
+	pub fn dict_mut(&mut self) -> &mut Dictionary {
+		return self.doc.objects.get_mut(&self.id).and_then(PdfObject::as_dict_mut).expect("field object is not a dictionary");
+	}
+
+	/// Sets the additional action of the field, running the script.
+	fn set_action(&mut self, trigger: &str, code: &str) -> &mut Self {
+		let dict = self.dict_mut();
+		if !dict.get("AA").is_some_and(|actions| actions.as_dict().is_some()) {
+			dict.set("AA", Dictionary::new());
+		}
+		dict.get_mut("AA").and_then(PdfObject::as_dict_mut).expect("additional actions are not a dictionary").set(trigger, javascript_action(code));
+		return self;
+	}
+
+	/// Sets the script formatting the value of the field for display.
+	pub fn set_format_action(&mut self, code: &str) -> &mut Self {
+		return self.set_action("F", code);
+	}
+
+	/// Sets the script checking the value of the field when it's changed.
+	pub fn set_validate_action(&mut self, code: &str) -> &mut Self {
+		return self.set_action("V", code);
+	}
+
+	/// Sets the script calculating the value of the field when others
+	/// change. The field is added to the calculation order of the form, so
+	/// fields are recalculated in the order their actions are set.
+	pub fn set_calculate_action(&mut self, code: &str) -> &mut Self {
+		let id = self.id;
+		let acroform = self.doc.acroform_mut();
+		match acroform.get_mut("CO") {
+			Some(PdfObject::Array(order)) => {
+				if !order.contains(&id.into()) {
+					order.push(id.into());
+				}
+			},
+			_ => acroform.set("CO", std::vec![PdfObject::Reference(id)]),
+		}
+		return self.set_action("C", code);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::*;
+
+	#[test]
+	fn default_appearance_strings() {
+		let appearance = parse_default_appearance(b"/Helv 0 Tf 0 0 1 rg");
+		assert_eq!(appearance.font.as_deref(), Some("Helv"));
+		assert!(appearance.is_auto_size());
+		assert_eq!(appearance.color, Some(Color::Rgb(0.0, 0.0, 1.0)));
+
+		let appearance = parse_default_appearance(b"0.5 g /TiRo 9.5 Tf 0 0 0 1 k");
+		assert_eq!((appearance.font.as_deref(), appearance.size, appearance.color), (Some("TiRo"), 9.5, Some(Color::Cmyk(0.0, 0.0, 0.0, 1.0))));
+		assert_eq!(parse_default_appearance(b"12 Tf ) g"), DefaultAppearance::default());
+	}
+
+	#[test]
+	fn form_default_appearance() {
+		let mut doc = Document::new();
+		let helvetica = doc.add_base_font("Helvetica");
+		let page = doc.add_page(612.0, 792.0).id();
+		assert_eq!(doc.set_form_default_appearance(helvetica, 0.0, Color::Rgb(0.0, 0.0, 1.0)).unwrap(), "Helv");
+		assert!(doc.set_form_default_appearance(FontRef(page), 12.0, Color::Gray(0.0)).is_err());
+
+		let mut field = Dictionary::new();
+		field.set("FT", PdfObject::name("Tx"));
+		field.set("T", PdfObject::text_string("name"));
+		field.set("DA", PdfObject::string(b"/Helv 10 Tf 0 g".to_vec()));
+		let field = doc.add_object(field);
+		if let Some(PdfObject::Array(fields)) = doc.acroform_mut().get_mut("Fields") {
+			fields.push(field.into());
+		}
+
+		let mut output = Vec::new();
+		doc.write(&mut Writer::new(&mut output)).unwrap();
+		let reader = Reader::new(&output).unwrap();
+		let acroform = reader.catalog().unwrap().get("AcroForm").and_then(PdfObject::as_dict).unwrap().clone();
+		assert_eq!(acroform.get("DA").and_then(PdfObject::as_string), Some(&b"/Helv 0 Tf 0 0 1 rg"[..]));
+		let field = acroform.get("Fields").and_then(PdfObject::as_array).unwrap()[0].as_reference().unwrap();
+		let da = reader.resolve(field).unwrap().as_dict().unwrap().get("DA").and_then(PdfObject::as_string).unwrap().to_vec();
+		let name = std::str::from_utf8(&da).unwrap().split_ascii_whitespace().next().unwrap().strip_prefix('/').unwrap().to_string();
+		let fonts = acroform.get("DR").and_then(PdfObject::as_dict).and_then(|dr| dr.get("Font")).and_then(PdfObject::as_dict).unwrap();
+		let font = reader.resolve(fonts.get(&name).and_then(PdfObject::as_reference).unwrap()).unwrap();
+		assert_eq!(font.as_dict().unwrap().get_name("BaseFont"), Some(&b"Helvetica"[..]));
+	}
+
+	#[test]
+	fn calculated_fields() {
+		let mut doc = Document::new();
+		let page = doc.add_page(612.0, 792.0).id();
+		let price = doc.add_text_field("price").set_format_action("AFNumber_Format(2, 0, 0, 0, \"\", true);").id();
+		let mut total = doc.add_text_field("total");
+		total.set_calculate_action("AFSimple_Calculate(\"SUM\", [\"price\"]);").set_validate_action("true;");
+		total.set_calculate_action("AFSimple_Calculate(\"SUM\", [\"price\", \"tax\"]);");
+		let total = total.id();
+		assert!(doc.form_field(price).is_some());
+		assert!(doc.form_field(page).is_none());
+
+		let mut output = Vec::new();
+		doc.write(&mut Writer::new(&mut output)).unwrap();
+		let reader = Reader::new(&output).unwrap();
+		let acroform = reader.catalog().unwrap().get("AcroForm").and_then(PdfObject::as_dict).unwrap().clone();
+		assert_eq!(acroform.get("Fields"), Some(&PdfObject::Array(std::vec![price.into(), total.into()])));
+		assert_eq!(acroform.get("CO"), Some(&PdfObject::Array(std::vec![total.into()])));
+
+		let field = reader.resolve(total).unwrap();
+		let actions = field.as_dict().unwrap().get("AA").and_then(PdfObject::as_dict).unwrap();
+		let calculate = actions.get("C").and_then(PdfObject::as_dict).unwrap();
+		assert_eq!(calculate.get_name("S"), Some(&b"JavaScript"[..]));
+		assert_eq!(calculate.get("JS").and_then(PdfObject::as_string), Some(&b"AFSimple_Calculate(\"SUM\", [\"price\", \"tax\"]);"[..]));
+		assert!(actions.contains_key("V") && !actions.contains_key("F"));
+		assert!(String::from_utf8_lossy(&output).contains("/AA << /C << /S /JavaScript /JS (AFSimple_Calculate"));
+	}
+}
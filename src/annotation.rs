@@ -0,0 +1,1051 @@
+//! Page annotations, and generation of their appearance streams.
+
+use crate::{parse_default_appearance, Color, ContentStream, Dictionary, Document, FontRef, Matrix, ObjectId, Page, PdfError, PdfObject, Point, Rectangle, Stream};
+
+/// Quadrilateral, usually enclosing a run of text. Points follow the order
+/// used by viewers in practice: upper left, upper right, lower left, and
+/// lower right corner.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Quad {
+	pub points: [Point; 4],
+}
+
+impl Quad {
+	/// Creates new quadrilateral covering the given rectangle.
+	pub fn from_rect(rect: Rectangle) -> Self {
+		return Self {
+			points: [
+				Point::new(rect.llx, rect.ury),
+				Point::new(rect.urx, rect.ury),
+				Point::new(rect.llx, rect.lly),
+				Point::new(rect.urx, rect.lly),
+			],
+		};
+	}
+}
+
+/// Flags of an annotation (`/F`), which can be combined with `|`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct AnnotationFlags(u32);
+
+impl AnnotationFlags {
+	/// Annotation of an unknown subtype is not shown.
+	pub const INVISIBLE: Self = Self(1 << 0);
+	/// Annotation is neither shown nor printed.
+	pub const HIDDEN: Self = Self(1 << 1);
+	pub const PRINT: Self = Self(1 << 2);
+	/// Annotation keeps its size when the page is zoomed.
+	pub const NO_ZOOM: Self = Self(1 << 3);
+	/// Annotation keeps its orientation when the page is rotated.
+	pub const NO_ROTATE: Self = Self(1 << 4);
+	/// Annotation is not shown, but it may still be printed.
+	pub const NO_VIEW: Self = Self(1 << 5);
+	pub const READ_ONLY: Self = Self(1 << 6);
+	/// Annotation can't be deleted or have its properties modified.
+	pub const LOCKED: Self = Self(1 << 7);
+	/// Inverts the meaning of `NO_VIEW` for certain events, e.g. hovering.
+	pub const TOGGLE_NO_VIEW: Self = Self(1 << 8);
+	/// Contents of the annotation can't be modified.
+	pub const LOCKED_CONTENTS: Self = Self(1 << 9);
+
+	/// Creates new set of flags with none set.
+	#[inline]
+	pub const fn empty() -> Self {
+		return Self(0);
+	}
+
+	/// Creates new set of flags from the `/F` integer, ignoring unknown bits.
+	#[inline]
+	pub const fn from_bits(bits: u32) -> Self {
+		return Self(bits & 0x3ff);
+	}
+
+	#[inline]
+	pub const fn bits(self) -> u32 {
+		return self.0;
+	}
+
+	/// Checks whether all the given flags are set.
+	#[inline]
+	pub const fn contains(self, other: Self) -> bool {
+		return self.0 & other.0 == other.0;
+	}
+}
+
+impl std::ops::BitOr for AnnotationFlags {
+	type Output = Self;
+
+	#[inline]
+	fn bitor(self, other: Self) -> Self {
+		return Self(self.0 | other.0);
+	}
+}
+
+impl std::ops::BitOrAssign for AnnotationFlags {
+	#[inline]
+	fn bitor_assign(&mut self, other: Self) {
+		self.0 |= other.0;
+	}
+}
+
+/// Kind of a text markup annotation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MarkupKind {
+	Highlight,
+	Underline,
+	StrikeOut,
+	Squiggly,
+}
+
+impl MarkupKind {
+	fn subtype(self) -> &'static str {
+		return match self {
+			Self::Highlight => "Highlight",
+			Self::Underline => "Underline",
+			Self::StrikeOut => "StrikeOut",
+			Self::Squiggly => "Squiggly",
+		};
+	}
+}
+
+/// Icon displayed for a text annotation, i.e. a sticky note.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TextIcon {
+	Comment,
+	Key,
+	Note,
+	Help,
+	NewParagraph,
+	Paragraph,
+	Insert,
+}
+
+impl TextIcon {
+	fn name(self) -> &'static str {
+		return match self {
+			Self::Comment => "Comment",
+			Self::Key => "Key",
+			Self::Note => "Note",
+			Self::Help => "Help",
+			Self::NewParagraph => "NewParagraph",
+			Self::Paragraph => "Paragraph",
+			Self::Insert => "Insert",
+		};
+	}
+}
+
+/// Shape drawn at an end of a line annotation (`/LE`).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LineEnding {
+	#[default]
+	None,
+	Square,
+	Circle,
+	Diamond,
+	/// Two short lines meeting at the end point.
+	OpenArrow,
+	/// Filled triangle with its apex at the end point.
+	ClosedArrow,
+	/// Short line perpendicular to the line.
+	Butt,
+	/// Open arrow pointing back along the line.
+	ROpenArrow,
+	/// Closed arrow pointing back along the line.
+	RClosedArrow,
+	/// Short line slanted by 30 degrees from the perpendicular.
+	Slash,
+}
+
+impl LineEnding {
+	fn name(self) -> &'static str {
+		return match self {
+			Self::None => "None",
+			Self::Square => "Square",
+			Self::Circle => "Circle",
+			Self::Diamond => "Diamond",
+			Self::OpenArrow => "OpenArrow",
+			Self::ClosedArrow => "ClosedArrow",
+			Self::Butt => "Butt",
+			Self::ROpenArrow => "ROpenArrow",
+			Self::RClosedArrow => "RClosedArrow",
+			Self::Slash => "Slash",
+		};
+	}
+
+	/// Draws the ending at the point, where the direction is the unit vector
+	/// pointing away from the line.
+	fn draw(self, content: &mut ContentStream, point: Point, direction: (f64, f64)) {
+		let size = LINE_ENDING_SIZE;
+		let (dx, dy) = direction;
+		// Offsets along the line and perpendicular to it.
+		let at = |along: f64, across: f64| Point::new(point.x + dx * along - dy * across, point.y + dy * along + dx * across);
+
+		match self {
+			Self::None => {},
+			Self::Square => {
+				content.rect(Rectangle::new(point.x - size / 2.0, point.y - size / 2.0, point.x + size / 2.0, point.y + size / 2.0)).fill_stroke();
+			},
+			Self::Circle => {
+				circle(content, point, size / 2.0, size / 2.0);
+				content.close_path().fill_stroke();
+			},
+			Self::Diamond => {
+				content.move_to(Point::new(point.x + size / 2.0, point.y)).line_to(Point::new(point.x, point.y + size / 2.0));
+				content.line_to(Point::new(point.x - size / 2.0, point.y)).line_to(Point::new(point.x, point.y - size / 2.0));
+				content.close_path().fill_stroke();
+			},
+			Self::OpenArrow => {
+				content.move_to(at(-size, size / 2.0)).line_to(point).line_to(at(-size, -size / 2.0)).stroke();
+			},
+			Self::ClosedArrow => {
+				content.move_to(at(-size, size / 2.0)).line_to(point).line_to(at(-size, -size / 2.0)).close_path().fill_stroke();
+			},
+			Self::Butt => {
+				content.move_to(at(0.0, size / 2.0)).line_to(at(0.0, -size / 2.0)).stroke();
+			},
+			Self::ROpenArrow => {
+				content.move_to(at(0.0, size / 2.0)).line_to(at(-size, 0.0)).line_to(at(0.0, -size / 2.0)).stroke();
+			},
+			Self::RClosedArrow => {
+				content.move_to(at(0.0, size / 2.0)).line_to(at(-size, 0.0)).line_to(at(0.0, -size / 2.0)).close_path().fill_stroke();
+			},
+			Self::Slash => {
+				// Perpendicular rotated by 30 degrees, i.e. sin and cos of 30.
+				let (along, across) = (size / 4.0, size / 2.0 * 0.8660254038);
+				content.move_to(at(along, across)).line_to(at(-along, -across)).stroke();
+			},
+		}
+	}
+}
+
+/// Length of the line endings, along the line.
+const LINE_ENDING_SIZE: f64 = 6.0;
+
+/// Font size of the captions of line annotations.
+const CAPTION_SIZE: f64 = 8.0;
+
+/// Distance of the Bézier control points from the ends of a quarter of an
+/// ellipse, relative to its radius.
+const KAPPA: f64 = 0.5522847498;
+
+/// Appends the ellipse with the given centre and radii, as four Bézier
+/// curves starting at the rightmost point.
+fn circle(content: &mut ContentStream, center: Point, rx: f64, ry: f64) {
+	let (cx, cy) = (center.x, center.y);
+	let (kx, ky) = (rx * KAPPA, ry * KAPPA);
+	content.move_to(Point::new(cx + rx, cy));
+	content.curve_to(Point::new(cx + rx, cy + ky), Point::new(cx + kx, cy + ry), Point::new(cx, cy + ry));
+	content.curve_to(Point::new(cx - kx, cy + ry), Point::new(cx - rx, cy + ky), Point::new(cx - rx, cy));
+	content.curve_to(Point::new(cx - rx, cy - ky), Point::new(cx - kx, cy - ry), Point::new(cx, cy - ry));
+	content.curve_to(Point::new(cx + kx, cy - ry), Point::new(cx + rx, cy - ky), Point::new(cx + rx, cy));
+}
+
+/// Width of the border drawn by the geometric annotations.
+const SHAPE_LINE_WIDTH: f64 = 1.0;
+
+/// Creates new form XObject for use as an annotation appearance stream.
+pub(crate) fn appearance_stream(bbox: Rectangle, content: ContentStream) -> Stream {
+	let mut dict = Dictionary::new();
+	dict.set("Type", PdfObject::name("XObject"));
+	dict.set("Subtype", PdfObject::name("Form"));
+	dict.set("BBox", bbox.to_pdf_array());
+	dict.set("Resources", content.resources().to_dict());
+	return Stream::new(dict, content.finish());
+}
+
+/// Attempts to compute the `/Matrix` of an appearance stream, so that its
+/// bounding box, rotated counterclockwise by the given multiple of 90 degrees,
+/// exactly fills the annotation rectangle. Viewers then draw the appearance
+/// as it is, without any further scaling.
+pub fn appearance_matrix(bbox: Rectangle, rect: Rectangle, rotation: i32) -> Result<Matrix, PdfError> {
+	let rotate = match rotation.rem_euclid(360) {
+		_ if rotation % 90 != 0 => return Err(PdfError::InvalidArgument(std::format!("rotation {} is not a multiple of 90 degrees", rotation))),
+		0 => Matrix::IDENTITY,
+		90 => Matrix::new(0.0, 1.0, -1.0, 0.0, 0.0, 0.0),
+		180 => Matrix::new(-1.0, 0.0, 0.0, -1.0, 0.0, 0.0),
+		_ => Matrix::new(0.0, -1.0, 1.0, 0.0, 0.0, 0.0),
+	};
+
+	let corners = [Point::new(bbox.llx, bbox.lly), Point::new(bbox.urx, bbox.lly), Point::new(bbox.llx, bbox.ury), Point::new(bbox.urx, bbox.ury)];
+	let rotated = Rectangle::bounding(corners.map(|corner| rotate.transform(corner)));
+	let (width, height) = (rotated.urx - rotated.llx, rotated.ury - rotated.lly);
+	if width <= 0.0 || height <= 0.0 {
+		return Err(PdfError::InvalidArgument("appearance bounding box is empty".to_string()));
+	}
+
+	let (sx, sy) = ((rect.urx - rect.llx) / width, (rect.ury - rect.lly) / height);
+	let fit = Matrix::new(sx, 0.0, 0.0, sy, rect.llx - rotated.llx * sx, rect.lly - rotated.lly * sy);
+	return Ok(rotate.then(&fit));
+}
+
+/// Attempts to create the appearance stream with the given bounding box, and
+/// the matrix fitting it into the annotation rectangle, see
+/// [`appearance_matrix`].
+pub fn fit_appearance(rect: Rectangle, bbox: Rectangle, rotation: i32, content: ContentStream) -> Result<Stream, PdfError> {
+	let matrix = appearance_matrix(bbox, rect, rotation)?;
+	let mut stream = appearance_stream(bbox, content);
+	if matrix != Matrix::IDENTITY {
+		stream.dict.set("Matrix", matrix.to_pdf_array());
+	}
+	return Ok(stream);
+}
+
+fn markup_appearance(kind: MarkupKind, quads: &[Quad], color: Color) -> ContentStream {
+	let mut content = ContentStream::new();
+	match kind {
+		MarkupKind::Highlight => {
+			content.set_fill_color(color);
+			for quad in quads {
+				let [ul, ur, ll, lr] = quad.points;
+				content.move_to(ul).line_to(ur).line_to(lr).line_to(ll).close_path();
+			}
+			content.fill();
+		},
+		MarkupKind::Underline | MarkupKind::StrikeOut | MarkupKind::Squiggly => {
+			content.set_stroke_color(color);
+			for quad in quads {
+				let [ul, _, ll, lr] = quad.points;
+				let height = ul.y - ll.y;
+				content.set_line_width((height / 14.0).max(0.5));
+
+				match kind {
+					MarkupKind::Underline => {
+						let offset = height / 14.0;
+						content.move_to(Point::new(ll.x, ll.y + offset)).line_to(Point::new(lr.x, lr.y + offset));
+					},
+					MarkupKind::StrikeOut => {
+						let middle = height / 2.0;
+						content.move_to(Point::new(ll.x, ll.y + middle)).line_to(Point::new(lr.x, lr.y + middle));
+					},
+					_ => {
+						// Zigzag along the bottom edge, with the period proportional to the
+						// height of the text.
+						let step = (height / 6.0).max(1.0);
+						let amplitude = height / 12.0;
+						let mut x = ll.x;
+						let mut up = false;
+						content.move_to(Point::new(x, ll.y));
+						while x < lr.x {
+							x = (x + step).min(lr.x);
+							up = !up;
+							content.line_to(Point::new(x, ll.y + if up { amplitude } else { 0.0 }));
+						}
+					},
+				}
+			}
+			content.stroke();
+		},
+	}
+
+	return content;
+}
+
+/// Maps the font names conventionally used in default appearance strings to
+/// the standard 14 fonts.
+fn standard_font_for(name: &str) -> Option<&'static str> {
+	return match name {
+		"Helv" => Some("Helvetica"),
+		"TiRo" => Some("Times-Roman"),
+		"TiBo" => Some("Times-Bold"),
+		"Cour" => Some("Courier"),
+		"ZaDb" => Some("ZapfDingbats"),
+		"Symb" => Some("Symbol"),
+		_ => None,
+	};
+}
+
+/// Gets the name conventionally used for the standard font in default
+/// appearance strings.
+pub(crate) fn default_appearance_name(base_font: &str) -> Option<&'static str> {
+	return match base_font {
+		"Helvetica" => Some("Helv"),
+		"Times-Roman" => Some("TiRo"),
+		"Times-Bold" => Some("TiBo"),
+		"Courier" => Some("Cour"),
+		"ZapfDingbats" => Some("ZaDb"),
+		"Symbol" => Some("Symb"),
+		_ => None,
+	};
+}
+
+/// Encodes the text for showing with one of the standard fonts, replacing
+/// the characters which cannot be represented with a question mark.
+fn encode_single_byte(text: &str) -> Vec<u8> {
+	return text.chars().map(|c| if (c as u32) < 256 { c as u8 } else { b'?' }).collect();
+}
+
+/// Handle to an annotation of the document, allowing it to be modified.
+pub struct Annotation<'a> {
+	pub(crate) doc: &'a mut Document,
+	pub(crate) id: ObjectId,
+}
+
+impl Annotation<'_> {
+	/// Gets the object id of the annotation.
+	#[inline]
+	pub fn id(&self) -> ObjectId {
+		return self.id;
+	}
+
+	/// Gets the flags of the annotation.
+	pub fn flags(&self) -> AnnotationFlags {
+		return self.doc.annotation_flags(self.id).unwrap_or_default();
+	}
+
+	/// Sets the flags of the annotation, e.g. to make it printed but not
+	/// shown on screen.
+	pub fn set_flags(&mut self, flags: AnnotationFlags) {
+		if let Some(dict) = self.doc.object_mut(self.id).and_then(PdfObject::as_dict_mut) {
+			dict.set("F", flags.bits() as i64);
+		}
+	}
+}
+
+/// Checks whether the dictionary looks like an annotation, which needs not
+/// have its `/Type` set.
+fn is_annotation(dict: &Dictionary) -> bool {
+	return dict.get_name("Type") == Some(b"Annot") || (dict.contains_key("Subtype") && dict.contains_key("Rect"));
+}
+
+impl Document {
+	/// Gets a handle to the annotation with the given id, if the object is
+	/// an annotation.
+	pub fn annotation_mut(&mut self, annotation: ObjectId) -> Option<Annotation<'_>> {
+		if !self.object(annotation).and_then(PdfObject::as_dict).is_some_and(is_annotation) {
+			return None;
+		}
+		return Some(Annotation { doc: self, id: annotation });
+	}
+
+	/// Attempts to set the flags of the annotation, e.g. to make it printed
+	/// but not shown on screen.
+	pub fn set_annotation_flags(&mut self, annotation: ObjectId, flags: AnnotationFlags) -> Result<(), PdfError> {
+		let Some(mut handle) = self.annotation_mut(annotation) else {
+			return Err(PdfError::InvalidArgument(std::format!("object {} {} R is not an annotation", annotation.number, annotation.generation)));
+		};
+		handle.set_flags(flags);
+		return Ok(());
+	}
+
+	/// Gets the standard font used by the appearance streams of annotations,
+	/// adding it the first time it's used.
+	fn appearance_font(&mut self, base_font: &'static str) -> FontRef {
+		if let Some(&font) = self.appearance_fonts.get(base_font) {
+			return font;
+		}
+		let font = self.add_base_font(base_font);
+		self.appearance_fonts.insert(base_font, font);
+		return font;
+	}
+
+	/// Gets the flags of the annotation, if it's one.
+	pub fn annotation_flags(&self, annotation: ObjectId) -> Option<AnnotationFlags> {
+		let dict = self.object(annotation).and_then(PdfObject::as_dict)?;
+		let bits = dict.get("F").and_then(PdfObject::as_integer).unwrap_or(0);
+		return Some(AnnotationFlags::from_bits(bits as u32));
+	}
+}
+
+impl Page<'_> {
+	/// Adds new annotation with the given subtype and rectangle, letting the
+	/// caller fill in the remaining entries.
+	pub(crate) fn add_annotation(&mut self, subtype: &str, rect: Rectangle, fill: impl FnOnce(&mut Dictionary)) -> ObjectId {
+		let mut dict = Dictionary::new();
+		dict.set("Type", PdfObject::name("Annot"));
+		dict.set("Subtype", PdfObject::name(subtype));
+		dict.set("Rect", rect.to_pdf_array());
+		dict.set("P", self.id);
+		fill(&mut dict);
+
+		let id = self.doc.add_object(dict);
+		self.push_annotation(id);
+		return id;
+	}
+
+	/// Adds text markup annotation of the given kind, covering the quads.
+	pub fn add_markup(&mut self, kind: MarkupKind, quads: &[Quad], color: Color) -> ObjectId {
+		let rect = Rectangle::bounding(quads.iter().flat_map(|quad| quad.points));
+		let quad_points = quads.iter()
+			.flat_map(|quad| quad.points)
+			.flat_map(|point| [PdfObject::Real(point.x), PdfObject::Real(point.y)])
+			.collect::<Vec<_>>();
+
+		let appearance = appearance_stream(rect, markup_appearance(kind, quads, color));
+		let appearance = self.doc.add_object(appearance);
+
+		return self.add_annotation(kind.subtype(), rect, |dict| {
+			dict.set("QuadPoints", quad_points);
+			dict.set("C", color.to_pdf_array());
+			let mut ap = Dictionary::new();
+			ap.set("N", appearance);
+			dict.set("AP", ap);
+		});
+	}
+
+	/// Adds highlight annotation over the quads.
+	#[inline]
+	pub fn add_highlight(&mut self, quads: &[Quad], color: Color) -> ObjectId {
+		return self.add_markup(MarkupKind::Highlight, quads, color);
+	}
+
+	/// Adds underline annotation under the quads.
+	#[inline]
+	pub fn add_underline(&mut self, quads: &[Quad], color: Color) -> ObjectId {
+		return self.add_markup(MarkupKind::Underline, quads, color);
+	}
+
+	/// Adds strikeout annotation through the quads.
+	#[inline]
+	pub fn add_strikeout(&mut self, quads: &[Quad], color: Color) -> ObjectId {
+		return self.add_markup(MarkupKind::StrikeOut, quads, color);
+	}
+
+	/// Adds squiggly underline annotation under the quads.
+	#[inline]
+	pub fn add_squiggly(&mut self, quads: &[Quad], color: Color) -> ObjectId {
+		return self.add_markup(MarkupKind::Squiggly, quads, color);
+	}
+
+	/// Adds text annotation, displayed as a sticky note with the given icon,
+	/// which pops up the contents when opened.
+	pub fn add_text_note(&mut self, rect: Rectangle, contents: &str, icon: TextIcon) -> ObjectId {
+		return self.add_annotation("Text", rect, |dict| {
+			dict.set("Contents", PdfObject::text_string(contents));
+			dict.set("Name", PdfObject::name(icon.name()));
+		});
+	}
+
+	/// Attempts to add free text annotation, which displays the contents
+	/// directly on the page using the font and colour given by the default
+	/// appearance string. The font has to be one of the names conventionally
+	/// used for the standard fonts, e.g. `/Helv` or `/TiRo`, and Helvetica of
+	/// 12 points is used if the string doesn't set any. Automatic font size,
+	/// i.e. `0 Tf`, is not supported, as the annotation has no field to fit.
+	pub fn add_free_text(&mut self, rect: Rectangle, contents: &str, da: &str) -> Result<ObjectId, PdfError> {
+		const PADDING: f64 = 2.0;
+
+		let mut appearance = parse_default_appearance(da.as_bytes());
+		// Free text annotations have to set the font in their default
+		// appearance, unlike the fields, which can inherit it.
+		let da = match appearance.font {
+			Some(_) => da.to_string(),
+			None => {
+				appearance.font = Some("Helv".to_string());
+				appearance.size = 12.0;
+				std::format!("{} /Helv 12 Tf", da).trim_start().to_string()
+			},
+		};
+		let (font_name, size) = (appearance.font.as_deref().unwrap_or_default(), appearance.size);
+		let Some(base_font) = standard_font_for(font_name) else {
+			return Err(PdfError::InvalidArgument(std::format!("font /{} of the default appearance is not a standard font name", font_name)));
+		};
+		// Zero is the automatic size, which is only defined for fields.
+		if size <= 0.0 || !size.is_finite() {
+			return Err(PdfError::InvalidArgument(std::format!("font size {} of the default appearance is not supported", size)));
+		}
+		let font = self.doc.appearance_font(base_font);
+
+		// Appearance stream uses its own coordinate system, starting at the lower
+		// left corner of the bounding box, so we place the first line at the top.
+		let bbox = Rectangle::new(0.0, 0.0, rect.width(), rect.height());
+		let mut content = ContentStream::new();
+		content.begin_text();
+		content.raw(da.as_bytes());
+		content.move_text(PADDING, bbox.ury - PADDING - size);
+		content.show_text(&encode_single_byte(contents));
+		content.end_text();
+
+		let mut appearance = appearance_stream(bbox, content);
+		let mut fonts = Dictionary::new();
+		fonts.set(font_name, font.0);
+		let mut resources = Dictionary::new();
+		resources.set("Font", fonts);
+		appearance.dict.set("Resources", resources);
+		let appearance = self.doc.add_object(appearance);
+
+		return Ok(self.add_annotation("FreeText", rect, |dict| {
+			dict.set("Contents", PdfObject::text_string(contents));
+			dict.set("DA", PdfObject::string(da.as_bytes()));
+			let mut ap = Dictionary::new();
+			ap.set("N", appearance);
+			dict.set("AP", ap);
+		}));
+	}
+
+	/// Adds geometric annotation with the appearance stream drawn directly in
+	/// the page coordinates, so that its bounding box is the annotation rect.
+	fn add_shape(&mut self, subtype: &str, rect: Rectangle, content: ContentStream, fill: impl FnOnce(&mut Dictionary)) -> ObjectId {
+		let appearance = self.doc.add_object(appearance_stream(rect, content));
+		return self.add_annotation(subtype, rect, |dict| {
+			fill(dict);
+			let mut ap = Dictionary::new();
+			ap.set("N", appearance);
+			dict.set("AP", ap);
+		});
+	}
+
+	/// Adds line annotation between the two points.
+	pub fn add_line_annotation(&mut self, p1: Point, p2: Point, color: Color) -> ObjectId {
+		return self.add_line_with_endings(p1, p2, color, [LineEnding::None; 2], None);
+	}
+
+	/// Adds line annotation between the two points, with the given shapes at
+	/// its start and end, and optionally the caption shown above the middle
+	/// of the line.
+	pub fn add_line_with_endings(&mut self, p1: Point, p2: Point, color: Color, endings: [LineEnding; 2], caption: Option<&str>) -> ObjectId {
+		let mut margin = SHAPE_LINE_WIDTH;
+		if endings.iter().any(|&ending| ending != LineEnding::None) {
+			margin += LINE_ENDING_SIZE;
+		}
+		let mut rect = Rectangle::bounding([p1, p2]).inflate(margin);
+		let mut content = ContentStream::new();
+		content.set_stroke_color(color).set_fill_color(color).set_line_width(SHAPE_LINE_WIDTH);
+		content.move_to(p1).line_to(p2).stroke();
+
+		let length = ((p2.x - p1.x).powi(2) + (p2.y - p1.y).powi(2)).sqrt();
+		if length > 0.0 {
+			let direction = ((p2.x - p1.x) / length, (p2.y - p1.y) / length);
+			endings[0].draw(&mut content, p1, (-direction.0, -direction.1));
+			endings[1].draw(&mut content, p2, direction);
+
+			if let Some(caption) = caption {
+				// Standard fonts have no metrics here, so the width of the
+				// caption is estimated with half an em per character.
+				let font = self.doc.appearance_font("Helvetica");
+				let name = content.use_font(font);
+				let text = encode_single_byte(caption);
+				let width = text.len() as f64 * CAPTION_SIZE / 2.0;
+				let middle = Point::new((p1.x + p2.x) / 2.0, (p1.y + p2.y) / 2.0);
+				content.with_state(|content| {
+					content.transform(Matrix::new(direction.0, direction.1, -direction.1, direction.0, middle.x, middle.y));
+					content.begin_text().set_font(&name, CAPTION_SIZE).move_text(-width / 2.0, SHAPE_LINE_WIDTH + 1.0).show_text(&text).end_text();
+				});
+				let reach = width / 2.0 + CAPTION_SIZE + SHAPE_LINE_WIDTH;
+				rect = Rectangle::bounding([Point::new(rect.llx, rect.lly), Point::new(rect.urx, rect.ury), Point::new(middle.x - reach, middle.y - reach), Point::new(middle.x + reach, middle.y + reach)]);
+			}
+		}
+
+		return self.add_shape("Line", rect, content, |dict| {
+			dict.set("L", vec![p1.x.into(), p1.y.into(), p2.x.into(), p2.y.into()]);
+			dict.set("C", color.to_pdf_array());
+			if endings != [LineEnding::None; 2] {
+				dict.set("LE", endings.map(|ending| PdfObject::name(ending.name())).to_vec());
+			}
+			if let Some(caption) = caption {
+				dict.set("Contents", PdfObject::text_string(caption));
+				dict.set("Cap", true);
+			}
+		});
+	}
+
+	/// Adds square annotation, i.e. a rectangle inscribed in the given rect.
+	pub fn add_square(&mut self, rect: Rectangle, color: Color) -> ObjectId {
+		let half = SHAPE_LINE_WIDTH / 2.0;
+		let mut content = ContentStream::new();
+		content.set_stroke_color(color).set_line_width(SHAPE_LINE_WIDTH);
+		content.rect(rect.inflate(-half)).stroke();
+
+		return self.add_shape("Square", rect, content, |dict| {
+			dict.set("C", color.to_pdf_array());
+		});
+	}
+
+	/// Adds circle annotation, i.e. an ellipse inscribed in the given rect.
+	pub fn add_circle(&mut self, rect: Rectangle, color: Color) -> ObjectId {
+		let inner = rect.inflate(-SHAPE_LINE_WIDTH / 2.0);
+		let center = Point::new((inner.llx + inner.urx) / 2.0, (inner.lly + inner.ury) / 2.0);
+
+		let mut content = ContentStream::new();
+		content.set_stroke_color(color).set_line_width(SHAPE_LINE_WIDTH);
+		circle(&mut content, center, inner.width() / 2.0, inner.height() / 2.0);
+		content.close_path().stroke();
+
+		return self.add_shape("Circle", rect, content, |dict| {
+			dict.set("C", color.to_pdf_array());
+		});
+	}
+
+	/// Adds polygon annotation with the given vertices.
+	pub fn add_polygon(&mut self, points: &[Point], color: Color) -> ObjectId {
+		let rect = Rectangle::bounding(points.iter().copied()).inflate(SHAPE_LINE_WIDTH);
+		let mut content = ContentStream::new();
+		content.set_stroke_color(color).set_line_width(SHAPE_LINE_WIDTH);
+		if let Some((&first, rest)) = points.split_first() {
+			content.move_to(first);
+			for &point in rest {
+				content.line_to(point);
+			}
+			content.close_path().stroke();
+		}
+
+		let vertices = points.iter().flat_map(|point| [PdfObject::Real(point.x), PdfObject::Real(point.y)]).collect::<Vec<_>>();
+		return self.add_shape("Polygon", rect, content, |dict| {
+			dict.set("Vertices", vertices);
+			dict.set("C", color.to_pdf_array());
+		});
+	}
+
+	/// Adds ink annotation, i.e. a freehand drawing made of separate strokes.
+	pub fn add_ink_annotation(&mut self, strokes: &[Vec<Point>], color: Color, width: f64) -> ObjectId {
+		let rect = Rectangle::bounding(strokes.iter().flatten().copied()).inflate(width);
+		let mut content = ContentStream::new();
+		content.set_stroke_color(color).set_line_width(width);
+		// Round caps and joins, so that the strokes look like drawn with a pen.
+		content.set_line_cap(1).set_line_join(1);
+		for stroke in strokes {
+			smooth_path(&mut content, stroke);
+		}
+		content.stroke();
+
+		let ink_list = strokes.iter().map(|stroke| {
+			return PdfObject::Array(stroke.iter().flat_map(|point| [PdfObject::Real(point.x), PdfObject::Real(point.y)]).collect());
+		}).collect::<Vec<_>>();
+
+		return self.add_shape("Ink", rect, content, |dict| {
+			dict.set("InkList", ink_list);
+			dict.set("C", color.to_pdf_array());
+			let mut border = Dictionary::new();
+			border.set("W", width);
+			dict.set("BS", border);
+		});
+	}
+
+	/// Adds rubber stamp annotation with one of the standard names, e.g.
+	/// `Approved` or `Draft`, drawn as the name in a red frame.
+	pub fn add_stamp(&mut self, rect: Rectangle, name: &str) -> ObjectId {
+		const RED: Color = Color::Rgb(0.8, 0.0, 0.0);
+
+		let bbox = Rectangle::new(0.0, 0.0, rect.width(), rect.height());
+		let size = (bbox.height() * 0.6).min(bbox.width() / (name.len().max(1) as f64 * 0.7));
+		let mut content = ContentStream::new();
+		content.set_stroke_color(RED).set_line_width(2.0).rect(bbox.inflate(-1.0)).stroke();
+		content.set_fill_color(RED);
+		content.begin_text();
+		content.set_font("Helv", size);
+		content.move_text(bbox.width() * 0.1, (bbox.height() - size * 0.7) / 2.0);
+		content.show_text(&encode_single_byte(&name.to_uppercase()));
+		content.end_text();
+
+		let font = self.doc.appearance_font("Helvetica-Bold");
+		let mut appearance = appearance_stream(bbox, content);
+		let mut fonts = Dictionary::new();
+		fonts.set("Helv", font.0);
+		let mut resources = Dictionary::new();
+		resources.set("Font", fonts);
+		appearance.dict.set("Resources", resources);
+
+		return self.add_stamp_with_appearance(rect, name, appearance);
+	}
+
+	/// Adds rubber stamp annotation with the custom appearance, which should be
+	/// a form XObject.
+	pub fn add_stamp_with_appearance(&mut self, rect: Rectangle, name: &str, appearance: Stream) -> ObjectId {
+		let appearance = self.doc.add_object(appearance);
+		return self.add_annotation("Stamp", rect, |dict| {
+			dict.set("Name", PdfObject::name(name));
+			let mut ap = Dictionary::new();
+			ap.set("N", appearance);
+			dict.set("AP", ap);
+		});
+	}
+
+	/// Adds redaction annotation, marking the regions for removal by
+	/// [`crate::Document::apply_redactions`].
+	pub fn add_redaction(&mut self, rects: &[Rectangle]) -> ObjectId {
+		let rect = Rectangle::bounding(rects.iter().flat_map(|r| [Point::new(r.llx, r.lly), Point::new(r.urx, r.ury)]));
+		let quad_points = rects.iter()
+			.flat_map(|&r| Quad::from_rect(r).points)
+			.flat_map(|point| [PdfObject::Real(point.x), PdfObject::Real(point.y)])
+			.collect::<Vec<_>>();
+
+		return self.add_annotation("Redact", rect, |dict| {
+			dict.set("QuadPoints", quad_points);
+			// Colour used to fill the regions once the redaction is applied.
+			dict.set("IC", Color::Gray(0.0).to_pdf_array());
+		});
+	}
+}
+
+/// Appends a smooth curve passing through all the points, by converting the
+/// Catmull-Rom spline through them into cubic Bézier segments.
+fn smooth_path(content: &mut ContentStream, points: &[Point]) {
+	let Some(&first) = points.first() else {
+		return;
+	};
+
+	content.move_to(first);
+	if points.len() == 1 {
+		// Zero-length line, which is still drawn as a dot thanks to round caps.
+		content.line_to(first);
+		return;
+	}
+
+	for i in 0..points.len() - 1 {
+		let p0 = points[i.saturating_sub(1)];
+		let p1 = points[i];
+		let p2 = points[i + 1];
+		let p3 = points[(i + 2).min(points.len() - 1)];
+
+		let c1 = Point::new(p1.x + (p2.x - p0.x) / 6.0, p1.y + (p2.y - p0.y) / 6.0);
+		let c2 = Point::new(p2.x - (p3.x - p1.x) / 6.0, p2.y - (p3.y - p1.y) / 6.0);
+		content.curve_to(c1, c2, p2);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::*;
+
+	#[test]
+	fn highlight_over_text() {
+		let mut doc = Document::new();
+		let mut page = doc.add_page(612.0, 792.0);
+		let quad = Quad::from_rect(Rectangle::new(12.0, 773.0, 40.0, 785.0));
+		let id = page.add_highlight(&[quad], Color::Rgb(1.0, 1.0, 0.0));
+
+		let annot = doc.object(id).and_then(PdfObject::as_dict).unwrap();
+		assert_eq!(annot.get_name("Subtype"), Some(&b"Highlight"[..]));
+		assert_eq!(annot.get("QuadPoints").and_then(PdfObject::as_array).map(Vec::len), Some(8));
+
+		let ap = annot.get("AP").and_then(PdfObject::as_dict).unwrap();
+		let normal = ap.get("N").and_then(PdfObject::as_reference).unwrap();
+		let stream = doc.object(normal).and_then(PdfObject::as_stream).unwrap();
+		assert_eq!(stream.data, b"1 1 0 rg\n12 785 m\n40 785 l\n40 773 l\n12 773 l\nh\nf\n");
+
+		let page = doc.page_mut(0).unwrap();
+		let annots = page.dict().get("Annots").and_then(PdfObject::as_array).unwrap();
+		assert_eq!(annots, &vec![PdfObject::Reference(id)]);
+	}
+
+	#[test]
+	fn flags() {
+		let mut doc = Document::new();
+		let mut page = doc.add_page(612.0, 792.0);
+		let id = page.add_text_note(Rectangle::new(0.0, 0.0, 20.0, 20.0), "Printed only", TextIcon::Note);
+		let page_id = page.id();
+
+		let flags = AnnotationFlags::PRINT | AnnotationFlags::NO_VIEW;
+		doc.set_annotation_flags(id, flags).unwrap();
+		assert_eq!(doc.object(id).and_then(PdfObject::as_dict).unwrap().get("F"), Some(&PdfObject::Integer(36)));
+		let read = doc.annotation_flags(id).unwrap();
+		assert!(read.contains(AnnotationFlags::PRINT) && !read.contains(AnnotationFlags::HIDDEN));
+		assert!(matches!(doc.set_annotation_flags(page_id, flags), Err(PdfError::InvalidArgument(_))));
+	}
+
+	#[test]
+	fn flags_through_handle() {
+		let mut doc = Document::new();
+		let mut page = doc.add_page(612.0, 792.0);
+		let id = page.add_text_note(Rectangle::new(0.0, 0.0, 20.0, 20.0), "Hidden", TextIcon::Note);
+		let page_id = page.id();
+
+		let mut annotation = doc.annotation_mut(id).unwrap();
+		assert_eq!(annotation.id(), id);
+		assert_eq!(annotation.flags(), AnnotationFlags::default());
+		annotation.set_flags(AnnotationFlags::HIDDEN);
+		assert_eq!(annotation.flags(), AnnotationFlags::HIDDEN);
+		assert_eq!(doc.annotation_flags(id), Some(AnnotationFlags::HIDDEN));
+		assert!(doc.annotation_mut(page_id).is_none());
+	}
+
+	#[test]
+	fn appearance_fills_rect() {
+		let bbox = Rectangle::new(0.0, 0.0, 100.0, 50.0);
+		let rect = Rectangle::new(10.0, 10.0, 210.0, 110.0);
+		let stream = fit_appearance(rect, bbox, 0, ContentStream::new()).unwrap();
+		assert_eq!(stream.dict.get("Matrix"), Some(&Matrix::new(2.0, 0.0, 0.0, 2.0, 10.0, 10.0).to_pdf_array()));
+		assert_eq!(stream.dict.get("BBox"), Some(&bbox.to_pdf_array()));
+
+		// Rotated appearances are as tall as the original one is wide.
+		let matrix = appearance_matrix(bbox, Rectangle::new(0.0, 0.0, 50.0, 100.0), 90).unwrap();
+		assert_eq!(matrix.transform(Point::new(0.0, 0.0)), Point::new(50.0, 0.0));
+		assert_eq!(matrix.transform(Point::new(100.0, 50.0)), Point::new(0.0, 100.0));
+		assert!(matches!(appearance_matrix(bbox, rect, 45), Err(PdfError::InvalidArgument(_))));
+		assert!(fit_appearance(bbox, bbox, 0, ContentStream::new()).unwrap().dict.get("Matrix").is_none());
+	}
+
+	#[test]
+	fn strikeout_is_stroked() {
+		let mut doc = Document::new();
+		let mut page = doc.add_page(612.0, 792.0);
+		let quad = Quad::from_rect(Rectangle::new(0.0, 0.0, 28.0, 14.0));
+		let id = page.add_strikeout(&[quad], Color::Gray(0.0));
+
+		let annot = doc.object(id).and_then(PdfObject::as_dict).unwrap();
+		assert_eq!(annot.get_name("Subtype"), Some(&b"StrikeOut"[..]));
+		let normal = annot.get("AP").and_then(PdfObject::as_dict).and_then(|ap| ap.get("N")).and_then(PdfObject::as_reference).unwrap();
+		let stream = doc.object(normal).and_then(PdfObject::as_stream).unwrap();
+		assert_eq!(stream.data, b"0 G\n1 w\n0 7 m\n28 7 l\nS\n");
+	}
+
+	#[test]
+	fn sticky_note_contents() {
+		let mut doc = Document::new();
+		let mut page = doc.add_page(612.0, 792.0);
+		let ascii = page.add_text_note(Rectangle::new(0.0, 0.0, 20.0, 20.0), "Hello!", TextIcon::Comment);
+		let unicode = page.add_text_note(Rectangle::new(0.0, 0.0, 20.0, 20.0), "Łódź", TextIcon::Note);
+
+		let annot = doc.object(ascii).and_then(PdfObject::as_dict).unwrap();
+		assert_eq!(annot.get_name("Subtype"), Some(&b"Text"[..]));
+		assert_eq!(annot.get_name("Name"), Some(&b"Comment"[..]));
+		assert_eq!(annot.get("Contents").and_then(PdfObject::as_string), Some(&b"Hello!"[..]));
+
+		let annot = doc.object(unicode).and_then(PdfObject::as_dict).unwrap();
+		let contents = annot.get("Contents").and_then(PdfObject::as_string).unwrap();
+		assert_eq!(contents, [0xfe, 0xff, 0x01, 0x41, 0x00, 0xf3, 0x00, 0x64, 0x01, 0x7a]);
+	}
+
+	#[test]
+	fn free_text_appearance() {
+		let mut doc = Document::new();
+		let mut page = doc.add_page(612.0, 792.0);
+		let id = page.add_free_text(Rectangle::new(100.0, 100.0, 300.0, 130.0), "Approved (draft)", "/Helv 12 Tf 1 0 0 rg").unwrap();
+
+		let annot = doc.object(id).and_then(PdfObject::as_dict).unwrap();
+		assert_eq!(annot.get_name("Subtype"), Some(&b"FreeText"[..]));
+		let normal = annot.get("AP").and_then(PdfObject::as_dict).and_then(|ap| ap.get("N")).and_then(PdfObject::as_reference).unwrap();
+		let stream = doc.object(normal).and_then(PdfObject::as_stream).unwrap();
+		assert_eq!(stream.data, b"BT\n/Helv 12 Tf 1 0 0 rg\n2 16 Td\n(Approved \\(draft\\)) Tj\nET\n");
+
+		let fonts = stream.dict.get("Resources").and_then(PdfObject::as_dict).and_then(|r| r.get("Font")).and_then(PdfObject::as_dict).unwrap();
+		let font = fonts.get("Helv").and_then(PdfObject::as_reference).unwrap();
+		let font = doc.object(font).and_then(PdfObject::as_dict).unwrap();
+		assert_eq!(font.get_name("BaseFont"), Some(&b"Helvetica"[..]));
+	}
+
+	#[test]
+	fn free_text_default_appearance() {
+		let mut doc = Document::new();
+		let mut page = doc.add_page(612.0, 792.0);
+		let rect = Rectangle::new(100.0, 100.0, 300.0, 130.0);
+		assert!(matches!(page.add_free_text(rect, "Zero", "/Helv 0 Tf"), Err(PdfError::InvalidArgument(_))));
+		assert!(matches!(page.add_free_text(rect, "Unknown", "/F1 12 Tf"), Err(PdfError::InvalidArgument(_))));
+
+		// Without a font, the default one is added to the string.
+		let id = page.add_free_text(rect, "Red", "1 0 0 rg").unwrap();
+		let annot = doc.object(id).and_then(PdfObject::as_dict).unwrap();
+		assert_eq!(annot.get("DA").and_then(PdfObject::as_string), Some(&b"1 0 0 rg /Helv 12 Tf"[..]));
+		let normal = annot.get("AP").and_then(PdfObject::as_dict).and_then(|ap| ap.get("N")).and_then(PdfObject::as_reference).unwrap();
+		let stream = doc.object(normal).and_then(PdfObject::as_stream).unwrap();
+		assert_eq!(stream.data, b"BT\n1 0 0 rg /Helv 12 Tf\n2 16 Td\n(Red) Tj\nET\n");
+	}
+
+	#[test]
+	fn shared_appearance_fonts() {
+		let mut doc = Document::new();
+		let mut page = doc.add_page(612.0, 792.0);
+		let rect = Rectangle::new(100.0, 100.0, 300.0, 130.0);
+		let ids = [page.add_free_text(rect, "One", "/Helv 12 Tf"), page.add_free_text(rect, "Two", "/Helv 10 Tf"), page.add_free_text(rect, "Three", "/TiRo 12 Tf")].map(Result::unwrap);
+		let font = |id: ObjectId| {
+			let annot = doc.object(id).and_then(PdfObject::as_dict).unwrap();
+			let normal = annot.get("AP").and_then(PdfObject::as_dict).and_then(|ap| ap.get("N")).and_then(PdfObject::as_reference).unwrap();
+			let resources = doc.object(normal).and_then(PdfObject::as_stream).unwrap().dict.get("Resources").and_then(PdfObject::as_dict).unwrap();
+			let fonts = resources.get("Font").and_then(PdfObject::as_dict).unwrap();
+			return fonts.iter().next().and_then(|(_, font)| font.as_reference()).unwrap();
+		};
+		assert_eq!(font(ids[0]), font(ids[1]));
+		assert_ne!(font(ids[0]), font(ids[2]));
+		let fonts = doc.objects.values().filter(|object| object.as_dict().is_some_and(|dict| dict.get_name("Type") == Some(b"Font"))).count();
+		assert_eq!(fonts, 2);
+	}
+
+	#[test]
+	fn line_and_polygon_geometry() {
+		let mut doc = Document::new();
+		let mut page = doc.add_page(612.0, 792.0);
+		let line = page.add_line_annotation(Point::new(10.0, 20.0), Point::new(110.0, 70.0), Color::Rgb(1.0, 0.0, 0.0));
+		let points = [Point::new(0.0, 0.0), Point::new(50.0, 0.0), Point::new(25.5, 40.0)];
+		let polygon = page.add_polygon(&points, Color::Gray(0.0));
+
+		let annot = doc.object(line).and_then(PdfObject::as_dict).unwrap();
+		assert_eq!(annot.get_name("Subtype"), Some(&b"Line"[..]));
+		let l = vec![PdfObject::Real(10.0), PdfObject::Real(20.0), PdfObject::Real(110.0), PdfObject::Real(70.0)];
+		assert_eq!(annot.get("L"), Some(&PdfObject::Array(l)));
+
+		let annot = doc.object(polygon).and_then(PdfObject::as_dict).unwrap();
+		assert_eq!(annot.get_name("Subtype"), Some(&b"Polygon"[..]));
+		let vertices = annot.get("Vertices").and_then(PdfObject::as_array).unwrap();
+		let expected = points.iter().flat_map(|p| [PdfObject::Real(p.x), PdfObject::Real(p.y)]).collect::<Vec<_>>();
+		assert_eq!(vertices, &expected);
+		assert_eq!(annot.get("Rect"), Some(&Rectangle::new(-1.0, -1.0, 51.0, 41.0).to_pdf_array()));
+	}
+
+	#[test]
+	fn circle_is_made_of_curves() {
+		let mut doc = Document::new();
+		let mut page = doc.add_page(612.0, 792.0);
+		let id = page.add_circle(Rectangle::new(0.0, 0.0, 101.0, 51.0), Color::Gray(0.0));
+
+		let annot = doc.object(id).and_then(PdfObject::as_dict).unwrap();
+		assert_eq!(annot.get_name("Subtype"), Some(&b"Circle"[..]));
+		let normal = annot.get("AP").and_then(PdfObject::as_dict).and_then(|ap| ap.get("N")).and_then(PdfObject::as_reference).unwrap();
+		let stream = doc.object(normal).and_then(PdfObject::as_stream).unwrap();
+		let text = String::from_utf8(stream.data.clone()).unwrap();
+		assert_eq!(text.lines().filter(|line| line.ends_with(" c")).count(), 4);
+		assert!(text.starts_with("0 G\n1 w\n100.5 25.5 m\n"));
+	}
+
+	#[test]
+	fn stamp_name_and_appearance() {
+		let mut doc = Document::new();
+		let mut page = doc.add_page(612.0, 792.0);
+		let id = page.add_stamp(Rectangle::new(400.0, 700.0, 550.0, 750.0), "Approved");
+
+		let annot = doc.object(id).and_then(PdfObject::as_dict).unwrap();
+		assert_eq!(annot.get_name("Subtype"), Some(&b"Stamp"[..]));
+		assert_eq!(annot.get_name("Name"), Some(&b"Approved"[..]));
+		let normal = annot.get("AP").and_then(PdfObject::as_dict).and_then(|ap| ap.get("N")).and_then(PdfObject::as_reference).unwrap();
+		let stream = doc.object(normal).and_then(PdfObject::as_stream).unwrap();
+		assert!(stream.data.windows(12).any(|w| w == b"(APPROVED) T"));
+	}
+
+	#[test]
+	fn ink_strokes() {
+		let mut doc = Document::new();
+		let mut page = doc.add_page(612.0, 792.0);
+		let strokes = vec![
+			vec![Point::new(10.0, 10.0), Point::new(20.0, 15.0), Point::new(30.0, 10.0), Point::new(40.0, 15.0)],
+			vec![Point::new(10.0, 30.0), Point::new(40.0, 30.0)],
+		];
+		let id = page.add_ink_annotation(&strokes, Color::Rgb(0.0, 0.0, 1.0), 2.0);
+
+		let annot = doc.object(id).and_then(PdfObject::as_dict).unwrap();
+		assert_eq!(annot.get_name("Subtype"), Some(&b"Ink"[..]));
+		let ink_list = annot.get("InkList").and_then(PdfObject::as_array).unwrap();
+		assert_eq!(ink_list.len(), 2);
+		assert_eq!(ink_list[0].as_array().map(Vec::len), Some(8));
+		assert_eq!(ink_list[1].as_array().map(Vec::len), Some(4));
+
+		let normal = annot.get("AP").and_then(PdfObject::as_dict).and_then(|ap| ap.get("N")).and_then(PdfObject::as_reference).unwrap();
+		let stream = doc.object(normal).and_then(PdfObject::as_stream).unwrap();
+		let text = String::from_utf8(stream.data.clone()).unwrap();
+		assert_eq!(text.lines().filter(|line| line.ends_with(" m")).count(), 2);
+		assert_eq!(text.lines().filter(|line| line.ends_with(" c")).count(), 4);
+	}
+
+	#[test]
+	fn line_endings() {
+		let mut doc = Document::new();
+		let mut page = doc.add_page(612.0, 792.0);
+		let plain = page.add_line_annotation(Point::new(10.0, 20.0), Point::new(110.0, 20.0), Color::Gray(0.0));
+		let arrow = page.add_line_with_endings(Point::new(10.0, 20.0), Point::new(110.0, 20.0), Color::Gray(0.0), [LineEnding::None, LineEnding::ClosedArrow], Some("100 mm"));
+
+		assert_eq!(doc.object(plain).and_then(PdfObject::as_dict).unwrap().get("LE"), None);
+		let annot = doc.object(arrow).and_then(PdfObject::as_dict).unwrap();
+		assert_eq!(annot.get("LE"), Some(&PdfObject::Array(vec![PdfObject::name("None"), PdfObject::name("ClosedArrow")])));
+		assert_eq!(annot.get("Cap"), Some(&PdfObject::Boolean(true)));
+		assert_eq!(annot.get("Contents"), Some(&PdfObject::text_string("100 mm")));
+
+		let appearance = annot.get("AP").and_then(PdfObject::as_dict).and_then(|ap| ap.get("N")).and_then(PdfObject::as_reference).unwrap();
+		let stream = doc.object(appearance).and_then(PdfObject::as_stream).unwrap();
+		let operations = parse_operations(&stream.data).unwrap();
+		let operators: Vec<&[u8]> = operations.iter().map(|operation| operation.operator.as_slice()).collect();
+		let arrowhead = operators.windows(5).position(|window| window == [&b"m"[..], b"l", b"l", b"h", b"B"]).unwrap();
+		let points: Vec<Vec<f64>> = operations[arrowhead..arrowhead + 3].iter().map(|operation| operation.operands.iter().filter_map(PdfObject::as_number).collect()).collect();
+		assert_eq!(points, [vec![104.0, 23.0], vec![110.0, 20.0], vec![104.0, 17.0]]);
+		assert!(operations.iter().any(|operation| operation.operator == b"Tj" && operation.operands == [PdfObject::string(b"100 mm".to_vec())]));
+		assert!(stream.dict.get("Resources").and_then(PdfObject::as_dict).is_some_and(|resources| resources.contains_key("Font")));
+		let rect = Rectangle::from_pdf_array(annot.get("Rect").unwrap()).unwrap();
+		assert!(rect.urx >= 110.0 + 6.0 && rect.ury > 20.0 + 8.0);
+	}
+}
@@ -0,0 +1,90 @@
+//! Article threads, which lead the reader through related parts of pages,
+//! e.g. the columns of a magazine article.
+
+use crate::{Dictionary, Document, ObjectId, PdfError, PdfObject, Rectangle};
+
+impl Document {
+	/// Attempts to add an article thread, going through the given areas of
+	/// pages in order, with zero-based page indices. The beads of the thread
+	/// form a circular list, and the thread is added to the catalog.
+	pub fn add_article(&mut self, title: &str, beads: &[(usize, Rectangle)]) -> Result<ObjectId, PdfError> {
+		if beads.is_empty() {
+			return Err(PdfError::InvalidArgument("an article has to have at least one bead".to_string()));
+		}
+		let count = self.pages.len();
+		if let Some(&(index, _)) = beads.iter().find(|(index, _)| *index >= count) {
+			return Err(PdfError::PageOutOfRange { index, count });
+		}
+
+		let thread = self.reserve_id();
+		let ids: Vec<ObjectId> = beads.iter().map(|_| self.reserve_id()).collect();
+		for (i, &(index, rect)) in beads.iter().enumerate() {
+			let page = self.pages[index];
+			let mut bead = Dictionary::new();
+			bead.set("Type", PdfObject::name("Bead"));
+			bead.set("T", thread);
+			bead.set("N", ids[(i + 1) % ids.len()]);
+			bead.set("V", ids[(i + ids.len() - 1) % ids.len()]);
+			bead.set("P", page);
+			bead.set("R", rect.to_pdf_array());
+			self.set_object(ids[i], bead);
+
+			let page = self.objects.get_mut(&page).and_then(PdfObject::as_dict_mut).expect("page object is not a dictionary");
+			match page.get_mut("B") {
+				Some(PdfObject::Array(list)) => list.push(ids[i].into()),
+				_ => page.set("B", std::vec![PdfObject::Reference(ids[i])]),
+			}
+		}
+
+		let mut info = Dictionary::new();
+		info.set("Title", PdfObject::text_string(title));
+		let mut dict = Dictionary::new();
+		dict.set("Type", PdfObject::name("Thread"));
+		dict.set("F", ids[0]);
+		dict.set("I", info);
+		self.set_object(thread, dict);
+
+		match self.catalog.get_mut("Threads") {
+			Some(PdfObject::Array(threads)) => threads.push(thread.into()),
+			_ => self.catalog.set("Threads", std::vec![PdfObject::Reference(thread)]),
+		}
+		return Ok(thread);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::*;
+
+	#[test]
+	fn two_bead_article() {
+		let mut doc = Document::new();
+		doc.add_page(612.0, 792.0);
+		doc.add_page(612.0, 792.0);
+		let columns = [(0, Rectangle::new(72.0, 72.0, 300.0, 720.0)), (1, Rectangle::new(312.0, 72.0, 540.0, 720.0))];
+		assert!(matches!(doc.add_article("Story", &[(2, columns[0].1)]), Err(PdfError::PageOutOfRange { index: 2, count: 2 })));
+		let thread = doc.add_article("Story", &columns).unwrap();
+
+		let mut data = Vec::new();
+		doc.write(&mut Writer::new(&mut data)).unwrap();
+		let reader = Reader::new(&data).unwrap();
+		let dict = |id| reader.resolve(id).unwrap().as_dict().unwrap().clone();
+		let reference = |dict: &Dictionary, key| dict.get(key).and_then(PdfObject::as_reference).unwrap();
+
+		let catalog = dict(reader.trailer().get("Root").and_then(PdfObject::as_reference).unwrap());
+		assert_eq!(catalog.get("Threads"), Some(&PdfObject::Array(std::vec![thread.into()])));
+		let first = reference(&dict(thread), "F");
+		let second = reference(&dict(first), "N");
+		assert_ne!(first, second);
+
+		let pages = reader.pages().unwrap();
+		for (bead, (next, page)) in [(first, (second, pages[0])), (second, (first, pages[1]))] {
+			let bead = dict(bead);
+			assert_eq!(bead.get_name("Type"), Some(&b"Bead"[..]));
+			assert_eq!((reference(&bead, "N"), reference(&bead, "V")), (next, next));
+			assert_eq!(reference(&bead, "T"), thread);
+			assert_eq!(reference(&bead, "P"), page);
+		}
+		assert_eq!(dict(pages[1]).get("B"), Some(&PdfObject::Array(std::vec![second.into()])));
+	}
+}
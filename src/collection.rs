@@ -0,0 +1,60 @@
+//! Portfolios, i.e. documents presenting their embedded files as a package
+//! (`/Collection`).
+
+use crate::{Dictionary, Document, PdfObject};
+
+/// Initial presentation of the files of a portfolio (`/View`).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CollectionView {
+	/// Files are listed with their details, e.g. the sizes.
+	#[default]
+	Details,
+	/// Files are shown as tiles, with their thumbnails.
+	Tile,
+	/// The list of the files is hidden, and the document itself is shown.
+	Hidden,
+}
+
+impl CollectionView {
+	fn name(self) -> &'static str {
+		return match self {
+			Self::Details => "D",
+			Self::Tile => "T",
+			Self::Hidden => "H",
+		};
+	}
+}
+
+impl Document {
+	/// Makes the document a portfolio, presenting the files of the
+	/// `/EmbeddedFiles` name tree, added with
+	/// [`Document::add_embedded_file`], as a package.
+	pub fn set_collection(&mut self, view: CollectionView) {
+		let mut collection = Dictionary::new();
+		collection.set("View", PdfObject::name(view.name()));
+		self.catalog.set("Collection", collection);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::*;
+
+	#[test]
+	fn detailed_portfolio() {
+		let mut doc = Document::new();
+		doc.add_page(612.0, 792.0);
+		let first = doc.add_embedded_file("first.txt", b"First", None).unwrap();
+		let second = doc.add_embedded_file("second.txt", b"Second", None).unwrap();
+		doc.set_collection(CollectionView::Details);
+
+		let mut output = Vec::new();
+		doc.write(&mut Writer::new(&mut output)).unwrap();
+		assert!(String::from_utf8_lossy(&output).contains("/Collection << /View /D >>"));
+		let reader = Reader::new(&output).unwrap();
+		assert_eq!(reader.name_tree_value("EmbeddedFiles", b"first.txt").unwrap(), Some(PdfObject::Reference(first)));
+		assert_eq!(reader.name_tree_value("EmbeddedFiles", b"second.txt").unwrap(), Some(PdfObject::Reference(second)));
+		let names = reader.catalog().unwrap();
+		assert!(names.get("Names").and_then(PdfObject::as_dict).is_some_and(|names| names.contains_key("EmbeddedFiles")));
+	}
+}
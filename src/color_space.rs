@@ -0,0 +1,112 @@
+//! Colour spaces which are indirect objects, shared by the images using them.
+
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+use crate::output_intent::icc_components;
+use crate::{filters, Dictionary, Document, Image, ObjectId, PdfError, PdfObject, Stream};
+
+/// Reference to a colour space array added to the document, e.g. an
+/// `/ICCBased` one, which can be used by any number of images.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct CsRef(pub ObjectId);
+
+impl From<CsRef> for ObjectId {
+	#[inline]
+	fn from(color_space: CsRef) -> Self {
+		return color_space.0;
+	}
+}
+
+impl Document {
+	/// Attempts to add the `/ICCBased` colour space with the given ICC
+	/// profile. The profile is stored only once, so adding the same profile
+	/// again returns the same colour space.
+	pub fn add_icc_color_space(&mut self, icc_profile: &[u8]) -> Result<CsRef, PdfError> {
+		let Some(components) = icc_components(icc_profile) else {
+			return Err(PdfError::InvalidArgument("colour space profile is not a supported ICC profile".to_string()));
+		};
+
+		let mut hasher = DefaultHasher::new();
+		icc_profile.hash(&mut hasher);
+		let hash = hasher.finish();
+		if let Some(&color_space) = self.icc_color_spaces.get(&hash) {
+			// Profiles with the same hash are compared, so that a collision
+			// doesn't make images use the wrong colours.
+			if self.icc_profile(color_space).as_deref() == Some(icc_profile) {
+				return Ok(color_space);
+			}
+		}
+
+		let mut profile = Stream::new(Dictionary::new(), filters::flate::encode(icc_profile));
+		profile.dict.set("N", components);
+		profile.dict.set("Filter", PdfObject::name("FlateDecode"));
+		let profile = self.add_object(profile);
+		let color_space = CsRef(self.add_object(std::vec![PdfObject::name("ICCBased"), profile.into()]));
+		self.icc_color_spaces.entry(hash).or_insert(color_space);
+		return Ok(color_space);
+	}
+
+	/// Gets the decoded profile of the `/ICCBased` colour space.
+	fn icc_profile(&self, color_space: CsRef) -> Option<Vec<u8>> {
+		let Some([_, PdfObject::Reference(profile)]) = self.object(color_space.0).and_then(PdfObject::as_array).map(Vec::as_slice) else {
+			return None;
+		};
+		return self.decode(self.object(*profile)?.as_stream()?).ok();
+	}
+
+	/// Attempts to add the image XObject, like [`Document::add_image`], using
+	/// the given colour space, which has to have as many components as the
+	/// image.
+	pub fn add_image_with_color_space(&mut self, image: &Image, color_space: CsRef) -> Result<ObjectId, PdfError> {
+		let components = self.icc_profile(color_space).as_deref().and_then(icc_components);
+		if components != Some(image.components as i64) {
+			return Err(PdfError::InvalidArgument(std::format!("colour space does not have {} components", image.components)));
+		}
+
+		let mut stream = image.to_stream();
+		stream.dict.set("ColorSpace", color_space.0);
+		return Ok(self.add_object(stream));
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::*;
+
+	fn profile(color_space: &[u8; 4]) -> Vec<u8> {
+		let mut profile = std::vec![0; 128];
+		profile[16..20].copy_from_slice(color_space);
+		profile[36..40].copy_from_slice(b"acsp");
+		return profile;
+	}
+
+	#[test]
+	fn shared_icc_profiles() {
+		let mut doc = Document::new();
+		let srgb = doc.add_icc_color_space(&profile(b"RGB ")).unwrap();
+		let gray = doc.add_icc_color_space(&profile(b"GRAY")).unwrap();
+		assert_ne!(srgb, gray);
+		assert!(doc.add_icc_color_space(b"not a profile").is_err());
+		assert!(doc.add_image_with_color_space(&Image::new(1, 1, 1, std::vec![0]), srgb).is_err());
+
+		let mut content = ContentStream::new();
+		for pixel in [0, 128, 255] {
+			let color_space = doc.add_icc_color_space(&profile(b"RGB ")).unwrap();
+			assert_eq!(color_space, srgb);
+			let image = doc.add_image_with_color_space(&Image::new(1, 1, 3, std::vec![pixel; 3]), color_space).unwrap();
+			let name = content.use_image(image);
+			content.draw_xobject(&name);
+		}
+		doc.add_page(612.0, 792.0).set_contents(content);
+
+		let mut output = Vec::new();
+		doc.write(&mut Writer::new(&mut output)).unwrap();
+		let reader = Reader::new(&output).unwrap();
+		let profiles = reader.object_ids().filter(|&id| reader.resolve(id).unwrap().as_stream().is_some_and(|stream| stream.dict.get("N") == Some(&PdfObject::Integer(3))));
+		assert_eq!(profiles.count(), 1);
+		let images = reader.images().unwrap();
+		assert_eq!(images.len(), 3);
+		assert!(images.iter().all(|image| image.color_space.as_deref() == Some("ICCBased")));
+		assert_eq!(String::from_utf8_lossy(&output).matches("[/ICCBased").count(), 2);
+	}
+}
@@ -0,0 +1,906 @@
+//! Builder for page content streams, i.e. sequences of drawing operators.
+
+use crate::lexer::{self, Token};
+use crate::object::{format_real, write_literal_string, write_name};
+use crate::parser::Parser;
+use crate::{filters, Color, Dictionary, FontRef, Matrix, ObjectId, PdfError, PdfObject, Point, Rectangle, ResourceManager, Writer, XObjectRef};
+
+// Small overview of the text rendering facilities in PDF 1.7:
+//   Each rendered text needs to be a stream object. The stream starts with
+// command "BT", which stands for "Begin Text", and ends with command "ET",
+// for "End Text". Between these you select font and size using "Tf" command,
+// which accepts font name ("/F13") and size in points/units. Text position
+// is provided using "Td" command, which accepts offset from the "0,0" point
+// (lower left corner) in units. "Tj" command draws the text provided as a
+// string using the currently set options (colour, size, font, position.)
+
+/// Direction in which the glyphs of a text are laid out.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum WritingMode {
+	#[default]
+	LeftToRight,
+	/// Used by e.g. Arabic and Hebrew scripts.
+	RightToLeft,
+	/// Vertical writing, used by e.g. Chinese and Japanese scripts.
+	TopToBottom,
+}
+
+/// Operand of a content stream operator.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Operand<'a> {
+	Int(i64),
+	/// Number written without an exponent, like the real objects.
+	Real(f64),
+	Name(&'a str),
+	/// Literal string, escaped where needed.
+	String(&'a [u8]),
+	Array(&'a [Operand<'a>]),
+	Dictionary(&'a Dictionary),
+}
+
+/// Property list attached to marked content.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PropertyList {
+	/// Dictionary written directly in the content stream.
+	Inline(Dictionary),
+	/// Indirect dictionary, referred to by its name in the `/Properties`
+	/// resources.
+	Resource(ObjectId),
+}
+
+/// Operands expected by the colour operators in the selected colour space.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ColorOperands {
+	/// Given number of colour components.
+	Components(usize),
+	/// Pattern name, preceded by the components of uncoloured patterns.
+	Pattern,
+	/// Colour space from the resources, whose components are not known.
+	Unknown,
+}
+
+impl ColorOperands {
+	/// Gets the operands of the colour space with the given name.
+	fn of(color_space: &str) -> Self {
+		return match color_space {
+			"DeviceGray" | "CalGray" => Self::Components(1),
+			"DeviceRGB" | "CalRGB" | "Lab" => Self::Components(3),
+			"DeviceCMYK" => Self::Components(4),
+			"Pattern" => Self::Pattern,
+			_ => Self::Unknown,
+		};
+	}
+}
+
+/// Colour spaces selected for stroking and filling.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct ColorSpaces {
+	stroke: ColorOperands,
+	fill: ColorOperands,
+}
+
+impl Default for ColorSpaces {
+	fn default() -> Self {
+		// Both colour spaces are initially `/DeviceGray`.
+		return Self {
+			stroke: ColorOperands::Components(1),
+			fill: ColorOperands::Components(1),
+		};
+	}
+}
+
+/// Content stream, which is built operator by operator. Every operator is
+/// written on its own line.
+#[derive(Clone, Debug, Default)]
+pub struct ContentStream {
+	buf: Vec<u8>,
+	/// Resources used by the operators, which become the page resources.
+	pub(crate) resources: ResourceManager,
+	/// Colour spaces of the current graphics state, so that the operands of
+	/// the colour operators can be checked.
+	color_spaces: ColorSpaces,
+	/// Colour spaces of the graphics states saved, and not restored yet.
+	saved_states: Vec<ColorSpaces>,
+}
+
+/// Guard of the graphics state saved by [`ContentStream::save_state`], which
+/// restores it when dropped. Operators can be added through the guard.
+#[derive(Debug)]
+pub struct StateGuard<'a> {
+	content: &'a mut ContentStream,
+}
+
+impl std::ops::Deref for StateGuard<'_> {
+	type Target = ContentStream;
+
+	#[inline]
+	fn deref(&self) -> &ContentStream {
+		return self.content;
+	}
+}
+
+impl std::ops::DerefMut for StateGuard<'_> {
+	#[inline]
+	fn deref_mut(&mut self) -> &mut ContentStream {
+		return self.content;
+	}
+}
+
+impl Drop for StateGuard<'_> {
+	fn drop(&mut self) {
+		self.content.pop_state();
+	}
+}
+
+impl ContentStream {
+	/// Creates new empty content stream.
+	#[inline]
+	pub fn new() -> Self {
+		return Self {
+			buf: Vec::new(),
+			resources: ResourceManager::new(),
+			color_spaces: ColorSpaces::default(),
+			saved_states: Vec::new(),
+		};
+	}
+
+	/// Creates new empty content stream, which names the resources after the
+	/// given ones, e.g. the ones used by the other contents of the page.
+	#[inline]
+	pub fn with_resources(resources: ResourceManager) -> Self {
+		return Self {
+			resources,
+			..Self::new()
+		};
+	}
+
+	/// Gets the resources used by the content stream.
+	#[inline]
+	pub fn resources(&self) -> &ResourceManager {
+		return &self.resources;
+	}
+
+	/// Requests the font, returning the name under which it can be selected.
+	pub fn use_font(&mut self, font: FontRef) -> String {
+		return self.resources.font(font);
+	}
+
+	/// Requests the image XObject, returning the name under which it can be
+	/// painted.
+	pub fn use_image(&mut self, image: ObjectId) -> String {
+		return self.resources.image(image);
+	}
+
+	/// Requests the form XObject, e.g. a transparency group, returning the
+	/// name under which it can be painted.
+	pub fn use_xobject(&mut self, xobject: XObjectRef) -> String {
+		return self.resources.form(xobject.0);
+	}
+
+	/// Requests the graphics state parameter dictionary, returning the name
+	/// under which it can be set.
+	pub fn use_graphics_state(&mut self, state: ObjectId) -> String {
+		return self.resources.graphics_state(state);
+	}
+
+	/// Returns the bytes of the content stream built so far.
+	#[inline]
+	pub fn as_bytes(&self) -> &[u8] {
+		return &self.buf;
+	}
+
+	/// Consumes the builder, returning the content stream bytes. Every saved
+	/// graphics state has to be restored by then, which is checked in debug
+	/// builds.
+	#[inline]
+	pub fn finish(self) -> Vec<u8> {
+		debug_assert!(self.saved_states.is_empty(), "graphics state saved without being restored");
+		return self.buf;
+	}
+
+	fn operand(&mut self, operand: &Operand<'_>) {
+		match *operand {
+			Operand::Int(value) => self.buf.extend_from_slice(value.to_string().as_bytes()),
+			Operand::Real(value) => self.buf.extend_from_slice(format_real(value).as_bytes()),
+			Operand::Name(name) => {
+				// Writing into a vector never fails.
+				let _ = write_name(&mut Writer::new(&mut self.buf), name.as_bytes());
+			},
+			Operand::String(bytes) => {
+				// Line ends are escaped too, since a carriage return in a
+				// literal string is read back as a line feed.
+				let _ = write_literal_string(&mut Writer::new(&mut self.buf), bytes);
+			},
+			Operand::Array(items) => {
+				self.buf.push(b'[');
+				for (i, item) in items.iter().enumerate() {
+					if i > 0 {
+						self.buf.push(b' ');
+					}
+					self.operand(item);
+				}
+				self.buf.push(b']');
+			},
+			Operand::Dictionary(dict) => {
+				// Writing into a vector never fails.
+				let _ = PdfObject::Dictionary(dict.clone()).write_inline(&mut Writer::new(&mut self.buf));
+			},
+		}
+	}
+
+	/// Formats the operators for debugging, one per line, with the graphics
+	/// state and text object blocks indented. Contents which can't be parsed
+	/// are given as they are.
+	pub fn to_pretty_string(&self) -> String {
+		return match parse_operations(&self.buf) {
+			Ok(operations) => pretty_print(&operations),
+			Err(_) => String::from_utf8_lossy(&self.buf).into_owned(),
+		};
+	}
+
+	/// Appends the operator with its operands, each followed by a space, and
+	/// the operator by a newline.
+	pub(crate) fn op(&mut self, operands: &[Operand<'_>], operator: &str) -> &mut Self {
+		for operand in operands {
+			self.operand(operand);
+			self.buf.push(b' ');
+		}
+		self.buf.extend_from_slice(operator.as_bytes());
+		self.buf.push(b'\n');
+		return self;
+	}
+
+	/// Appends the raw operators, e.g. from a default appearance string. The
+	/// operators are terminated with a newline.
+	pub fn raw(&mut self, operators: &[u8]) -> &mut Self {
+		self.buf.extend_from_slice(operators);
+		self.buf.push(b'\n');
+		return self;
+	}
+
+	/// Begins a text object (`BT`).
+	pub fn begin_text(&mut self) -> &mut Self {
+		return self.op(&[], "BT");
+	}
+
+	/// Ends a text object (`ET`).
+	pub fn end_text(&mut self) -> &mut Self {
+		return self.op(&[], "ET");
+	}
+
+	/// Selects the font resource and size (`Tf`).
+	pub fn set_font(&mut self, name: &str, size: f64) -> &mut Self {
+		return self.op(&[Operand::Name(name), Operand::Real(size)], "Tf");
+	}
+
+	/// Moves to the start of the next line, offset by the given amount (`Td`).
+	pub fn move_text(&mut self, x: f64, y: f64) -> &mut Self {
+		return self.op(&[Operand::Real(x), Operand::Real(y)], "Td");
+	}
+
+	/// Sets the text matrix, and the text line matrix along with it, placing
+	/// the next line of text absolutely, e.g. rotated or scaled (`Tm`). The
+	/// matrix is not combined with the previous one.
+	pub fn set_text_matrix(&mut self, matrix: Matrix) -> &mut Self {
+		let operands = [matrix.a, matrix.b, matrix.c, matrix.d, matrix.e, matrix.f].map(Operand::Real);
+		return self.op(&operands, "Tm");
+	}
+
+	/// Shows the given text string (`Tj`).
+	pub fn show_text(&mut self, text: &[u8]) -> &mut Self {
+		return self.op(&[Operand::String(text)], "Tj");
+	}
+
+	/// Shows the text string in the given writing direction, starting at the
+	/// current text position. The text is given in logical order, as the codes
+	/// of the currently selected font, which are `code_length` bytes long, i.e.
+	/// 1 for simple fonts, and 2 for composite fonts with an identity encoding.
+	///
+	/// Vertical text needs the font to be in the vertical writing mode, e.g.
+	/// one embedded with [`Document::embed_vertical_font`], which moves the
+	/// text position down by the vertical displacement of every glyph, so the
+	/// codes are shown as they are.
+	///
+	/// [`Document::embed_vertical_font`]: crate::Document::embed_vertical_font
+	pub fn show_text_directed(&mut self, text: &[u8], mode: WritingMode, code_length: usize) -> &mut Self {
+		match mode {
+			WritingMode::LeftToRight | WritingMode::TopToBottom => {
+				self.show_text(text);
+			},
+			WritingMode::RightToLeft => {
+				// Glyphs are always painted left to right, so the visual order is the
+				// reverse of the logical one, with the bytes of every code kept in
+				// their order.
+				let reversed = text.chunks(code_length.max(1)).rev().flatten().copied().collect::<Vec<_>>();
+				self.show_text(&reversed);
+			},
+		}
+		return self;
+	}
+
+	/// Saves the current graphics state on the stack (`q`), returning the guard
+	/// which restores it when dropped (`Q`).
+	pub fn save_state(&mut self) -> StateGuard<'_> {
+		self.push_state();
+		return StateGuard {
+			content: self,
+		};
+	}
+
+	/// Runs the closure between saving and restoring the graphics state, so
+	/// that its changes don't apply to the operators following it.
+	pub fn with_state(&mut self, f: impl FnOnce(&mut Self)) -> &mut Self {
+		self.push_state();
+		f(self);
+		return self.pop_state();
+	}
+
+	/// Saves the current graphics state on the stack (`q`), which has to be
+	/// restored with [`ContentStream::pop_state`].
+	pub fn push_state(&mut self) -> &mut Self {
+		self.saved_states.push(self.color_spaces);
+		return self.op(&[], "q");
+	}
+
+	/// Restores the most recently saved graphics state (`Q`).
+	pub fn pop_state(&mut self) -> &mut Self {
+		debug_assert!(!self.saved_states.is_empty(), "graphics state restored without being saved");
+		if let Some(color_spaces) = self.saved_states.pop() {
+			self.color_spaces = color_spaces;
+		}
+		return self.op(&[], "Q");
+	}
+
+	/// Sets the parameters from the named graphics state resource (`gs`).
+	pub fn set_graphics_state(&mut self, name: &str) -> &mut Self {
+		return self.op(&[Operand::Name(name)], "gs");
+	}
+
+	/// Paints the named XObject resource (`Do`).
+	pub fn draw_xobject(&mut self, name: &str) -> &mut Self {
+		return self.op(&[Operand::Name(name)], "Do");
+	}
+
+	/// Paints the form XObject, e.g. a logo repeated on every page, with the
+	/// given transformation from the form space to the current user space.
+	/// The transformation only applies to the form, as it's painted with the
+	/// graphics state saved (`q`, `cm`, `Do`, and `Q`).
+	pub fn draw_form(&mut self, form: XObjectRef, matrix: Matrix) -> &mut Self {
+		let name = self.use_xobject(form);
+		return self.with_state(|content| {
+			content.transform(matrix).draw_xobject(&name);
+		});
+	}
+
+	/// Modifies the current transformation matrix, applying the given
+	/// transformation before the current one (`cm`).
+	pub fn transform(&mut self, matrix: Matrix) -> &mut Self {
+		let operands = [matrix.a, matrix.b, matrix.c, matrix.d, matrix.e, matrix.f].map(Operand::Real);
+		return self.op(&operands, "cm");
+	}
+
+	/// Begins content which is only visible when the layer, or the layers of
+	/// the membership dictionary, are visible (`BDC`).
+	pub fn begin_layer(&mut self, group: impl Into<ObjectId>) -> &mut Self {
+		let name = self.resources.properties(group.into());
+		return self.op(&[Operand::Name("OC"), Operand::Name(&name)], "BDC");
+	}
+
+	/// Ends the content started by [`ContentStream::begin_layer`] (`EMC`).
+	pub fn end_layer(&mut self) -> &mut Self {
+		return self.op(&[], "EMC");
+	}
+
+	/// Appends the marked content operator with the tag, and the optional
+	/// property list.
+	fn tag(&mut self, tag: &str, properties: Option<PropertyList>, operators: [&str; 2]) -> &mut Self {
+		return match properties {
+			Some(PropertyList::Inline(dict)) => self.op(&[Operand::Name(tag), Operand::Dictionary(&dict)], operators[1]),
+			Some(PropertyList::Resource(id)) => {
+				let name = self.resources.properties(id);
+				self.op(&[Operand::Name(tag), Operand::Name(&name)], operators[1])
+			},
+			None => self.op(&[Operand::Name(tag)], operators[0]),
+		};
+	}
+
+	/// Begins marked content with the given tag, e.g. `Span`, and the optional
+	/// property list (`BMC` or `BDC`). Marked content can be nested, and every
+	/// sequence has to be ended with [`ContentStream::end_marked_content`].
+	pub fn begin_marked_content(&mut self, tag: &str, properties: Option<PropertyList>) -> &mut Self {
+		return self.tag(tag, properties, ["BMC", "BDC"]);
+	}
+
+	/// Ends the most recently begun marked content (`EMC`).
+	pub fn end_marked_content(&mut self) -> &mut Self {
+		return self.op(&[], "EMC");
+	}
+
+	/// Marks a single point in the content stream with the given tag, and the
+	/// optional property list (`MP` or `DP`).
+	pub fn mark_point(&mut self, tag: &str, properties: Option<PropertyList>) -> &mut Self {
+		return self.tag(tag, properties, ["MP", "DP"]);
+	}
+
+	/// Sets the colour used for filling (`g`, `rg`, or `k`).
+	pub fn set_fill_color(&mut self, color: Color) -> &mut Self {
+		self.color_spaces.fill = ColorOperands::Components(color.components().len());
+		let operands: Vec<Operand<'_>> = color.components().into_iter().map(Operand::Real).collect();
+		return self.op(&operands, match color {
+			Color::Gray(..) => "g",
+			Color::Rgb(..) => "rg",
+			Color::Cmyk(..) => "k",
+		});
+	}
+
+	/// Sets the colour used for stroking (`G`, `RG`, or `K`).
+	pub fn set_stroke_color(&mut self, color: Color) -> &mut Self {
+		self.color_spaces.stroke = ColorOperands::Components(color.components().len());
+		let operands: Vec<Operand<'_>> = color.components().into_iter().map(Operand::Real).collect();
+		return self.op(&operands, match color {
+			Color::Gray(..) => "G",
+			Color::Rgb(..) => "RG",
+			Color::Cmyk(..) => "K",
+		});
+	}
+
+	/// Sets the colour space used for filling (`cs`), given either by its
+	/// name, e.g. `DeviceRGB` or `Pattern`, or by the name of a colour space
+	/// resource. The colour is reset to the initial one of the colour space.
+	pub fn set_fill_color_space(&mut self, name: &str) -> &mut Self {
+		self.color_spaces.fill = ColorOperands::of(name);
+		return self.op(&[Operand::Name(name)], "cs");
+	}
+
+	/// Sets the colour space used for stroking (`CS`).
+	pub fn set_stroke_color_space(&mut self, name: &str) -> &mut Self {
+		self.color_spaces.stroke = ColorOperands::of(name);
+		return self.op(&[Operand::Name(name)], "CS");
+	}
+
+	/// Checks that the operands match the colour space, and appends the colour
+	/// operator.
+	fn color(&mut self, stroke: bool, components: &[f64], pattern: Option<&str>, operator: &str) -> Result<&mut Self, PdfError> {
+		let expected = if stroke { self.color_spaces.stroke } else { self.color_spaces.fill };
+		let valid = match (expected, pattern) {
+			(ColorOperands::Components(count), None) => components.len() == count,
+			(ColorOperands::Pattern, Some(_)) => true,
+			(ColorOperands::Unknown, _) => true,
+			_ => false,
+		};
+		if !valid {
+			let expected = match expected {
+				ColorOperands::Components(count) => std::format!("{} colour components", count),
+				_ => "a pattern".to_string(),
+			};
+			return Err(PdfError::InvalidArgument(std::format!("{} operator expects {} in the current colour space", operator, expected)));
+		}
+
+		let mut operands: Vec<Operand<'_>> = components.iter().copied().map(Operand::Real).collect();
+		operands.extend(pattern.map(Operand::Name));
+		return Ok(self.op(&operands, operator));
+	}
+
+	/// Attempts to set the colour used for filling in the current colour space
+	/// (`sc`). The number of components has to match the colour space.
+	pub fn set_fill_components(&mut self, components: &[f64]) -> Result<&mut Self, PdfError> {
+		return self.color(false, components, None, "sc");
+	}
+
+	/// Attempts to set the colour used for stroking in the current colour space
+	/// (`SC`).
+	pub fn set_stroke_components(&mut self, components: &[f64]) -> Result<&mut Self, PdfError> {
+		return self.color(true, components, None, "SC");
+	}
+
+	/// Attempts to set the named pattern resource used for filling (`scn`),
+	/// when the colour space is `/Pattern`. Uncoloured patterns are given the
+	/// components of the colour to paint them with.
+	pub fn set_fill_pattern(&mut self, pattern: &str, components: &[f64]) -> Result<&mut Self, PdfError> {
+		return self.color(false, components, Some(pattern), "scn");
+	}
+
+	/// Attempts to set the named pattern resource used for stroking (`SCN`).
+	pub fn set_stroke_pattern(&mut self, pattern: &str, components: &[f64]) -> Result<&mut Self, PdfError> {
+		return self.color(true, components, Some(pattern), "SCN");
+	}
+
+	/// Sets the line width used for stroking (`w`).
+	pub fn set_line_width(&mut self, width: f64) -> &mut Self {
+		return self.op(&[Operand::Real(width)], "w");
+	}
+
+	/// Sets the shape of the ends of stroked open subpaths (`J`): 0 for butt, 1
+	/// for round, and 2 for projecting square caps.
+	pub fn set_line_cap(&mut self, style: u8) -> &mut Self {
+		return self.op(&[Operand::Int(style as i64)], "J");
+	}
+
+	/// Sets the shape of the corners of stroked paths (`j`): 0 for miter, 1 for
+	/// round, and 2 for bevel joins.
+	pub fn set_line_join(&mut self, style: u8) -> &mut Self {
+		return self.op(&[Operand::Int(style as i64)], "j");
+	}
+
+	/// Sets the dash pattern used for stroking (`d`): the lengths of the
+	/// alternating dashes and gaps, and the distance into the pattern at which
+	/// the dash starts. An empty array gives solid lines.
+	pub fn set_dash_pattern(&mut self, lengths: &[f64], phase: f64) -> &mut Self {
+		let lengths: Vec<Operand<'_>> = lengths.iter().copied().map(Operand::Real).collect();
+		return self.op(&[Operand::Array(&lengths), Operand::Real(phase)], "d");
+	}
+
+	/// Begins a new subpath at the given point (`m`).
+	pub fn move_to(&mut self, point: Point) -> &mut Self {
+		return self.op(&[Operand::Real(point.x), Operand::Real(point.y)], "m");
+	}
+
+	/// Appends a straight line segment to the current subpath (`l`).
+	pub fn line_to(&mut self, point: Point) -> &mut Self {
+		return self.op(&[Operand::Real(point.x), Operand::Real(point.y)], "l");
+	}
+
+	/// Appends a cubic Bézier curve to the current subpath (`c`).
+	pub fn curve_to(&mut self, c1: Point, c2: Point, end: Point) -> &mut Self {
+		let operands = [c1.x, c1.y, c2.x, c2.y, end.x, end.y].map(Operand::Real);
+		return self.op(&operands, "c");
+	}
+
+	/// Appends a rectangle as a complete subpath (`re`).
+	pub fn rect(&mut self, rect: Rectangle) -> &mut Self {
+		return self.op(&[rect.llx, rect.lly, rect.width(), rect.height()].map(Operand::Real), "re");
+	}
+
+	/// Closes the current subpath (`h`).
+	pub fn close_path(&mut self) -> &mut Self {
+		return self.op(&[], "h");
+	}
+
+	/// Fills the path using the non-zero winding number rule (`f`).
+	pub fn fill(&mut self) -> &mut Self {
+		return self.op(&[], "f");
+	}
+
+	/// Strokes the path (`S`).
+	pub fn stroke(&mut self) -> &mut Self {
+		return self.op(&[], "S");
+	}
+
+	/// Fills the path using the non-zero winding number rule, and then
+	/// strokes it (`B`).
+	pub fn fill_stroke(&mut self) -> &mut Self {
+		return self.op(&[], "B");
+	}
+}
+
+/// Single operator of a content stream, together with its operands.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Operation {
+	pub operator: Vec<u8>,
+	pub operands: Vec<PdfObject>,
+}
+
+impl Operation {
+	#[inline]
+	pub fn new(operator: &str, operands: Vec<PdfObject>) -> Self {
+		return Self {
+			operator: operator.as_bytes().to_vec(),
+			operands,
+		};
+	}
+
+	/// Returns the operand as a number, or zero if it is missing.
+	pub(crate) fn number(&self, index: usize) -> f64 {
+		return self.operands.get(index).and_then(PdfObject::as_number).unwrap_or(0.0);
+	}
+}
+
+/// Attempts to parse the content stream into a sequence of operations.
+///
+/// Inline images are returned as a single `BI` operation, whose operands are
+/// the image dictionary and the image data as a string.
+pub fn parse_operations(data: &[u8]) -> Result<Vec<Operation>, PdfError> {
+	let mut parser = Parser::content(data);
+	let mut operations = Vec::new();
+	let mut operands = Vec::new();
+
+	loop {
+		let pos = parser.pos();
+		let Some(token) = parser.lexer.next()? else {
+			break;
+		};
+
+		let operator = match token {
+			Token::Keyword(b"true" | b"false" | b"null") => None,
+			Token::Keyword(keyword) => Some(keyword),
+			_ => None,
+		};
+
+		let Some(operator) = operator else {
+			operands.push(parser.parse_from(token, pos)?);
+			continue;
+		};
+
+		if operator == b"BI" {
+			operations.push(inline_image(&mut parser)?);
+			operands.clear();
+			continue;
+		}
+
+		operations.push(Operation {
+			operator: operator.to_vec(),
+			operands: std::mem::take(&mut operands),
+		});
+	}
+
+	return Ok(operations);
+}
+
+fn inline_image(parser: &mut Parser<'_>) -> Result<Operation, PdfError> {
+	let mut dict = Dictionary::new();
+	loop {
+		let pos = parser.pos();
+		match parser.lexer.next()? {
+			Some(Token::Keyword(b"ID")) => break,
+			Some(Token::Name(key)) => {
+				let mut value = parser.parse_object()?;
+				if matches!(&key[..], b"F" | b"Filter") {
+					value = filters::expand_filter_names(&value);
+				}
+				dict.set_raw(&key, value);
+			},
+			_ => return Err(PdfError::syntax(pos, "malformed inline image dictionary")),
+		}
+	}
+
+	// Image data starts after a single whitespace character following `ID`, and
+	// ends with the `EI` keyword, which needs to be surrounded by whitespace.
+	let data = parser.lexer.data();
+	let start = parser.pos() + 1;
+	let mut end = start;
+	loop {
+		if end + 2 > data.len() {
+			return Err(PdfError::syntax(start, "unterminated inline image"));
+		}
+
+		if &data[end..end + 2] == b"EI"
+			&& end > start && lexer::is_whitespace(data[end - 1])
+			&& data.get(end + 2).is_none_or(|&b| lexer::is_whitespace(b)) {
+			break;
+		}
+
+		end += 1;
+	}
+
+	parser.lexer.set_pos(end + 2);
+	return Ok(Operation::new("BI", vec![PdfObject::Dictionary(dict), PdfObject::string(&data[start..end - 1])]));
+}
+
+/// Writes the operations back into the content stream syntax, one operation
+/// per line.
+pub fn write_operations(operations: &[Operation]) -> Vec<u8> {
+	let mut buf = Vec::new();
+	let mut writer = Writer::new(&mut buf);
+	for operation in operations {
+		// Writing into a vector never fails.
+		let _ = write_operation(&mut writer, operation);
+	}
+	return buf;
+}
+
+/// Formats the operations for reading, one per line, with the contents of the
+/// graphics state (`q`/`Q`) and text object (`BT`/`ET`) blocks indented, and
+/// the data of inline images left out.
+pub(crate) fn pretty_print(operations: &[Operation]) -> String {
+	let mut output = String::new();
+	let mut depth = 0usize;
+	for operation in operations {
+		if matches!(operation.operator.as_slice(), b"Q" | b"ET") {
+			depth = depth.saturating_sub(1);
+		}
+		let line = match operation.operator.as_slice() {
+			b"BI" => {
+				let mut dict = Vec::new();
+				if let Some(PdfObject::Dictionary(params)) = operation.operands.first() {
+					// Writing into a vector never fails.
+					let _ = PdfObject::Dictionary(params.clone()).write_inline(&mut Writer::new(&mut dict));
+				}
+				let length = operation.operands.get(1).and_then(PdfObject::as_string).map_or(0, <[u8]>::len);
+				std::format!("BI {} ID <{} bytes> EI", String::from_utf8_lossy(&dict), length)
+			},
+			_ => String::from_utf8_lossy(&write_operations(std::slice::from_ref(operation))).trim_end().to_string(),
+		};
+		output.push_str(&"  ".repeat(depth));
+		output.push_str(&line);
+		output.push('\n');
+		if matches!(operation.operator.as_slice(), b"q" | b"BT") {
+			depth += 1;
+		}
+	}
+	return output;
+}
+
+fn write_operation(writer: &mut Writer<'_>, operation: &Operation) -> std::io::Result<()> {
+	if operation.operator == b"BI" {
+		writer.write(b"BI")?;
+		if let Some(PdfObject::Dictionary(dict)) = operation.operands.first() {
+			for (key, value) in dict.iter() {
+				writer.write(b" ")?;
+				write_name(writer, key)?;
+				writer.write(b" ")?;
+				// Inline images have to use the abbreviated filter names.
+				if matches!(key, b"F" | b"Filter") {
+					filters::abbreviate_filter_names(value).write_inline(writer)?;
+				} else {
+					value.write_inline(writer)?;
+				}
+			}
+		}
+		writer.write(b" ID ")?;
+		if let Some(data) = operation.operands.get(1).and_then(PdfObject::as_string) {
+			writer.write(data)?;
+		}
+		writer.write(b"\nEI\n")?;
+		return Ok(());
+	}
+
+	for operand in &operation.operands {
+		operand.write_inline(writer)?;
+		writer.write(b" ")?;
+	}
+	writer.write(&operation.operator)?;
+	writer.write(b"\n")?;
+	return Ok(());
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::content::*;
+	use crate::*;
+
+	#[test]
+	fn graphics_state_guards() {
+		let mut content = ContentStream::new();
+		{
+			let mut state = content.save_state();
+			state.set_line_width(2.0).with_state(|content| {
+				content.set_fill_color(Color::Gray(0.5)).with_state(|content| {
+					content.rect(Rectangle::new(0.0, 0.0, 1.0, 1.0)).fill();
+				});
+			});
+		}
+		content.push_state().pop_state();
+		let data = content.finish();
+		let text = String::from_utf8(data).unwrap();
+		assert!(text.starts_with("q\n2 w\nq\n0.5 g\nq\n"));
+		assert!(text.ends_with("f\nQ\nQ\nQ\nq\nQ\n"));
+		assert_eq!(text.matches('q').count(), text.matches('Q').count());
+	}
+
+	#[test]
+	#[cfg(debug_assertions)]
+	#[should_panic(expected = "graphics state saved without being restored")]
+	fn unbalanced_graphics_state() {
+		let mut content = ContentStream::new();
+		content.push_state().set_line_width(2.0);
+		content.finish();
+	}
+
+	#[test]
+	fn marked_content() {
+		let mut properties = Dictionary::new();
+		properties.set("MCID", 0);
+		let mut content = ContentStream::new();
+		content.begin_marked_content("OC", Some(PropertyList::Inline(properties)));
+		content.begin_marked_content("Span", None).mark_point("Break", None).end_marked_content();
+		content.mark_point("Note", Some(PropertyList::Resource(ObjectId::new(7, 0)))).end_marked_content();
+		assert_eq!(content.as_bytes(), b"/OC << /MCID 0 >> BDC\n/Span BMC\n/Break MP\nEMC\n/Note /OC0 DP\nEMC\n");
+		assert_eq!(content.resources().to_dict().get("Properties").and_then(PdfObject::as_dict).and_then(|dict| dict.get("OC0")), Some(&PdfObject::Reference(ObjectId::new(7, 0))));
+
+		let operations = parse_operations(content.as_bytes()).unwrap();
+		assert_eq!(operations.iter().map(|operation| operation.operator.as_slice()).collect::<Vec<_>>(), [&b"BDC"[..], b"BMC", b"MP", b"EMC", b"DP", b"EMC"]);
+	}
+
+	#[test]
+	fn writing_modes() {
+		let mut content = ContentStream::new();
+		content.show_text_directed(b"abc", WritingMode::RightToLeft, 1);
+		assert_eq!(content.as_bytes(), b"(cba) Tj\n");
+
+		// Codes of composite fonts are reversed as a whole.
+		let mut content = ContentStream::new();
+		content.show_text_directed(&[0x01, 0x02, 0x03, 0x04, 0x05, 0x06], WritingMode::RightToLeft, 2);
+		let operations = parse_operations(content.as_bytes()).unwrap();
+		assert_eq!(operations[0].operands[0].as_string(), Some(&[0x05, 0x06, 0x03, 0x04, 0x01, 0x02][..]));
+
+		let mut content = ContentStream::new();
+		content.show_text_directed(&[0x00, 0x01, 0x00, 0x02], WritingMode::TopToBottom, 2);
+		assert_eq!(content.as_bytes(), b"(\x00\x01\x00\x02) Tj\n");
+	}
+
+	#[test]
+	fn operations_round_trip() {
+		let data = b"q 1 0 0 1 10 20 cm\nBT /F13 10 Tf [(He) -20 (llo!)] TJ ET\nBI /W 2 /H 1 /BPC 8 /CS /G ID \x00\xff\nEI Q\n";
+		let operations = parse_operations(data).unwrap();
+		let operators = operations.iter().map(|op| std::str::from_utf8(&op.operator).unwrap()).collect::<Vec<_>>();
+		assert_eq!(operators, ["q", "cm", "BT", "Tf", "TJ", "ET", "BI", "Q"]);
+		assert_eq!(operations[6].operands[1], PdfObject::string(vec![0x00, 0xff]));
+
+		let written = write_operations(&operations);
+		assert_eq!(parse_operations(&written).unwrap(), operations);
+	}
+
+	#[test]
+	fn text_object() {
+		let mut content = ContentStream::new();
+		content.begin_text().set_font("F13", 10.0).move_text(12.0, 775.0).show_text(b"Hello!").end_text();
+		assert_eq!(content.as_bytes(), b"BT\n/F13 10 Tf\n12 775 Td\n(Hello!) Tj\nET\n");
+	}
+
+	#[test]
+	fn color_operands() {
+		let mut content = ContentStream::new();
+		assert!(content.set_fill_components(&[0.5]).is_ok());
+		content.set_fill_color_space("DeviceRGB");
+		assert!(content.set_fill_components(&[0.5]).is_err());
+		content.set_fill_components(&[1.0, 0.0, 0.0]).unwrap();
+		assert!(content.set_stroke_components(&[1.0, 0.0, 0.0]).is_err());
+
+		content.with_state(|content| {
+			content.set_fill_color_space("Pattern");
+			assert!(content.set_fill_components(&[1.0, 0.0, 0.0]).is_err());
+			content.set_fill_pattern("P0", &[]).unwrap();
+			content.set_fill_color(Color::Cmyk(0.0, 0.0, 0.0, 1.0));
+			assert!(content.set_fill_pattern("P0", &[]).is_err());
+		});
+		content.set_fill_components(&[0.0, 0.0, 1.0]).unwrap();
+		content.set_stroke_color_space("CS0").set_stroke_components(&[0.1, 0.2]).unwrap();
+		assert_eq!(content.as_bytes(), b"0.5 sc\n/DeviceRGB cs\n1 0 0 sc\nq\n/Pattern cs\n/P0 scn\n0 0 0 1 k\nQ\n0 0 1 sc\n/CS0 CS\n0.1 0.2 SC\n");
+	}
+
+	#[test]
+	fn pretty_printing() {
+		let mut content = ContentStream::new();
+		content.with_state(|content| {
+			content.set_fill_color(Color::Gray(0.0));
+			content.begin_text().set_font("F13", 10.0).move_text(12.0, 775.0).show_text(b"Hello!").end_text();
+		});
+		content.raw(b"BI /W 2 /H 1 /BPC 8 /CS /G ID \x00\xff\nEI\n");
+		assert_eq!(content.to_pretty_string(), "q\n  0 g\n  BT\n    /F13 10 Tf\n    12 775 Td\n    (Hello!) Tj\n  ET\nQ\nBI << /W 2 /H 1 /BPC 8 /CS /G >> ID <2 bytes> EI\n");
+	}
+
+	#[test]
+	fn operands() {
+		let mut content = ContentStream::new();
+		content.op(&[Operand::Real(0.1), Operand::Int(2)], "l");
+		content.op(&[Operand::Name("F1"), Operand::String(b"a(b)\\\r"), Operand::Array(&[Operand::Int(1), Operand::Real(-0.5)])], "x");
+		content.set_dash_pattern(&[3.0, 1.5], 0.0).set_dash_pattern(&[], 0.0);
+		assert_eq!(content.as_bytes(), b"0.1 2 l\n/F1 (a\\(b\\)\\\\\\r) [1 -0.5] x\n[3 1.5] 0 d\n[] 0 d\n");
+
+		// Glyph 13 of a font with two-byte codes, and a carriage return.
+		let mut content = ContentStream::new();
+		content.show_text(&[0x00, 0x0d, b'a', b'\r', b'b']);
+		let operations = parse_operations(content.as_bytes()).unwrap();
+		assert_eq!(operations[0].operands[0].as_string(), Some(&[0x00, 0x0d, b'a', b'\r', b'b'][..]));
+	}
+
+	#[test]
+	fn inline_image_filters() {
+		let operations = parse_operations(b"BI /W 2 /H 1 /BPC 8 /CS /G /F [/AHx /Fl] ID 789c63f80f0001010100>\nEI\n").unwrap();
+		let [image] = operations.as_slice() else {
+			panic!("expected a single inline image");
+		};
+		let dict = image.operands[0].as_dict().unwrap();
+		assert_eq!(dict.get("F"), Some(&PdfObject::Array(std::vec![PdfObject::name("ASCIIHexDecode"), PdfObject::name("FlateDecode")])));
+		assert_eq!(filters::decode_inline_image(dict, image.operands[1].as_string().unwrap()).unwrap(), [0x00, 0xff]);
+
+		let mut dict = Dictionary::new();
+		dict.set("W", 2);
+		dict.set("H", 1);
+		dict.set("F", PdfObject::name("FlateDecode"));
+		let data = write_operations(&[Operation::new("BI", std::vec![dict.into(), PdfObject::string(filters::flate::encode(&[0x00, 0xff]))])]);
+		assert!(data.starts_with(b"BI /W 2 /H 1 /F /Fl ID "));
+		let image = &parse_operations(&data).unwrap()[0];
+		assert_eq!(filters::decode_inline_image(image.operands[0].as_dict().unwrap(), image.operands[1].as_string().unwrap()).unwrap(), [0x00, 0xff]);
+	}
+}
@@ -0,0 +1,123 @@
+//! Dates, written as strings of the form `D:YYYYMMDDHHmmSSOHH'mm'`.
+
+use crate::{PdfError, PdfObject};
+
+/// Date and time, with an optional offset from the universal time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PdfDate {
+	pub year: u16,
+	pub month: u8,
+	pub day: u8,
+	pub hour: u8,
+	pub minute: u8,
+	pub second: u8,
+	/// Offset of the local time from the universal time, in minutes, or `None`
+	/// if the relationship is unknown.
+	pub utc_offset: Option<i16>,
+}
+
+fn invalid(date: &str) -> PdfError {
+	return PdfError::InvalidArgument(std::format!("invalid date {:?}", date));
+}
+
+impl PdfDate {
+	/// Attempts to create new date from its parts, checking their ranges.
+	pub fn from_parts(year: u16, month: u8, day: u8, hour: u8, minute: u8, second: u8, utc_offset: Option<i16>) -> Result<Self, PdfError> {
+		let date = Self {
+			year,
+			month,
+			day,
+			hour,
+			minute,
+			second,
+			utc_offset,
+		};
+		let valid = year <= 9999 && (1..=12).contains(&month) && (1..=31).contains(&day) && hour <= 23 && minute <= 59 && second <= 59 && utc_offset.is_none_or(|offset| offset.abs() < 24 * 60);
+		if !valid {
+			return Err(PdfError::InvalidArgument(std::format!("invalid date {:?}", date)));
+		}
+		return Ok(date);
+	}
+
+	/// Attempts to parse the date string. Any of the parts after the year may
+	/// be left out, together with all the following ones, and so may the
+	/// apostrophes of the offset from the universal time.
+	pub fn parse(date: &str) -> Result<Self, PdfError> {
+		let text = date.strip_prefix("D:").unwrap_or(date);
+		let bytes = text.as_bytes();
+		let digits = bytes.iter().take_while(|byte| byte.is_ascii_digit()).count();
+		if !(4..=14).contains(&digits) || !digits.is_multiple_of(2) {
+			return Err(invalid(date));
+		}
+
+		let number = |start: usize, default: u8| -> u8 {
+			if start + 2 > digits {
+				return default;
+			}
+			return text[start..start + 2].parse().unwrap_or(default);
+		};
+		let year = text[..4].parse().map_err(|_| invalid(date))?;
+
+		let utc_offset = match &bytes[digits..] {
+			[] => None,
+			[b'Z', rest @ ..] if rest.iter().all(|&byte| byte == b'0' || byte == b'\'') => Some(0),
+			[sign @ (b'+' | b'-'), rest @ ..] => {
+				let rest = std::str::from_utf8(rest).map_err(|_| invalid(date))?;
+				let rest = rest.strip_suffix('\'').unwrap_or(rest);
+				let (hours, minutes) = rest.split_once('\'').unwrap_or((rest, "0"));
+				let (Ok(hours), Ok(minutes)) = (hours.parse::<i16>(), minutes.parse::<i16>()) else {
+					return Err(invalid(date));
+				};
+				if !(0..=23).contains(&hours) || !(0..=59).contains(&minutes) {
+					return Err(invalid(date));
+				}
+				let offset = hours * 60 + minutes;
+				Some(if *sign == b'-' { -offset } else { offset })
+			},
+			_ => return Err(invalid(date)),
+		};
+
+		return Self::from_parts(year, number(4, 1), number(6, 1), number(8, 0), number(10, 0), number(12, 0), utc_offset).map_err(|_| invalid(date));
+	}
+
+	/// Formats the date in the canonical form, with all the parts.
+	pub fn to_pdf_string(&self) -> String {
+		let mut text = std::format!("D:{:04}{:02}{:02}{:02}{:02}{:02}", self.year, self.month, self.day, self.hour, self.minute, self.second);
+		match self.utc_offset {
+			Some(0) => text.push('Z'),
+			Some(offset) => {
+				let sign = if offset < 0 { '-' } else { '+' };
+				text.push_str(&std::format!("{}{:02}'{:02}'", sign, offset.abs() / 60, offset.abs() % 60));
+			},
+			None => {},
+		}
+		return text;
+	}
+}
+
+impl From<PdfDate> for PdfObject {
+	#[inline]
+	fn from(date: PdfDate) -> Self {
+		return Self::string(date.to_pdf_string());
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::*;
+
+	#[test]
+	fn parse_and_format() {
+		let date = PdfDate::parse("D:20200615120000+02'00'").unwrap();
+		assert_eq!(date, PdfDate::from_parts(2020, 6, 15, 12, 0, 0, Some(120)).unwrap());
+		assert_eq!(date.to_pdf_string(), "D:20200615120000+02'00'");
+		assert_eq!(PdfDate::parse("D:20200615120000+02'00").unwrap(), date);
+
+		assert_eq!(PdfDate::parse("D:1999").unwrap().to_pdf_string(), "D:19990101000000");
+		assert_eq!(PdfDate::parse("D:20011224Z").unwrap().to_pdf_string(), "D:20011224000000Z");
+		assert_eq!(PdfDate::parse("19981223195200-08'30'").unwrap().utc_offset, Some(-510));
+		for invalid in ["D:", "D:199", "D:19991301", "D:2000+1x", "D:2000010100000000"] {
+			assert!(matches!(PdfDate::parse(invalid), Err(PdfError::InvalidArgument(_))), "{}", invalid);
+		}
+	}
+}
@@ -0,0 +1,89 @@
+//! Named destinations, which links and outlines can refer to by name.
+//!
+//! Since PDF 1.2 they're kept in the `/Dests` name tree of the `/Names`
+//! dictionary of the catalog, while PDF 1.1 used the `/Dests` dictionary of
+//! the catalog, which some readers still only support.
+
+use crate::names::with_entry_dict;
+use crate::{Document, PdfError, PdfObject, Reader};
+
+impl Document {
+	/// Attempts to add the named destination, showing the whole page with the
+	/// given zero-based index. The destination is added to the name tree,
+	/// unless `legacy` is set, in which case it's added to the PDF 1.1
+	/// `/Dests` dictionary of the catalog instead, for compatibility with old
+	/// readers.
+	pub fn add_named_destination(&mut self, name: &str, index: usize, legacy: bool) -> Result<(), PdfError> {
+		let count = self.pages.len();
+		let Some(&page) = self.pages.get(index) else {
+			return Err(PdfError::PageOutOfRange { index, count });
+		};
+		let destination = PdfObject::Array(std::vec![page.into(), PdfObject::name("Fit")]);
+
+		if legacy {
+			with_entry_dict(&mut self.objects, &mut self.catalog, "Dests", |_, dests| dests.set(name, destination));
+			return Ok(());
+		}
+
+		return self.insert_into_name_tree("Dests", name.as_bytes(), destination);
+	}
+}
+
+impl Reader<'_> {
+	/// Attempts to find the named destination, looking in the name tree
+	/// first, and in the PDF 1.1 `/Dests` dictionary of the catalog if it's
+	/// not there. Returns the destination array, e.g. `[3 0 R /Fit]`.
+	pub fn named_destination(&self, name: &[u8]) -> Result<Option<Vec<PdfObject>>, PdfError> {
+		let mut destination = self.name_tree_value("Dests", name)?;
+		if destination.is_none() {
+			if let Some(dests) = self.catalog()?.get("Dests") {
+				destination = self.resolve_object(dests)?.as_dict().and_then(|dests| dests.get_raw(name)).cloned();
+			}
+		}
+		let Some(destination) = destination else {
+			return Ok(None);
+		};
+
+		// Destinations can also be dictionaries, with the array under `/D`.
+		return Ok(match self.resolve_object(&destination)? {
+			PdfObject::Array(destination) => Some(destination),
+			PdfObject::Dictionary(dict) => match dict.get("D") {
+				Some(destination) => self.resolve_object(destination)?.as_array().cloned(),
+				None => None,
+			},
+			_ => None,
+		});
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::*;
+
+	#[test]
+	fn legacy_destinations() {
+		let mut doc = Document::new();
+		doc.add_page(612.0, 792.0);
+		let page = doc.add_page(612.0, 792.0).id();
+		doc.add_named_destination("tree", 1, false).unwrap();
+		doc.add_named_destination("legacy", 1, true).unwrap();
+		doc.add_named_destination("first", 0, false).unwrap();
+		doc.add_named_destination("first", 1, false).unwrap();
+		assert!(matches!(doc.add_named_destination("missing", 2, true), Err(PdfError::PageOutOfRange { index: 2, count: 2 })));
+
+		let mut output = Vec::new();
+		doc.write(&mut Writer::new(&mut output)).unwrap();
+		let reader = Reader::new(&output).unwrap();
+		let tree = reader.named_destination(b"tree").unwrap();
+		assert_eq!(tree, Some(std::vec![PdfObject::Reference(page), PdfObject::name("Fit")]));
+		assert_eq!(reader.named_destination(b"legacy").unwrap(), tree);
+		assert_eq!(reader.named_destination(b"first").unwrap(), tree);
+		assert_eq!(reader.named_destination(b"missing").unwrap(), None);
+
+		let catalog = reader.catalog().unwrap();
+		assert!(catalog.get("Dests").and_then(PdfObject::as_dict).is_some_and(|dests| dests.contains_key("legacy") && !dests.contains_key("tree")));
+		let names = catalog.get("Names").and_then(PdfObject::as_dict).and_then(|names| names.get("Dests")).and_then(PdfObject::as_dict).unwrap();
+		let keys: Vec<_> = names.get("Names").and_then(PdfObject::as_array).unwrap().iter().step_by(2).filter_map(PdfObject::as_string).collect();
+		assert_eq!(keys, [&b"first"[..], b"tree"]);
+	}
+}
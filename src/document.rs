@@ -0,0 +1,1471 @@
+//! High-level document builder.
+
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{DefaultHasher, Hasher};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::content::{parse_operations, Operation};
+use crate::incremental::object_hash;
+use crate::reader::{parse_version, ParseMode, Reader, INHERITABLE};
+use crate::filters::FilterRegistry;
+use crate::text;
+use crate::{ContentStream, CsRef, Dictionary, NameTree, ObjectId, PdfAVersion, PdfError, PdfObject, PositionedText, Rectangle, ResourceManager, Stream, WriteStyle, Writer};
+
+/// Reference to a font object added to the document. The font is a single
+/// indirect object, which can be used by any number of pages.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct FontRef(pub ObjectId);
+
+/// Largest width or height of a page, in units of its user space.
+const MAX_PAGE_UNITS: f64 = 14400.0;
+/// Largest width or height of a page, in default user space units, i.e. the
+/// 15 million inches supported by common viewers.
+const MAX_PAGE_SIZE: f64 = 15_000_000.0 * 72.0;
+
+/// Which page content streams are compressed when the document is written.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CompressionPolicy {
+	/// Content streams are written as they are.
+	#[default]
+	None,
+	/// Content streams of at least the given number of bytes are compressed
+	/// with `/FlateDecode`, while the smaller ones, for which the compression
+	/// would barely help, or even make them larger, are left as they are.
+	Threshold(usize),
+}
+
+pub struct Document {
+	/// All indirect objects of the document, apart from the catalog and the page
+	/// tree root, which are generated when the document is written.
+	pub(crate) objects: BTreeMap<ObjectId, PdfObject>,
+	pub(crate) next_number: u32,
+	pub(crate) catalog_id: ObjectId,
+	pub(crate) pages_id: ObjectId,
+	/// Additional entries of the document catalog.
+	pub(crate) catalog: Dictionary,
+	/// Document information dictionary, referred to by the trailer.
+	pub(crate) info: Option<ObjectId>,
+	/// Page objects, in the order they appear in the document.
+	pub(crate) pages: Vec<ObjectId>,
+	/// Resources used by the page content streams, which are added to the page
+	/// resources when the document is written.
+	pub(crate) resources: BTreeMap<ObjectId, ResourceManager>,
+	/// Version written in the header of the file.
+	pub(crate) version: (u8, u8),
+	/// Part of the PDF/A standard the document conforms to.
+	pub(crate) pdfa: Option<PdfAVersion>,
+	/// File identifier, i.e. the permanent one, which is kept across updates,
+	/// and the one of the last written revision.
+	pub(crate) file_id: Option<[Vec<u8>; 2]>,
+	/// Whether the identifiers are generated only from the objects, so that
+	/// the same document is always written the same.
+	pub(crate) deterministic_id: bool,
+	/// Colour spaces added with [`Document::add_icc_color_space`], by the hash
+	/// of their profile.
+	pub(crate) icc_color_spaces: HashMap<u64, CsRef>,
+	/// Maximum number of kids of a page tree node, if the pages are grouped
+	/// under intermediate nodes rather than being kids of the root.
+	pub(crate) page_tree_branching: Option<usize>,
+	/// Intermediate page tree nodes, created when the document is written.
+	pub(crate) page_tree_nodes: Vec<ObjectId>,
+	pub(crate) compression: CompressionPolicy,
+	/// Layout of the whitespace in the written objects.
+	pub(crate) write_style: WriteStyle,
+	/// Bytes of the comment after the header, marking the file as binary.
+	pub(crate) binary_marker: Option<[u8; 4]>,
+	/// Structure elements begun but not ended yet, innermost last.
+	pub(crate) struct_elements: Vec<ObjectId>,
+	/// Dimensions of the pages added with [`Document::add_default_page`],
+	/// which inherit their media box from the page tree root.
+	pub(crate) default_page_size: Option<(f64, f64)>,
+	/// Hashes of the objects as they were loaded, to find the ones changed
+	/// since, which an incremental update has to write.
+	pub(crate) loaded: BTreeMap<ObjectId, u64>,
+	/// Name trees of the `/Names` dictionary, by their entry, whose nodes
+	/// are created when the document is written.
+	pub(crate) name_trees: BTreeMap<String, NameTree>,
+	pub(crate) name_tree_nodes: Vec<ObjectId>,
+	/// Whether the objects are written in object streams.
+	pub(crate) object_streams: bool,
+	/// Lowest version which has every feature used by the document.
+	pub(crate) required_version: (u8, u8),
+	/// Standard fonts used by the appearance streams of annotations, by their
+	/// base font, so that each of them is added only once.
+	pub(crate) appearance_fonts: BTreeMap<&'static str, FontRef>,
+	/// Filters used to decode and encode the streams, other than the built-in
+	/// ones.
+	pub(crate) filters: Option<FilterRegistry>,
+}
+
+impl Document {
+	/// Creates new empty document.
+	pub fn new() -> Self {
+		return Self {
+			objects: BTreeMap::new(),
+			next_number: 3,
+			catalog_id: ObjectId::new(1, 0),
+			pages_id: ObjectId::new(2, 0),
+			catalog: Dictionary::new(),
+			info: None,
+			pages: Vec::new(),
+			resources: BTreeMap::new(),
+			version: DEFAULT_VERSION,
+			pdfa: None,
+			file_id: None,
+			deterministic_id: false,
+			icc_color_spaces: HashMap::new(),
+			page_tree_branching: None,
+			page_tree_nodes: Vec::new(),
+			compression: CompressionPolicy::None,
+			write_style: WriteStyle::Default,
+			binary_marker: Some(DEFAULT_BINARY_MARKER),
+			struct_elements: Vec::new(),
+			default_page_size: None,
+			loaded: BTreeMap::new(),
+			name_trees: BTreeMap::new(),
+			name_tree_nodes: Vec::new(),
+			object_streams: false,
+			required_version: (1, 0),
+			appearance_fonts: BTreeMap::new(),
+			filters: None,
+		};
+	}
+
+	/// Creates new document with a single blank page, i.e. the smallest valid
+	/// document, e.g. to start from in tests.
+	#[inline]
+	pub fn empty() -> Self {
+		return Self::blank_pages(1);
+	}
+
+	/// Creates new document with the given number of blank US Letter pages,
+	/// which have no contents.
+	pub fn blank_pages(count: usize) -> Self {
+		let mut doc = Self::new();
+		for _ in 0..count {
+			doc.add_page(LETTER_WIDTH, LETTER_HEIGHT);
+		}
+		return doc;
+	}
+
+	/// Attempts to load the whole document from the file data, so that it can
+	/// be modified and written again. The page tree is flattened, with the
+	/// inherited page attributes copied into the pages.
+	#[inline]
+	pub fn load(data: &[u8]) -> Result<Self, PdfError> {
+		return Self::read(data, None);
+	}
+
+	/// Attempts to load the whole document from the file data, like
+	/// [`Document::load`], decoding the streams with the given filters, which
+	/// are then used by the document, as if set with
+	/// [`Document::set_filters`].
+	#[inline]
+	pub fn load_with_filters(data: &[u8], filters: FilterRegistry) -> Result<Self, PdfError> {
+		return Self::read(data, Some(filters));
+	}
+
+	fn read(data: &[u8], filters: Option<FilterRegistry>) -> Result<Self, PdfError> {
+		let mut reader = Reader::new(data)?;
+		if let Some(filters) = &filters {
+			reader.set_filters(filters);
+		}
+		let Some(root) = reader.trailer().get("Root").and_then(PdfObject::as_reference) else {
+			return Err(PdfError::syntax(0, "trailer has no /Root"));
+		};
+
+		let mut doc = Self::new();
+		doc.version = reader.pdf_version();
+		if let Some([PdfObject::String(first, _), PdfObject::String(second, _)]) = reader.trailer().get("ID").and_then(PdfObject::as_array).map(Vec::as_slice) {
+			doc.file_id = Some([first.clone(), second.clone()]);
+		}
+		for id in reader.object_ids() {
+			let object = reader.resolve(id)?;
+			// Cross-reference and object streams are only a part of the file
+			// structure, which is written anew.
+			if matches!(&object, PdfObject::Stream(stream) if matches!(stream.dict.get_name("Type"), Some(b"XRef" | b"ObjStm"))) {
+				continue;
+			}
+			doc.loaded.insert(id, object_hash(&object));
+			doc.objects.insert(id, object);
+		}
+		doc.info = reader.info_id().filter(|id| doc.objects.contains_key(id));
+		doc.filters = filters;
+		doc.next_number = doc.objects.keys().map(|id| id.number + 1).max().unwrap_or(1);
+
+		let Some(PdfObject::Dictionary(catalog)) = doc.objects.remove(&root) else {
+			return Err(PdfError::MissingObject(root));
+		};
+		let Some(pages) = catalog.get("Pages").and_then(PdfObject::as_reference) else {
+			return Err(PdfError::syntax(0, "catalog has no /Pages"));
+		};
+
+		doc.catalog_id = root;
+		doc.pages_id = pages;
+		for (key, value) in catalog.iter().filter(|(key, _)| !matches!(*key, b"Type" | b"Pages")) {
+			doc.catalog.set_raw(key, value.clone());
+		}
+
+		doc.flatten_page_tree(pages, Dictionary::new())?;
+		return Ok(doc);
+	}
+
+	/// Moves the pages of the page tree node into the list of pages, removing
+	/// the intermediate nodes.
+	fn flatten_page_tree(&mut self, node: ObjectId, mut inherited: Dictionary) -> Result<(), PdfError> {
+		let Some(PdfObject::Dictionary(mut dict)) = self.objects.remove(&node) else {
+			return Err(PdfError::MissingObject(node));
+		};
+
+		if dict.get_name("Type") == Some(b"Page") || !dict.contains_key("Kids") {
+			for key in INHERITABLE {
+				if let (false, Some(value)) = (dict.contains_key(key), inherited.get(key)) {
+					dict.set(key, value.clone());
+				}
+			}
+			dict.set("Parent", self.pages_id);
+			self.objects.insert(node, PdfObject::Dictionary(dict));
+			self.pages.push(node);
+			return Ok(());
+		}
+
+		for key in INHERITABLE {
+			if let Some(value) = dict.get(key) {
+				inherited.set(key, value.clone());
+			}
+		}
+		// Nodes are removed before visiting their kids, so loops end up as missing objects.
+		for kid in dict.get("Kids").and_then(PdfObject::as_array).into_iter().flatten() {
+			let Some(kid) = kid.as_reference() else {
+				return Err(PdfError::syntax(0, "page tree kid is not a reference"));
+			};
+			self.flatten_page_tree(kid, inherited.clone())?;
+		}
+		return Ok(());
+	}
+
+	/// Sets the filters used to decode and encode the streams, e.g. with a
+	/// custom filter registered, for the page contents, the text extraction,
+	/// and the compression of the written streams. Cross-reference streams
+	/// are always encoded with the built-in filters.
+	pub fn set_filters(&mut self, filters: FilterRegistry) {
+		self.filters = Some(filters);
+	}
+
+	/// Gets the filters used to decode and encode the streams.
+	pub(crate) fn filter_registry(&self) -> &FilterRegistry {
+		return match &self.filters {
+			Some(filters) => filters,
+			None => FilterRegistry::builtin(),
+		};
+	}
+
+	/// Attempts to decode the data of the stream with the filters of the
+	/// document. The objects the decoding parameters refer to are resolved
+	/// for the filters, as they are by the [`Reader`].
+	pub(crate) fn decode(&self, stream: &Stream) -> Result<Vec<u8>, PdfError> {
+		let Some(params) = stream.dict.get("DecodeParms") else {
+			return self.filter_registry().decode_stream(&stream.dict, &stream.data);
+		};
+		let resolve = |object: &PdfObject| match object {
+			PdfObject::Reference(id) => self.objects.get(id).cloned().unwrap_or(PdfObject::Null),
+			object => object.clone(),
+		};
+		let resolve_dict = |params: PdfObject| match params {
+			PdfObject::Dictionary(mut params) => {
+				for value in params.values_mut() {
+					*value = resolve(value);
+				}
+				PdfObject::Dictionary(params)
+			},
+			params => params,
+		};
+		let mut dict = stream.dict.clone();
+		dict.set("DecodeParms", match resolve(params) {
+			PdfObject::Array(params) => PdfObject::Array(params.iter().map(|params| resolve_dict(resolve(params))).collect()),
+			params => resolve_dict(params),
+		});
+		return self.filter_registry().decode_stream(&dict, &stream.data);
+	}
+
+	/// Gets the version written in the header of the file.
+	#[inline]
+	pub fn version(&self) -> (u8, u8) {
+		return self.version;
+	}
+
+	/// Sets the version written in the header of the file. If the catalog
+	/// overrides the version, e.g. in a loaded document, it's updated as well.
+	pub fn set_version(&mut self, version: (u8, u8)) {
+		self.version = version;
+		if self.catalog.contains_key("Version") {
+			self.set_catalog_version(version);
+		}
+	}
+
+	/// Gets the version the document is written with, which is the version set
+	/// with [`Document::set_version`], unless the features used by the
+	/// document need a newer one, e.g. object streams need PDF 1.5.
+	pub fn effective_version(&self) -> (u8, u8) {
+		return self.version.max(self.required_version);
+	}
+
+	/// Notes that the document uses a feature of the given version, so that
+	/// it's written with it at least.
+	pub(crate) fn require_version(&mut self, version: (u8, u8)) {
+		self.required_version = self.required_version.max(version);
+	}
+
+	/// Sets the version in the catalog, which overrides the one in the header
+	/// when it's newer. This allows incremental updates to change the version.
+	pub fn set_catalog_version(&mut self, version: (u8, u8)) {
+		self.catalog.set("Version", PdfObject::name(&std::format!("{}.{}", version.0, version.1)));
+	}
+
+	/// Sets the natural language of the document, e.g. `en-US`, for screen
+	/// readers. Viewers are also asked to show the document title instead of
+	/// the file name, as required for the PDF/UA conformance.
+	pub fn set_language(&mut self, language: &str) {
+		self.catalog.set("Lang", PdfObject::text_string(language));
+		if !matches!(self.catalog.get("ViewerPreferences"), Some(PdfObject::Dictionary(_))) {
+			self.catalog.set("ViewerPreferences", Dictionary::new());
+		}
+		if let Some(preferences) = self.catalog.get_mut("ViewerPreferences").and_then(PdfObject::as_dict_mut) {
+			preferences.set("DisplayDocTitle", true);
+		}
+	}
+
+	/// Reserves an object identifier, for an object to be added later with
+	/// [`Document::set_object`].
+	pub fn reserve_id(&mut self) -> ObjectId {
+		let id = ObjectId::new(self.next_number, 0);
+		self.next_number += 1;
+		return id;
+	}
+
+	/// Adds new indirect object to the document, returning its identifier.
+	pub fn add_object(&mut self, object: impl Into<PdfObject>) -> ObjectId {
+		let id = self.reserve_id();
+		self.objects.insert(id, object.into());
+		return id;
+	}
+
+	/// Sets the indirect object with the given identifier.
+	pub fn set_object(&mut self, id: ObjectId, object: impl Into<PdfObject>) {
+		self.objects.insert(id, object.into());
+	}
+
+	#[inline]
+	pub fn object(&self, id: ObjectId) -> Option<&PdfObject> {
+		return self.objects.get(&id);
+	}
+
+	#[inline]
+	pub fn object_mut(&mut self, id: ObjectId) -> Option<&mut PdfObject> {
+		return self.objects.get_mut(&id);
+	}
+
+	/// Removes the indirect object, leaving a free entry in its place.
+	#[inline]
+	pub fn remove_object(&mut self, id: ObjectId) -> Option<PdfObject> {
+		return self.objects.remove(&id);
+	}
+
+	/// Gets the identifier of the page tree root.
+	#[inline]
+	pub fn pages_id(&self) -> ObjectId {
+		return self.pages_id;
+	}
+
+	#[inline]
+	pub fn page_count(&self) -> usize {
+		return self.pages.len();
+	}
+
+	/// Appends new page with the given media box dimensions, in units.
+	#[inline]
+	pub fn add_page(&mut self, width: f64, height: f64) -> Page<'_> {
+		return self.append_page(Some(Rectangle::new(0.0, 0.0, width, height)));
+	}
+
+	/// Sets the dimensions of the pages added with
+	/// [`Document::add_default_page`], including the ones added already.
+	pub fn set_default_page_size(&mut self, width: f64, height: f64) {
+		self.default_page_size = Some((width, height));
+	}
+
+	/// Appends new page with the default dimensions, set with
+	/// [`Document::set_default_page_size`], or US Letter if they're not set.
+	/// The media box is written once, on the page tree root, and the pages
+	/// inherit it.
+	pub fn add_default_page(&mut self) -> Page<'_> {
+		self.default_page_size.get_or_insert((LETTER_WIDTH, LETTER_HEIGHT));
+		return self.append_page(None);
+	}
+
+	fn append_page(&mut self, media_box: Option<Rectangle>) -> Page<'_> {
+		let mut dict = Dictionary::new();
+		dict.set("Type", PdfObject::name("Page"));
+		dict.set("Parent", self.pages_id);
+		if let Some(media_box) = media_box {
+			dict.set("MediaBox", media_box.to_pdf_array());
+		}
+		dict.set("Resources", Dictionary::new());
+
+		let id = self.add_object(dict);
+		self.pages.push(id);
+		return Page {
+			doc: self,
+			id,
+		};
+	}
+
+	/// Gets the page with the given index for modification.
+	pub fn page_mut(&mut self, index: usize) -> Option<Page<'_>> {
+		let id = *self.pages.get(index)?;
+		return Some(Page {
+			doc: self,
+			id,
+		});
+	}
+
+	/// Adds one of the standard 14 fonts, e.g. `Times-Roman`.
+	pub fn add_base_font(&mut self, base_font: &str) -> FontRef {
+		let mut dict = Dictionary::new();
+		dict.set("Type", PdfObject::name("Font"));
+		dict.set("Subtype", PdfObject::name("Type1"));
+		dict.set("BaseFont", PdfObject::name(base_font));
+		dict.set("Encoding", PdfObject::name("MacRomanEncoding"));
+		return FontRef(self.add_object(dict));
+	}
+
+	/// Attempts to write the entire document using the provided [`Writer`].
+	pub fn write(&mut self, writer: &mut Writer<'_>) -> std::io::Result<()> {
+		if let Some(version) = self.pdfa {
+			self.prepare_pdfa(version).map_err(std::io::Error::other)?;
+		}
+		let version = self.effective_version();
+		// A catalog version left from an older one would override the header.
+		if self.catalog.get_name("Version").and_then(parse_version).is_some_and(|catalog| catalog < version) {
+			self.set_catalog_version(version);
+		}
+		write_header(writer, version, self.binary_marker)?;
+		self.merge_resources();
+		self.build_name_trees();
+		let compressed = self.compressed_contents();
+
+		if self.object_streams {
+			let roots = [(self.catalog_id, PdfObject::Dictionary(self.catalog_dict())), (self.pages_id, PdfObject::Dictionary(self.page_tree_dict()))];
+			return self.write_object_streams(writer, roots, &compressed);
+		}
+
+		// Offsets are kept by object number, along with the generation, which
+		// isn't always 0 for the objects of loaded documents.
+		let mut offsets = BTreeMap::new();
+		offsets.insert(self.catalog_id.number, (writer.pos(), self.catalog_id.generation));
+		write_indirect(writer, self.catalog_id, &PdfObject::Dictionary(self.catalog_dict()), self.write_style)?;
+		offsets.insert(self.pages_id.number, (writer.pos(), self.pages_id.generation));
+		write_indirect(writer, self.pages_id, &PdfObject::Dictionary(self.page_tree_dict()), self.write_style)?;
+		for (&id, object) in &self.objects {
+			offsets.insert(id.number, (writer.pos(), id.generation));
+			write_indirect(writer, id, compressed.get(&id).unwrap_or(object), self.write_style)?;
+		}
+
+		let (size, root, info) = (self.next_number, self.catalog_id, self.info);
+		let id = self.next_file_id();
+		return write_xref_and_trailer(writer, size, root, info, Some(id), |number| offsets.get(&number).copied());
+	}
+
+	/// Adds the resources used by the content streams to the pages.
+	pub(crate) fn merge_resources(&mut self) {
+		for (id, resources) in &self.resources {
+			let Some(page) = self.objects.get_mut(id).and_then(PdfObject::as_dict_mut) else {
+				continue;
+			};
+			if !page.contains_key("Resources") {
+				page.set("Resources", Dictionary::new());
+			}
+			if let Some(dict) = page.get_mut("Resources").and_then(PdfObject::as_dict_mut) {
+				resources.merge_into(dict);
+			}
+		}
+	}
+
+	/// Sets which page content streams are compressed when the document is
+	/// written.
+	pub fn set_compression(&mut self, compression: CompressionPolicy) {
+		self.compression = compression;
+	}
+
+	/// Sets the layout of the whitespace in the written objects, e.g. compact
+	/// for the smallest files.
+	pub fn set_write_style(&mut self, style: WriteStyle) {
+		self.write_style = style;
+	}
+
+	/// Sets the bytes of the comment written after the header, which marks
+	/// the file as containing binary data, or leaves the comment out, e.g.
+	/// for files which are only text. The bytes should be at least 128, and
+	/// PDF/A requires them to be.
+	pub fn set_binary_marker(&mut self, marker: Option<[u8; 4]>) {
+		self.binary_marker = marker;
+	}
+
+	/// Compresses copies of the page content streams which don't have any
+	/// filters yet, according to the compression policy, if it makes them
+	/// smaller. The copies are written in place of the streams, which are
+	/// left as they are, so the document can still be modified after it's
+	/// written.
+	pub(crate) fn compressed_contents(&self) -> BTreeMap<ObjectId, PdfObject> {
+		let mut compressed = BTreeMap::new();
+		let CompressionPolicy::Threshold(threshold) = self.compression else {
+			return compressed;
+		};
+		for &page in &self.pages {
+			let Some(page) = self.objects.get(&page).and_then(PdfObject::as_dict) else {
+				continue;
+			};
+			for id in content_ids(page) {
+				let Some(PdfObject::Stream(stream)) = self.objects.get(&id) else {
+					continue;
+				};
+				if stream.data.len() < threshold || stream.dict.contains_key("Filter") {
+					continue;
+				}
+				let mut dict = stream.dict.clone();
+				let Ok(data) = self.filter_registry().encode_stream(&mut dict, &stream.data, &["FlateDecode"]) else {
+					continue;
+				};
+				if data.len() < stream.data.len() {
+					compressed.insert(id, PdfObject::Stream(Stream::new(dict, data)));
+				}
+			}
+		}
+		return compressed;
+	}
+
+	/// Attempts to write the document, like [`Document::write`], but checks
+	/// that the offsets in the cross-reference table point at the objects,
+	/// and that every written object can be read back, before passing the file
+	/// to the writer.
+	pub fn write_verified(&mut self, writer: &mut Writer<'_>) -> Result<(), PdfError> {
+		let mut data = Vec::new();
+		self.write(&mut Writer::new(&mut data))?;
+		let reader = Reader::with_mode(&data, ParseMode::Strict)?;
+		reader.verify_offsets()?;
+		for &id in [self.catalog_id, self.pages_id].iter().chain(self.objects.keys()) {
+			reader.resolve(id)?;
+		}
+		writer.write(&data)?;
+		return Ok(());
+	}
+
+	/// Creates the catalog dictionary, with the additional entries.
+	pub(crate) fn catalog_dict(&self) -> Dictionary {
+		let mut catalog = Dictionary::new();
+		catalog.set("Type", PdfObject::name("Catalog"));
+		catalog.set("Pages", self.pages_id);
+		for (key, value) in self.catalog.iter() {
+			catalog.set_raw(key, value.clone());
+		}
+		return catalog;
+	}
+
+	/// Sets the maximum number of kids of a page tree node. With `Some`, the
+	/// pages are grouped under intermediate nodes, so that the page tree is
+	/// balanced, with all the pages at the same depth, and with `None`, all
+	/// pages are the kids of the root. Large documents are opened faster by
+	/// viewers with a balanced page tree, e.g. with a branching factor of 10.
+	pub fn set_page_tree_branching(&mut self, branching: Option<usize>) {
+		self.page_tree_branching = branching.map(|branching| branching.max(2));
+	}
+
+	/// Creates the page tree root, adding the intermediate nodes between it
+	/// and the pages, if the page tree is balanced, and updating the parents of
+	/// the pages.
+	pub(crate) fn page_tree_dict(&mut self) -> Dictionary {
+		// Nodes from a previous write are replaced, keeping their identifiers.
+		let mut free = std::mem::take(&mut self.page_tree_nodes).into_iter();
+		for id in free.as_slice() {
+			self.objects.remove(id);
+		}
+
+		let mut level: Vec<(ObjectId, usize)> = self.pages.iter().map(|&id| (id, 1)).collect();
+		let branching = self.page_tree_branching.unwrap_or(usize::MAX);
+		while level.len() > branching {
+			let mut parents = Vec::with_capacity(level.len().div_ceil(branching));
+			for kids in level.chunks(branching) {
+				let id = free.next().unwrap_or_else(|| self.reserve_id());
+				let count = kids.iter().map(|&(_, count)| count).sum();
+				let mut node = Dictionary::new();
+				node.set("Type", PdfObject::name("Pages"));
+				node.set("Kids", kids.iter().map(|&(kid, _)| PdfObject::Reference(kid)).collect::<Vec<_>>());
+				node.set("Count", count);
+				self.set_parents(kids, id);
+				self.objects.insert(id, PdfObject::Dictionary(node));
+				self.page_tree_nodes.push(id);
+				parents.push((id, count));
+			}
+			level = parents;
+		}
+		for id in free {
+			self.objects.remove(&id);
+		}
+
+		self.set_parents(&level, self.pages_id);
+		let mut page_tree = Dictionary::new();
+		page_tree.set("Type", PdfObject::name("Pages"));
+		page_tree.set("Kids", level.iter().map(|&(id, _)| PdfObject::Reference(id)).collect::<Vec<_>>());
+		page_tree.set("Count", self.pages.len());
+		if let Some((width, height)) = self.default_page_size {
+			page_tree.set("MediaBox", Rectangle::new(0.0, 0.0, width, height).to_pdf_array());
+		}
+		return page_tree;
+	}
+
+	/// Sets the parent of the page tree nodes.
+	fn set_parents(&mut self, kids: &[(ObjectId, usize)], parent: ObjectId) {
+		for (kid, _) in kids {
+			if let Some(kid) = self.objects.get_mut(kid).and_then(PdfObject::as_dict_mut) {
+				kid.set("Parent", parent);
+			}
+		}
+	}
+
+	/// Sets whether the file identifiers are generated only from the objects
+	/// of the document, rather than also from the time it's written, so that
+	/// the same document is always written the same.
+	pub fn set_deterministic_id(&mut self, deterministic: bool) {
+		self.deterministic_id = deterministic;
+	}
+
+	/// Gets the file identifier, i.e. the permanent one and the one of the
+	/// last written revision, if the document was written or loaded.
+	#[inline]
+	pub fn file_id(&self) -> Option<&[Vec<u8>; 2]> {
+		return self.file_id.as_ref();
+	}
+
+	/// Generates the file identifier of the revision about to be written. The
+	/// permanent identifier is kept, if there's one already.
+	pub(crate) fn next_file_id(&mut self) -> &[Vec<u8>; 2] {
+		let revision = self.generate_id();
+		let permanent = match self.file_id.take() {
+			Some([permanent, _]) => permanent,
+			None => revision.clone(),
+		};
+		return self.file_id.insert([permanent, revision]);
+	}
+
+	/// Generates an identifier from the objects of the document, and the
+	/// current time, unless it should be deterministic.
+	fn generate_id(&self) -> Vec<u8> {
+		let mut hashers = [DefaultHasher::new(), DefaultHasher::new()];
+		hashers[1].write_u8(0xff);
+		if !self.deterministic_id {
+			let time = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+			for hasher in &mut hashers {
+				hasher.write_u128(time.as_nanos());
+			}
+		}
+		for (id, object) in &self.objects {
+			let mut data = Vec::new();
+			// Writing into memory can't fail.
+			let _ = object.write_to(&mut Writer::new(&mut data));
+			for hasher in &mut hashers {
+				hasher.write_u32(id.number);
+				hasher.write(&data);
+			}
+		}
+		return hashers.iter().flat_map(|hasher| hasher.finish().to_be_bytes()).collect();
+	}
+}
+
+impl Default for Document {
+	fn default() -> Self {
+		return Self::new();
+	}
+}
+
+/// Version of the files written, unless it's set otherwise.
+pub(crate) const DEFAULT_VERSION: (u8, u8) = (1, 7);
+
+/// Bytes of the comment marking the file as binary, written by default.
+pub(crate) const DEFAULT_BINARY_MARKER: [u8; 4] = [0x80, 0x81, 0x82, 0x83];
+
+/// Dimensions of the US Letter paper size, in points.
+const LETTER_WIDTH: f64 = 612.0;
+const LETTER_HEIGHT: f64 = 792.0;
+
+/// Writes the PDF header.
+pub(crate) fn write_header(writer: &mut Writer<'_>, version: (u8, u8), marker: Option<[u8; 4]>) -> std::io::Result<()> {
+	const NEWLINE: u8 = 0x0a;
+	const PERCENT: u8 = 0x25;
+
+	writer.write(format!("%PDF-{}.{}\n", version.0, version.1).as_bytes())?;
+	// Mark the file as containing binary data, because we want to be able to
+	// embed fonts and images.
+	if let Some([a, b, c, d]) = marker {
+		writer.write(&[PERCENT, a, b, c, d, NEWLINE])?;
+	}
+	return Ok(());
+}
+
+pub(crate) fn write_indirect(writer: &mut Writer<'_>, id: ObjectId, object: &PdfObject, style: WriteStyle) -> std::io::Result<()> {
+	writer.write(format!("{} {} obj\n", id.number, id.generation).as_bytes())?;
+	object.write_styled(writer, style)?;
+	writer.write(b"\nendobj\n")?;
+	return Ok(());
+}
+
+/// Size of every entry of a cross-reference table, including the end of line.
+const XREF_ENTRY_SIZE: usize = 20;
+
+/// Buffer of the entries of a cross-reference table, which are formatted by
+/// hand and written in chunks, as tables can have hundreds of thousands of
+/// entries.
+pub(crate) struct XrefEntries {
+	buf: [u8; XREF_ENTRY_SIZE * 512],
+	len: usize,
+}
+
+impl XrefEntries {
+	#[inline]
+	pub(crate) fn new() -> Self {
+		return Self {
+			buf: [0; XREF_ENTRY_SIZE * 512],
+			len: 0,
+		};
+	}
+
+	/// Attempts to add the entry, either in use (`n`) or free (`f`), writing
+	/// the buffer if it's full.
+	pub(crate) fn push(&mut self, writer: &mut Writer<'_>, offset: usize, generation: u16, kind: u8) -> std::io::Result<()> {
+		if offset > 9_999_999_999 {
+			return Err(std::io::Error::other("offset does not fit in a cross-reference entry"));
+		}
+		if self.len == self.buf.len() {
+			self.flush(writer)?;
+		}
+
+		let entry = &mut self.buf[self.len..self.len + XREF_ENTRY_SIZE];
+		let mut value = offset;
+		for digit in entry[..10].iter_mut().rev() {
+			*digit = b'0' + (value % 10) as u8;
+			value /= 10;
+		}
+		let mut value = generation;
+		for digit in entry[11..16].iter_mut().rev() {
+			*digit = b'0' + (value % 10) as u8;
+			value /= 10;
+		}
+		entry[10] = b' ';
+		entry[16] = b' ';
+		entry[17] = kind;
+		entry[18..].copy_from_slice(b"\r\n");
+		self.len += XREF_ENTRY_SIZE;
+		return Ok(());
+	}
+
+	/// Attempts to write the buffered entries.
+	pub(crate) fn flush(&mut self, writer: &mut Writer<'_>) -> std::io::Result<()> {
+		writer.write(&self.buf[..self.len])?;
+		self.len = 0;
+		return Ok(());
+	}
+}
+
+/// Writes the cross-reference table and the trailer, finishing the file. The
+/// offsets of the objects, and their generations, are looked up by their
+/// object number.
+pub(crate) fn write_xref_and_trailer(writer: &mut Writer<'_>, size: u32, root: ObjectId, info: Option<ObjectId>, id: Option<&[Vec<u8>; 2]>, offset: impl Fn(u32) -> Option<(usize, u16)>) -> std::io::Result<()> {
+	let xref_pos = writer.pos();
+	writer.write(format!("xref\n0 {}\n", size).as_bytes())?;
+	let mut entries = XrefEntries::new();
+	entries.push(writer, 0, 65535, b'f')?;
+	for number in 1..size {
+		match offset(number) {
+			Some((offset, generation)) => entries.push(writer, offset, generation, b'n')?,
+			None => entries.push(writer, 0, 1, b'f')?,
+		}
+	}
+	entries.flush(writer)?;
+
+	return write_trailer(writer, size, root, info, id, None, xref_pos);
+}
+
+/// Writes the trailer, pointing at the cross-reference section at the given
+/// position, and the previous one, if the file is updated.
+pub(crate) fn write_trailer(writer: &mut Writer<'_>, size: u32, root: ObjectId, info: Option<ObjectId>, id: Option<&[Vec<u8>; 2]>, prev: Option<usize>, xref_pos: usize) -> std::io::Result<()> {
+	writer.write(b"trailer\n")?;
+	writer.write(b"<<\n")?;
+	std::writeln!(writer, "/Size {}", size)?;
+	std::writeln!(writer, "/Root {} {} R", root.number, root.generation)?;
+	if let Some(info) = info {
+		std::writeln!(writer, "/Info {} {} R", info.number, info.generation)?;
+	}
+	if let Some(prev) = prev {
+		std::writeln!(writer, "/Prev {}", prev)?;
+	}
+	if let Some([first, second]) = id {
+		writer.write(b"/ID [")?;
+		PdfObject::hex_string(first.clone()).write_to(writer)?;
+		writer.write(b" ")?;
+		PdfObject::hex_string(second.clone()).write_to(writer)?;
+		writer.write(b"]\n")?;
+	}
+	writer.write(b">>\n")?;
+
+	writer.write(b"startxref\n")?;
+	std::writeln!(writer, "{}", xref_pos)?;
+	writer.write(b"%%EOF\n")?;
+
+	return Ok(());
+}
+
+/// Gets the identifiers of the content streams of the page, given either as
+/// one stream or as an array of them.
+pub(crate) fn content_ids(page: &Dictionary) -> Vec<ObjectId> {
+	return match page.get("Contents") {
+		Some(PdfObject::Array(contents)) => contents.iter().filter_map(PdfObject::as_reference).collect(),
+		Some(contents) => contents.as_reference().into_iter().collect(),
+		None => Vec::new(),
+	};
+}
+
+/// Handle to a page of a [`Document`], which allows modifying it.
+pub struct Page<'a> {
+	pub(crate) doc: &'a mut Document,
+	pub(crate) id: ObjectId,
+}
+
+impl Page<'_> {
+	/// Gets the identifier of the page object.
+	#[inline]
+	pub fn id(&self) -> ObjectId {
+		return self.id;
+	}
+
+	/// Gets the media box of the page, which is inherited from the page tree
+	/// root by the pages added with [`Document::add_default_page`].
+	pub fn media_box(&self) -> Option<Rectangle> {
+		return match self.dict().get("MediaBox") {
+			Some(media_box) => Rectangle::from_pdf_array(media_box),
+			None => self.doc.default_page_size.map(|(width, height)| Rectangle::new(0.0, 0.0, width, height)),
+		};
+	}
+
+	/// Gets the page dictionary.
+	pub fn dict(&self) -> &Dictionary {
+		return self.doc.objects[&self.id].as_dict().expect("page object is not a dictionary");
+	}
+
+	/// Gets the page dictionary for modification.
+	pub fn dict_mut(&mut self) -> &mut Dictionary {
+		return self.doc.objects.get_mut(&self.id).and_then(PdfObject::as_dict_mut).expect("page object is not a dictionary");
+	}
+
+	/// Sets the content stream of the page. Resources requested from the
+	/// content stream replace the ones of the previous contents.
+	pub fn set_contents(&mut self, mut content: ContentStream) {
+		let resources = std::mem::take(&mut content.resources);
+		if resources.is_empty() {
+			self.doc.resources.remove(&self.id);
+		} else {
+			self.doc.resources.insert(self.id, resources);
+		}
+
+		let id = self.doc.add_object(Stream::new(Dictionary::new(), content.finish()));
+		self.dict_mut().set("Contents", id);
+	}
+
+	/// Attempts to append the content stream to the contents of the page, which
+	/// become an array of streams, painted one after another. The resources of
+	/// the content stream are added to the ones of the previous contents, and
+	/// have to be named differently, e.g. by creating the content stream with
+	/// [`Page::new_content`].
+	pub fn add_content(&mut self, mut content: ContentStream) -> Result<(), PdfError> {
+		let resources = std::mem::take(&mut content.resources);
+		self.doc.resources.entry(self.id).or_default().merge(resources)?;
+
+		let id = self.doc.add_object(Stream::new(Dictionary::new(), content.finish()));
+		let contents = match self.dict_mut().remove("Contents") {
+			Some(PdfObject::Array(mut contents)) => {
+				contents.push(id.into());
+				PdfObject::Array(contents)
+			},
+			Some(previous) => PdfObject::Array(std::vec![previous, id.into()]),
+			None => id.into(),
+		};
+		self.dict_mut().set("Contents", contents);
+		return Ok(());
+	}
+
+	/// Creates new content stream, which names the resources after the ones
+	/// used by the contents of the page, so that it can be added with
+	/// [`Page::add_content`].
+	pub fn new_content(&self) -> ContentStream {
+		return ContentStream::with_resources(self.doc.resources.get(&self.id).cloned().unwrap_or_default());
+	}
+
+	/// Gets the identifiers of all the page content streams.
+	#[inline]
+	pub(crate) fn content_ids(&self) -> Vec<ObjectId> {
+		return content_ids(self.dict());
+	}
+
+	/// Gets the resources of the page, as they're written, i.e. including the
+	/// ones used by the content streams, which are only added to the page
+	/// resources when it's written. The categories are resolved, if they're
+	/// references.
+	pub(crate) fn resources(&self) -> Dictionary {
+		let resolve = |object: &PdfObject| match object {
+			PdfObject::Reference(id) => self.doc.object(*id).cloned().unwrap_or(PdfObject::Null),
+			object => object.clone(),
+		};
+		let mut resources = match self.dict().get("Resources").map(resolve) {
+			Some(PdfObject::Dictionary(resources)) => resources,
+			_ => Dictionary::new(),
+		};
+		for value in resources.values_mut() {
+			*value = resolve(value);
+		}
+		if let Some(used) = self.doc.resources.get(&self.id) {
+			used.merge_into(&mut resources);
+		}
+		return resources;
+	}
+
+	/// Parses the page content streams into a sequence of operations, after
+	/// decoding them with the filters of the document. The streams are joined with a
+	/// newline between them, so an operation may span two of them.
+	pub fn operations(&self) -> Result<Vec<Operation>, PdfError> {
+		let mut data = Vec::new();
+		for stream in self.content_ids().into_iter().filter_map(|id| self.doc.object(id)).filter_map(PdfObject::as_stream) {
+			if stream.dict.contains_key("Filter") {
+				data.extend_from_slice(&self.doc.decode(stream)?);
+			} else {
+				data.extend_from_slice(&stream.data);
+			}
+			data.push(b'\n');
+		}
+		return parse_operations(&data);
+	}
+
+	/// Extracts the text shown on the page.
+	pub fn extract_text(&self) -> Result<String, PdfError> {
+		return Ok(text::extract_text_with_fonts(&self.operations()?, &self.font_decoders()));
+	}
+
+	/// Extracts the text shown on the page, like [`Page::extract_text`], with
+	/// the position of every shown string.
+	pub fn extract_text_positioned(&self) -> Result<Vec<PositionedText>, PdfError> {
+		return Ok(text::positioned_text(&self.operations()?, &self.font_decoders()));
+	}
+
+	/// Makes the font available to the page content under the given name.
+	pub fn add_font_resource(&mut self, name: &str, font: FontRef) {
+		self.resource_category("Font").set(name, font.0);
+	}
+
+	/// Gets the given category of the page resources, e.g. `/Font`, creating it
+	/// if it does not exist yet.
+	pub(crate) fn resource_category(&mut self, category: &str) -> &mut Dictionary {
+		let page = self.dict_mut();
+		if !page.contains_key("Resources") {
+			page.set("Resources", Dictionary::new());
+		}
+
+		let resources = page.get_mut("Resources").and_then(PdfObject::as_dict_mut).expect("page resources are not a dictionary");
+		if !resources.contains_key(category) {
+			resources.set(category, Dictionary::new());
+		}
+
+		return resources.get_mut(category).and_then(PdfObject::as_dict_mut).expect("resource category is not a dictionary");
+	}
+
+	/// Attempts to set the size of the page user space unit, as a multiple of
+	/// 1/72 inch, so that pages larger than 200 inches can be represented. The
+	/// media box is given in these units, and has to stay within the limits.
+	pub fn set_user_unit(&mut self, factor: f64) -> Result<(), PdfError> {
+		if !(factor.is_finite() && factor > 0.0) {
+			return Err(PdfError::InvalidArgument(std::format!("invalid user unit {}", factor)));
+		}
+
+		if let Some(media_box) = self.media_box() {
+			let size = media_box.width().max(media_box.height());
+			if size > MAX_PAGE_UNITS {
+				return Err(PdfError::InvalidArgument(std::format!("media box size {} exceeds {} units", size, MAX_PAGE_UNITS)));
+			}
+			if size * factor > MAX_PAGE_SIZE {
+				return Err(PdfError::InvalidArgument(std::format!("page size {} is too large", size * factor)));
+			}
+		}
+
+		self.dict_mut().set("UserUnit", factor);
+		return Ok(());
+	}
+
+	/// Appends the annotation to the page `/Annots` array.
+	pub(crate) fn push_annotation(&mut self, annotation: ObjectId) {
+		let page = self.dict_mut();
+		match page.get_mut("Annots") {
+			Some(PdfObject::Array(annots)) => annots.push(annotation.into()),
+			_ => page.set("Annots", vec![PdfObject::Reference(annotation)]),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::document::*;
+	use crate::*;
+
+	#[test]
+	fn xref_entries() {
+		let offset = |number: u32| (number % 7 != 3).then(|| (number as usize * 9_876_543 % 9_999_999_999, 0));
+		let root = ObjectId::new(1, 0);
+		let mut output = Vec::new();
+		write_xref_and_trailer(&mut Writer::new(&mut output), 200_000, root, None, None, offset).unwrap();
+
+		// Formatted entry by entry, as the entries used to be.
+		let mut expected = std::format!("xref\n0 {}\n0000000000 65535 f\r\n", 200_000);
+		for number in 1..200_000 {
+			match offset(number) {
+				Some((offset, _)) => expected.push_str(&std::format!("{:0>10} 00000 n\r\n", offset)),
+				None => expected.push_str("0000000000 00001 f\r\n"),
+			}
+		}
+		assert_eq!(output[..expected.len()], *expected.as_bytes());
+		assert!(output[expected.len()..].starts_with(b"trailer\n"));
+
+		let mut output = Vec::new();
+		let mut writer = Writer::new(&mut output);
+		let mut entries = XrefEntries::new();
+		assert!(entries.push(&mut writer, 10_000_000_000, 0, b'n').is_err());
+		entries.push(&mut writer, 1234, 7, b'n').unwrap();
+		entries.flush(&mut writer).unwrap();
+		assert_eq!(writer.pos(), 20);
+		assert_eq!(output, b"0000001234 00007 n\r\n");
+	}
+
+	#[test]
+	fn content_resources_are_named() {
+		let mut doc = Document::new();
+		let times = doc.add_base_font("Times-Roman");
+		let courier = doc.add_base_font("Courier");
+
+		let mut content = ContentStream::new();
+		let (f0, f1) = (content.use_font(times), content.use_font(courier));
+		content.begin_text().set_font(&f0, 12.0).show_text(b"Times").set_font(&f1, 12.0).show_text(b"Courier");
+		assert_eq!(content.use_font(times), "F0");
+		content.end_text();
+
+		let mut page = doc.add_page(612.0, 792.0);
+		page.set_contents(content);
+		let id = page.id();
+
+		let mut output = Vec::new();
+		doc.write(&mut Writer::new(&mut output)).unwrap();
+		let output = String::from_utf8_lossy(&output);
+		assert!(output.contains("/Resources << /Font << /F0 3 0 R /F1 4 0 R >> >>"));
+		assert!(output.contains("/F0 12 Tf\n(Times) Tj\n/F1 12 Tf\n(Courier) Tj\n"));
+
+		let resources = doc.object(id).unwrap().as_dict().unwrap().get("Resources").unwrap().as_dict().unwrap();
+		assert_eq!(resources.get("Font").and_then(PdfObject::as_dict).map(Dictionary::len), Some(2));
+	}
+
+	#[test]
+	fn user_unit() {
+		let mut doc = Document::new();
+		let mut page = doc.add_page(1000.0, 1000.0);
+		page.set_user_unit(10.0).unwrap();
+		assert!(matches!(page.set_user_unit(0.0), Err(PdfError::InvalidArgument(_))));
+		assert!(matches!(doc.add_page(20000.0, 1000.0).set_user_unit(10.0), Err(PdfError::InvalidArgument(_))));
+		assert!(matches!(doc.add_page(1000.0, 1000.0).set_user_unit(2_000_000.0), Err(PdfError::InvalidArgument(_))));
+
+		let mut output = Vec::new();
+		doc.write(&mut Writer::new(&mut output)).unwrap();
+		let output = String::from_utf8_lossy(&output);
+		assert!(output.contains("/MediaBox [0 0 1000 1000]\n/Resources << >>\n/UserUnit 10\n"));
+		assert_eq!(output.matches("/UserUnit").count(), 1);
+	}
+
+	#[test]
+	fn language() {
+		let mut doc = Document::new();
+		doc.add_page(612.0, 792.0);
+		doc.set_language("en-US");
+
+		let mut output = Vec::new();
+		doc.write(&mut Writer::new(&mut output)).unwrap();
+		let catalog = Reader::new(&output).unwrap().catalog().unwrap();
+		assert_eq!(catalog.get("Lang"), Some(&PdfObject::string(b"en-US".to_vec())));
+		let preferences = catalog.get("ViewerPreferences").and_then(PdfObject::as_dict).unwrap();
+		assert_eq!(preferences.get("DisplayDocTitle"), Some(&PdfObject::Boolean(true)));
+		assert!(String::from_utf8_lossy(&output).contains("/Lang (en-US)\n"));
+	}
+
+	#[test]
+	fn fonts_are_shared_between_pages() {
+		let mut doc = Document::new();
+		let font = doc.add_base_font("Helvetica");
+		for _ in 0..2 {
+			doc.add_page(612.0, 792.0).add_font_resource("F1", font);
+		}
+
+		let mut content = ContentStream::new();
+		let name = content.use_font(font);
+		content.begin_text().set_font(&name, 10.0).show_text(b"Third").end_text();
+		doc.add_page(612.0, 792.0).set_contents(content);
+
+		let mut output = Vec::new();
+		doc.write(&mut Writer::new(&mut output)).unwrap();
+		let output = String::from_utf8_lossy(&output);
+		assert_eq!(output.matches("/Type /Font\n").count(), 1);
+		assert_eq!(output.matches("/Resources << /Font << /F1 3 0 R >> >>").count(), 2);
+		assert_eq!(output.matches("/Resources << /Font << /F0 3 0 R >> >>").count(), 1);
+	}
+
+	#[test]
+	fn balanced_page_tree() {
+		let mut doc = Document::new();
+		doc.set_deterministic_id(true);
+		let pages: Vec<ObjectId> = (0..1000).map(|_| doc.add_page(612.0, 792.0).id()).collect();
+		doc.set_page_tree_branching(Some(10));
+
+		let mut output = Vec::new();
+		doc.write(&mut Writer::new(&mut output)).unwrap();
+		let reader = Reader::new(&output).unwrap();
+		assert_eq!(reader.pages().unwrap(), pages);
+
+		// Gets the number of pages below the node, checking its /Count and the
+		// parents of its kids, and the depth of the pages.
+		fn count(reader: &Reader<'_>, node: ObjectId, depth: usize, depths: &mut Vec<usize>) -> i64 {
+			let dict = reader.resolve(node).unwrap().as_dict().unwrap().clone();
+			if dict.get_name("Type") == Some(b"Page") {
+				depths.push(depth);
+				return 1;
+			}
+			let kids = dict.get("Kids").and_then(PdfObject::as_array).unwrap();
+			assert!(kids.len() <= 10);
+			let total = kids.iter().map(|kid| {
+				let kid = kid.as_reference().unwrap();
+				assert_eq!(reader.resolve(kid).unwrap().as_dict().unwrap().get("Parent"), Some(&PdfObject::Reference(node)));
+				return count(reader, kid, depth + 1, depths);
+			}).sum();
+			assert_eq!(dict.get("Count").and_then(PdfObject::as_integer), Some(total));
+			return total;
+		}
+		let mut depths = Vec::new();
+		assert_eq!(count(&reader, doc.pages_id(), 0, &mut depths), 1000);
+		assert!(depths.iter().all(|&depth| depth == 3));
+
+		let mut again = Vec::new();
+		doc.write(&mut Writer::new(&mut again)).unwrap();
+		assert_eq!(again, output);
+		doc.set_page_tree_branching(None);
+		let mut flat = Vec::new();
+		doc.write(&mut Writer::new(&mut flat)).unwrap();
+		let reader = Reader::new(&flat).unwrap();
+		assert_eq!(reader.object_ids().count(), 1002);
+		assert_eq!(reader.resolve(pages[0]).unwrap().as_dict().unwrap().get("Parent"), Some(&PdfObject::Reference(doc.pages_id())));
+	}
+
+	#[test]
+	fn content_arrays() {
+		let mut doc = Document::new();
+		let (helvetica, times) = (doc.add_base_font("Helvetica"), doc.add_base_font("Times-Roman"));
+		let mut page = doc.add_page(612.0, 792.0);
+		let mut first = page.new_content();
+		let name = first.use_font(helvetica);
+		first.begin_text().set_font(&name, 12.0).move_text(72.0, 720.0);
+		page.add_content(first).unwrap();
+		let mut second = page.new_content();
+		let name = second.use_font(times);
+		second.set_font(&name, 12.0).show_text(b"Hello").end_text();
+		page.add_content(second).unwrap();
+
+		let mut conflicting = ContentStream::new();
+		conflicting.use_font(times);
+		assert!(page.add_content(conflicting).is_err());
+		assert_eq!(page.operations().unwrap().len(), 6);
+
+		let mut output = Vec::new();
+		doc.write(&mut Writer::new(&mut output)).unwrap();
+		let reader = Reader::new(&output).unwrap();
+		let page = reader.page(0).unwrap();
+		let contents = page.get("Contents").unwrap().unwrap();
+		assert_eq!(contents.as_array().map(|contents| contents.iter().filter_map(PdfObject::as_reference).count()), Some(2));
+		let operators = page.operations().unwrap().into_iter().map(|operation| String::from_utf8(operation.operator).unwrap()).collect::<Vec<_>>();
+		assert_eq!(operators, ["BT", "Tf", "Td", "Tf", "Tj", "ET"]);
+		let fonts = page.get("Resources").unwrap().unwrap().as_dict().unwrap().get("Font").and_then(PdfObject::as_dict).unwrap().clone();
+		assert_eq!((fonts.get("F0"), fonts.get("F1")), (Some(&PdfObject::Reference(helvetica.0)), Some(&PdfObject::Reference(times.0))));
+	}
+
+	#[test]
+	fn compression_threshold() {
+		let mut doc = Document::new();
+		doc.set_compression(CompressionPolicy::Threshold(1024));
+		let mut small = ContentStream::new();
+		small.rect(Rectangle::new(0.0, 0.0, 10.0, 10.0)).fill();
+		assert_eq!(small.as_bytes().len(), 15);
+		doc.add_page(612.0, 792.0).set_contents(small.clone());
+		let mut large = ContentStream::new();
+		for i in 0..1000 {
+			large.move_to(Point::new(i as f64, 0.0)).line_to(Point::new(i as f64, 100.0));
+		}
+		large.stroke();
+		let length = large.as_bytes().len();
+		assert!(length > 10_000);
+		doc.add_page(612.0, 792.0).set_contents(large.clone());
+
+		let mut output = Vec::new();
+		doc.write(&mut Writer::new(&mut output)).unwrap();
+		let reader = Reader::new(&output).unwrap();
+		let contents = |index: usize| {
+			let id = reader.page(index).unwrap().get("Contents").unwrap().unwrap().as_reference().unwrap();
+			return reader.resolve(id).unwrap().as_stream().unwrap().clone();
+		};
+		assert_eq!(contents(0).dict.get("Filter"), None);
+		assert_eq!(contents(0).data, small.as_bytes());
+		assert_eq!(contents(1).dict.get_name("Filter"), Some(&b"FlateDecode"[..]));
+		assert!(contents(1).data.len() < length);
+		assert_eq!(reader.page(1).unwrap().contents().unwrap(), large.as_bytes());
+	}
+
+	#[test]
+	fn compact_documents() {
+		let build = |style: WriteStyle| {
+			let mut doc = Document::new();
+			doc.set_deterministic_id(true);
+			doc.set_write_style(style);
+			let font = doc.add_base_font("Helvetica");
+			let mut content = ContentStream::new();
+			let name = content.use_font(font);
+			content.begin_text().set_font(&name, 12.0).show_text(b"Compact").end_text();
+			doc.add_page(612.0, 792.0).set_contents(content);
+			let mut output = Vec::new();
+			doc.write(&mut Writer::new(&mut output)).unwrap();
+			return output;
+		};
+		let (default, compact) = (build(WriteStyle::Default), build(WriteStyle::Compact));
+		assert!(compact.len() < default.len());
+		for output in [compact, build(WriteStyle::Pretty)] {
+			let reader = Reader::new(&output).unwrap();
+			assert_eq!(reader.page(0).unwrap().extract_text().unwrap().trim(), "Compact");
+		}
+	}
+
+	#[test]
+	fn blank_documents() {
+		let mut output = Vec::new();
+		Document::empty().write(&mut Writer::new(&mut output)).unwrap();
+		let reader = Reader::with_mode(&output, ParseMode::Strict).unwrap();
+		assert_eq!(reader.page_count().unwrap(), 1);
+		let page = reader.resolve_dict(reader.pages().unwrap()[0]).unwrap();
+		assert_eq!(page.get("Contents"), None);
+		assert_eq!(page.get("MediaBox").and_then(Rectangle::from_pdf_array), Some(Rectangle::new(0.0, 0.0, 612.0, 792.0)));
+
+		let mut doc = Document::blank_pages(3);
+		assert_eq!(doc.page_count(), 3);
+		output.clear();
+		doc.write(&mut Writer::new(&mut output)).unwrap();
+		assert_eq!(Reader::new(&output).unwrap().page_count().unwrap(), 3);
+		assert_eq!(Document::blank_pages(0).page_count(), 0);
+	}
+
+	#[test]
+	fn binary_markers() {
+		let write = |marker| {
+			let mut doc = Document::empty();
+			doc.set_binary_marker(marker);
+			let mut output = Vec::new();
+			doc.write(&mut Writer::new(&mut output)).unwrap();
+			assert_eq!(Reader::with_mode(&output, ParseMode::Strict).unwrap().page_count().unwrap(), 1);
+			return output;
+		};
+
+		assert!(write(Some(DEFAULT_BINARY_MARKER)).starts_with(b"%PDF-1.7\n%\x80\x81\x82\x83\n1 0 obj"));
+		assert!(write(None).starts_with(b"%PDF-1.7\n1 0 obj"));
+		assert!(write(Some(*b"\xE2\xE3\xCF\xD3")).starts_with(b"%PDF-1.7\n%\xE2\xE3\xCF\xD3\n"));
+	}
+
+	#[test]
+	fn verified_offsets() {
+		let mut doc = Document::blank_pages(2);
+		doc.set_info(&DocumentInfo {
+			title: Some("Verified".to_string()),
+			..DocumentInfo::default()
+		});
+		let mut output = Vec::new();
+		doc.write_verified(&mut Writer::new(&mut output)).unwrap();
+		let reader = Reader::new(&output).unwrap();
+		assert_eq!(reader.page_count().unwrap(), 2);
+
+		// Moves the offset of the catalog by one byte.
+		let offset = output.windows(7).position(|window| window == b"1 0 obj").unwrap();
+		let entry = std::format!("{:010} 00000 n", offset);
+		let pos = output.windows(entry.len()).position(|window| window == entry.as_bytes()).unwrap();
+		output[pos..pos + 10].copy_from_slice(std::format!("{:010}", offset + 1).as_bytes());
+		let error = Reader::with_mode(&output, ParseMode::Strict).unwrap().verify_offsets().unwrap_err();
+		assert!(matches!(error, PdfError::Syntax { offset: moved, .. } if moved == offset + 1));
+	}
+
+	#[test]
+	fn verified_corrupted() {
+		// Streams can only be indirect objects, so the one nested in the
+		// array is written, but cannot be read back.
+		let mut doc = Document::blank_pages(1);
+		let stream = Stream::new(Dictionary::new(), b"0 0 m".to_vec());
+		doc.add_object(PdfObject::Array(vec![PdfObject::Stream(stream)]));
+		let mut output = Vec::new();
+		assert!(doc.write_verified(&mut Writer::new(&mut output)).is_err());
+		assert!(output.is_empty());
+	}
+
+	#[test]
+	fn default_page_size() {
+		let mut doc = Document::new();
+		doc.set_default_page_size(595.0, 842.0);
+		for _ in 0..3 {
+			doc.add_default_page();
+		}
+		assert_eq!(doc.page_mut(1).unwrap().media_box(), Some(Rectangle::new(0.0, 0.0, 595.0, 842.0)));
+
+		let mut output = Vec::new();
+		doc.write(&mut Writer::new(&mut output)).unwrap();
+		assert_eq!(String::from_utf8_lossy(&output).matches("/MediaBox").count(), 1);
+		let reader = Reader::new(&output).unwrap();
+		let root = reader.resolve_dict(reader.catalog().unwrap().get("Pages").and_then(PdfObject::as_reference).unwrap()).unwrap();
+		assert_eq!(root.get("MediaBox").and_then(Rectangle::from_pdf_array), Some(Rectangle::new(0.0, 0.0, 595.0, 842.0)));
+		for index in 0..3 {
+			let page = reader.page(index).unwrap();
+			assert!(!page.dict().contains_key("MediaBox"));
+			assert_eq!(page.get("MediaBox").unwrap().as_ref().and_then(Rectangle::from_pdf_array), Some(Rectangle::new(0.0, 0.0, 595.0, 842.0)));
+		}
+	}
+
+	#[test]
+	fn later_generations() {
+		let mut doc = Document::new();
+		let font = doc.add_base_font("Helvetica");
+		doc.add_page(612.0, 792.0).add_font_resource("F1", font);
+		let mut original = Vec::new();
+		doc.write(&mut Writer::new(&mut original)).unwrap();
+
+		// Objects of loaded documents can be of later generations, e.g. when
+		// their numbers were reused by an update.
+		let mut doc = Document::load(&original).unwrap();
+		let object = doc.objects.remove(&font.0).unwrap();
+		let font = ObjectId::new(font.0.number, 1);
+		doc.objects.insert(font, object);
+		doc.catalog_id = ObjectId::new(doc.catalog_id.number, 2);
+		let mut output = Vec::new();
+		doc.write_verified(&mut Writer::new(&mut output)).unwrap();
+
+		let text = String::from_utf8_lossy(&output);
+		assert!(text.contains(&std::format!("/Root {} 2 R", doc.catalog_id.number)));
+		let reader = Reader::with_mode(&output, ParseMode::Strict).unwrap();
+		assert_eq!(reader.resolve_dict(font).unwrap().get_name("BaseFont"), Some(&b"Helvetica"[..]));
+		assert!(reader.resolve_dict(doc.catalog_id).is_ok());
+	}
+
+	#[test]
+	fn compressed_loaded_text() {
+		let mut doc = Document::new();
+		doc.set_compression(CompressionPolicy::Threshold(0));
+		let font = doc.add_base_font("Helvetica");
+		let mut page = doc.add_page(612.0, 792.0);
+		page.add_font_resource("F1", font);
+		let mut content = ContentStream::new();
+		content.begin_text().set_font("F1", 12.0).move_text(72.0, 720.0).show_text(b"Hello, hello, hello").end_text();
+		page.set_contents(content);
+		let mut content = page.new_content();
+		content.begin_text().set_font("F1", 12.0).move_text(72.0, 700.0).show_text(b"World").end_text();
+		page.add_content(content).unwrap();
+		let mut output = Vec::new();
+		doc.write(&mut Writer::new(&mut output)).unwrap();
+		assert!(String::from_utf8_lossy(&output).contains("/FlateDecode"));
+
+		let mut doc = Document::load(&output).unwrap();
+		let page = doc.page_mut(0).unwrap();
+		assert_eq!(page.content_ids().len(), 2);
+		let text = page.extract_text().unwrap();
+		assert!(text.contains("Hello, hello, hello") && text.contains("World"));
+	}
+
+	#[test]
+	fn compression_keeps_contents() {
+		let mut doc = Document::new();
+		doc.set_compression(CompressionPolicy::Threshold(0));
+		let mut content = ContentStream::new();
+		content.begin_text().set_font("F1", 12.0).move_text(72.0, 720.0).show_text(b"Hello, hello, hello").end_text();
+		doc.add_page(612.0, 792.0).set_contents(content);
+		let mut first = Vec::new();
+		doc.write(&mut Writer::new(&mut first)).unwrap();
+		assert!(String::from_utf8_lossy(&first).contains("/Filter /FlateDecode"));
+
+		let page = doc.page_mut(0).unwrap();
+		let contents = page.content_ids()[0];
+		assert_eq!(page.extract_text().unwrap(), "Hello, hello, hello");
+		assert!(!doc.object(contents).and_then(PdfObject::as_stream).unwrap().dict.contains_key("Filter"));
+		let mut second = Vec::new();
+		doc.write(&mut Writer::new(&mut second)).unwrap();
+		assert_eq!(String::from_utf8_lossy(&second).matches("/FlateDecode").count(), 1);
+	}
+
+	#[test]
+	fn document_filters() {
+		use crate::filters::{Filter, FilterRegistry};
+		use std::sync::atomic::{AtomicUsize, Ordering};
+		static ENCODED: AtomicUsize = AtomicUsize::new(0);
+		struct Shift;
+		impl Filter for Shift {
+			fn decode(&self, data: &[u8], _: &Dictionary, _: Option<usize>) -> Result<Vec<u8>, PdfError> {
+				return Ok(data.iter().map(|byte| byte.wrapping_sub(1)).collect());
+			}
+
+			fn encode(&self, data: &[u8]) -> Option<Vec<u8>> {
+				ENCODED.fetch_add(1, Ordering::Relaxed);
+				return Some(data.iter().map(|byte| byte.wrapping_add(1)).collect());
+			}
+		}
+
+		let mut doc = Document::new();
+		let font = doc.add_base_font("Helvetica");
+		let mut page = doc.add_page(612.0, 792.0);
+		page.add_font_resource("F1", font);
+		let mut content = ContentStream::new();
+		content.begin_text().set_font("F1", 12.0).show_text(b"Shifted").end_text();
+		page.set_contents(content);
+		let contents = page.content_ids()[0];
+		let Some(PdfObject::Stream(stream)) = doc.object_mut(contents) else {
+			unreachable!();
+		};
+		stream.data = stream.data.iter().map(|byte| byte + 1).collect();
+		stream.dict.set("Filter", PdfObject::name("Shift"));
+		assert!(doc.page_mut(0).unwrap().extract_text().is_err());
+
+		let mut registry = FilterRegistry::new();
+		registry.register("Shift", Shift);
+		doc.set_filters(registry.clone());
+		assert_eq!(doc.page_mut(0).unwrap().extract_text().unwrap(), "Shifted");
+		let mut output = Vec::new();
+		doc.write(&mut Writer::new(&mut output)).unwrap();
+		let mut loaded = Document::load_with_filters(&output, registry.clone()).unwrap();
+		assert_eq!(loaded.page_mut(0).unwrap().extract_text().unwrap(), "Shifted");
+
+		// Written streams are compressed with the registered filter as well.
+		registry.register("FlateDecode", Shift);
+		let mut doc = Document::new();
+		doc.set_filters(registry);
+		doc.set_compression(CompressionPolicy::Threshold(0));
+		let mut page = doc.add_page(612.0, 792.0);
+		let mut content = ContentStream::new();
+		content.rect(Rectangle::new(0.0, 0.0, 10.0, 10.0)).fill();
+		page.set_contents(content);
+		doc.write(&mut Writer::new(&mut Vec::new())).unwrap();
+		assert_eq!(ENCODED.load(Ordering::Relaxed), 1);
+	}
+}
@@ -0,0 +1,61 @@
+//! Files embedded in the document, e.g. attachments, which are listed in the
+//! `/EmbeddedFiles` name tree.
+
+use crate::{filters, Dictionary, Document, ObjectId, PdfError, PdfObject, Stream};
+
+impl Document {
+	/// Attempts to embed the file with the given name, and optionally its MIME
+	/// type, e.g. `text/plain`, listing it in the `/EmbeddedFiles` name tree.
+	/// Returns the file specification. Embedding a file with the same name
+	/// again replaces it in the tree.
+	pub fn add_embedded_file(&mut self, name: &str, data: &[u8], mime_type: Option<&str>) -> Result<ObjectId, PdfError> {
+		let mut file = Stream::new(Dictionary::new(), filters::flate::encode(data));
+		file.dict.set("Type", PdfObject::name("EmbeddedFile"));
+		if let Some(mime_type) = mime_type {
+			file.dict.set("Subtype", PdfObject::name(mime_type));
+		}
+		file.dict.set("Filter", PdfObject::name("FlateDecode"));
+		let mut params = Dictionary::new();
+		params.set("Size", data.len());
+		file.dict.set("Params", params);
+		let file = self.add_object(file);
+
+		let mut spec = Dictionary::new();
+		spec.set("Type", PdfObject::name("Filespec"));
+		spec.set("F", PdfObject::string(name));
+		spec.set("UF", PdfObject::text_string(name));
+		let mut files = Dictionary::new();
+		files.set("F", file);
+		spec.set("EF", files);
+		let spec = self.add_object(spec);
+
+		self.insert_into_name_tree("EmbeddedFiles", name.as_bytes(), spec.into())?;
+		return Ok(spec);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::*;
+
+	#[test]
+	fn embedded_files() {
+		let mut doc = Document::new();
+		doc.add_page(612.0, 792.0);
+		doc.add_embedded_file("notes.txt", b"First", None).unwrap();
+		let spec = doc.add_embedded_file("notes.txt", b"Second", Some("text/plain")).unwrap();
+
+		let mut output = Vec::new();
+		doc.write(&mut Writer::new(&mut output)).unwrap();
+		let reader = Reader::new(&output).unwrap();
+		let found = reader.name_tree_value("EmbeddedFiles", b"notes.txt").unwrap();
+		assert_eq!(found, Some(PdfObject::Reference(spec)));
+		let spec = reader.resolve_dict(spec).unwrap();
+		assert_eq!(spec.get("UF"), Some(&PdfObject::text_string("notes.txt")));
+		let file = spec.get("EF").and_then(PdfObject::as_dict).and_then(|files| files.get("F")).and_then(PdfObject::as_reference).unwrap();
+		assert_eq!(&*reader.stream_decoded(file).unwrap(), b"Second");
+		let file = reader.resolve_dict(file).unwrap();
+		assert_eq!(file.get_name("Subtype"), Some(&b"text/plain"[..]));
+		assert_eq!(file.get("Params").and_then(PdfObject::as_dict).and_then(|params| params.get("Size")), Some(&PdfObject::Integer(6)));
+	}
+}
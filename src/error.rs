@@ -0,0 +1,79 @@
+//! Error type shared by the parser, reader, and writer.
+
+#[derive(Debug)]
+pub enum PdfError {
+	/// Underlying stream failed to read or write.
+	Io(std::io::Error),
+	/// Input does not follow the PDF syntax at the given byte offset.
+	Syntax {
+		offset: usize,
+		message: String,
+	},
+	/// Stream data could not be decoded using the named filter.
+	Filter {
+		filter: String,
+		message: String,
+	},
+	/// Embedded font program is malformed or unsupported.
+	Font(String),
+	/// Referenced object does not exist in the file.
+	MissingObject(crate::ObjectId),
+	/// Argument of an operation is not valid for the document.
+	InvalidArgument(String),
+	/// Page index is not lower than the number of pages.
+	PageOutOfRange {
+		index: usize,
+		count: usize,
+	},
+	/// Document doesn't meet a constraint of the standard it should conform to.
+	Conformance(String),
+}
+
+impl PdfError {
+	#[inline]
+	pub(crate) fn syntax(offset: usize, message: impl Into<String>) -> Self {
+		return Self::Syntax {
+			offset,
+			message: message.into(),
+		};
+	}
+
+	#[inline]
+	pub(crate) fn filter(filter: &str, message: impl Into<String>) -> Self {
+		return Self::Filter {
+			filter: filter.to_string(),
+			message: message.into(),
+		};
+	}
+}
+
+impl std::fmt::Display for PdfError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		return match self {
+			Self::Io(error) => write!(f, "i/o error: {}", error),
+			Self::Syntax { offset, message } => write!(f, "syntax error at offset {}: {}", offset, message),
+			Self::Filter { filter, message } => write!(f, "{} filter failed: {}", filter, message),
+			Self::Font(message) => write!(f, "invalid font: {}", message),
+			Self::MissingObject(id) => write!(f, "object {} {} R does not exist", id.number, id.generation),
+			Self::InvalidArgument(message) => write!(f, "invalid argument: {}", message),
+			Self::PageOutOfRange { index, count } => write!(f, "page {} is out of range, document has {} pages", index, count),
+			Self::Conformance(message) => write!(f, "conformance error: {}", message),
+		};
+	}
+}
+
+impl std::error::Error for PdfError {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		return match self {
+			Self::Io(error) => Some(error),
+			_ => None,
+		};
+	}
+}
+
+impl From<std::io::Error> for PdfError {
+	#[inline]
+	fn from(error: std::io::Error) -> Self {
+		return Self::Io(error);
+	}
+}
@@ -0,0 +1,129 @@
+//! `ASCII85Decode` and `ASCIIHexDecode` filters, which represent binary data
+//! with printable characters only.
+
+use crate::lexer::is_whitespace;
+use crate::PdfError;
+
+const FILTER: &str = "ASCII85Decode";
+const HEX_FILTER: &str = "ASCIIHexDecode";
+
+/// Attempts to decode the base-85 data, up to the `~>` end-of-data marker.
+pub fn decode(data: &[u8]) -> Result<Vec<u8>, PdfError> {
+	let mut out = Vec::with_capacity(data.len() * 4 / 5);
+	let mut group = [0u8; 5];
+	let mut count = 0;
+	let mut bytes = data.iter().copied().filter(|&byte| !is_whitespace(byte));
+	while let Some(byte) = bytes.next() {
+		match byte {
+			b'~' => {
+				if bytes.next() != Some(b'>') {
+					return Err(PdfError::filter(FILTER, "invalid end-of-data marker"));
+				}
+				break;
+			},
+			b'z' if count == 0 => out.extend_from_slice(&[0; 4]),
+			b'!'..=b'u' => {
+				group[count] = byte - b'!';
+				count += 1;
+				if count == 5 {
+					out.extend_from_slice(&group_value(&group)?.to_be_bytes());
+					count = 0;
+				}
+			},
+			_ => return Err(PdfError::filter(FILTER, std::format!("invalid character {:?}", byte as char))),
+		}
+	}
+
+	// The final partial group of n characters encodes n - 1 bytes, with the
+	// missing characters being the highest digit.
+	if count == 1 {
+		return Err(PdfError::filter(FILTER, "final group is too short"));
+	}
+	if count > 1 {
+		group[count..].fill(b'u' - b'!');
+		out.extend_from_slice(&group_value(&group)?.to_be_bytes()[..count - 1]);
+	}
+	return Ok(out);
+}
+
+fn group_value(group: &[u8; 5]) -> Result<u32, PdfError> {
+	let value = group.iter().fold(0u64, |value, &digit| value * 85 + digit as u64);
+	return u32::try_from(value).map_err(|_| PdfError::filter(FILTER, "group is out of range"));
+}
+
+/// Encodes the data in base 85, with the end-of-data marker.
+pub fn encode(data: &[u8]) -> Vec<u8> {
+	let mut out = Vec::with_capacity(data.len() * 5 / 4 + 2);
+	for chunk in data.chunks(4) {
+		let mut bytes = [0u8; 4];
+		bytes[..chunk.len()].copy_from_slice(chunk);
+		let mut value = u32::from_be_bytes(bytes);
+		if value == 0 && chunk.len() == 4 {
+			out.push(b'z');
+			continue;
+		}
+
+		let mut digits = [0u8; 5];
+		for digit in digits.iter_mut().rev() {
+			*digit = (value % 85) as u8 + b'!';
+			value /= 85;
+		}
+		out.extend_from_slice(&digits[..chunk.len() + 1]);
+	}
+	out.extend_from_slice(b"~>");
+	return out;
+}
+
+/// Attempts to decode the hexadecimal data, up to the `>` end-of-data marker.
+pub fn decode_hex(data: &[u8]) -> Result<Vec<u8>, PdfError> {
+	let mut out = Vec::with_capacity(data.len() / 2);
+	let mut high = None;
+	for &byte in data.iter().filter(|&&byte| !is_whitespace(byte)) {
+		if byte == b'>' {
+			break;
+		}
+		let Some(value) = (byte as char).to_digit(16) else {
+			return Err(PdfError::filter(HEX_FILTER, std::format!("invalid character {:?}", byte as char)));
+		};
+		match high.take() {
+			Some(high) => out.push((high << 4 | value) as u8),
+			None => high = Some(value),
+		}
+	}
+	// Missing final digit is assumed to be zero.
+	if let Some(high) = high {
+		out.push((high << 4) as u8);
+	}
+	return Ok(out);
+}
+
+/// Encodes the data in hexadecimal, with the end-of-data marker.
+pub fn encode_hex(data: &[u8]) -> Vec<u8> {
+	const DIGITS: &[u8; 16] = b"0123456789ABCDEF";
+
+	let mut out = Vec::with_capacity(data.len() * 2 + 1);
+	for &byte in data {
+		out.extend_from_slice(&[DIGITS[(byte >> 4) as usize], DIGITS[(byte & 0xf) as usize]]);
+	}
+	out.push(b'>');
+	return out;
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::filters::ascii85::*;
+
+	#[test]
+	fn round_trips() {
+		assert_eq!(encode(b"Man "), b"9jqo^~>");
+		assert_eq!(encode(&[0, 0, 0, 0, 1]), b"z!<~>");
+		assert_eq!(decode(b"9jqo^\nBla~>").unwrap(), b"Man is");
+		assert!(decode(b"9jqo^B~>").is_err());
+		for length in 0..12 {
+			let data: Vec<u8> = (0..length).map(|byte| (byte * 37 + 200) as u8).collect();
+			assert_eq!(decode(&encode(&data)).unwrap(), data);
+			assert_eq!(decode_hex(&encode_hex(&data)).unwrap(), data);
+		}
+		assert_eq!(decode_hex(b"48 65 6c6C 7>").unwrap(), b"Hellp");
+	}
+}
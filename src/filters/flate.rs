@@ -0,0 +1,703 @@
+//! Encoder and decoder for the `FlateDecode` filter, i.e. zlib wrapped
+//! DEFLATE data, as described in RFC 1950 and RFC 1951.
+
+use crate::PdfError;
+
+const FILTER: &str = "FlateDecode";
+
+/// Base lengths and number of extra bits of the length codes 257 to 285.
+const LENGTH_BASE: [u16; 29] = [3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 258];
+const LENGTH_EXTRA: [u8; 29] = [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0];
+/// Base distances and number of extra bits of the distance codes.
+const DISTANCE_BASE: [u16; 30] = [1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537, 2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577];
+const DISTANCE_EXTRA: [u8; 30] = [0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13];
+/// Order in which the code length code lengths are stored.
+const CODE_LENGTH_ORDER: [usize; 19] = [16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15];
+
+const MAX_BITS: usize = 15;
+const WINDOW_SIZE: usize = 32768;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+
+/// Code lengths of the fixed literal/length and distance codes.
+fn fixed_lengths() -> (Vec<u8>, Vec<u8>) {
+	let mut literals = std::vec![8; 288];
+	literals[144..256].fill(9);
+	literals[256..280].fill(7);
+	return (literals, std::vec![5; 30]);
+}
+
+/// Computes the Adler-32 checksum of the data.
+fn adler32(data: &[u8]) -> u32 {
+	let (mut a, mut b) = (1u32, 0u32);
+	// Largest number of bytes which can't overflow the sums before the modulo.
+	for chunk in data.chunks(5552) {
+		for &byte in chunk {
+			a += byte as u32;
+			b += a;
+		}
+		a %= 65521;
+		b %= 65521;
+	}
+	return (b << 16) | a;
+}
+
+/// Reader of the bits packed starting with the least significant one.
+struct BitReader<'a> {
+	data: &'a [u8],
+	pos: usize,
+	buffer: u32,
+	count: u32,
+}
+
+impl BitReader<'_> {
+	fn bits(&mut self, count: u32) -> Result<u32, PdfError> {
+		while self.count < count {
+			let Some(&byte) = self.data.get(self.pos) else {
+				return Err(PdfError::filter(FILTER, "unexpected end of data"));
+			};
+			self.buffer |= (byte as u32) << self.count;
+			self.pos += 1;
+			self.count += 8;
+		}
+
+		let value = self.buffer & ((1u32 << count) - 1);
+		self.buffer >>= count;
+		self.count -= count;
+		return Ok(value);
+	}
+
+	/// Discards the bits up to the next byte boundary.
+	fn align(&mut self) {
+		self.buffer = 0;
+		self.count = 0;
+	}
+}
+
+/// Canonical Huffman code used for decoding, stored as the number of codes of
+/// each length and the symbols ordered by their codes.
+struct Huffman {
+	counts: [u16; MAX_BITS + 1],
+	symbols: Vec<u16>,
+}
+
+impl Huffman {
+	fn new(lengths: &[u8]) -> Result<Self, PdfError> {
+		let mut counts = [0u16; MAX_BITS + 1];
+		for &length in lengths {
+			counts[length as usize] += 1;
+		}
+		counts[0] = 0;
+
+		// Incomplete codes are allowed, as encoders use them for single symbols.
+		let mut left = 1i32;
+		for &count in &counts[1..] {
+			left = (left << 1) - count as i32;
+			if left < 0 {
+				return Err(PdfError::filter(FILTER, "over-subscribed Huffman code"));
+			}
+		}
+
+		let mut offsets = [0u16; MAX_BITS + 2];
+		for length in 1..=MAX_BITS {
+			offsets[length + 1] = offsets[length] + counts[length];
+		}
+
+		let mut symbols = std::vec![0; offsets[MAX_BITS + 1] as usize];
+		for (symbol, &length) in lengths.iter().enumerate() {
+			if length != 0 {
+				symbols[offsets[length as usize] as usize] = symbol as u16;
+				offsets[length as usize] += 1;
+			}
+		}
+
+		return Ok(Self { counts, symbols });
+	}
+
+	fn decode(&self, reader: &mut BitReader<'_>) -> Result<u16, PdfError> {
+		let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+		for length in 1..=MAX_BITS {
+			code |= reader.bits(1)? as i32;
+			let count = self.counts[length] as i32;
+			if code - count < first {
+				return Ok(self.symbols[(index + code - first) as usize]);
+			}
+			index += count;
+			first = (first + count) << 1;
+			code <<= 1;
+		}
+
+		return Err(PdfError::filter(FILTER, "invalid Huffman code"));
+	}
+}
+
+/// Inflates the raw DEFLATE data, returning the decoded bytes and the position
+/// just after the last block.
+fn inflate(data: &[u8], capacity: usize) -> Result<(Vec<u8>, usize), PdfError> {
+	let mut reader = BitReader {
+		data,
+		pos: 0,
+		buffer: 0,
+		count: 0,
+	};
+	let mut out = Vec::with_capacity(capacity);
+
+	loop {
+		let last = reader.bits(1)? == 1;
+		match reader.bits(2)? {
+			0 => {
+				reader.align();
+				let Some(header) = data.get(reader.pos..reader.pos + 4) else {
+					return Err(PdfError::filter(FILTER, "unexpected end of data"));
+				};
+				let length = u16::from_le_bytes([header[0], header[1]]);
+				if length != !u16::from_le_bytes([header[2], header[3]]) {
+					return Err(PdfError::filter(FILTER, "stored block length mismatch"));
+				}
+
+				let start = reader.pos + 4;
+				let Some(bytes) = data.get(start..start + length as usize) else {
+					return Err(PdfError::filter(FILTER, "unexpected end of data"));
+				};
+				out.extend_from_slice(bytes);
+				reader.pos = start + length as usize;
+			},
+			1 => {
+				let (literals, distances) = fixed_lengths();
+				inflate_block(&mut reader, &mut out, &Huffman::new(&literals)?, &Huffman::new(&distances)?)?;
+			},
+			2 => {
+				let (literals, distances) = dynamic_codes(&mut reader)?;
+				inflate_block(&mut reader, &mut out, &literals, &distances)?;
+			},
+			_ => return Err(PdfError::filter(FILTER, "invalid block type")),
+		}
+
+		if last {
+			break;
+		}
+	}
+
+	return Ok((out, reader.pos));
+}
+
+/// Reads the code definitions from the header of a dynamic block.
+fn dynamic_codes(reader: &mut BitReader<'_>) -> Result<(Huffman, Huffman), PdfError> {
+	let literal_count = reader.bits(5)? as usize + 257;
+	let distance_count = reader.bits(5)? as usize + 1;
+	let code_count = reader.bits(4)? as usize + 4;
+	if literal_count > 286 || distance_count > 30 {
+		return Err(PdfError::filter(FILTER, "too many codes in dynamic block"));
+	}
+
+	let mut code_lengths = [0u8; 19];
+	for &index in &CODE_LENGTH_ORDER[..code_count] {
+		code_lengths[index] = reader.bits(3)? as u8;
+	}
+	let code = Huffman::new(&code_lengths)?;
+
+	let mut lengths = Vec::with_capacity(literal_count + distance_count);
+	while lengths.len() < literal_count + distance_count {
+		let symbol = code.decode(reader)?;
+		let (value, repeat) = match symbol {
+			0..=15 => (symbol as u8, 1),
+			16 => {
+				let Some(&previous) = lengths.last() else {
+					return Err(PdfError::filter(FILTER, "repeated code length without a previous one"));
+				};
+				(previous, 3 + reader.bits(2)?)
+			},
+			17 => (0, 3 + reader.bits(3)?),
+			_ => (0, 11 + reader.bits(7)?),
+		};
+
+		if lengths.len() + repeat as usize > literal_count + distance_count {
+			return Err(PdfError::filter(FILTER, "too many code lengths"));
+		}
+		lengths.extend(std::iter::repeat_n(value, repeat as usize));
+	}
+
+	if lengths[256] == 0 {
+		return Err(PdfError::filter(FILTER, "missing end-of-block code"));
+	}
+	return Ok((Huffman::new(&lengths[..literal_count])?, Huffman::new(&lengths[literal_count..])?));
+}
+
+fn inflate_block(reader: &mut BitReader<'_>, out: &mut Vec<u8>, literals: &Huffman, distances: &Huffman) -> Result<(), PdfError> {
+	loop {
+		let symbol = literals.decode(reader)? as usize;
+		match symbol {
+			0..=255 => out.push(symbol as u8),
+			256 => return Ok(()),
+			257..=285 => {
+				let index = symbol - 257;
+				let length = LENGTH_BASE[index] as usize + reader.bits(LENGTH_EXTRA[index] as u32)? as usize;
+
+				let index = distances.decode(reader)? as usize;
+				if index >= DISTANCE_BASE.len() {
+					return Err(PdfError::filter(FILTER, "invalid distance code"));
+				}
+				let distance = DISTANCE_BASE[index] as usize + reader.bits(DISTANCE_EXTRA[index] as u32)? as usize;
+				if distance > out.len() {
+					return Err(PdfError::filter(FILTER, "distance is too far back"));
+				}
+
+				// Copies may overlap with the bytes they produce.
+				let start = out.len() - distance;
+				for i in 0..length {
+					out.push(out[start + i]);
+				}
+			},
+			_ => return Err(PdfError::filter(FILTER, "invalid literal/length code")),
+		}
+	}
+}
+
+/// Decodes the zlib stream.
+pub fn decode(data: &[u8]) -> Result<Vec<u8>, PdfError> {
+	return decode_with_capacity(data, data.len() * 4);
+}
+
+/// Decodes the zlib stream, preallocating the given number of bytes for the
+/// output.
+pub(crate) fn decode_with_capacity(data: &[u8], capacity: usize) -> Result<Vec<u8>, PdfError> {
+	let [cmf, flg, ..] = *data else {
+		return Err(PdfError::filter(FILTER, "missing zlib header"));
+	};
+	if cmf & 0x0F != 8 || !(cmf as u16 * 256 + flg as u16).is_multiple_of(31) {
+		return Err(PdfError::filter(FILTER, "invalid zlib header"));
+	}
+	if flg & 0x20 != 0 {
+		return Err(PdfError::filter(FILTER, "preset dictionaries are not supported"));
+	}
+
+	let (out, end) = inflate(&data[2..], capacity)?;
+	// Many writers leave out the checksum, so we only verify it when present.
+	if let Some(checksum) = data.get(2 + end..2 + end + 4) {
+		if u32::from_be_bytes([checksum[0], checksum[1], checksum[2], checksum[3]]) != adler32(&out) {
+			return Err(PdfError::filter(FILTER, "checksum mismatch"));
+		}
+	}
+	return Ok(out);
+}
+
+/// Writer of the bits packed starting with the least significant one.
+struct BitWriter {
+	out: Vec<u8>,
+	buffer: u64,
+	count: u32,
+}
+
+impl BitWriter {
+	fn bits(&mut self, value: u32, count: u32) {
+		self.buffer |= (value as u64) << self.count;
+		self.count += count;
+		while self.count >= 8 {
+			self.out.push(self.buffer as u8);
+			self.buffer >>= 8;
+			self.count -= 8;
+		}
+	}
+
+	/// Writes the Huffman code, which is stored starting from its most
+	/// significant bit.
+	fn code(&mut self, code: u16, length: u8) {
+		let reversed = code.reverse_bits() >> (16 - length as u32);
+		self.bits(reversed as u32, length as u32);
+	}
+
+	fn align(&mut self) {
+		if self.count > 0 {
+			self.bits(0, 8 - self.count);
+		}
+	}
+}
+
+/// Computes the Huffman code lengths for the frequencies, limited to the given
+/// number of bits. When the optimal code is too long, the frequencies are
+/// flattened until it fits.
+fn code_lengths(frequencies: &[u32], limit: u8) -> Vec<u8> {
+	let mut frequencies = frequencies.to_vec();
+	loop {
+		let lengths = huffman_lengths(&frequencies);
+		if lengths.iter().all(|&length| length <= limit) {
+			return lengths;
+		}
+
+		for frequency in frequencies.iter_mut().filter(|frequency| **frequency > 0) {
+			*frequency = frequency.div_ceil(2);
+		}
+	}
+}
+
+fn huffman_lengths(frequencies: &[u32]) -> Vec<u8> {
+	let mut lengths = std::vec![0; frequencies.len()];
+	let used: Vec<usize> = (0..frequencies.len()).filter(|&symbol| frequencies[symbol] > 0).collect();
+	match used.len() {
+		0 => return lengths,
+		1 => {
+			lengths[used[0]] = 1;
+			return lengths;
+		},
+		_ => {},
+	}
+
+	// Nodes are the used symbols followed by the internal nodes, each internal
+	// node pointing at its parent once it's merged.
+	let mut parents = std::vec![usize::MAX; used.len()];
+	let mut heap: std::collections::BinaryHeap<std::cmp::Reverse<(u32, usize)>> = used.iter().enumerate().map(|(node, &symbol)| std::cmp::Reverse((frequencies[symbol], node))).collect();
+	while heap.len() > 1 {
+		let std::cmp::Reverse((first, a)) = heap.pop().unwrap();
+		let std::cmp::Reverse((second, b)) = heap.pop().unwrap();
+		let node = parents.len();
+		parents.push(usize::MAX);
+		parents[a] = node;
+		parents[b] = node;
+		heap.push(std::cmp::Reverse((first + second, node)));
+	}
+
+	for (node, &symbol) in used.iter().enumerate() {
+		let mut depth = 0;
+		let mut current = node;
+		while parents[current] != usize::MAX {
+			current = parents[current];
+			depth += 1;
+		}
+		lengths[symbol] = depth;
+	}
+	return lengths;
+}
+
+/// Assigns the canonical codes to the code lengths.
+fn canonical_codes(lengths: &[u8]) -> Vec<u16> {
+	let mut counts = [0u16; MAX_BITS + 1];
+	for &length in lengths {
+		counts[length as usize] += 1;
+	}
+	counts[0] = 0;
+
+	let mut next = [0u16; MAX_BITS + 1];
+	let mut code = 0;
+	for length in 1..=MAX_BITS {
+		code = (code + counts[length - 1]) << 1;
+		next[length] = code;
+	}
+
+	return lengths
+		.iter()
+		.map(|&length| {
+			if length == 0 {
+				return 0;
+			}
+			let code = next[length as usize];
+			next[length as usize] += 1;
+			return code;
+		})
+		.collect();
+}
+
+/// Literal byte, or a back-reference into the already written data.
+#[derive(Clone, Copy)]
+enum Symbol {
+	Literal(u8),
+	Match { length: u16, distance: u16 },
+}
+
+impl Symbol {
+	/// Gets the literal/length code of the symbol.
+	fn code(self) -> usize {
+		return match self {
+			Self::Literal(byte) => byte as usize,
+			Self::Match { length, .. } => 257 + LENGTH_BASE.iter().rposition(|&base| base <= length).unwrap(),
+		};
+	}
+}
+
+fn distance_code(distance: u16) -> usize {
+	return DISTANCE_BASE.iter().rposition(|&base| base <= distance).unwrap();
+}
+
+/// Finds the repeated sequences in the data using hash chains.
+struct Matcher<'a> {
+	data: &'a [u8],
+	head: Vec<usize>,
+	prev: Vec<usize>,
+}
+
+impl<'a> Matcher<'a> {
+	const HASH_BITS: usize = 15;
+	const MAX_CHAIN: usize = 128;
+
+	fn new(data: &'a [u8]) -> Self {
+		return Self {
+			data,
+			head: std::vec![usize::MAX; 1 << Self::HASH_BITS],
+			prev: std::vec![usize::MAX; WINDOW_SIZE],
+		};
+	}
+
+	fn hash(&self, pos: usize) -> usize {
+		let value = (self.data[pos] as usize) << 16 | (self.data[pos + 1] as usize) << 8 | self.data[pos + 2] as usize;
+		return (value.wrapping_mul(0x9E37_79B1) >> 8) & ((1 << Self::HASH_BITS) - 1);
+	}
+
+	fn insert(&mut self, pos: usize) {
+		if pos + MIN_MATCH > self.data.len() {
+			return;
+		}
+		let hash = self.hash(pos);
+		self.prev[pos % WINDOW_SIZE] = self.head[hash];
+		self.head[hash] = pos;
+	}
+
+	/// Finds the longest match for the data at the position, not extending
+	/// past the end.
+	fn longest(&self, pos: usize, end: usize) -> Option<(usize, usize)> {
+		if pos + MIN_MATCH > end {
+			return None;
+		}
+
+		let limit = (end - pos).min(MAX_MATCH);
+		let mut best: Option<(usize, usize)> = None;
+		let mut candidate = self.head[self.hash(pos)];
+		for _ in 0..Self::MAX_CHAIN {
+			if candidate == usize::MAX || candidate >= pos || pos - candidate > WINDOW_SIZE {
+				break;
+			}
+
+			let length = self.data[candidate..].iter().zip(&self.data[pos..pos + limit]).take_while(|(a, b)| a == b).count();
+			if length >= MIN_MATCH && best.is_none_or(|(best, _)| length > best) {
+				best = Some((length, pos - candidate));
+				if length == limit {
+					break;
+				}
+			}
+
+			let previous = self.prev[candidate % WINDOW_SIZE];
+			// Entries of the ring buffer get overwritten by newer positions.
+			if previous != usize::MAX && previous >= candidate {
+				break;
+			}
+			candidate = previous;
+		}
+		return best;
+	}
+}
+
+/// Number of input bytes compressed into a single block, which is also the
+/// largest possible stored block.
+const BLOCK_SIZE: usize = u16::MAX as usize;
+
+/// Encodes the data as a zlib stream.
+pub fn encode(data: &[u8]) -> Vec<u8> {
+	let mut writer = BitWriter {
+		out: std::vec![0x78, 0x9C],
+		buffer: 0,
+		count: 0,
+	};
+
+	let mut matcher = Matcher::new(data);
+	let mut start = 0;
+	loop {
+		let end = (start + BLOCK_SIZE).min(data.len());
+		let mut symbols = Vec::new();
+		let mut pos = start;
+		while pos < end {
+			match matcher.longest(pos, end) {
+				Some((length, distance)) => {
+					symbols.push(Symbol::Match {
+						length: length as u16,
+						distance: distance as u16,
+					});
+					for i in pos..pos + length {
+						matcher.insert(i);
+					}
+					pos += length;
+				},
+				None => {
+					symbols.push(Symbol::Literal(data[pos]));
+					matcher.insert(pos);
+					pos += 1;
+				},
+			}
+		}
+
+		write_block(&mut writer, &data[start..end], &symbols, end == data.len());
+		if end == data.len() {
+			break;
+		}
+		start = end;
+	}
+
+	writer.align();
+	writer.out.extend_from_slice(&adler32(data).to_be_bytes());
+	return writer.out;
+}
+
+/// Writes the block in whichever of the stored, fixed, or dynamic forms is the
+/// smallest.
+fn write_block(writer: &mut BitWriter, data: &[u8], symbols: &[Symbol], last: bool) {
+	let mut literal_frequencies = [0u32; 286];
+	let mut distance_frequencies = [0u32; 30];
+	for &symbol in symbols {
+		literal_frequencies[symbol.code()] += 1;
+		if let Symbol::Match { distance, .. } = symbol {
+			distance_frequencies[distance_code(distance)] += 1;
+		}
+	}
+	literal_frequencies[256] = 1;
+
+	let literals = code_lengths(&literal_frequencies, MAX_BITS as u8);
+	let mut distances = code_lengths(&distance_frequencies, MAX_BITS as u8);
+	if distances.iter().all(|&length| length == 0) {
+		// At least one distance code has to be defined.
+		distances[0] = 1;
+	}
+
+	let literal_count = 257 + literals[257..].iter().rposition(|&length| length != 0).map_or(0, |index| index + 1);
+	let distance_count = 1 + distances.iter().rposition(|&length| length != 0).unwrap_or(0);
+	let header = run_lengths(&[&literals[..literal_count], &distances[..distance_count]].concat());
+
+	let mut code_frequencies = [0u32; 19];
+	for &(symbol, _) in &header {
+		code_frequencies[symbol as usize] += 1;
+	}
+	let code_lengths = code_lengths(&code_frequencies, 7);
+	let code_count = 4.max(1 + CODE_LENGTH_ORDER.iter().rposition(|&index| code_lengths[index] != 0).unwrap_or(0));
+
+	let symbols_cost = |literals: &[u8], distances: &[u8]| -> usize {
+		let mut cost = literals[256] as usize;
+		for &symbol in symbols {
+			let code = symbol.code();
+			cost += literals[code] as usize;
+			if let Symbol::Match { distance, .. } = symbol {
+				let index = distance_code(distance);
+				cost += LENGTH_EXTRA[code - 257] as usize + distances[index] as usize + DISTANCE_EXTRA[index] as usize;
+			}
+		}
+		return cost;
+	};
+
+	let (fixed_literals, fixed_distances) = fixed_lengths();
+	let fixed_cost = symbols_cost(&fixed_literals, &fixed_distances);
+	let header_cost = 14 + code_count * 3 + header.iter().map(|&(symbol, _)| code_lengths[symbol as usize] as usize + [2, 3, 7].get((symbol as usize).wrapping_sub(16)).copied().unwrap_or(0)).sum::<usize>();
+	let dynamic_cost = header_cost + symbols_cost(&literals, &distances);
+	// Stored blocks come with a 32 bit header, after the padding.
+	let stored_cost = (data.len() + 4) * 8 + 8;
+
+	writer.bits(last as u32, 1);
+	if stored_cost < fixed_cost.min(dynamic_cost) {
+		writer.bits(0, 2);
+		writer.align();
+		let length = data.len() as u16;
+		writer.out.extend_from_slice(&length.to_le_bytes());
+		writer.out.extend_from_slice(&(!length).to_le_bytes());
+		writer.out.extend_from_slice(data);
+		return;
+	}
+
+	if fixed_cost <= dynamic_cost {
+		writer.bits(1, 2);
+		write_symbols(writer, symbols, &fixed_literals, &fixed_distances);
+		return;
+	}
+
+	writer.bits(2, 2);
+	writer.bits((literal_count - 257) as u32, 5);
+	writer.bits((distance_count - 1) as u32, 5);
+	writer.bits((code_count - 4) as u32, 4);
+	for &index in &CODE_LENGTH_ORDER[..code_count] {
+		writer.bits(code_lengths[index] as u32, 3);
+	}
+
+	let codes = canonical_codes(&code_lengths);
+	for (symbol, extra) in header {
+		writer.code(codes[symbol as usize], code_lengths[symbol as usize]);
+		match symbol {
+			16 => writer.bits(extra as u32, 2),
+			17 => writer.bits(extra as u32, 3),
+			18 => writer.bits(extra as u32, 7),
+			_ => {},
+		}
+	}
+	write_symbols(writer, symbols, &literals, &distances);
+}
+
+/// Run-length encodes the code lengths, using symbols 16 to 18 for repeats.
+fn run_lengths(lengths: &[u8]) -> Vec<(u8, u8)> {
+	let mut out = Vec::new();
+	let mut i = 0;
+	while i < lengths.len() {
+		let length = lengths[i];
+		let run = lengths[i..].iter().take_while(|&&other| other == length).count();
+		if length == 0 && run >= 11 {
+			let run = run.min(138);
+			out.push((18, (run - 11) as u8));
+			i += run;
+		} else if length == 0 && run >= 3 {
+			out.push((17, (run - 3) as u8));
+			i += run;
+		} else if length != 0 && run >= 4 {
+			out.push((length, 0));
+			let run = (run - 1).min(6);
+			out.push((16, (run - 3) as u8));
+			i += 1 + run;
+		} else {
+			out.push((length, 0));
+			i += 1;
+		}
+	}
+	return out;
+}
+
+fn write_symbols(writer: &mut BitWriter, symbols: &[Symbol], literals: &[u8], distances: &[u8]) {
+	let literal_codes = canonical_codes(literals);
+	let distance_codes = canonical_codes(distances);
+	for &symbol in symbols {
+		let code = symbol.code();
+		writer.code(literal_codes[code], literals[code]);
+		if let Symbol::Match { length, distance } = symbol {
+			let index = code - 257;
+			writer.bits((length - LENGTH_BASE[index]) as u32, LENGTH_EXTRA[index] as u32);
+			let index = distance_code(distance);
+			writer.code(distance_codes[index], distances[index]);
+			writer.bits((distance - DISTANCE_BASE[index]) as u32, DISTANCE_EXTRA[index] as u32);
+		}
+	}
+	writer.code(literal_codes[256], literals[256]);
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::filters::flate::*;
+
+	#[test]
+	fn decodes_zlib_streams() {
+		// Produced by zlib for "Hello, Hello, Hello!" with a fixed Huffman block.
+		let data = [0x78, 0xDA, 0xF3, 0x48, 0xCD, 0xC9, 0xC9, 0xD7, 0x51, 0xF0, 0x40, 0xA2, 0x14, 0x01, 0x46, 0x3E, 0x06, 0x96];
+		assert_eq!(decode(&data).unwrap(), b"Hello, Hello, Hello!");
+
+		// Stored block, without the trailing checksum.
+		let data = [0x78, 0x01, 0x01, 0x03, 0x00, 0xFC, 0xFF, b'a', b'b', b'c'];
+		assert_eq!(decode(&data).unwrap(), b"abc");
+		assert!(decode(&[0x78, 0x9C, 0xFF]).is_err());
+	}
+
+	#[test]
+	fn encode_round_trip() {
+		let text = b"BT /F13 10 Tf 12 775 Td (Hello!) Tj ET\n".repeat(200);
+		let mut noise = Vec::new();
+		let mut state = 12345u32;
+		for _ in 0..70000 {
+			state = state.wrapping_mul(1103515245).wrapping_add(12345);
+			noise.push((state >> 16) as u8);
+		}
+
+		for data in [&b""[..], b"a", &text, &noise, &[text.clone(), noise.clone(), text.clone()].concat()] {
+			let encoded = encode(data);
+			assert_eq!(decode(&encoded).unwrap(), data);
+		}
+		assert!(encode(&text).len() < text.len() / 20);
+		assert!(encode(&noise).len() < noise.len() + 64);
+	}
+}
@@ -0,0 +1,758 @@
+//! Decoder for the `JBIG2Decode` filter.
+//!
+//! Only generic regions coded with the arithmetic (MQ) coder are supported,
+//! which covers the output of most scanners. Symbol and halftone coding, as
+//! well as MMR coded regions, are rejected with an error.
+
+use crate::PdfError;
+
+const FILTER: &str = "JBIG2Decode";
+/// Largest number of pixels of a bitmap, which take a byte each, enough for an
+/// A0 page scanned at 600 dpi, so that broken dimensions can't make us
+/// allocate more memory than there is.
+const MAX_PIXELS: usize = 1 << 30;
+
+/// Probability estimation table of the MQ coder, as `(Qe, NMPS, NLPS, SWITCH)`.
+pub(crate) const QE_TABLE: [(u32, u8, u8, bool); 47] = [
+	(0x5601, 1, 1, true),
+	(0x3401, 2, 6, false),
+	(0x1801, 3, 9, false),
+	(0x0AC1, 4, 12, false),
+	(0x0521, 5, 29, false),
+	(0x0221, 38, 33, false),
+	(0x5601, 7, 6, true),
+	(0x5401, 8, 14, false),
+	(0x4801, 9, 14, false),
+	(0x3801, 10, 14, false),
+	(0x3001, 11, 17, false),
+	(0x2401, 12, 18, false),
+	(0x1C01, 13, 20, false),
+	(0x1601, 29, 21, false),
+	(0x5601, 15, 14, true),
+	(0x5401, 16, 14, false),
+	(0x5101, 17, 15, false),
+	(0x4801, 18, 16, false),
+	(0x3801, 19, 17, false),
+	(0x3401, 20, 18, false),
+	(0x3001, 21, 19, false),
+	(0x2801, 22, 19, false),
+	(0x2401, 23, 20, false),
+	(0x2201, 24, 21, false),
+	(0x1C01, 25, 22, false),
+	(0x1801, 26, 23, false),
+	(0x1601, 27, 24, false),
+	(0x1401, 28, 25, false),
+	(0x1201, 29, 26, false),
+	(0x1101, 30, 27, false),
+	(0x0AC1, 31, 28, false),
+	(0x09C1, 32, 29, false),
+	(0x08A1, 33, 30, false),
+	(0x0521, 34, 31, false),
+	(0x0441, 35, 32, false),
+	(0x02A1, 36, 33, false),
+	(0x0221, 37, 34, false),
+	(0x0141, 38, 35, false),
+	(0x0111, 39, 36, false),
+	(0x0085, 40, 37, false),
+	(0x0049, 41, 38, false),
+	(0x0025, 42, 39, false),
+	(0x0015, 43, 40, false),
+	(0x0009, 44, 41, false),
+	(0x0005, 45, 42, false),
+	(0x0001, 45, 43, false),
+	(0x5601, 46, 46, false),
+];
+
+/// Nominal pixels of the generic region templates, relative to the pixel being
+/// decoded, excluding the adaptive ones.
+const TEMPLATES: [&[(i32, i32)]; 4] = [
+	&[(-1, -2), (0, -2), (1, -2), (-2, -1), (-1, -1), (0, -1), (1, -1), (2, -1), (-4, 0), (-3, 0), (-2, 0), (-1, 0)],
+	&[(-1, -2), (0, -2), (1, -2), (2, -2), (-2, -1), (-1, -1), (0, -1), (1, -1), (2, -1), (-3, 0), (-2, 0), (-1, 0)],
+	&[(-1, -2), (0, -2), (1, -2), (-2, -1), (-1, -1), (0, -1), (1, -1), (-2, 0), (-1, 0)],
+	&[(-3, -1), (-2, -1), (-1, -1), (0, -1), (1, -1), (-4, 0), (-3, 0), (-2, 0), (-1, 0)],
+];
+
+/// Contexts used to decode the typical prediction bit of each template.
+pub(crate) const TPGDON_CONTEXTS: [usize; 4] = [0x9B25, 0x0795, 0x00E5, 0x0195];
+
+/// Decoder of the MQ arithmetic coded data. Each context is stored as a byte
+/// holding the index into [`QE_TABLE`] and the more probable symbol.
+pub(crate) struct ArithmeticDecoder<'a> {
+	data: &'a [u8],
+	pos: usize,
+	chigh: u32,
+	clow: u32,
+	ct: u32,
+	a: u32,
+}
+
+impl<'a> ArithmeticDecoder<'a> {
+	/// Creates new decoder, reading the first bytes of the data.
+	pub fn new(data: &'a [u8]) -> Self {
+		let mut decoder = Self {
+			data,
+			pos: 0,
+			chigh: data.first().copied().unwrap_or(0xFF) as u32,
+			clow: 0,
+			ct: 0,
+			a: 0,
+		};
+
+		decoder.byte_in();
+		decoder.chigh = ((decoder.chigh << 7) & 0xFFFF) | ((decoder.clow >> 9) & 0x7F);
+		decoder.clow = (decoder.clow << 7) & 0xFFFF;
+		decoder.ct -= 7;
+		decoder.a = 0x8000;
+		return decoder;
+	}
+
+	/// Gets the byte at the given position, treating the end of data as an
+	/// endless run of `0xFF` bytes.
+	#[inline]
+	fn byte(&self, pos: usize) -> u32 {
+		return self.data.get(pos).copied().unwrap_or(0xFF) as u32;
+	}
+
+	fn byte_in(&mut self) {
+		if self.byte(self.pos) == 0xFF {
+			if self.byte(self.pos + 1) > 0x8F {
+				// Marker code, the decoder keeps feeding ones from now on.
+				self.clow += 0xFF00;
+				self.ct = 8;
+			} else {
+				self.pos += 1;
+				self.clow += self.byte(self.pos) << 9;
+				self.ct = 7;
+			}
+		} else {
+			self.pos += 1;
+			self.clow += self.byte(self.pos) << 8;
+			self.ct = 8;
+		}
+
+		if self.clow > 0xFFFF {
+			self.chigh += self.clow >> 16;
+			self.clow &= 0xFFFF;
+		}
+	}
+
+	/// Decodes a single bit using the given context.
+	pub fn decode(&mut self, contexts: &mut [u8], cx: usize) -> u8 {
+		let mut index = (contexts[cx] >> 1) as usize;
+		let mut mps = contexts[cx] & 1;
+		let (qe, nmps, nlps, switch) = QE_TABLE[index];
+		let mut a = self.a - qe;
+		let bit;
+
+		if self.chigh < qe {
+			// The LPS sub-interval was chosen, but it may be larger than the MPS one.
+			if a < qe {
+				bit = mps;
+				index = nmps as usize;
+			} else {
+				bit = 1 ^ mps;
+				if switch {
+					mps = bit;
+				}
+				index = nlps as usize;
+			}
+			a = qe;
+		} else {
+			self.chigh -= qe;
+			if a & 0x8000 != 0 {
+				self.a = a;
+				return mps;
+			}
+
+			if a < qe {
+				bit = 1 ^ mps;
+				if switch {
+					mps = bit;
+				}
+				index = nlps as usize;
+			} else {
+				bit = mps;
+				index = nmps as usize;
+			}
+		}
+
+		// Renormalise the interval.
+		while a & 0x8000 == 0 {
+			if self.ct == 0 {
+				self.byte_in();
+			}
+			a <<= 1;
+			self.chigh = ((self.chigh << 1) & 0xFFFF) | ((self.clow >> 15) & 1);
+			self.clow = (self.clow << 1) & 0xFFFF;
+			self.ct -= 1;
+		}
+
+		self.a = a;
+		contexts[cx] = ((index as u8) << 1) | mps;
+		return bit;
+	}
+}
+
+/// Attempts to get the number of pixels of a bitmap with the given
+/// dimensions, failing if there are more than [`MAX_PIXELS`].
+fn area(width: usize, height: usize) -> Result<usize, PdfError> {
+	return match width.checked_mul(height) {
+		Some(area) if area <= MAX_PIXELS => Ok(area),
+		_ => Err(PdfError::filter(FILTER, std::format!("bitmap of {}x{} pixels is too large", width, height))),
+	};
+}
+
+/// Bilevel image with one byte per pixel, where 1 means black.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Bitmap {
+	pub width: usize,
+	pub height: usize,
+	pub pixels: Vec<u8>,
+}
+
+impl Bitmap {
+	/// Attempts to create new bitmap filled with the given pixel value,
+	/// failing if it's larger than [`MAX_PIXELS`].
+	pub fn new(width: usize, height: usize, value: u8) -> Result<Self, PdfError> {
+		return Ok(Self {
+			width,
+			height,
+			pixels: std::vec![value; area(width, height)?],
+		});
+	}
+
+	/// Gets the pixel at the given position, or 0 outside of the bitmap.
+	#[inline]
+	pub fn get(&self, x: i64, y: i64) -> u8 {
+		if x < 0 || y < 0 || x as usize >= self.width || y as usize >= self.height {
+			return 0;
+		}
+		return self.pixels[y as usize * self.width + x as usize];
+	}
+
+	/// Packs the bitmap into rows of bytes, with the most significant bit first
+	/// and 0 meaning black, as the images in PDF expect.
+	pub fn to_packed(&self) -> Vec<u8> {
+		let stride = self.width.div_ceil(8);
+		let mut data = std::vec![0xFF; stride * self.height];
+		for y in 0..self.height {
+			for x in 0..self.width {
+				if self.pixels[y * self.width + x] != 0 {
+					data[y * stride + x / 8] &= !(0x80 >> (x % 8));
+				}
+			}
+		}
+		return data;
+	}
+
+	/// Combines the region into this bitmap at the given position, using one
+	/// of the JBIG2 combination operators.
+	fn compose(&mut self, region: &Bitmap, x: i64, y: i64, operator: u8) {
+		for ry in 0..region.height {
+			let ty = y + ry as i64;
+			if ty < 0 || ty as usize >= self.height {
+				continue;
+			}
+			for rx in 0..region.width {
+				let tx = x + rx as i64;
+				if tx < 0 || tx as usize >= self.width {
+					continue;
+				}
+
+				let target = &mut self.pixels[ty as usize * self.width + tx as usize];
+				let source = region.pixels[ry * region.width + rx];
+				*target = match operator {
+					0 => *target | source,
+					1 => *target & source,
+					2 => *target ^ source,
+					3 => 1 ^ *target ^ source,
+					_ => source,
+				};
+			}
+		}
+	}
+}
+
+/// Builds the list of pixels forming the context of a template, ordered so
+/// that the first pixel ends up in the most significant bit.
+pub(crate) fn template_pixels(template: usize, adaptive: &[(i32, i32)]) -> Vec<(i32, i32)> {
+	let mut pixels = TEMPLATES[template].to_vec();
+	pixels.extend_from_slice(adaptive);
+	pixels.sort_by_key(|&(x, y)| (y, x));
+	return pixels;
+}
+
+/// Computes the context of the pixel at the given position.
+#[inline]
+pub(crate) fn context(bitmap: &Bitmap, pixels: &[(i32, i32)], x: usize, y: usize) -> usize {
+	let mut context = 0;
+	for &(dx, dy) in pixels {
+		context = (context << 1) | bitmap.get(x as i64 + dx as i64, y as i64 + dy as i64) as usize;
+	}
+	return context;
+}
+
+/// Parameters of a generic region coded with the arithmetic coder.
+struct GenericRegion<'a> {
+	width: usize,
+	height: usize,
+	template: usize,
+	tpgdon: bool,
+	adaptive: Vec<(i32, i32)>,
+	data: &'a [u8],
+}
+
+impl GenericRegion<'_> {
+	/// Decodes the region bitmap, as described in section 6.2.5.7 of T.88.
+	fn decode(&self) -> Result<Bitmap, PdfError> {
+		let pixels = template_pixels(self.template, &self.adaptive);
+		let mut contexts = std::vec![0; 1 << 16];
+		let mut decoder = ArithmeticDecoder::new(self.data);
+		let mut bitmap = Bitmap::new(self.width, self.height, 0)?;
+		let mut typical = false;
+
+		for y in 0..self.height {
+			if self.tpgdon {
+				typical ^= decoder.decode(&mut contexts, TPGDON_CONTEXTS[self.template]) == 1;
+				if typical {
+					// Row is identical to the previous one, which is blank above the region.
+					if y > 0 {
+						let width = self.width;
+						bitmap.pixels.copy_within((y - 1) * width..y * width, y * width);
+					}
+					continue;
+				}
+			}
+
+			for x in 0..self.width {
+				let cx = context(&bitmap, &pixels, x, y);
+				bitmap.pixels[y * self.width + x] = decoder.decode(&mut contexts, cx);
+			}
+		}
+
+		return Ok(bitmap);
+	}
+}
+
+/// Cursor over big-endian segment data.
+struct Cursor<'a> {
+	data: &'a [u8],
+	pos: usize,
+}
+
+impl<'a> Cursor<'a> {
+	fn bytes(&mut self, len: usize) -> Result<&'a [u8], PdfError> {
+		if self.data.len() - self.pos < len {
+			return Err(PdfError::filter(FILTER, "unexpected end of data"));
+		}
+		let bytes = &self.data[self.pos..self.pos + len];
+		self.pos += len;
+		return Ok(bytes);
+	}
+
+	fn u8(&mut self) -> Result<u8, PdfError> {
+		return Ok(self.bytes(1)?[0]);
+	}
+
+	fn u32(&mut self) -> Result<u32, PdfError> {
+		let bytes = self.bytes(4)?;
+		return Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]));
+	}
+}
+
+/// Segment of the embedded JBIG2 stream.
+struct Segment<'a> {
+	number: u32,
+	kind: u8,
+	data: &'a [u8],
+}
+
+/// Splits the data into segments, following the sequential organisation which
+/// PDF requires for embedded streams.
+fn segments(data: &[u8]) -> Result<Vec<Segment<'_>>, PdfError> {
+	let mut cursor = Cursor { data, pos: 0 };
+	let mut segments = Vec::new();
+
+	while cursor.pos < data.len() {
+		let number = cursor.u32()?;
+		let flags = cursor.u8()?;
+
+		let referred = cursor.u8()?;
+		let mut count = (referred >> 5) as usize;
+		if count == 7 {
+			// Long form, with the retention flags in the following bytes.
+			cursor.pos -= 1;
+			count = (cursor.u32()? & 0x1FFF_FFFF) as usize;
+			cursor.bytes((count + 1).div_ceil(8))?;
+		}
+
+		let size = match number {
+			0..=256 => 1,
+			257..=65536 => 2,
+			_ => 4,
+		};
+		cursor.bytes(count * size)?;
+		cursor.bytes(if flags & 0x40 != 0 { 4 } else { 1 })?;
+
+		let length = cursor.u32()?;
+		if length == u32::MAX {
+			return Err(PdfError::filter(FILTER, "segments of unknown length are not supported"));
+		}
+
+		segments.push(Segment {
+			number,
+			kind: flags & 0x3F,
+			data: cursor.bytes(length as usize)?,
+		});
+	}
+
+	return Ok(segments);
+}
+
+/// Decoded page, together with the information whether its height is only
+/// known once all stripes have been decoded.
+struct PageState {
+	bitmap: Bitmap,
+	striped: bool,
+	default_pixel: u8,
+}
+
+fn page_information(data: &[u8]) -> Result<PageState, PdfError> {
+	let mut cursor = Cursor { data, pos: 0 };
+	let width = cursor.u32()? as usize;
+	let height = cursor.u32()?;
+	// Resolution is of no use for us.
+	cursor.bytes(8)?;
+	let flags = cursor.u8()?;
+
+	let default_pixel = (flags >> 2) & 1;
+	let striped = height == u32::MAX;
+	let height = if striped { 0 } else { height as usize };
+	return Ok(PageState {
+		bitmap: Bitmap::new(width, height, default_pixel)?,
+		striped,
+		default_pixel,
+	});
+}
+
+fn generic_region(segment: &Segment<'_>) -> Result<(Bitmap, i64, i64, u8), PdfError> {
+	let mut cursor = Cursor { data: segment.data, pos: 0 };
+	let width = cursor.u32()? as usize;
+	let height = cursor.u32()? as usize;
+	let x = cursor.u32()? as i64;
+	let y = cursor.u32()? as i64;
+	let operator = cursor.u8()? & 0x07;
+
+	let flags = cursor.u8()?;
+	if flags & 0x01 != 0 {
+		return Err(PdfError::filter(FILTER, "MMR coded generic regions are not supported"));
+	}
+
+	let template = ((flags >> 1) & 0x03) as usize;
+	let count = if template == 0 { 4 } else { 1 };
+	let adaptive = cursor.bytes(count * 2)?.chunks(2).map(|pair| (pair[0] as i8 as i32, pair[1] as i8 as i32)).collect();
+
+	let region = GenericRegion {
+		width,
+		height,
+		template,
+		tpgdon: flags & 0x08 != 0,
+		adaptive,
+		data: &segment.data[cursor.pos..],
+	};
+	return Ok((region.decode()?, x, y, operator));
+}
+
+/// Decodes the embedded JBIG2 stream, with the optional `JBIG2Globals` stream,
+/// into packed 1 bit per pixel rows, where 0 means black.
+pub fn decode(data: &[u8], globals: Option<&[u8]>) -> Result<Vec<u8>, PdfError> {
+	let mut all = match globals {
+		Some(globals) => segments(globals)?,
+		None => Vec::new(),
+	};
+	all.extend(segments(data)?);
+
+	let mut page: Option<PageState> = None;
+	for segment in &all {
+		match segment.kind {
+			// Page information.
+			48 => page = Some(page_information(segment.data)?),
+			// Immediate generic regions.
+			38 | 39 => {
+				let Some(page) = page.as_mut() else {
+					return Err(PdfError::filter(FILTER, "region before page information"));
+				};
+
+				let (region, x, y, operator) = generic_region(segment)?;
+				let bottom = y as usize + region.height;
+				if page.striped && bottom > page.bitmap.height {
+					let bitmap = &mut page.bitmap;
+					bitmap.pixels.resize(area(bitmap.width, bottom)?, page.default_pixel);
+					bitmap.height = bottom;
+				}
+				page.bitmap.compose(&region, x, y, operator);
+			},
+			// Dictionaries and tables are only used by the unsupported regions,
+			// the rest don't affect the page contents.
+			0 | 16 | 49 | 50 | 51 | 52 | 53 | 62 => {},
+			kind => {
+				return Err(PdfError::filter(FILTER, std::format!("segment {} has unsupported type {}", segment.number, kind)));
+			},
+		}
+	}
+
+	let Some(page) = page else {
+		return Err(PdfError::filter(FILTER, "missing page information"));
+	};
+	return Ok(page.bitmap.to_packed());
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::filters::jbig2::*;
+
+	/// MQ encoder, from section E.2 of T.88, used to build the test data.
+	struct Encoder {
+		a: u32,
+		c: u32,
+		ct: u32,
+		/// Output bytes, the first one being a placeholder.
+		out: Vec<u8>,
+	}
+
+	impl Encoder {
+		fn new() -> Self {
+			return Self {
+				a: 0x8000,
+				c: 0,
+				ct: 12,
+				out: std::vec![0],
+			};
+		}
+
+		fn encode(&mut self, contexts: &mut [u8], cx: usize, bit: u8) {
+			let (index, mps) = ((contexts[cx] >> 1) as usize, contexts[cx] & 1);
+			let (qe, nmps, nlps, switch) = QE_TABLE[index];
+			self.a -= qe;
+			if bit == mps {
+				if self.a & 0x8000 != 0 {
+					self.c += qe;
+					return;
+				}
+				if self.a < qe {
+					self.a = qe;
+				} else {
+					self.c += qe;
+				}
+				contexts[cx] = (nmps << 1) | mps;
+			} else {
+				if self.a < qe {
+					self.c += qe;
+				} else {
+					self.a = qe;
+				}
+				let mps = if switch { 1 - mps } else { mps };
+				contexts[cx] = (nlps << 1) | mps;
+			}
+
+			loop {
+				self.a <<= 1;
+				self.c <<= 1;
+				self.ct -= 1;
+				if self.ct == 0 {
+					self.byte_out();
+				}
+				if self.a & 0x8000 != 0 {
+					break;
+				}
+			}
+		}
+
+		fn byte_out(&mut self) {
+			let last = self.out.last_mut().unwrap();
+			if *last != 0xFF && self.c & 0x800_0000 != 0 {
+				*last += 1;
+				self.c &= 0x7FF_FFFF;
+			}
+
+			if *self.out.last().unwrap() == 0xFF {
+				self.out.push((self.c >> 20) as u8);
+				self.c &= 0xF_FFFF;
+				self.ct = 7;
+			} else {
+				self.out.push((self.c >> 19) as u8);
+				self.c &= 0x7_FFFF;
+				self.ct = 8;
+			}
+		}
+
+		fn finish(mut self) -> Vec<u8> {
+			let temp = self.c + self.a;
+			self.c |= 0xFFFF;
+			if self.c >= temp {
+				self.c -= 0x8000;
+			}
+			self.c <<= self.ct;
+			self.byte_out();
+			self.c <<= self.ct;
+			self.byte_out();
+
+			if self.out.last() == Some(&0xFF) {
+				self.out.pop();
+			}
+			self.out.remove(0);
+			self.out.extend_from_slice(&[0xFF, 0xAC]);
+			return self.out;
+		}
+	}
+
+	/// Test sequence from section H.2 of T.88, coded with a single context.
+	const PLAIN: [u8; 32] = [
+		0x00, 0x02, 0x00, 0x51, 0x00, 0x00, 0x00, 0xC0, 0x03, 0x52, 0x87, 0x2A, 0xAA, 0xAA, 0xAA, 0xAA, 0x82, 0xC0, 0x20, 0x00, 0xFC, 0xD7, 0x9E, 0xF6, 0xBF, 0x7F, 0xED, 0x90, 0x4F, 0x46, 0xA3, 0xBF,
+	];
+	const CODED: [u8; 30] = [
+		0x84, 0xC7, 0x3B, 0xFC, 0xE1, 0xA1, 0x43, 0x04, 0x02, 0x20, 0x00, 0x00, 0x41, 0x0D, 0xBB, 0x86, 0xF4, 0x31, 0x7F, 0xFF, 0x88, 0xFF, 0x37, 0x47, 0x1A, 0xDB, 0x6A, 0xDF, 0xFF, 0xAC,
+	];
+
+	#[test]
+	fn arithmetic_coder_test_sequence() {
+		let mut contexts = [0];
+		let mut decoder = ArithmeticDecoder::new(&CODED);
+		let mut encoder = Encoder::new();
+		let mut encoder_contexts = [0];
+		for byte in PLAIN {
+			let mut decoded = 0;
+			for i in (0..8).rev() {
+				decoded = (decoded << 1) | decoder.decode(&mut contexts, 0);
+				encoder.encode(&mut encoder_contexts, 0, (byte >> i) & 1);
+			}
+			assert_eq!(decoded, byte);
+		}
+		assert_eq!(encoder.finish(), CODED);
+	}
+
+	fn segment(number: u32, kind: u8, data: &[u8]) -> Vec<u8> {
+		let mut bytes = number.to_be_bytes().to_vec();
+		bytes.extend_from_slice(&[kind, 0, 1]);
+		bytes.extend_from_slice(&(data.len() as u32).to_be_bytes());
+		bytes.extend_from_slice(data);
+		return bytes;
+	}
+
+	/// Encodes the rows, where `#` is black, as a generic region with
+	/// template 0, the nominal adaptive pixels, and typical prediction.
+	/// Returns the bitmap, and the data of the page information and region
+	/// segments.
+	fn encode_region(rows: &[&str]) -> (Bitmap, Vec<u8>, Vec<u8>) {
+		let (width, height) = (rows[0].len(), rows.len());
+		let mut bitmap = Bitmap::new(width, height, 0).unwrap();
+		for (y, row) in rows.iter().enumerate() {
+			for (x, pixel) in row.bytes().enumerate() {
+				bitmap.pixels[y * width + x] = (pixel == b'#') as u8;
+			}
+		}
+
+		let adaptive = [(3, -1), (-3, -1), (2, -2), (-2, -2)];
+		let pixels = template_pixels(0, &adaptive);
+		let mut contexts = std::vec![0; 1 << 16];
+		let mut encoder = Encoder::new();
+		let mut typical = false;
+		for y in 0..height {
+			let same = y > 0 && bitmap.pixels[(y - 1) * width..y * width] == bitmap.pixels[y * width..(y + 1) * width];
+			encoder.encode(&mut contexts, TPGDON_CONTEXTS[0], (same != typical) as u8);
+			typical = same;
+			if same {
+				continue;
+			}
+			for x in 0..width {
+				encoder.encode(&mut contexts, context(&bitmap, &pixels, x, y), bitmap.pixels[y * width + x]);
+			}
+		}
+
+		let mut page = std::vec![];
+		for value in [width as u32, height as u32, 0, 0] {
+			page.extend_from_slice(&value.to_be_bytes());
+		}
+		page.extend_from_slice(&[0, 0, 0]);
+
+		let mut region = std::vec![];
+		for value in [width as u32, height as u32, 0, 0] {
+			region.extend_from_slice(&value.to_be_bytes());
+		}
+		region.extend_from_slice(&[0, 0x08]);
+		for (x, y) in adaptive {
+			region.extend_from_slice(&[x as i8 as u8, y as i8 as u8]);
+		}
+		region.extend(encoder.finish());
+		return (bitmap, page, region);
+	}
+
+	#[test]
+	fn generic_region() {
+		let rows = ["....................", ".####......######...", ".#..#......#.....#..", ".#..#......#.....#..", ".####......######...", "....................", "...................."];
+		let (bitmap, page, region) = encode_region(&rows);
+		let mut data = segment(0, 48, &page);
+		data.extend(segment(1, 38, &region));
+		data.extend(segment(2, 49, &[]));
+
+		let decoded = decode(&data, None).unwrap();
+		assert_eq!(decoded, bitmap.to_packed());
+		// Second row starts with a white pixel followed by four black ones.
+		assert_eq!(decoded[3], 0b1000_0111);
+	}
+
+	#[test]
+	fn oversized_bitmaps() {
+		let mut page = std::vec![];
+		for value in [u32::MAX, u32::MAX - 1, 0, 0] {
+			page.extend_from_slice(&value.to_be_bytes());
+		}
+		page.extend_from_slice(&[0, 0, 0]);
+		assert!(decode(&segment(0, 48, &page), None).is_err());
+
+		// Stripes can't extend the page past the limit either.
+		let (_, mut page, mut region) = encode_region(&["#.#."]);
+		page[4..8].copy_from_slice(&u32::MAX.to_be_bytes());
+		region[12..16].copy_from_slice(&u32::MAX.to_be_bytes());
+		let mut data = segment(0, 48, &page);
+		data.extend(segment(1, 38, &region));
+		assert!(decode(&data, None).is_err());
+	}
+
+	#[test]
+	fn document_images() {
+		let (bitmap, page, region) = encode_region(&["#.#.#.#.#.", "..........", "##########"]);
+		let mut doc = crate::Document::new();
+		let mut globals = crate::Dictionary::new();
+		let data = crate::filters::encode_stream(&mut globals, &segment(0, 48, &page), &["FlateDecode"]).unwrap();
+		let globals = doc.add_object(crate::Stream::new(globals, data));
+
+		let mut dict = crate::Dictionary::new();
+		dict.set("Type", crate::PdfObject::name("XObject"));
+		dict.set("Subtype", crate::PdfObject::name("Image"));
+		dict.set("Width", 10);
+		dict.set("Height", 3);
+		dict.set("BitsPerComponent", 1);
+		dict.set("ColorSpace", crate::PdfObject::name("DeviceGray"));
+		dict.set("Filter", crate::PdfObject::name("JBIG2Decode"));
+		let mut params = crate::Dictionary::new();
+		params.set("JBIG2Globals", globals);
+		dict.set("DecodeParms", params);
+		let mut data = segment(1, 38, &region);
+		data.extend(segment(2, 49, &[]));
+		let image = doc.add_object(crate::Stream::new(dict, data));
+		let mut content = crate::ContentStream::new();
+		let name = content.use_image(image);
+		content.draw_xobject(&name);
+		doc.add_page(612.0, 792.0).set_contents(content);
+
+		let mut output = Vec::new();
+		doc.write(&mut crate::Writer::new(&mut output)).unwrap();
+		let reader = crate::Reader::new(&output).unwrap();
+		let images = reader.images().unwrap();
+		assert_eq!(images.len(), 1);
+		assert_eq!(images[0].format, crate::ImageFormat::Raw);
+		assert_eq!(images[0].data, bitmap.to_packed());
+		assert_eq!(images[0].data[..2], [0b0101_0101, 0b0111_1111]);
+	}
+}
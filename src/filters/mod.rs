@@ -0,0 +1,460 @@
+//! Decoders and encoders of the stream filters.
+
+pub mod ascii85;
+pub mod flate;
+pub mod jbig2;
+pub mod predictor;
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, OnceLock};
+
+use crate::{Dictionary, ParseMode, PdfError, PdfObject, Stream};
+
+/// Largest ratio of the decoded to the encoded length of Flate data, so that
+/// the `/DL` of a broken stream can't make us allocate much more than it can
+/// decode to.
+const MAX_FLATE_RATIO: usize = 1032;
+
+/// Abbreviations of the filter names used by inline images, with the full
+/// names.
+const ABBREVIATIONS: [(&str, &str); 7] = [
+	("AHx", "ASCIIHexDecode"),
+	("A85", "ASCII85Decode"),
+	("LZW", "LZWDecode"),
+	("Fl", "FlateDecode"),
+	("RL", "RunLengthDecode"),
+	("CCF", "CCITTFaxDecode"),
+	("DCT", "DCTDecode"),
+];
+
+/// Decoder, and optionally encoder, of a stream filter, which can be
+/// registered under its name in a [`FilterRegistry`].
+pub trait Filter: Send + Sync {
+	/// Attempts to decode the data, with the decoding parameters of the
+	/// filter. When the stream is read with a [`crate::Reader`], the objects
+	/// the parameters refer to are resolved, e.g. `/JBIG2Globals` is given as
+	/// a stream with its still encoded data. The length hint is the expected
+	/// length of the output, if it's known, e.g. to preallocate it.
+	fn decode(&self, data: &[u8], params: &Dictionary, length_hint: Option<usize>) -> Result<Vec<u8>, PdfError>;
+
+	/// Attempts to decode the data, like [`Filter::decode`], with the
+	/// registry the filter is used from, e.g. to decode the streams its
+	/// parameters refer to with the same filters. Calls [`Filter::decode`]
+	/// by default.
+	fn decode_with_registry(&self, data: &[u8], params: &Dictionary, length_hint: Option<usize>, registry: &FilterRegistry) -> Result<Vec<u8>, PdfError> {
+		let _ = registry;
+		return self.decode(data, params, length_hint);
+	}
+
+	/// Encodes the data with the default parameters, or returns `None` if
+	/// the filter can only decode.
+	fn encode(&self, data: &[u8]) -> Option<Vec<u8>> {
+		let _ = data;
+		return None;
+	}
+}
+
+struct FlateFilter;
+
+impl Filter for FlateFilter {
+	fn decode(&self, data: &[u8], params: &Dictionary, length_hint: Option<usize>) -> Result<Vec<u8>, PdfError> {
+		let capacity = match length_hint {
+			Some(length) => length.min(data.len().saturating_mul(MAX_FLATE_RATIO)),
+			None => data.len() * 4,
+		};
+		return predictor::decode(flate::decode_with_capacity(data, capacity)?, params);
+	}
+
+	fn encode(&self, data: &[u8]) -> Option<Vec<u8>> {
+		return Some(flate::encode(data));
+	}
+}
+
+struct Ascii85Filter;
+
+impl Filter for Ascii85Filter {
+	fn decode(&self, data: &[u8], _: &Dictionary, _: Option<usize>) -> Result<Vec<u8>, PdfError> {
+		return ascii85::decode(data);
+	}
+
+	fn encode(&self, data: &[u8]) -> Option<Vec<u8>> {
+		return Some(ascii85::encode(data));
+	}
+}
+
+struct AsciiHexFilter;
+
+impl Filter for AsciiHexFilter {
+	fn decode(&self, data: &[u8], _: &Dictionary, _: Option<usize>) -> Result<Vec<u8>, PdfError> {
+		return ascii85::decode_hex(data);
+	}
+
+	fn encode(&self, data: &[u8]) -> Option<Vec<u8>> {
+		return Some(ascii85::encode_hex(data));
+	}
+}
+
+struct Jbig2Filter;
+
+impl Filter for Jbig2Filter {
+	#[inline]
+	fn decode(&self, data: &[u8], params: &Dictionary, length_hint: Option<usize>) -> Result<Vec<u8>, PdfError> {
+		return self.decode_with_registry(data, params, length_hint, FilterRegistry::builtin());
+	}
+
+	fn decode_with_registry(&self, data: &[u8], params: &Dictionary, _: Option<usize>, registry: &FilterRegistry) -> Result<Vec<u8>, PdfError> {
+		// Segments shared by the images of a document are kept in a separate
+		// stream, which can have filters of its own.
+		let globals = match params.get("JBIG2Globals") {
+			Some(PdfObject::Stream(globals)) => Some(registry.decode_stream(&globals.dict, &globals.data)?),
+			Some(_) => return Err(PdfError::filter("JBIG2Decode", "JBIG2Globals is not a resolved stream")),
+			None => None,
+		};
+		return jbig2::decode(data, globals.as_deref());
+	}
+}
+
+/// Filters used for decoding and encoding streams, by their names, so that
+/// filters the crate doesn't implement, e.g. vendor `/Crypt` filters, can be
+/// added. The built-in filters are registered from the start.
+#[derive(Clone)]
+pub struct FilterRegistry {
+	filters: BTreeMap<Vec<u8>, Arc<dyn Filter>>,
+}
+
+impl Default for FilterRegistry {
+	fn default() -> Self {
+		let mut registry = Self {
+			filters: BTreeMap::new(),
+		};
+		registry.register("FlateDecode", FlateFilter);
+		registry.register("ASCII85Decode", Ascii85Filter);
+		registry.register("ASCIIHexDecode", AsciiHexFilter);
+		registry.register("JBIG2Decode", Jbig2Filter);
+		return registry;
+	}
+}
+
+impl std::fmt::Debug for FilterRegistry {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		return f.debug_list().entries(self.filters.keys().map(|name| String::from_utf8_lossy(name))).finish();
+	}
+}
+
+impl FilterRegistry {
+	/// Creates new registry with the built-in filters.
+	#[inline]
+	pub fn new() -> Self {
+		return Self::default();
+	}
+
+	/// Gets the registry with only the built-in filters, used unless another
+	/// one is given.
+	pub(crate) fn builtin() -> &'static Self {
+		static BUILTIN: OnceLock<FilterRegistry> = OnceLock::new();
+		return BUILTIN.get_or_init(Self::new);
+	}
+
+	/// Registers the filter under the given name, e.g. `MyFilter`, replacing
+	/// the filter registered under it already, even a built-in one.
+	pub fn register(&mut self, name: &str, filter: impl Filter + 'static) {
+		self.filters.insert(name.as_bytes().to_vec(), Arc::new(filter));
+	}
+
+	/// Gets the filter registered under the given name.
+	pub fn get(&self, name: &[u8]) -> Option<&dyn Filter> {
+		return self.filters.get(name).map(|filter| &**filter);
+	}
+
+	/// Attempts to decode the raw stream data, applying the filters of the
+	/// stream dictionary in order.
+	pub fn decode_stream(&self, dict: &Dictionary, raw: &[u8]) -> Result<Vec<u8>, PdfError> {
+		let filters = filters(dict)?;
+		let last = filters.len().saturating_sub(1);
+		let mut data = raw.to_vec();
+		for (index, (name, params)) in filters.into_iter().enumerate() {
+			let Some(filter) = self.get(name) else {
+				return Err(PdfError::filter(&String::from_utf8_lossy(name), "filter is not supported"));
+			};
+			// Only the output of the last filter has the decoded length.
+			let length_hint = decoded_length(dict).filter(|_| index == last);
+			data = filter.decode_with_registry(&data, &params, length_hint, self)?;
+		}
+		return Ok(data);
+	}
+
+	/// Attempts to decode the stream data, like
+	/// [`FilterRegistry::decode_stream`]. In the strict mode, the decoded
+	/// length has to match the `/DL` of the stream, if it has one, while
+	/// otherwise it's only used to preallocate the output.
+	pub(crate) fn decode_with_mode(&self, dict: &Dictionary, raw: &[u8], mode: ParseMode) -> Result<Vec<u8>, PdfError> {
+		let data = self.decode_stream(dict, raw)?;
+		if mode == ParseMode::Strict && decoded_length(dict).is_some_and(|length| length != data.len()) {
+			return Err(PdfError::filter("DL", "decoded length does not match /DL"));
+		}
+		return Ok(data);
+	}
+
+	/// Attempts to encode the data with the named filters, so that they're
+	/// decoded in the given order, and sets the `/Filter` of the stream
+	/// dictionary accordingly. The filters are applied with their default
+	/// parameters.
+	pub fn encode_stream(&self, dict: &mut Dictionary, data: &[u8], names: &[&str]) -> Result<Vec<u8>, PdfError> {
+		let mut data = data.to_vec();
+		for &name in names.iter().rev() {
+			let Some(encoded) = self.get(name.as_bytes()).and_then(|filter| filter.encode(&data)) else {
+				return Err(PdfError::filter(name, "filter is not supported"));
+			};
+			data = encoded;
+		}
+
+		dict.remove("DecodeParms");
+		match names {
+			[] => {
+				dict.remove("Filter");
+			},
+			[name] => dict.set("Filter", PdfObject::name(name)),
+			names => dict.set("Filter", names.iter().map(|&name| PdfObject::name(name)).collect::<Vec<_>>()),
+		}
+		return Ok(data);
+	}
+}
+
+/// Attempts to decode the data of the stream, according to its `/Filter` and
+/// `/DecodeParms`.
+#[inline]
+pub fn decode(stream: &Stream) -> Result<Vec<u8>, PdfError> {
+	return decode_stream(&stream.dict, &stream.data);
+}
+
+/// Gets the decoded length of the stream data given by `/DL`.
+fn decoded_length(dict: &Dictionary) -> Option<usize> {
+	return dict.get("DL").and_then(PdfObject::as_integer).and_then(|length| usize::try_from(length).ok());
+}
+
+/// Gets the filters of the stream dictionary, in the order they're decoded,
+/// with their decoding parameters.
+fn filters(dict: &Dictionary) -> Result<Vec<(&[u8], Dictionary)>, PdfError> {
+	let names: Vec<&PdfObject> = match dict.get("Filter") {
+		None => return Ok(Vec::new()),
+		Some(PdfObject::Array(names)) => names.iter().collect(),
+		Some(name) => std::vec![name],
+	};
+	let params: Vec<Option<&PdfObject>> = match dict.get("DecodeParms") {
+		Some(PdfObject::Array(params)) => params.iter().map(Some).collect(),
+		params => std::vec![params],
+	};
+
+	let mut filters = Vec::with_capacity(names.len());
+	for (index, name) in names.into_iter().enumerate() {
+		let Some(name) = name.as_name() else {
+			return Err(PdfError::filter("Filter", "filter is not a name"));
+		};
+		// Parameters are left out, or null, for the filters which use the
+		// default ones.
+		let params = params.get(index).copied().flatten().and_then(PdfObject::as_dict).cloned().unwrap_or_else(Dictionary::new);
+		filters.push((name, params));
+	}
+	return Ok(filters);
+}
+
+/// Attempts to decode the raw stream data with the built-in filters, applying
+/// the filters of the stream dictionary in order.
+#[inline]
+pub fn decode_stream(dict: &Dictionary, raw: &[u8]) -> Result<Vec<u8>, PdfError> {
+	return FilterRegistry::builtin().decode_stream(dict, raw);
+}
+
+/// Attempts to encode the data with the named built-in filters, like
+/// [`FilterRegistry::encode_stream`].
+#[inline]
+pub fn encode_stream(dict: &mut Dictionary, data: &[u8], names: &[&str]) -> Result<Vec<u8>, PdfError> {
+	return FilterRegistry::builtin().encode_stream(dict, data, names);
+}
+
+/// Replaces the names of the `/Filter` value, either a single name or an
+/// array of them, using the given mapping, leaving the unmapped names as
+/// they are.
+fn map_filter_names(filter: &PdfObject, map: impl Fn(&[u8]) -> Option<&'static str>) -> PdfObject {
+	let map_name = |name: &PdfObject| -> PdfObject {
+		return match name.as_name().and_then(&map) {
+			Some(name) => PdfObject::name(name),
+			None => name.clone(),
+		};
+	};
+	return match filter {
+		PdfObject::Array(names) => PdfObject::Array(names.iter().map(map_name).collect()),
+		name => map_name(name),
+	};
+}
+
+/// Replaces the abbreviated filter names of inline images in the `/Filter`
+/// value, e.g. `/AHx`, with the full ones, e.g. `/ASCIIHexDecode`.
+pub(crate) fn expand_filter_names(filter: &PdfObject) -> PdfObject {
+	return map_filter_names(filter, |name| ABBREVIATIONS.iter().find(|(short, _)| short.as_bytes() == name).map(|&(_, full)| full));
+}
+
+/// Replaces the full filter names in the `/Filter` value with their
+/// abbreviations, as used by inline images.
+pub(crate) fn abbreviate_filter_names(filter: &PdfObject) -> PdfObject {
+	return map_filter_names(filter, |name| ABBREVIATIONS.iter().find(|(_, full)| full.as_bytes() == name).map(|&(short, _)| short));
+}
+
+/// Attempts to decode the data of the inline image, whose dictionary can use
+/// the abbreviated keys and filter names, e.g. `/F /AHx`.
+pub fn decode_inline_image(dict: &Dictionary, raw: &[u8]) -> Result<Vec<u8>, PdfError> {
+	let mut stream = Dictionary::new();
+	if let Some(filter) = dict.get("Filter").or_else(|| dict.get("F")) {
+		stream.set("Filter", expand_filter_names(filter));
+	}
+	if let Some(params) = dict.get("DecodeParms").or_else(|| dict.get("DP")) {
+		stream.set("DecodeParms", params.clone());
+	}
+	return decode_stream(&stream, raw);
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::filters::*;
+
+	#[test]
+	fn filter_chains() {
+		let original = b"BT /F1 12 Tf (Hello, Hello, Hello!) Tj ET".repeat(4);
+		let mut dict = Dictionary::new();
+		let data = encode_stream(&mut dict, &original, &["ASCII85Decode", "FlateDecode"]).unwrap();
+		assert_eq!(dict.get("Filter"), Some(&PdfObject::Array(std::vec![PdfObject::name("ASCII85Decode"), PdfObject::name("FlateDecode")])));
+		assert!(data.ends_with(b"~>") && data.iter().all(u8::is_ascii));
+		assert_eq!(decode_stream(&dict, &data).unwrap(), original);
+
+		// Parameters apply to the filter at the same index.
+		let rows = flate::encode(&[2, 1, 2, 2, 3, 4]);
+		let mut dict = Dictionary::new();
+		dict.set("Filter", std::vec![PdfObject::name("ASCIIHexDecode"), PdfObject::name("FlateDecode")]);
+		let mut params = Dictionary::new();
+		params.set("Predictor", 12);
+		params.set("Columns", 2);
+		dict.set("DecodeParms", std::vec![PdfObject::Null, PdfObject::Dictionary(params)]);
+		assert_eq!(decode_stream(&dict, &ascii85::encode_hex(&rows)).unwrap(), [1, 2, 4, 6]);
+
+		let mut dict = Dictionary::new();
+		let data = encode_stream(&mut dict, b"plain", &["FlateDecode"]).unwrap();
+		assert_eq!(dict.get_name("Filter"), Some(&b"FlateDecode"[..]));
+		assert_eq!(decode(&Stream::new(dict, data)).unwrap(), b"plain");
+	}
+
+	#[test]
+	fn decoded_length_hint() {
+		let original = b"0123456789".repeat(1000);
+		let mut dict = Dictionary::new();
+		let data = encode_stream(&mut dict, &original, &["FlateDecode"]).unwrap();
+		dict.set("DL", original.len());
+		let stream = Stream::new(dict, data);
+		let decoded = FilterRegistry::builtin().decode_with_mode(&stream.dict, &stream.data, ParseMode::Strict).unwrap();
+		assert_eq!(decoded, original);
+		assert_eq!(decoded.capacity(), original.len());
+
+		let mut wrong = stream.clone();
+		wrong.dict.set("DL", 10);
+		assert_eq!(FilterRegistry::builtin().decode_with_mode(&wrong.dict, &wrong.data, ParseMode::Lenient).unwrap(), original);
+		assert!(FilterRegistry::builtin().decode_with_mode(&wrong.dict, &wrong.data, ParseMode::Strict).is_err());
+		wrong.dict.set("DL", i64::MAX);
+		assert_eq!(FilterRegistry::builtin().decode_with_mode(&wrong.dict, &wrong.data, ParseMode::Lenient).unwrap(), original);
+	}
+
+	#[test]
+	fn custom_filters() {
+		use std::sync::atomic::{AtomicUsize, Ordering};
+		static CALLS: AtomicUsize = AtomicUsize::new(0);
+		struct PassThrough;
+		impl Filter for PassThrough {
+			fn decode(&self, data: &[u8], _: &Dictionary, _: Option<usize>) -> Result<Vec<u8>, PdfError> {
+				CALLS.fetch_add(1, Ordering::Relaxed);
+				return Ok(data.to_vec());
+			}
+		}
+
+		let mut doc = crate::Document::new();
+		doc.add_page(612.0, 792.0);
+		let mut dict = Dictionary::new();
+		dict.set("Filter", std::vec![PdfObject::name("MyFilter"), PdfObject::name("FlateDecode")]);
+		let id = doc.add_object(Stream::new(dict, flate::encode(b"custom")));
+		let mut output = Vec::new();
+		doc.write(&mut crate::Writer::new(&mut output)).unwrap();
+
+		let mut registry = FilterRegistry::new();
+		registry.register("MyFilter", PassThrough);
+		let mut reader = crate::Reader::new(&output).unwrap();
+		assert!(reader.stream_decoded(id).is_err());
+		reader.set_filters(&registry);
+		assert_eq!(&*reader.stream_decoded(id).unwrap(), b"custom");
+		assert_eq!(CALLS.load(Ordering::Relaxed), 1);
+
+		let mut dict = Dictionary::new();
+		assert!(registry.encode_stream(&mut dict, b"data", &["MyFilter"]).is_err());
+		assert_eq!(registry.encode_stream(&mut dict, b"data", &["ASCIIHexDecode"]).unwrap(), b"64617461>");
+	}
+
+	#[test]
+	fn jbig2_globals_filters() {
+		use std::sync::atomic::{AtomicUsize, Ordering};
+		static CALLS: AtomicUsize = AtomicUsize::new(0);
+		struct PassThrough;
+		impl Filter for PassThrough {
+			fn decode(&self, data: &[u8], _: &Dictionary, _: Option<usize>) -> Result<Vec<u8>, PdfError> {
+				CALLS.fetch_add(1, Ordering::Relaxed);
+				return Ok(data.to_vec());
+			}
+		}
+
+		let mut globals = Dictionary::new();
+		globals.set("Filter", PdfObject::name("MyFilter"));
+		let mut params = Dictionary::new();
+		params.set("JBIG2Globals", Stream::new(globals, Vec::new()));
+		let mut dict = Dictionary::new();
+		dict.set("Filter", PdfObject::name("JBIG2Decode"));
+		dict.set("DecodeParms", params);
+
+		let error = decode_stream(&dict, b"").unwrap_err();
+		assert!(error.to_string().contains("MyFilter"));
+		let mut registry = FilterRegistry::new();
+		registry.register("MyFilter", PassThrough);
+		let _ = registry.decode_stream(&dict, b"");
+		assert_eq!(CALLS.load(Ordering::Relaxed), 1);
+	}
+
+	#[test]
+	fn resolved_parameters() {
+		struct Prefix;
+		impl Filter for Prefix {
+			fn decode(&self, data: &[u8], params: &Dictionary, _: Option<usize>) -> Result<Vec<u8>, PdfError> {
+				let Some(PdfObject::Stream(prefix)) = params.get("Prefix") else {
+					return Err(PdfError::filter("Prefix", "prefix is not a stream"));
+				};
+				let mut decoded = decode(prefix)?;
+				decoded.extend_from_slice(data);
+				return Ok(decoded);
+			}
+		}
+
+		let mut doc = crate::Document::new();
+		doc.add_page(612.0, 792.0);
+		let mut dict = Dictionary::new();
+		let data = encode_stream(&mut dict, b"shared ", &["FlateDecode"]).unwrap();
+		let prefix = doc.add_object(Stream::new(dict, data));
+		let mut params = Dictionary::new();
+		params.set("Prefix", prefix);
+		let params = doc.add_object(params);
+		let mut dict = Dictionary::new();
+		dict.set("Filter", std::vec![PdfObject::name("Prefix")]);
+		dict.set("DecodeParms", std::vec![PdfObject::Reference(params)]);
+		let id = doc.add_object(Stream::new(dict, b"data".to_vec()));
+		let mut output = Vec::new();
+		doc.write(&mut crate::Writer::new(&mut output)).unwrap();
+
+		let mut registry = FilterRegistry::new();
+		registry.register("Prefix", Prefix);
+		let mut reader = crate::Reader::new(&output).unwrap();
+		reader.set_filters(&registry);
+		assert_eq!(&*reader.stream_decoded(id).unwrap(), b"shared data");
+	}
+}
@@ -0,0 +1,99 @@
+//! Predictors of the `FlateDecode` and `LZWDecode` filters, which transform
+//! the data before compression, e.g. so that the rows of an image compress
+//! better. The predictor and the layout of the rows are given by the
+//! `/DecodeParms` of the stream.
+
+use crate::{Dictionary, PdfError, PdfObject};
+
+const FILTER: &str = "Predictor";
+
+/// Paeth predictor of the PNG specification, choosing the neighbour closest
+/// to their linear estimate.
+fn paeth(left: u8, up: u8, up_left: u8) -> u8 {
+	let estimate = left as i16 + up as i16 - up_left as i16;
+	let (a, b, c) = ((estimate - left as i16).abs(), (estimate - up as i16).abs(), (estimate - up_left as i16).abs());
+	if a <= b && a <= c {
+		return left;
+	}
+	if b <= c {
+		return up;
+	}
+	return up_left;
+}
+
+/// Attempts to undo the prediction of the data, according to the decoding
+/// parameters.
+pub fn decode(data: Vec<u8>, params: &Dictionary) -> Result<Vec<u8>, PdfError> {
+	let param = |key, default| params.get(key).and_then(PdfObject::as_integer).unwrap_or(default);
+	let predictor = param("Predictor", 1);
+	let (colors, bits, columns) = (param("Colors", 1), param("BitsPerComponent", 8), param("Columns", 1));
+	if predictor == 1 {
+		return Ok(data);
+	}
+	if !(1..=32).contains(&colors) || ![1, 2, 4, 8, 16].contains(&bits) || columns < 1 {
+		return Err(PdfError::filter(FILTER, "invalid decoding parameters"));
+	}
+
+	// Predictors operate on bytes, so components smaller than a byte are
+	// predicted from the previous byte.
+	let pixel = ((colors * bits) as usize).div_ceil(8);
+	let row = ((colors * bits * columns) as usize).div_ceil(8);
+
+	return match predictor {
+		2 => {
+			if bits != 8 {
+				return Err(PdfError::filter(FILTER, "TIFF predictor is only supported with 8 bits per component"));
+			}
+			let mut data = data;
+			for row in data.chunks_mut(row) {
+				for i in pixel..row.len() {
+					row[i] = row[i].wrapping_add(row[i - pixel]);
+				}
+			}
+			Ok(data)
+		},
+		// Every row of the PNG predictors starts with its own filter type, so
+		// the particular predictor value does not matter.
+		10..=15 => {
+			let mut out = Vec::with_capacity(data.len() / (row + 1) * row);
+			let mut previous = std::vec![0; row];
+			for chunk in data.chunks(row + 1) {
+				let (&kind, bytes) = chunk.split_first().unwrap();
+				let mut current = std::vec![0; row];
+				current[..bytes.len()].copy_from_slice(bytes);
+				for i in 0..row {
+					let left = if i >= pixel { current[i - pixel] } else { 0 };
+					let up_left = if i >= pixel { previous[i - pixel] } else { 0 };
+					let up = previous[i];
+					current[i] = current[i].wrapping_add(match kind {
+						0 => 0,
+						1 => left,
+						2 => up,
+						3 => ((left as u16 + up as u16) / 2) as u8,
+						4 => paeth(left, up, up_left),
+						_ => return Err(PdfError::filter(FILTER, std::format!("invalid PNG filter type {}", kind))),
+					});
+				}
+				out.extend_from_slice(&current[..bytes.len()]);
+				previous = current;
+			}
+			Ok(out)
+		},
+		_ => Err(PdfError::filter(FILTER, std::format!("unknown predictor {}", predictor))),
+	};
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::filters::predictor::*;
+
+	#[test]
+	fn png_predictors() {
+		let mut params = Dictionary::new();
+		params.set("Predictor", 12);
+		params.set("Columns", 3);
+		// Rows filtered with None, Sub, Up, Average, and Paeth.
+		let data = std::vec![0, 1, 2, 3, 1, 4, 1, 1, 2, 1, 1, 1, 3, 4, 255, 255, 4, 1, 0, 255];
+		assert_eq!(decode(data, &params).unwrap(), [1, 2, 3, 4, 5, 6, 5, 6, 7, 6, 5, 5, 7, 6, 5]);
+	}
+}
@@ -0,0 +1,1502 @@
+//! Embedding and extraction of font programs, and reading of TrueType glyph
+//! outlines.
+
+use std::collections::BTreeMap;
+
+use crate::lexer::{Lexer, Token};
+use crate::text::{FontDecoder, VerticalMetrics, DEFAULT_CID_WIDTH, DEFAULT_GLYPH_WIDTH, DEFAULT_VERTICAL_ADVANCE};
+use crate::{filters, Dictionary, Document, FontRef, Matrix, ObjectId, Page, PdfError, PdfObject, Point, Reader, Rectangle, Stream};
+
+/// Font descriptor flags, see section 5.7.1 of the reference.
+const FIXED_PITCH: i64 = 1 << 0;
+const SYMBOLIC: i64 = 1 << 2;
+const NONSYMBOLIC: i64 = 1 << 5;
+const ITALIC: i64 = 1 << 6;
+
+/// Vertical stem width written into the descriptors. Font programs don't carry
+/// it in a form we can read easily, and viewers only use it for substitution.
+const DEFAULT_STEM_V: i64 = 80;
+
+/// Information required to build a font descriptor.
+#[derive(Clone, Debug, PartialEq)]
+struct FontMetrics {
+	name: Vec<u8>,
+	bbox: Rectangle,
+	italic_angle: f64,
+	ascent: f64,
+	descent: f64,
+	cap_height: f64,
+	fixed_pitch: bool,
+	/// Whether the font uses the standard Latin character set.
+	nonsymbolic: bool,
+}
+
+impl FontMetrics {
+	fn flags(&self) -> i64 {
+		let mut flags = if self.nonsymbolic { NONSYMBOLIC } else { SYMBOLIC };
+		if self.fixed_pitch {
+			flags |= FIXED_PITCH;
+		}
+		if self.italic_angle != 0.0 {
+			flags |= ITALIC;
+		}
+		return flags;
+	}
+
+	/// Creates the font descriptor dictionary, pointing at the font program.
+	fn descriptor(&self, file_key: &str, file: ObjectId) -> Dictionary {
+		let mut dict = Dictionary::new();
+		dict.set("Type", PdfObject::name("FontDescriptor"));
+		dict.set_raw(b"FontName", PdfObject::Name(self.name.clone()));
+		dict.set("Flags", self.flags());
+		dict.set("FontBBox", self.bbox.to_pdf_array());
+		dict.set("ItalicAngle", self.italic_angle);
+		dict.set("Ascent", self.ascent);
+		dict.set("Descent", self.descent);
+		dict.set("CapHeight", self.cap_height);
+		dict.set("StemV", DEFAULT_STEM_V);
+		dict.set(file_key, file);
+		return dict;
+	}
+}
+
+/// Portions of a Type 1 font program, as stored in the PFB segments.
+struct Type1Program<'a> {
+	/// Clear text portion, ending with `eexec`.
+	ascii: Vec<&'a [u8]>,
+	/// Encrypted portion.
+	binary: Vec<&'a [u8]>,
+	/// Zeros and `cleartomark` following the encrypted portion.
+	trailer: Vec<&'a [u8]>,
+}
+
+/// Splits the PFB file into segments. Each one starts with `0x80`, followed by
+/// its type and little-endian length, and the last one has type 3.
+fn pfb_segments(data: &[u8]) -> Result<Type1Program<'_>, PdfError> {
+	let mut program = Type1Program {
+		ascii: Vec::new(),
+		binary: Vec::new(),
+		trailer: Vec::new(),
+	};
+
+	let mut pos = 0;
+	loop {
+		let (Some(0x80), Some(&kind)) = (data.get(pos), data.get(pos + 1)) else {
+			return Err(PdfError::Font(std::format!("missing PFB segment header at offset {}", pos)));
+		};
+		if kind == 3 {
+			break;
+		}
+
+		let Some(length) = data.get(pos + 2..pos + 6) else {
+			return Err(PdfError::Font("truncated PFB segment header".to_string()));
+		};
+		let length = u32::from_le_bytes([length[0], length[1], length[2], length[3]]) as usize;
+		let Some(segment) = data.get(pos + 6..pos + 6 + length) else {
+			return Err(PdfError::Font("truncated PFB segment".to_string()));
+		};
+
+		match kind {
+			1 if program.binary.is_empty() => program.ascii.push(segment),
+			1 => program.trailer.push(segment),
+			2 if program.trailer.is_empty() => program.binary.push(segment),
+			_ => return Err(PdfError::Font(std::format!("unexpected PFB segment type {}", kind))),
+		}
+		pos += 6 + length;
+	}
+
+	if program.ascii.is_empty() || program.binary.is_empty() {
+		return Err(PdfError::Font("PFB file has no encrypted portion".to_string()));
+	}
+	return Ok(program);
+}
+
+/// Reads the font metrics from the clear text portion of a Type 1 font.
+fn type1_metrics(ascii: &[u8]) -> Result<FontMetrics, PdfError> {
+	let mut metrics = FontMetrics {
+		name: Vec::new(),
+		bbox: Rectangle::new(0.0, 0.0, 0.0, 0.0),
+		italic_angle: 0.0,
+		ascent: 0.0,
+		descent: 0.0,
+		cap_height: 0.0,
+		fixed_pitch: false,
+		nonsymbolic: false,
+	};
+
+	let mut lexer = Lexer::new(ascii, 0);
+	let number = |token: Option<Token<'_>>| match token {
+		Some(Token::Integer(value)) => value as f64,
+		Some(Token::Real(value)) => value,
+		_ => 0.0,
+	};
+
+	// The header is PostScript, but we only look for the few well-known keys,
+	// and stop at anything our lexer doesn't understand.
+	while let Ok(Some(token)) = lexer.next() {
+		let Token::Name(key) = token else {
+			continue;
+		};
+
+		match key.as_slice() {
+			b"FontName" => {
+				if let Ok(Some(Token::Name(name))) = lexer.next() {
+					metrics.name = name;
+				}
+			},
+			b"FontBBox" => {
+				// Either a procedure or an array, with the same numbers inside.
+				let _ = lexer.next();
+				let mut values = [0.0; 4];
+				for value in &mut values {
+					*value = number(lexer.next().ok().flatten());
+				}
+				metrics.bbox = Rectangle::new(values[0], values[1], values[2], values[3]);
+			},
+			b"ItalicAngle" => metrics.italic_angle = number(lexer.next().ok().flatten()),
+			b"isFixedPitch" => metrics.fixed_pitch = matches!(lexer.next(), Ok(Some(Token::Keyword(b"true")))),
+			b"Encoding" => metrics.nonsymbolic = matches!(lexer.next(), Ok(Some(Token::Keyword(b"StandardEncoding")))),
+			_ => {},
+		}
+	}
+
+	if metrics.name.is_empty() {
+		return Err(PdfError::Font("Type 1 font has no /FontName".to_string()));
+	}
+
+	metrics.ascent = metrics.bbox.ury;
+	metrics.descent = metrics.bbox.lly;
+	metrics.cap_height = metrics.bbox.ury;
+	return Ok(metrics);
+}
+
+/// Glyph names of the printable ASCII characters in the standard encoding of
+/// Type 1 fonts, which differs from ASCII only in the quotes.
+const STANDARD_ENCODING: [&str; 95] = [
+	"space", "exclam", "quotedbl", "numbersign", "dollar", "percent", "ampersand", "quoteright", "parenleft", "parenright", "asterisk", "plus", "comma", "hyphen", "period", "slash",
+	"zero", "one", "two", "three", "four", "five", "six", "seven", "eight", "nine", "colon", "semicolon", "less", "equal", "greater", "question",
+	"at", "A", "B", "C", "D", "E", "F", "G", "H", "I", "J", "K", "L", "M", "N", "O", "P", "Q", "R", "S", "T", "U", "V", "W", "X", "Y", "Z",
+	"bracketleft", "backslash", "bracketright", "asciicircum", "underscore", "quoteleft",
+	"a", "b", "c", "d", "e", "f", "g", "h", "i", "j", "k", "l", "m", "n", "o", "p", "q", "r", "s", "t", "u", "v", "w", "x", "y", "z",
+	"braceleft", "bar", "braceright", "asciitilde",
+];
+
+/// Keys of the encryption of the private portion of Type 1 fonts, and of the
+/// charstrings in it.
+const EEXEC_KEY: u16 = 55665;
+const CHARSTRING_KEY: u16 = 4330;
+
+/// Reads the glyph names of the character codes from the clear text portion
+/// of a Type 1 font, either the standard encoding, or the built-in one given
+/// by `dup <code> /<name> put`.
+fn type1_encoding(ascii: &[u8]) -> BTreeMap<u8, Vec<u8>> {
+	let mut encoding = BTreeMap::new();
+	let mut lexer = Lexer::new(ascii, 0);
+	let mut tokens = [None, None, None];
+	while let Ok(Some(token)) = lexer.next() {
+		match (&tokens, &token) {
+			([_, _, Some(Token::Name(key))], Token::Keyword(b"StandardEncoding")) if key == b"Encoding" => {
+				encoding.extend(STANDARD_ENCODING.iter().enumerate().map(|(index, name)| (32 + index as u8, name.as_bytes().to_vec())));
+			},
+			([Some(Token::Keyword(b"dup")), Some(Token::Integer(code)), Some(Token::Name(name))], Token::Keyword(b"put")) => {
+				if let Ok(code) = u8::try_from(*code) {
+					encoding.insert(code, name.clone());
+				}
+			},
+			_ => {},
+		}
+		tokens = [tokens[1].take(), tokens[2].take(), Some(token)];
+	}
+	return encoding;
+}
+
+/// Decrypts the data of a Type 1 font encrypted with the given key, dropping
+/// the random bytes it starts with.
+fn type1_decrypt(data: &[u8], key: u16, skip: usize) -> Vec<u8> {
+	let mut r = key;
+	let mut plain = Vec::with_capacity(data.len());
+	for &cipher in data {
+		plain.push(cipher ^ (r >> 8) as u8);
+		r = (cipher as u16).wrapping_add(r).wrapping_mul(52845).wrapping_add(22719);
+	}
+	plain.drain(..skip.min(plain.len()));
+	return plain;
+}
+
+/// Reads the advance widths of the glyphs, by their names, from the
+/// charstrings in the encrypted portion of a Type 1 font, in glyph space
+/// units, which are thousandths of an em with the usual font matrix.
+fn type1_widths(binary: &[u8]) -> BTreeMap<Vec<u8>, f64> {
+	let private = type1_decrypt(binary, EEXEC_KEY, 4);
+	let find = |needle: &[u8], from: usize| private.get(from..)?.windows(needle.len()).position(|window| window == needle).map(|pos| from + pos + needle.len());
+	let skip_whitespace = |mut pos: usize| {
+		while private.get(pos).is_some_and(u8::is_ascii_whitespace) {
+			pos += 1;
+		}
+		return pos;
+	};
+	let word = |pos: usize| {
+		let end = private[pos..].iter().position(|byte| byte.is_ascii_whitespace()).map_or(private.len(), |end| pos + end);
+		return (&private[pos..end], end);
+	};
+	// Bytes of every charstring kept unencrypted, after the random ones.
+	let len_iv = find(b"/lenIV", 0).and_then(|pos| std::str::from_utf8(word(skip_whitespace(pos)).0).ok()?.parse().ok()).unwrap_or(4);
+
+	// Every charstring is given as `/<name> <length> RD <bytes> ND`.
+	let mut widths = BTreeMap::new();
+	let Some(mut pos) = find(b"/CharStrings", 0) else {
+		return widths;
+	};
+	while let Some(start) = find(b"/", pos) {
+		let (name, end) = word(start);
+		let (length, end) = word(skip_whitespace(end));
+		let Some(length) = std::str::from_utf8(length).ok().and_then(|length| length.parse::<usize>().ok()) else {
+			break;
+		};
+		// The token after the length, e.g. `RD`, is followed by a single space.
+		let (_, end) = word(skip_whitespace(end));
+		let Some(charstring) = private.get(end + 1..end + 1 + length) else {
+			break;
+		};
+		if let Some(width) = charstring_width(&type1_decrypt(charstring, CHARSTRING_KEY, len_iv)) {
+			widths.insert(name.to_vec(), width);
+		}
+		pos = end + 1 + length;
+	}
+	return widths;
+}
+
+/// Reads the advance width of the glyph from the `hsbw` or `sbw` command,
+/// which the Type 1 charstrings start with.
+fn charstring_width(charstring: &[u8]) -> Option<f64> {
+	let mut stack = Vec::new();
+	let mut pos = 0;
+	while let Some(&value) = charstring.get(pos) {
+		pos += 1;
+		match value {
+			32..=246 => stack.push(value as f64 - 139.0),
+			247..=254 => {
+				let next = *charstring.get(pos)? as f64;
+				pos += 1;
+				stack.push(match value {
+					247..=250 => (value as f64 - 247.0) * 256.0 + next + 108.0,
+					_ => -(value as f64 - 251.0) * 256.0 - next - 108.0,
+				});
+			},
+			255 => {
+				let bytes = charstring.get(pos..pos + 4)?;
+				pos += 4;
+				stack.push(i32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f64);
+			},
+			// hsbw
+			13 => return stack.get(1).copied(),
+			12 => {
+				let escape = *charstring.get(pos)?;
+				pos += 1;
+				match escape {
+					// sbw
+					7 => return stack.get(2).copied(),
+					// div, for the widths which are not integers.
+					12 => {
+						let (b, a) = (stack.pop()?, stack.pop()?);
+						stack.push(a / b);
+					},
+					_ => return None,
+				}
+			},
+			_ => return None,
+		}
+	}
+	return None;
+}
+
+/// Reads big-endian integers from font tables, returning `None` past the end.
+fn u16_at(data: &[u8], pos: usize) -> Option<u16> {
+	let bytes = data.get(pos..pos + 2)?;
+	return Some(u16::from_be_bytes([bytes[0], bytes[1]]));
+}
+
+fn i16_at(data: &[u8], pos: usize) -> Option<i16> {
+	return u16_at(data, pos).map(|value| value as i16);
+}
+
+fn u32_at(data: &[u8], pos: usize) -> Option<u32> {
+	let bytes = data.get(pos..pos + 4)?;
+	return Some(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]));
+}
+
+/// Tables of an OpenType (sfnt) font file.
+struct OpenType<'a> {
+	tables: Vec<([u8; 4], &'a [u8])>,
+}
+
+impl<'a> OpenType<'a> {
+	fn parse(data: &'a [u8]) -> Result<Self, PdfError> {
+		let truncated = || PdfError::Font("truncated OpenType table directory".to_string());
+		let count = u16_at(data, 4).ok_or_else(truncated)? as usize;
+
+		let mut tables = Vec::with_capacity(count);
+		for i in 0..count {
+			let record = 12 + i * 16;
+			let tag = data.get(record..record + 4).ok_or_else(truncated)?;
+			let offset = u32_at(data, record + 8).ok_or_else(truncated)? as usize;
+			let length = u32_at(data, record + 12).ok_or_else(truncated)? as usize;
+			let Some(table) = data.get(offset..offset.saturating_add(length)) else {
+				return Err(PdfError::Font(std::format!("OpenType table {} is out of bounds", String::from_utf8_lossy(tag))));
+			};
+			tables.push(([tag[0], tag[1], tag[2], tag[3]], table));
+		}
+
+		return Ok(Self { tables });
+	}
+
+	fn table(&self, tag: &[u8; 4]) -> Option<&'a [u8]> {
+		return self.tables.iter().find(|(other, _)| other == tag).map(|&(_, table)| table);
+	}
+
+	/// Gets the PostScript name of the font from the `name` table.
+	fn postscript_name(&self) -> Option<Vec<u8>> {
+		let table = self.table(b"name")?;
+		let count = u16_at(table, 2)? as usize;
+		let storage = u16_at(table, 4)? as usize;
+		for i in 0..count {
+			let record = 6 + i * 12;
+			let (platform, name_id) = (u16_at(table, record)?, u16_at(table, record + 6)?);
+			if name_id != 6 {
+				continue;
+			}
+
+			let length = u16_at(table, record + 8)? as usize;
+			let offset = storage + u16_at(table, record + 10)? as usize;
+			let bytes = table.get(offset..offset + length)?;
+			// Windows names are UTF-16BE, while PostScript names are always ASCII.
+			return Some(match platform {
+				3 => bytes.chunks(2).filter_map(|pair| pair.get(1).copied()).collect(),
+				_ => bytes.to_vec(),
+			});
+		}
+		return None;
+	}
+
+	/// Finds the Unicode `cmap` subtable, returning the table with the offset
+	/// of the subtable. Only the format 4 subtables are supported, which cover
+	/// the BMP.
+	fn unicode_subtable(&self) -> Option<(&'a [u8], usize)> {
+		let table = self.table(b"cmap")?;
+		let count = u16_at(table, 2)? as usize;
+		let subtable = (0..count).find_map(|i| {
+			let record = 4 + i * 8;
+			let (platform, encoding) = (u16_at(table, record)?, u16_at(table, record + 2)?);
+			let offset = u32_at(table, record + 4)? as usize;
+			let unicode = platform == 0 || (platform == 3 && matches!(encoding, 1 | 10));
+			return (unicode && u16_at(table, offset)? == 4).then_some(offset);
+		})?;
+		return Some((table, subtable));
+	}
+
+	/// Maps the character code to a glyph, using the Unicode `cmap` subtable.
+	fn glyph(&self, code: u16) -> Option<u16> {
+		let (table, subtable) = self.unicode_subtable()?;
+
+		let segments = u16_at(table, subtable + 6)? as usize / 2;
+		let ends = subtable + 14;
+		let starts = ends + segments * 2 + 2;
+		let deltas = starts + segments * 2;
+		let ranges = deltas + segments * 2;
+		for i in 0..segments {
+			if u16_at(table, ends + i * 2)? < code {
+				continue;
+			}
+			let start = u16_at(table, starts + i * 2)?;
+			if start > code {
+				return None;
+			}
+
+			let delta = u16_at(table, deltas + i * 2)?;
+			let range = u16_at(table, ranges + i * 2)? as usize;
+			if range == 0 {
+				return Some(code.wrapping_add(delta));
+			}
+			let glyph = u16_at(table, ranges + i * 2 + range + (code - start) as usize * 2)?;
+			return (glyph != 0).then(|| glyph.wrapping_add(delta));
+		}
+		return None;
+	}
+
+	/// Maps the glyphs back to the characters, using the Unicode `cmap`
+	/// subtable. Glyphs of several characters are mapped to the first one.
+	fn glyph_chars(&self) -> BTreeMap<u16, char> {
+		let mut chars = BTreeMap::new();
+		let Some((table, subtable)) = self.unicode_subtable() else {
+			return chars;
+		};
+		let segments = u16_at(table, subtable + 6).unwrap_or(0) as usize / 2;
+		let (ends, starts) = (subtable + 14, subtable + 16 + segments * 2);
+		for i in 0..segments {
+			let (Some(start), Some(end)) = (u16_at(table, starts + i * 2), u16_at(table, ends + i * 2)) else {
+				break;
+			};
+			// The last segment only maps 0xFFFF, which is not a character.
+			for code in start..=end.min(0xFFFE) {
+				let (Some(c), Some(glyph)) = (char::from_u32(code as u32), self.glyph(code)) else {
+					continue;
+				};
+				chars.entry(glyph).or_insert(c);
+			}
+		}
+		return chars;
+	}
+
+	/// Gets the advance width of the glyph from the `hmtx` table, in font units.
+	fn advance(&self, glyph: u16) -> Option<u16> {
+		let metrics = u16_at(self.table(b"hhea")?, 34)? as usize;
+		let table = self.table(b"hmtx")?;
+		// Glyphs past the last metric share its advance.
+		let index = (glyph as usize).min(metrics.checked_sub(1)?);
+		return u16_at(table, index * 4);
+	}
+
+	/// Gets the advance height of the glyph from the `vmtx` table, in font
+	/// units, if the font has vertical metrics.
+	fn vertical_advance(&self, glyph: u16) -> Option<u16> {
+		let metrics = u16_at(self.table(b"vhea")?, 34)? as usize;
+		let table = self.table(b"vmtx")?;
+		let index = (glyph as usize).min(metrics.checked_sub(1)?);
+		return u16_at(table, index * 4);
+	}
+
+	/// Gets the number of glyphs, from the `maxp` table, or from the number of
+	/// horizontal metrics if it's missing.
+	fn glyph_count(&self) -> u16 {
+		return self.table(b"maxp").and_then(|maxp| u16_at(maxp, 4)).or_else(|| u16_at(self.table(b"hhea")?, 34)).unwrap_or(0);
+	}
+}
+
+/// Range of character codes for which the widths of OpenType fonts are written.
+const FIRST_CHAR: u16 = 32;
+const LAST_CHAR: u16 = 126;
+
+/// Segment of a glyph outline, in font units.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PathSegment {
+	/// Start of a new contour.
+	MoveTo(Point),
+	LineTo(Point),
+	/// Quadratic Bézier curve, with its control point and end point.
+	QuadTo(Point, Point),
+	/// End of the contour, which is closed with a straight line.
+	Close,
+}
+
+/// Maximum nesting of composite glyphs, which also rules out loops.
+const MAX_COMPONENT_DEPTH: usize = 8;
+
+/// Flags of the points of simple glyphs.
+const ON_CURVE: u8 = 1 << 0;
+const X_SHORT: u8 = 1 << 1;
+const Y_SHORT: u8 = 1 << 2;
+const REPEAT: u8 = 1 << 3;
+/// For short coordinates, it's the sign of the delta, otherwise it means the
+/// coordinate is the same as the previous one.
+const X_SAME: u8 = 1 << 4;
+const Y_SAME: u8 = 1 << 5;
+
+/// Flags of the components of composite glyphs.
+const ARGS_ARE_WORDS: u16 = 1 << 0;
+const ARGS_ARE_XY_VALUES: u16 = 1 << 1;
+const HAS_SCALE: u16 = 1 << 3;
+const MORE_COMPONENTS: u16 = 1 << 5;
+const HAS_XY_SCALE: u16 = 1 << 6;
+const HAS_TWO_BY_TWO: u16 = 1 << 7;
+
+/// TrueType font program, i.e. an OpenType font with glyph outlines in the
+/// `glyf` table.
+#[derive(Clone, Debug)]
+pub struct TrueTypeFont {
+	data: Vec<u8>,
+}
+
+impl TrueTypeFont {
+	/// Attempts to parse the font program, which has to have the tables needed
+	/// to find the glyph outlines.
+	pub fn parse(data: Vec<u8>) -> Result<Self, PdfError> {
+		let font = OpenType::parse(&data)?;
+		for tag in [b"cmap", b"glyf", b"head", b"loca"] {
+			if font.table(tag).is_none() {
+				return Err(PdfError::Font(std::format!("TrueType font has no {} table", String::from_utf8_lossy(tag))));
+			}
+		}
+		return Ok(Self { data });
+	}
+
+	/// Gets the outline of the glyph of the character, i.e. its contours made
+	/// of straight lines and quadratic curves, in font units. Components of
+	/// composite glyphs are transformed and merged together.
+	pub fn glyph_outline(&self, codepoint: char) -> Option<Vec<PathSegment>> {
+		let font = OpenType::parse(&self.data).ok()?;
+		let glyph = font.glyph(u16::try_from(codepoint as u32).ok()?)?;
+		let mut segments = Vec::new();
+		glyph_outline(&font, glyph, Matrix::IDENTITY, 0, &mut segments)?;
+		return Some(segments);
+	}
+
+	/// Gets the codes showing the text with the font embedded as a composite
+	/// font with an identity encoding, e.g. with
+	/// [`Document::embed_vertical_font`], i.e. the glyph identifiers as two
+	/// bytes each. Characters without a glyph are shown with the `.notdef`
+	/// glyph.
+	pub fn identity_codes(&self, text: &str) -> Vec<u8> {
+		let font = OpenType::parse(&self.data).ok();
+		return text.chars().flat_map(|c| {
+			let glyph = font.as_ref().and_then(|font| font.glyph(u16::try_from(c as u32).ok()?)).unwrap_or(0);
+			return glyph.to_be_bytes();
+		}).collect();
+	}
+}
+
+/// Gets the data of the glyph from the `glyf` table, using the `loca` table.
+fn glyph_data<'a>(font: &OpenType<'a>, glyph: u16) -> Option<&'a [u8]> {
+	let loca = font.table(b"loca")?;
+	let index = glyph as usize;
+	// Short offsets are stored divided by two.
+	let (start, end) = match i16_at(font.table(b"head")?, 50)? {
+		0 => (u16_at(loca, index * 2)? as usize * 2, u16_at(loca, index * 2 + 2)? as usize * 2),
+		_ => (u32_at(loca, index * 4)? as usize, u32_at(loca, index * 4 + 4)? as usize),
+	};
+	return font.table(b"glyf")?.get(start..end);
+}
+
+fn glyph_outline(font: &OpenType<'_>, glyph: u16, transform: Matrix, depth: usize, segments: &mut Vec<PathSegment>) -> Option<()> {
+	let data = glyph_data(font, glyph)?;
+	// Glyphs without any contours, e.g. the space, have no data at all.
+	if data.is_empty() {
+		return Some(());
+	}
+
+	return match i16_at(data, 0)? {
+		contours @ 0.. => simple_outline(data, contours as usize, transform, segments),
+		_ if depth < MAX_COMPONENT_DEPTH => composite_outline(font, data, transform, depth, segments),
+		_ => None,
+	};
+}
+
+fn simple_outline(data: &[u8], contours: usize, transform: Matrix, segments: &mut Vec<PathSegment>) -> Option<()> {
+	let ends = (0..contours).map(|i| u16_at(data, 10 + i * 2).map(usize::from)).collect::<Option<Vec<_>>>()?;
+	let count = ends.last().map_or(0, |end| end + 1);
+	let instructions = u16_at(data, 10 + contours * 2)? as usize;
+	let mut pos = 12 + contours * 2 + instructions;
+
+	let mut flags = Vec::with_capacity(count);
+	while flags.len() < count {
+		let flag = *data.get(pos)?;
+		pos += 1;
+		flags.push(flag);
+		if flag & REPEAT != 0 {
+			let repeat = *data.get(pos)?;
+			pos += 1;
+			flags.extend(std::iter::repeat_n(flag, repeat as usize));
+		}
+	}
+	flags.truncate(count);
+
+	let xs = glyph_coordinates(data, &mut pos, &flags, X_SHORT, X_SAME)?;
+	let ys = glyph_coordinates(data, &mut pos, &flags, Y_SHORT, Y_SAME)?;
+	let points: Vec<(Point, bool)> = (0..count).map(|i| (transform.transform(Point::new(xs[i] as f64, ys[i] as f64)), flags[i] & ON_CURVE != 0)).collect();
+
+	let mut start = 0;
+	for end in ends {
+		if end < start || end >= count {
+			return None;
+		}
+		contour_segments(&points[start..=end], segments);
+		start = end + 1;
+	}
+	return Some(());
+}
+
+/// Reads the coordinates of the points, which are stored as deltas.
+fn glyph_coordinates(data: &[u8], pos: &mut usize, flags: &[u8], short: u8, same: u8) -> Option<Vec<i32>> {
+	let mut value = 0i32;
+	let mut coordinates = Vec::with_capacity(flags.len());
+	for &flag in flags {
+		if flag & short != 0 {
+			let delta = *data.get(*pos)? as i32;
+			*pos += 1;
+			value += if flag & same != 0 { delta } else { -delta };
+		} else if flag & same == 0 {
+			value += i16_at(data, *pos)? as i32;
+			*pos += 2;
+		}
+		coordinates.push(value);
+	}
+	return Some(coordinates);
+}
+
+#[inline]
+fn midpoint(a: Point, b: Point) -> Point {
+	return Point::new((a.x + b.x) / 2.0, (a.y + b.y) / 2.0);
+}
+
+/// Converts the points of a contour into segments. Two consecutive off-curve
+/// points have an implied on-curve point halfway between them.
+fn contour_segments(points: &[(Point, bool)], segments: &mut Vec<PathSegment>) {
+	let (Some(&(first, first_on)), Some(&(last, last_on))) = (points.first(), points.last()) else {
+		return;
+	};
+
+	// The contour has to start on the curve, and the points following the
+	// start are visited in order, ending back at it.
+	let (start, rest) = match (first_on, last_on) {
+		(true, _) => (first, &points[1..]),
+		(false, true) => (last, &points[..points.len() - 1]),
+		(false, false) => (midpoint(first, last), points),
+	};
+
+	segments.push(PathSegment::MoveTo(start));
+	let mut control: Option<Point> = None;
+	for &(point, on_curve) in rest.iter().chain(std::iter::once(&(start, true))) {
+		match (control, on_curve) {
+			(Some(previous), true) => segments.push(PathSegment::QuadTo(previous, point)),
+			(None, true) => segments.push(PathSegment::LineTo(point)),
+			(Some(previous), false) => segments.push(PathSegment::QuadTo(previous, midpoint(previous, point))),
+			(None, false) => {},
+		}
+		control = (!on_curve).then_some(point);
+	}
+	segments.push(PathSegment::Close);
+}
+
+fn composite_outline(font: &OpenType<'_>, data: &[u8], transform: Matrix, depth: usize, segments: &mut Vec<PathSegment>) -> Option<()> {
+	let f2dot14 = |pos| i16_at(data, pos).map(|value| value as f64 / 16384.0);
+
+	let mut pos = 10;
+	loop {
+		let (flags, glyph) = (u16_at(data, pos)?, u16_at(data, pos + 2)?);
+		pos += 4;
+		let (dx, dy) = if flags & ARGS_ARE_WORDS != 0 {
+			pos += 4;
+			(i16_at(data, pos - 4)? as f64, i16_at(data, pos - 2)? as f64)
+		} else {
+			pos += 2;
+			(*data.get(pos - 2)? as i8 as f64, *data.get(pos - 1)? as i8 as f64)
+		};
+		// Components can also be positioned by matching their points with
+		// the ones of the parent, which is rare enough not to be supported.
+		let (dx, dy) = if flags & ARGS_ARE_XY_VALUES != 0 { (dx, dy) } else { (0.0, 0.0) };
+
+		let (a, b, c, d) = if flags & HAS_SCALE != 0 {
+			pos += 2;
+			let scale = f2dot14(pos - 2)?;
+			(scale, 0.0, 0.0, scale)
+		} else if flags & HAS_XY_SCALE != 0 {
+			pos += 4;
+			(f2dot14(pos - 4)?, 0.0, 0.0, f2dot14(pos - 2)?)
+		} else if flags & HAS_TWO_BY_TWO != 0 {
+			pos += 8;
+			(f2dot14(pos - 8)?, f2dot14(pos - 6)?, f2dot14(pos - 4)?, f2dot14(pos - 2)?)
+		} else {
+			(1.0, 0.0, 0.0, 1.0)
+		};
+
+		let component = Matrix::new(a, b, c, d, dx, dy);
+		glyph_outline(font, glyph, component.then(&transform), depth + 1, segments)?;
+		if flags & MORE_COMPONENTS == 0 {
+			return Some(());
+		}
+	}
+}
+
+impl Document {
+	/// Attempts to embed the Type 1 font program, read from a PFB file. The
+	/// font uses its built-in encoding, and the widths of the characters are
+	/// read from the charstrings of the program.
+	pub fn embed_type1_font(&mut self, pfb: &[u8]) -> Result<FontRef, PdfError> {
+		let program = pfb_segments(pfb)?;
+		let ascii = program.ascii.concat();
+		let metrics = type1_metrics(&ascii)?;
+
+		let binary = program.binary.concat();
+		let glyph_widths = type1_widths(&binary);
+		let widths: BTreeMap<u8, i64> = type1_encoding(&ascii).into_iter().filter_map(|(code, name)| Some((code, glyph_widths.get(&name)?.round() as i64))).collect();
+		let (Some(&first), Some(&last)) = (widths.keys().next(), widths.keys().next_back()) else {
+			return Err(PdfError::Font("Type 1 font has no widths for its encoded characters".to_string()));
+		};
+
+		let trailer = program.trailer.concat();
+		let mut file = Stream::new(Dictionary::new(), Vec::with_capacity(ascii.len() + binary.len() + trailer.len()));
+		file.dict.set("Length1", ascii.len());
+		file.dict.set("Length2", binary.len());
+		file.dict.set("Length3", trailer.len());
+		file.data.extend_from_slice(&ascii);
+		file.data.extend_from_slice(&binary);
+		file.data.extend_from_slice(&trailer);
+		let file = self.add_object(file);
+
+		let descriptor = self.add_object(metrics.descriptor("FontFile", file));
+
+		let mut dict = Dictionary::new();
+		dict.set("Type", PdfObject::name("Font"));
+		dict.set("Subtype", PdfObject::name("Type1"));
+		dict.set_raw(b"BaseFont", PdfObject::Name(metrics.name));
+		dict.set("FirstChar", first as i64);
+		dict.set("LastChar", last as i64);
+		dict.set("Widths", (first..=last).map(|code| PdfObject::Integer(widths.get(&code).copied().unwrap_or(0))).collect::<Vec<_>>());
+		dict.set("FontDescriptor", descriptor);
+		return Ok(FontRef(self.add_object(dict)));
+	}
+
+	/// Attempts to embed the OpenType font with CFF outlines. The bare CFF
+	/// table is embedded as a compressed `/FontFile3` of the `/Type1C` subtype,
+	/// and the widths of the printable ASCII characters are taken from the
+	/// Unicode `cmap`.
+	pub fn embed_opentype_cff(&mut self, otf: &[u8]) -> Result<FontRef, PdfError> {
+		if !otf.starts_with(b"OTTO") {
+			return Err(PdfError::Font("not an OpenType font with CFF outlines".to_string()));
+		}
+
+		let font = OpenType::parse(otf)?;
+		let Some(cff) = font.table(b"CFF ") else {
+			return Err(PdfError::Font("OpenType font has no CFF table".to_string()));
+		};
+		let metrics = opentype_metrics(&font)?;
+
+		let mut file = Stream::new(Dictionary::new(), filters::flate::encode(cff));
+		file.dict.set("Subtype", PdfObject::name("Type1C"));
+		file.dict.set("Filter", PdfObject::name("FlateDecode"));
+		let file = self.add_object(file);
+
+		let descriptor = self.add_object(metrics.descriptor("FontFile3", file));
+		return Ok(self.add_simple_font("Type1", &font, metrics, descriptor));
+	}
+
+	/// Attempts to embed the TrueType font, e.g. one extracted from another
+	/// document with [`Reader::font_program`]. The whole program is embedded
+	/// as a compressed `/FontFile2`, and the widths of the printable ASCII
+	/// characters are taken from the Unicode `cmap`.
+	///
+	/// [`Reader::font_program`]: crate::Reader::font_program
+	pub fn embed_truetype_font(&mut self, ttf: &[u8]) -> Result<FontRef, PdfError> {
+		TrueTypeFont::parse(ttf.to_vec())?;
+		let font = OpenType::parse(ttf)?;
+		let metrics = opentype_metrics(&font)?;
+
+		let mut file = Stream::new(Dictionary::new(), filters::flate::encode(ttf));
+		file.dict.set("Length1", ttf.len());
+		file.dict.set("Filter", PdfObject::name("FlateDecode"));
+		let file = self.add_object(file);
+
+		let descriptor = self.add_object(metrics.descriptor("FontFile2", file));
+		return Ok(self.add_simple_font("TrueType", &font, metrics, descriptor));
+	}
+
+	/// Attempts to embed the TrueType font as a composite font for vertical
+	/// writing, with the `Identity-V` encoding, so the text is shown with the
+	/// glyph identifiers, e.g. from [`TrueTypeFont::identity_codes`]. The
+	/// vertical displacements of the glyphs are taken from the `vmtx` table,
+	/// or are one em down if the font has no vertical metrics.
+	pub fn embed_vertical_font(&mut self, ttf: &[u8]) -> Result<FontRef, PdfError> {
+		TrueTypeFont::parse(ttf.to_vec())?;
+		let font = OpenType::parse(ttf)?;
+		let metrics = opentype_metrics(&font)?;
+
+		let mut file = Stream::new(Dictionary::new(), filters::flate::encode(ttf));
+		file.dict.set("Length1", ttf.len());
+		file.dict.set("Filter", PdfObject::name("FlateDecode"));
+		let file = self.add_object(file);
+		let descriptor = self.add_object(metrics.descriptor("FontFile2", file));
+
+		let units = units_per_em(&font);
+		let scale = |value: u16| (value as f64 * 1000.0 / units).round() as i64;
+		let glyphs = 0..font.glyph_count();
+		let widths: Vec<i64> = glyphs.clone().map(|glyph| font.advance(glyph).map_or(0, scale)).collect();
+
+		let mut system_info = Dictionary::new();
+		system_info.set("Registry", PdfObject::string(b"Adobe".to_vec()));
+		system_info.set("Ordering", PdfObject::string(b"Identity".to_vec()));
+		system_info.set("Supplement", 0);
+		let mut cid_font = Dictionary::new();
+		cid_font.set("Type", PdfObject::name("Font"));
+		cid_font.set("Subtype", PdfObject::name("CIDFontType2"));
+		cid_font.set_raw(b"BaseFont", PdfObject::Name(metrics.name.clone()));
+		cid_font.set("CIDSystemInfo", system_info);
+		cid_font.set("FontDescriptor", descriptor);
+		cid_font.set("CIDToGIDMap", PdfObject::name("Identity"));
+		cid_font.set("W", std::vec![PdfObject::Integer(0), widths.iter().copied().map(PdfObject::Integer).collect::<Vec<_>>().into()]);
+		if font.table(b"vmtx").is_some() {
+			// Every glyph has its displacement, and the position of its
+			// vertical origin, centred above it at the ascent.
+			let mut metrics_w2 = Vec::with_capacity(widths.len() * 3);
+			for (glyph, width) in glyphs.zip(&widths) {
+				metrics_w2.push(PdfObject::Integer(-font.vertical_advance(glyph).map_or(1000, scale)));
+				metrics_w2.push(PdfObject::Integer(width / 2));
+				metrics_w2.push(PdfObject::Integer(metrics.ascent.round() as i64));
+			}
+			cid_font.set("W2", std::vec![PdfObject::Integer(0), PdfObject::Array(metrics_w2)]);
+		}
+		let cid_font = self.add_object(cid_font);
+
+		let mut dict = Dictionary::new();
+		dict.set("Type", PdfObject::name("Font"));
+		dict.set("Subtype", PdfObject::name("Type0"));
+		dict.set_raw(b"BaseFont", PdfObject::Name(metrics.name));
+		dict.set("Encoding", PdfObject::name("Identity-V"));
+		dict.set("DescendantFonts", std::vec![PdfObject::Reference(cid_font)]);
+		return Ok(FontRef(self.add_object(dict)));
+	}
+
+	/// Adds the simple font dictionary, with the widths of the printable ASCII
+	/// characters.
+	fn add_simple_font(&mut self, subtype: &str, font: &OpenType<'_>, metrics: FontMetrics, descriptor: ObjectId) -> FontRef {
+		let mut dict = Dictionary::new();
+		dict.set("Type", PdfObject::name("Font"));
+		dict.set("Subtype", PdfObject::name(subtype));
+		dict.set_raw(b"BaseFont", PdfObject::Name(metrics.name));
+		set_widths(&mut dict, font, FIRST_CHAR as u8..=LAST_CHAR as u8);
+		dict.set("FontDescriptor", descriptor);
+		return FontRef(self.add_object(dict));
+	}
+
+	/// Attempts to replace the widths of the simple font, e.g. one embedded
+	/// with [`Document::embed_truetype_font`], with the ones of the characters
+	/// used by the text, taken from its program. This keeps the `/Widths` short
+	/// for fonts used only for a few characters.
+	pub fn set_font_widths(&mut self, font: FontRef, program: &TrueTypeFont, text: &[u8]) -> Result<(), PdfError> {
+		let program = OpenType::parse(&program.data)?;
+		let Some(dict) = self.object_mut(font.0).and_then(PdfObject::as_dict_mut).filter(|dict| dict.get_name("Type") == Some(b"Font")) else {
+			return Err(PdfError::InvalidArgument(std::format!("object {} {} R is not a font", font.0.number, font.0.generation)));
+		};
+		set_widths(dict, &program, text.iter().copied());
+		return Ok(());
+	}
+}
+
+/// Gets the number of font units per em of the OpenType font.
+fn units_per_em(font: &OpenType<'_>) -> f64 {
+	return font.table(b"head").and_then(|head| u16_at(head, 18)).filter(|&units| units != 0).unwrap_or(1000) as f64;
+}
+
+/// Computes the widths of the characters, in thousandths of an em, from the
+/// first to the last one used, with zero for the ones in between which are
+/// not used. Returns the first character with the widths, or `None` if no
+/// characters are used.
+fn glyph_widths(font: &OpenType<'_>, used: impl IntoIterator<Item = u8>) -> Option<(u8, Vec<i64>)> {
+	let mut used_codes = [false; 256];
+	for code in used {
+		used_codes[code as usize] = true;
+	}
+	let first = used_codes.iter().position(|&used| used)?;
+	let last = used_codes.iter().rposition(|&used| used)?;
+
+	let units = units_per_em(font);
+	let widths = (first..=last).map(|code| {
+		if !used_codes[code] {
+			return 0;
+		}
+		let advance = font.glyph(code as u16).and_then(|glyph| font.advance(glyph)).unwrap_or(0);
+		return (advance as f64 * 1000.0 / units).round() as i64;
+	}).collect();
+	return Some((first as u8, widths));
+}
+
+/// Sets the `/FirstChar`, `/LastChar`, and `/Widths` of the simple font
+/// dictionary, covering the used characters.
+fn set_widths(dict: &mut Dictionary, font: &OpenType<'_>, used: impl IntoIterator<Item = u8>) {
+	let Some((first, widths)) = glyph_widths(font, used) else {
+		for key in ["FirstChar", "LastChar", "Widths"] {
+			dict.remove(key);
+		}
+		return;
+	};
+	dict.set("FirstChar", first as i64);
+	dict.set("LastChar", first as i64 + widths.len() as i64 - 1);
+	dict.set("Widths", widths.into_iter().map(PdfObject::Integer).collect::<Vec<_>>());
+}
+
+/// Reads the metrics of the glyphs of a composite font, given by the `/W`
+/// or `/W2` arrays, each either as the first code followed by an array of the
+/// metrics of the consecutive codes, or as the first and the last code
+/// followed by the metrics they share. Every metric takes `stride` numbers,
+/// of which the first one is returned.
+pub(crate) fn cid_metrics(array: &[PdfObject], stride: usize) -> BTreeMap<u16, f64> {
+	let mut metrics = BTreeMap::new();
+	let mut pos = 0;
+	while let Some(first) = array.get(pos).and_then(PdfObject::as_integer) {
+		match array.get(pos + 1) {
+			Some(PdfObject::Array(values)) => {
+				for (code, values) in (first..).zip(values.chunks_exact(stride)) {
+					if let (Ok(code), Some(value)) = (u16::try_from(code), values[0].as_number()) {
+						metrics.insert(code, value);
+					}
+				}
+				pos += 2;
+			},
+			Some(last) => {
+				let (Some(last), Some(value)) = (last.as_integer(), array.get(pos + 2).and_then(PdfObject::as_number)) else {
+					break;
+				};
+				for code in first.max(0)..=last.min(u16::MAX as i64) {
+					metrics.insert(code as u16, value);
+				}
+				pos += 2 + stride;
+			},
+			None => break,
+		}
+	}
+	return metrics;
+}
+
+/// Attempts to read the metrics of the OpenType font.
+fn opentype_metrics(font: &OpenType<'_>) -> Result<FontMetrics, PdfError> {
+	let Some(head) = font.table(b"head") else {
+		return Err(PdfError::Font("OpenType font has no head table".to_string()));
+	};
+	let Some(name) = font.postscript_name() else {
+		return Err(PdfError::Font("OpenType font has no PostScript name".to_string()));
+	};
+
+	// All metrics are in font units, while PDF wants thousandths of an em.
+	let units = units_per_em(font);
+	let scale = |value: Option<i16>| value.unwrap_or(0) as f64 * 1000.0 / units;
+	let bbox = Rectangle::new(scale(i16_at(head, 36)), scale(i16_at(head, 38)), scale(i16_at(head, 40)), scale(i16_at(head, 42)));
+
+	let hhea = font.table(b"hhea").unwrap_or_default();
+	let post = font.table(b"post").unwrap_or_default();
+	let os2 = font.table(b"OS/2").unwrap_or_default();
+	let metrics = FontMetrics {
+		name,
+		bbox,
+		// Stored as a 16.16 fixed point number.
+		italic_angle: u32_at(post, 4).map_or(0.0, |angle| angle as i32 as f64 / 65536.0),
+		ascent: scale(i16_at(hhea, 4)),
+		descent: scale(i16_at(hhea, 6)),
+		// Cap height is only present since version 2 of the OS/2 table.
+		cap_height: match u16_at(os2, 0) {
+			Some(2..) => scale(i16_at(os2, 88)),
+			_ => bbox.ury,
+		},
+		fixed_pitch: u32_at(post, 12).is_some_and(|fixed| fixed != 0),
+		nonsymbolic: true,
+	};
+	return Ok(metrics);
+}
+
+/// Format of an embedded font program, given by the key of the font
+/// descriptor it's stored under, and its `/Subtype`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FontFileKind {
+	/// Type 1 program (`/FontFile`), with the cleartext and encrypted parts
+	/// concatenated, rather than in PFB segments.
+	Type1,
+	/// TrueType program (`/FontFile2`).
+	TrueType,
+	/// Bare CFF table of a simple font (`/FontFile3` of `/Type1C`).
+	Type1C,
+	/// Bare CFF table of a composite font (`/FontFile3` of `/CIDFontType0C`).
+	CIDFontType0C,
+	/// Whole OpenType program (`/FontFile3` of `/OpenType`).
+	OpenType,
+}
+
+impl Reader<'_> {
+	/// Attempts to extract the program embedded for the font, decoded.
+	/// Composite fonts use the program of their descendant. Returns `None`
+	/// if the font has no embedded program, e.g. one of the standard fonts.
+	pub fn font_program(&self, font: ObjectId) -> Result<Option<(FontFileKind, Vec<u8>)>, PdfError> {
+		let mut dict = self.resolve_dict(font)?;
+		if dict.get_name("Subtype") == Some(b"Type0") {
+			let descendants = self.resolve_object(dict.get("DescendantFonts").unwrap_or(&PdfObject::Null))?;
+			let Some(descendant) = descendants.as_array().and_then(|fonts| fonts.first()) else {
+				return Err(PdfError::Font("composite font has no descendant".to_string()));
+			};
+			dict = self.resolve_object(descendant)?.as_dict().cloned().unwrap_or_default();
+		}
+		let Some(descriptor) = dict.get("FontDescriptor") else {
+			return Ok(None);
+		};
+		let descriptor = self.resolve_object(descriptor)?.as_dict().cloned().unwrap_or_default();
+
+		for key in ["FontFile", "FontFile2", "FontFile3"] {
+			let Some(&PdfObject::Reference(id)) = descriptor.get(key) else {
+				continue;
+			};
+			let Some(file) = self.resolve(id)?.as_stream().cloned() else {
+				return Err(PdfError::Font(std::format!("/{} is not a stream", key)));
+			};
+			let kind = match (key, file.dict.get_name("Subtype")) {
+				("FontFile", _) => FontFileKind::Type1,
+				("FontFile2", _) => FontFileKind::TrueType,
+				(_, Some(b"Type1C")) => FontFileKind::Type1C,
+				(_, Some(b"CIDFontType0C")) => FontFileKind::CIDFontType0C,
+				(_, Some(b"OpenType")) => FontFileKind::OpenType,
+				_ => return Err(PdfError::Font("/FontFile3 has an unknown /Subtype".to_string())),
+			};
+			return Ok(Some((kind, self.decode(id, &file)?)));
+		}
+		return Ok(None);
+	}
+
+	/// Finds the decoders of the fonts in the resources, by their names, like
+	/// [`font_decoders`].
+	pub(crate) fn font_decoders(&self, resources: Option<&PdfObject>) -> BTreeMap<Vec<u8>, FontDecoder> {
+		let fonts = resources.and_then(|resources| self.resolve_object(resources).ok()).and_then(|resources| resources.as_dict()?.get("Font").cloned());
+		let Some(PdfObject::Dictionary(fonts)) = fonts.and_then(|fonts| self.resolve_object(&fonts).ok()) else {
+			return BTreeMap::new();
+		};
+		let program = |id| match self.font_program(id) {
+			Ok(Some((FontFileKind::TrueType | FontFileKind::OpenType, program))) => Some(program),
+			_ => None,
+		};
+		return font_decoders(&fonts, |object| self.resolve_object(object).ok(), program);
+	}
+}
+
+impl Document {
+	/// Finds the decoders of the fonts of the resource dictionary, by their
+	/// names, like [`font_decoders`].
+	pub(crate) fn resource_font_decoders(&self, resources: &Dictionary) -> BTreeMap<Vec<u8>, FontDecoder> {
+		let resolve = |object: &PdfObject| -> Option<PdfObject> {
+			return match object {
+				PdfObject::Reference(id) => self.object(*id).cloned(),
+				object => Some(object.clone()),
+			};
+		};
+		let Some(PdfObject::Dictionary(fonts)) = resources.get("Font").and_then(resolve) else {
+			return BTreeMap::new();
+		};
+		return font_decoders(&fonts, resolve, |id| self.truetype_font(FontRef(id)).ok().map(|font| font.data));
+	}
+}
+
+impl Page<'_> {
+	/// Finds the decoders of the fonts used by the page, by their names, like
+	/// [`font_decoders`], including the ones used by the content streams
+	/// which are only added to the page resources when it's written.
+	#[inline]
+	pub(crate) fn font_decoders(&self) -> BTreeMap<Vec<u8>, FontDecoder> {
+		return self.doc.resource_font_decoders(&self.resources());
+	}
+}
+
+/// Finds the decoders of the fonts, by their names, with the widths of their
+/// glyphs, from `/Widths`, or from `/W` for composite fonts. The objects are
+/// resolved with the given function, and the TrueType or OpenType programs
+/// are read with the other one, to map the codes of the composite fonts
+/// without a `/ToUnicode` map back to the characters through their `cmap`.
+/// Composite fonts are only decoded if their codes are the glyph
+/// identifiers, i.e. with the `Identity-H` or `Identity-V` encoding.
+fn font_decoders(fonts: &Dictionary, resolve: impl Fn(&PdfObject) -> Option<PdfObject>, program: impl Fn(ObjectId) -> Option<Vec<u8>>) -> BTreeMap<Vec<u8>, FontDecoder> {
+	let mut decoders = BTreeMap::new();
+	for (name, font) in fonts.iter() {
+		let Some(id) = font.as_reference() else {
+			continue;
+		};
+		let Some(PdfObject::Dictionary(dict)) = resolve(font) else {
+			continue;
+		};
+		let get = |dict: &Dictionary, key: &str| dict.get(key).and_then(&resolve);
+
+		if dict.get_name("Subtype") != Some(b"Type0") {
+			// Widths of simple fonts are given from the first code, and the
+			// missing ones from the font descriptor.
+			let first = get(&dict, "FirstChar").and_then(|first| first.as_integer()).unwrap_or(0);
+			let mut widths = BTreeMap::new();
+			for (index, width) in get(&dict, "Widths").and_then(|widths| widths.as_array().cloned()).unwrap_or_default().iter().enumerate() {
+				if let (Ok(code), Some(width)) = (u16::try_from(first + index as i64), resolve(width).and_then(|width| width.as_number())) {
+					widths.insert(code, width);
+				}
+			}
+			let missing = get(&dict, "FontDescriptor").and_then(|descriptor| descriptor.as_dict()?.get("MissingWidth")?.as_number());
+			decoders.insert(name.to_vec(), FontDecoder {
+				default_width: if widths.is_empty() { DEFAULT_GLYPH_WIDTH } else { missing.unwrap_or(0.0) },
+				widths,
+				..FontDecoder::default()
+			});
+			continue;
+		}
+
+		let encoding = dict.get_name("Encoding");
+		if !matches!(encoding, Some(b"Identity-H" | b"Identity-V")) {
+			continue;
+		}
+		let descendant = get(&dict, "DescendantFonts").and_then(|fonts| fonts.as_array()?.first().and_then(&resolve));
+		let descendant = descendant.and_then(|font| font.as_dict().cloned()).unwrap_or_default();
+		let chars = match dict.contains_key("ToUnicode") {
+			false => program(id).and_then(|program| Some(OpenType::parse(&program).ok()?.glyph_chars())).unwrap_or_default(),
+			true => BTreeMap::new(),
+		};
+		// Fonts in the vertical writing mode move the text down, by the
+		// displacements of their descendant.
+		let vertical = (encoding == Some(b"Identity-V")).then(|| VerticalMetrics {
+			default: get(&descendant, "DW2").and_then(|dw2| dw2.as_array()?.get(1)?.as_number()).unwrap_or(DEFAULT_VERTICAL_ADVANCE),
+			advances: get(&descendant, "W2").and_then(|w2| w2.as_array().map(|w2| cid_metrics(w2, 3))).unwrap_or_default(),
+		});
+		decoders.insert(name.to_vec(), FontDecoder {
+			two_byte: true,
+			chars,
+			widths: get(&descendant, "W").and_then(|w| w.as_array().map(|w| cid_metrics(w, 1))).unwrap_or_default(),
+			default_width: get(&descendant, "DW").and_then(|dw| dw.as_number()).unwrap_or(DEFAULT_CID_WIDTH),
+			vertical,
+		});
+	}
+	return decoders;
+}
+
+impl Document {
+	/// Attempts to read the TrueType program embedded for the font, e.g. in a
+	/// loaded document. Composite fonts use the program of their descendant.
+	pub fn truetype_font(&self, font: FontRef) -> Result<TrueTypeFont, PdfError> {
+		let resolve = |object: &PdfObject| -> Option<PdfObject> {
+			return match object {
+				PdfObject::Reference(id) => self.object(*id).cloned(),
+				object => Some(object.clone()),
+			};
+		};
+
+		let Some(mut dict) = self.object(font.0).and_then(PdfObject::as_dict).cloned() else {
+			return Err(PdfError::MissingObject(font.0));
+		};
+		if dict.get_name("Subtype") == Some(b"Type0") {
+			let descendant = dict.get("DescendantFonts").and_then(resolve).and_then(|fonts| fonts.as_array()?.first().and_then(resolve));
+			dict = descendant.and_then(|font| font.as_dict().cloned()).unwrap_or_default();
+		}
+		let descriptor = dict.get("FontDescriptor").and_then(resolve);
+		let Some(PdfObject::Stream(file)) = descriptor.as_ref().and_then(PdfObject::as_dict).and_then(|descriptor| descriptor.get("FontFile2")).and_then(resolve) else {
+			return Err(PdfError::Font("font has no embedded TrueType program".to_string()));
+		};
+		return TrueTypeFont::parse(self.decode(&file)?);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::font::*;
+	use crate::*;
+
+	fn pfb(segments: &[(u8, &[u8])]) -> Vec<u8> {
+		let mut data = Vec::new();
+		for (kind, segment) in segments {
+			data.extend_from_slice(&[0x80, *kind]);
+			data.extend_from_slice(&(segment.len() as u32).to_le_bytes());
+			data.extend_from_slice(segment);
+		}
+		data.extend_from_slice(&[0x80, 3]);
+		return data;
+	}
+
+	/// Encrypts the data like the portions of Type 1 fonts, after the random
+	/// bytes.
+	fn type1_encrypt(data: &[u8], key: u16, random: &[u8]) -> Vec<u8> {
+		let mut r = key;
+		return random.iter().chain(data).map(|&plain| {
+			let cipher = plain ^ (r >> 8) as u8;
+			r = (cipher as u16).wrapping_add(r).wrapping_mul(52845).wrapping_add(22719);
+			return cipher;
+		}).collect();
+	}
+
+	/// Builds the encrypted portion of a Type 1 font with the charstrings.
+	fn type1_private(charstrings: &[(&str, &[u8])]) -> Vec<u8> {
+		let mut private = b"dup /Private 8 dict dup begin\n/RD {string currentfile exch readstring pop} executeonly def\n/lenIV 4 def\n".to_vec();
+		private.extend_from_slice(std::format!("2 index /CharStrings {} dict dup begin\n", charstrings.len()).as_bytes());
+		for (name, charstring) in charstrings {
+			let charstring = type1_encrypt(charstring, 4330, &[0; 4]);
+			private.extend_from_slice(std::format!("/{} {} RD ", name, charstring.len()).as_bytes());
+			private.extend_from_slice(&charstring);
+			private.extend_from_slice(b" ND\n");
+		}
+		private.extend_from_slice(b"end\nend\nreadonly put\nnoaccess put\ndup /FontName get exch definefont pop\nmark currentfile closefile\n");
+		return type1_encrypt(&private, 55665, &[0xD9, 0xD6, 0x6F, 0x63]);
+	}
+
+	#[test]
+	fn type1_embedding() {
+		let ascii = b"%!PS-AdobeFont-1.0: TestSans 001.000\n/FontName /TestSans def\n/FontBBox {-50 -210 1000 750} readonly def\n/ItalicAngle -12 def\n/Encoding StandardEncoding def\ncurrentfile eexec\n";
+		// Glyphs with `0 0 hsbw`, `20 600 hsbw`, `0 0 720 0 sbw`, and `0 1001 2 div
+		// hsbw`, each followed by `endchar`.
+		let binary = type1_private(&[(".notdef", &[139, 139, 13, 14]), ("A", &[159, 248, 236, 13, 14]), ("C", &[139, 139, 249, 100, 139, 12, 7, 14]), ("quoteright", &[139, 255, 0, 0, 3, 233, 141, 12, 12, 13, 14])]);
+		let trailer = [b"0000000000000000\n".repeat(8), b"cleartomark\n".to_vec()].concat();
+		let data = pfb(&[(1, ascii), (2, &binary[..4]), (2, &binary[4..]), (1, &trailer)]);
+
+		let mut doc = Document::new();
+		let font = doc.embed_type1_font(&data).unwrap();
+
+		let font = doc.object(font.0).unwrap().as_dict().unwrap().clone();
+		assert_eq!(font.get_name("BaseFont"), Some(&b"TestSans"[..]));
+		let descriptor = doc.object(font.get("FontDescriptor").unwrap().as_reference().unwrap()).unwrap().as_dict().unwrap();
+		assert_eq!(descriptor.get("Flags"), Some(&PdfObject::Integer(32 | 64)));
+		assert_eq!(descriptor.get("FontBBox"), Some(&Rectangle::new(-50.0, -210.0, 1000.0, 750.0).to_pdf_array()));
+
+		let file = doc.object(descriptor.get("FontFile").unwrap().as_reference().unwrap()).unwrap().as_stream().unwrap();
+		let length = |key| file.dict.get(key).and_then(PdfObject::as_integer).unwrap() as usize;
+		assert_eq!((length("Length1"), length("Length2"), length("Length3")), (ascii.len(), binary.len(), trailer.len()));
+		assert_eq!(length("Length1") + length("Length2") + length("Length3"), file.data.len());
+
+		// Codes between the encoded glyphs have no width.
+		assert_eq!((font.get("FirstChar"), font.get("LastChar")), (Some(&PdfObject::Integer(39)), Some(&PdfObject::Integer(67))));
+		let widths = font.get("Widths").and_then(PdfObject::as_array).unwrap();
+		assert_eq!((widths.len(), &widths[0], &widths[65 - 39], &widths[66 - 39], &widths[67 - 39]), (29, &PdfObject::Integer(501), &PdfObject::Integer(600), &PdfObject::Integer(0), &PdfObject::Integer(720)));
+	}
+
+	#[test]
+	fn type1_rejects_missing_segments() {
+		let mut doc = Document::new();
+		assert!(doc.embed_type1_font(b"%!PS-AdobeFont-1.0").is_err());
+		assert!(doc.embed_type1_font(&pfb(&[(1, b"/FontName /X def")])).is_err());
+	}
+
+	/// Builds an OpenType font from the tables, which have to be sorted by tag.
+	fn otf(tables: &[(&[u8; 4], Vec<u8>)]) -> Vec<u8> {
+		let mut data = b"OTTO".to_vec();
+		data.extend_from_slice(&(tables.len() as u16).to_be_bytes());
+		data.extend_from_slice(&[0; 6]);
+
+		let mut offset = 12 + tables.len() * 16;
+		for (tag, table) in tables {
+			data.extend_from_slice(*tag);
+			data.extend_from_slice(&[0; 4]);
+			data.extend_from_slice(&(offset as u32).to_be_bytes());
+			data.extend_from_slice(&(table.len() as u32).to_be_bytes());
+			offset += table.len();
+		}
+		for (_, table) in tables {
+			data.extend_from_slice(table);
+		}
+		return data;
+	}
+
+	fn be(values: &[i32]) -> Vec<u8> {
+		return values.iter().flat_map(|&value| (value as u16).to_be_bytes()).collect();
+	}
+
+	#[test]
+	fn truetype_outlines() {
+		let mut head = std::vec![0; 54];
+		head[18..20].copy_from_slice(&1000u16.to_be_bytes());
+		let mut cmap = be(&[0, 1, 3, 1, 0, 12]);
+		cmap.extend(be(&[4, 32, 0, 4, 0, 0, 0, 0x43, -1, 0, 0x41, -1, -0x40, 1, 0, 0]));
+
+		// Glyph 1 is a square with a square hole, whose last corner is off
+		// the curve. Glyph 2 is the same, scaled by half and moved right.
+		let mut square = be(&[2, 0, 0, 100, 100, 3, 7, 0]);
+		square.extend_from_slice(&[ON_CURVE | REPEAT, 3, ON_CURVE, ON_CURVE, ON_CURVE, 0]);
+		square.extend(be(&[0, 100, 0, -100, 25, 0, 50, 0]));
+		square.extend(be(&[0, 0, 100, 0, -75, 50, 0, -50]));
+		let composite = be(&[-1, 0, 0, 0, 0, (ARGS_ARE_WORDS | ARGS_ARE_XY_VALUES | HAS_SCALE) as i32, 1, 200, 0, 0x2000]);
+		let loca = be(&[0, 0, square.len() as i32 / 2, (square.len() + composite.len()) as i32 / 2]);
+		let glyf = [square, composite].concat();
+		let data = otf(&[(b"cmap", cmap), (b"glyf", glyf), (b"head", head), (b"loca", loca)]);
+
+		let font = TrueTypeFont::parse(data.clone()).unwrap();
+		let outline = font.glyph_outline('A').unwrap();
+		let contours = outline.iter().filter(|segment| matches!(segment, PathSegment::MoveTo(_))).count();
+		let lines = outline.iter().filter(|segment| matches!(segment, PathSegment::LineTo(_))).count();
+		assert_eq!((contours, lines), (2, 6));
+		assert_eq!(outline[..2], [PathSegment::MoveTo(Point::new(0.0, 0.0)), PathSegment::LineTo(Point::new(100.0, 0.0))]);
+		assert!(outline.contains(&PathSegment::QuadTo(Point::new(75.0, 25.0), Point::new(25.0, 25.0))));
+
+		let scaled = font.glyph_outline('B').unwrap();
+		assert_eq!(scaled.len(), outline.len());
+		assert_eq!(scaled[1], PathSegment::LineTo(Point::new(250.0, 0.0)));
+		assert_eq!(font.glyph_outline('C'), None);
+		assert!(TrueTypeFont::parse(otf(&[(b"head", std::vec![0; 54])])).is_err());
+
+		let mut doc = Document::new();
+		let mut file = Stream::new(Dictionary::new(), filters::flate::encode(&data));
+		file.dict.set("Filter", PdfObject::name("FlateDecode"));
+		let mut descriptor = Dictionary::new();
+		descriptor.set("FontFile2", doc.add_object(file));
+		let mut dict = Dictionary::new();
+		dict.set("Type", PdfObject::name("Font"));
+		dict.set("Subtype", PdfObject::name("TrueType"));
+		dict.set("FontDescriptor", doc.add_object(descriptor));
+		let font = FontRef(doc.add_object(dict));
+		assert_eq!(doc.truetype_font(font).unwrap().glyph_outline('A'), Some(outline));
+	}
+
+	#[test]
+	fn opentype_cff_embedding() {
+		let cff = [0x01, 0x00, 0x04, 0x01, 0x00, 0x01, 0x01, 0x01, 0x09, b'T', b'e', b's', b't', b'S', b'a', b'n', b's'].to_vec();
+		let mut head = std::vec![0; 36];
+		head[18..20].copy_from_slice(&2048u16.to_be_bytes());
+		head.extend(be(&[-100, -400, 2000, 1600]));
+		let mut hhea = std::vec![0; 4];
+		hhea.extend(be(&[1500, -500]));
+		hhea.resize(34, 0);
+		hhea.extend(be(&[3]));
+		// Glyphs 1 to 3 are 'A' to 'C', the last one sharing the final metric.
+		let hmtx = be(&[0, 0, 1024, 0, 1229, 0]);
+		let mut cmap = be(&[0, 1, 3, 1, 0, 12]);
+		cmap.extend(be(&[4, 32, 0, 4, 0, 0, 0, 0x43, -1, 0, 0x41, -1, -0x40, 1, 0, 0]));
+		let mut name = be(&[0, 1, 18, 3, 1, 0x409, 6, 16, 0]);
+		name.extend(be(&[0x54, 0x65, 0x73, 0x74, 0x53, 0x61, 0x6E, 0x73]));
+
+		let data = otf(&[(b"CFF ", cff.clone()), (b"cmap", cmap), (b"head", head), (b"hhea", hhea), (b"hmtx", hmtx), (b"name", name)]);
+		let mut doc = Document::new();
+		let font = doc.embed_opentype_cff(&data).unwrap();
+
+		let font = doc.object(font.0).unwrap().as_dict().unwrap().clone();
+		assert_eq!(font.get_name("BaseFont"), Some(&b"TestSans"[..]));
+		let widths = font.get("Widths").unwrap().as_array().unwrap();
+		assert_eq!(widths.len(), 95);
+		assert_eq!((&widths[0x41 - 32], &widths[0x43 - 32], &widths[0x44 - 32]), (&PdfObject::Integer(500), &PdfObject::Integer(600), &PdfObject::Integer(0)));
+
+		let descriptor = doc.object(font.get("FontDescriptor").unwrap().as_reference().unwrap()).unwrap().as_dict().unwrap();
+		assert_eq!(descriptor.get("Ascent"), Some(&PdfObject::Real(732.421875)));
+		let file = doc.object(descriptor.get("FontFile3").unwrap().as_reference().unwrap()).unwrap().as_stream().unwrap();
+		assert_eq!(file.dict.get_name("Subtype"), Some(&b"Type1C"[..]));
+		let decoded = filters::flate::decode(&file.data).unwrap();
+		assert_eq!(decoded[..4], [1, 0, 4, 1]);
+		assert_eq!(decoded, cff);
+	}
+
+	#[test]
+	fn font_program_round_trip() {
+		let mut head = std::vec![0; 54];
+		head[18..20].copy_from_slice(&1000u16.to_be_bytes());
+		let mut cmap = be(&[0, 1, 3, 1, 0, 12]);
+		cmap.extend(be(&[4, 32, 0, 4, 0, 0, 0, 0x41, -1, 0, 0x41, -1, -0x40, 1, 0, 0]));
+		let mut square = be(&[1, 0, 0, 100, 100, 3, 0]);
+		square.extend_from_slice(&[ON_CURVE | REPEAT, 3]);
+		square.extend(be(&[0, 100, 0, -100, 0, 0, 100, 0]));
+		let loca = be(&[0, 0, square.len() as i32 / 2]);
+		let mut name = be(&[0, 1, 18, 3, 1, 0x409, 6, 16, 0]);
+		name.extend(be(&[0x54, 0x65, 0x73, 0x74, 0x53, 0x61, 0x6E, 0x73]));
+		let data = otf(&[(b"cmap", cmap), (b"glyf", square), (b"head", head), (b"loca", loca), (b"name", name)]);
+
+		let mut doc = Document::new();
+		let font = doc.embed_truetype_font(&data).unwrap();
+		let standard = doc.add_base_font("Helvetica");
+		assert!(doc.embed_truetype_font(&otf(&[(b"head", std::vec![0; 54])])).is_err());
+		let mut output = Vec::new();
+		doc.write(&mut Writer::new(&mut output)).unwrap();
+
+		let reader = Reader::new(&output).unwrap();
+		assert_eq!(reader.font_program(standard.0).unwrap(), None);
+		let (kind, program) = reader.font_program(font.0).unwrap().unwrap();
+		assert_eq!((kind, &program), (FontFileKind::TrueType, &data));
+		assert!(TrueTypeFont::parse(program.clone()).unwrap().glyph_outline('A').is_some());
+
+		let mut copy = Document::new();
+		let font = copy.embed_truetype_font(&program).unwrap();
+		let dict = copy.object(font.0).unwrap().as_dict().unwrap();
+		assert_eq!((dict.get_name("Subtype"), dict.get_name("BaseFont")), (Some(&b"TrueType"[..]), Some(&b"TestSans"[..])));
+		assert_eq!(copy.truetype_font(font).unwrap().glyph_outline('A'), TrueTypeFont::parse(data).unwrap().glyph_outline('A'));
+	}
+
+	#[test]
+	fn used_widths() {
+		let mut head = std::vec![0; 54];
+		head[18..20].copy_from_slice(&2048u16.to_be_bytes());
+		let mut cmap = be(&[0, 1, 3, 1, 0, 12]);
+		cmap.extend(be(&[4, 32, 0, 4, 0, 0, 0, 0x43, -1, 0, 0x41, -1, -0x40, 1, 0, 0]));
+		let mut hhea = be(&[0, 0, 1500, -500]);
+		hhea.resize(34, 0);
+		hhea.extend(be(&[4]));
+		let advances = [0, 1024, 1229, 700];
+		let hmtx = be(&advances.iter().flat_map(|&advance| [advance, 0]).collect::<Vec<_>>());
+		let mut name = be(&[0, 1, 18, 3, 1, 0x409, 6, 16, 0]);
+		name.extend(be(&[0x54, 0x65, 0x73, 0x74, 0x53, 0x61, 0x6E, 0x73]));
+		let data = otf(&[(b"cmap", cmap), (b"glyf", Vec::new()), (b"head", head), (b"hhea", hhea), (b"hmtx", hmtx), (b"loca", be(&[0; 5])), (b"name", name)]);
+
+		let mut doc = Document::new();
+		let font = doc.embed_truetype_font(&data).unwrap();
+		let program = TrueTypeFont::parse(data).unwrap();
+		doc.set_font_widths(font, &program, b"CAA").unwrap();
+		let dict = doc.object(font.0).unwrap().as_dict().unwrap();
+		assert_eq!((dict.get("FirstChar"), dict.get("LastChar")), (Some(&PdfObject::Integer(0x41)), Some(&PdfObject::Integer(0x43))));
+		let width = |advance: i32| PdfObject::Integer((advance as f64 * 1000.0 / 2048.0).round() as i64);
+		assert_eq!(dict.get("Widths").and_then(PdfObject::as_array), Some(&std::vec![width(advances[1]), PdfObject::Integer(0), width(advances[3])]));
+		assert_eq!(width(advances[1]), PdfObject::Integer(500));
+
+		let standard = doc.add_base_font("Helvetica");
+		assert!(doc.set_font_widths(standard, &program, b"A").is_ok());
+		assert!(doc.set_font_widths(FontRef(ObjectId::new(999, 0)), &program, b"A").is_err());
+	}
+
+	#[test]
+	fn text_through_cmap() {
+		let mut head = std::vec![0; 54];
+		head[18..20].copy_from_slice(&1000u16.to_be_bytes());
+		let mut cmap = be(&[0, 1, 3, 1, 0, 12]);
+		cmap.extend(be(&[4, 40, 0, 6, 0, 0, 0, 0x48, 0x69, -1, 0, 0x48, 0x69, -1, -0x47, -0x67, 1, 0, 0, 0]));
+		let mut name = be(&[0, 1, 18, 3, 1, 0x409, 6, 16, 0]);
+		name.extend(be(&[0x54, 0x65, 0x73, 0x74, 0x53, 0x61, 0x6E, 0x73]));
+		let data = otf(&[(b"cmap", cmap), (b"glyf", Vec::new()), (b"head", head), (b"loca", be(&[0; 4])), (b"name", name)]);
+
+		let mut doc = Document::new();
+		let simple = doc.embed_truetype_font(&data).unwrap();
+		let descriptor = doc.object(simple.0).and_then(PdfObject::as_dict).and_then(|dict| dict.get("FontDescriptor")).cloned().unwrap();
+		let mut cid_font = Dictionary::new();
+		cid_font.set("Type", PdfObject::name("Font"));
+		cid_font.set("Subtype", PdfObject::name("CIDFontType2"));
+		cid_font.set("BaseFont", PdfObject::name("TestSans"));
+		cid_font.set("FontDescriptor", descriptor);
+		let cid_font = doc.add_object(cid_font);
+		let mut composite = Dictionary::new();
+		composite.set("Type", PdfObject::name("Font"));
+		composite.set("Subtype", PdfObject::name("Type0"));
+		composite.set("BaseFont", PdfObject::name("TestSans"));
+		composite.set("Encoding", PdfObject::name("Identity-H"));
+		composite.set("DescendantFonts", std::vec![PdfObject::Reference(cid_font)]);
+		let composite = FontRef(doc.add_object(composite));
+
+		let mut page = doc.add_page(612.0, 792.0);
+		page.add_font_resource("F0", composite);
+		let mut content = ContentStream::new();
+		content.begin_text().set_font("F0", 12.0).move_text(72.0, 720.0);
+		content.raw(b"<00010002> Tj");
+		content.end_text();
+		page.set_contents(content);
+
+		let mut output = Vec::new();
+		doc.write(&mut Writer::new(&mut output)).unwrap();
+		let reader = Reader::new(&output).unwrap();
+		assert_eq!(reader.page(0).unwrap().extract_text().unwrap(), "Hi");
+	}
+
+	#[test]
+	fn vertical_writing() {
+		let mut head = std::vec![0; 54];
+		head[18..20].copy_from_slice(&1000u16.to_be_bytes());
+		let mut cmap = be(&[0, 1, 3, 1, 0, 12]);
+		cmap.extend(be(&[4, 32, 0, 4, 0, 0, 0, 0x43, -1, 0, 0x41, -1, -0x40, 1, 0, 0]));
+		let mut hhea = be(&[0, 0, 880, -120]);
+		hhea.resize(34, 0);
+		hhea.extend(be(&[4]));
+		let hmtx = be(&[0, 0, 1000, 0, 1000, 0, 600, 0]);
+		let mut vhea = std::vec![0; 34];
+		vhea.extend(be(&[4]));
+		let vmtx = be(&[0, 0, 1000, 0, 800, 0, 1200, 0]);
+		let mut name = be(&[0, 1, 18, 3, 1, 0x409, 6, 16, 0]);
+		name.extend(be(&[0x54, 0x65, 0x73, 0x74, 0x53, 0x61, 0x6E, 0x73]));
+		let data = otf(&[(b"cmap", cmap), (b"glyf", Vec::new()), (b"head", head), (b"hhea", hhea), (b"hmtx", hmtx), (b"loca", be(&[0; 5])), (b"name", name), (b"vhea", vhea), (b"vmtx", vmtx)]);
+
+		let mut doc = Document::new();
+		let font = doc.embed_vertical_font(&data).unwrap();
+		let program = TrueTypeFont::parse(data).unwrap();
+		let codes = program.identity_codes("ABC");
+		assert_eq!(codes, [0, 1, 0, 2, 0, 3]);
+		let mut page = doc.add_page(612.0, 792.0);
+		page.add_font_resource("F0", font);
+		let mut content = ContentStream::new();
+		content.begin_text().set_font("F0", 10.0).move_text(100.0, 700.0);
+		content.show_text_directed(&codes, WritingMode::TopToBottom, 2).end_text();
+		page.set_contents(content);
+
+		let mut output = Vec::new();
+		doc.write(&mut Writer::new(&mut output)).unwrap();
+		let reader = Reader::new(&output).unwrap();
+		let dict = reader.resolve_dict(font.0).unwrap();
+		assert_eq!(dict.get_name("Encoding"), Some(&b"Identity-V"[..]));
+		let descendant = reader.resolve_dict(dict.get("DescendantFonts").and_then(PdfObject::as_array).unwrap()[0].as_reference().unwrap()).unwrap();
+		assert_eq!(cid_metrics(descendant.get("W2").and_then(PdfObject::as_array).unwrap(), 3).get(&3), Some(&-1200.0));
+
+		// The glyphs go down by their displacements, of 10, 8, and 12 points.
+		let text = reader.page(0).unwrap().extract_text_positioned().unwrap();
+		assert_eq!(text.len(), 1);
+		assert_eq!(text[0].text, "ABC");
+		assert_eq!(text[0].origin, Point::new(100.0, 700.0));
+		assert_eq!((text[0].bbox.lly, text[0].bbox.ury), (670.0, 700.0));
+		assert!(text[0].bbox.llx < 100.0 && text[0].bbox.urx > 100.0);
+	}
+}
@@ -0,0 +1,110 @@
+//! Form XObjects, i.e. content streams which can be painted by other ones.
+
+use crate::{ContentStream, Dictionary, Document, ObjectId, PdfObject, Rectangle, Stream};
+
+/// Reference to a form XObject added to the document, which can be painted
+/// any number of times.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct XObjectRef(pub ObjectId);
+
+impl From<XObjectRef> for ObjectId {
+	#[inline]
+	fn from(xobject: XObjectRef) -> Self {
+		return xobject.0;
+	}
+}
+
+/// Creates new form XObject, with the resources used by the content.
+fn form_xobject(bbox: Rectangle, mut content: ContentStream) -> Stream {
+	let mut dict = Dictionary::new();
+	dict.set("Type", PdfObject::name("XObject"));
+	dict.set("Subtype", PdfObject::name("Form"));
+	dict.set("BBox", bbox.to_pdf_array());
+	dict.set("Resources", std::mem::take(&mut content.resources).to_dict());
+	return Stream::new(dict, content.finish());
+}
+
+impl Document {
+	/// Adds the content as a form XObject, which is stored only once however
+	/// many times it's painted, e.g. with [`ContentStream::draw_form`]. The
+	/// content is clipped to the bounding box, given in the form space.
+	pub fn add_form_xobject(&mut self, bbox: Rectangle, content: ContentStream) -> XObjectRef {
+		return XObjectRef(self.add_object(form_xobject(bbox, content)));
+	}
+
+	/// Adds the content as a transparency group, i.e. a form XObject whose
+	/// content is composited together before being blended with the
+	/// backdrop, and which is clipped to the bounding box. An isolated group
+	/// is composited onto a fully transparent backdrop instead of the page,
+	/// and in a knockout group, every object is composited onto the initial
+	/// backdrop of the group, rather than onto the earlier objects.
+	pub fn add_transparency_group(&mut self, bbox: Rectangle, content: ContentStream, isolated: bool, knockout: bool) -> XObjectRef {
+		let mut group = Dictionary::new();
+		group.set("S", PdfObject::name("Transparency"));
+		group.set("I", isolated);
+		group.set("K", knockout);
+
+		let mut form = form_xobject(bbox, content);
+		form.dict.set("Group", group);
+		return XObjectRef(self.add_object(form));
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::*;
+
+	#[test]
+	fn repeated_forms() {
+		let mut doc = Document::new();
+		let font = doc.add_base_font("Helvetica");
+		let mut logo = ContentStream::new();
+		let name = logo.use_font(font);
+		logo.set_fill_color(Color::Rgb(0.8, 0.1, 0.1)).rect(Rectangle::new(0.0, 0.0, 20.0, 20.0)).fill();
+		logo.begin_text().set_font(&name, 10.0).move_text(24.0, 6.0).show_text(b"ACME").end_text();
+		let logo = doc.add_form_xobject(Rectangle::new(0.0, 0.0, 60.0, 20.0), logo);
+		for _ in 0..10 {
+			let mut content = ContentStream::new();
+			content.draw_form(logo, Matrix::translate(36.0, 756.0));
+			doc.add_page(612.0, 792.0).set_contents(content);
+		}
+
+		let mut output = Vec::new();
+		doc.write(&mut Writer::new(&mut output)).unwrap();
+		let text = String::from_utf8_lossy(&output);
+		assert_eq!(text.matches("/Subtype /Form").count(), 1);
+		assert_eq!(text.matches("q\n1 0 0 1 36 756 cm\n/Fm0 Do\nQ\n").count(), 10);
+		assert_eq!(text.matches(&std::format!("/XObject << /Fm0 {} {} R >>", logo.0.number, logo.0.generation)).count(), 10);
+
+		let reader = Reader::new(&output).unwrap();
+		let form = reader.resolve(logo.0).unwrap();
+		let resources = form.as_stream().unwrap().dict.get("Resources").and_then(PdfObject::as_dict).unwrap();
+		assert_eq!(resources.get("Font").and_then(PdfObject::as_dict).and_then(|fonts| fonts.get("F0")), Some(&PdfObject::Reference(font.0)));
+	}
+
+	#[test]
+	fn transparency_groups() {
+		let mut doc = Document::new();
+		let state = doc.add_ext_gstate(&ExtGState {
+			fill_alpha: Some(0.5),
+			..ExtGState::new()
+		});
+		let mut content = ContentStream::new();
+		let name = content.use_graphics_state(state);
+		content.set_graphics_state(&name).rect(Rectangle::new(0.0, 0.0, 60.0, 60.0)).fill().rect(Rectangle::new(40.0, 40.0, 60.0, 60.0)).fill();
+		let group = doc.add_transparency_group(Rectangle::new(0.0, 0.0, 100.0, 100.0), content, true, true);
+
+		let mut content = ContentStream::new();
+		let name = content.use_xobject(group);
+		content.draw_xobject(&name);
+		doc.add_page(612.0, 792.0).set_contents(content);
+
+		let mut output = Vec::new();
+		doc.write(&mut Writer::new(&mut output)).unwrap();
+		let output = String::from_utf8_lossy(&output);
+		assert!(output.contains("/Group << /S /Transparency /I true /K true >>\n"));
+		assert!(output.contains(&std::format!("/Resources << /ExtGState << /GS0 {} {} R >> >>\n", state.number, state.generation)));
+		assert!(output.contains(&std::format!("/XObject << /Fm0 {} {} R >>", group.0.number, group.0.generation)));
+		assert!(output.contains("/Fm0 Do\n"));
+	}
+}
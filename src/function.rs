@@ -0,0 +1,214 @@
+//! Functions mapping input values to output values, used e.g. by shadings,
+//! tint transformations of separation colour spaces, and transfer functions.
+
+use crate::{filters, Dictionary, Document, PdfObject, Stream};
+
+/// Kind of the function, with its type-specific parameters.
+#[derive(Clone, Debug, PartialEq)]
+pub enum FunctionKind {
+	/// Table of sample values (`/FunctionType 0`), interpolated linearly.
+	Sampled {
+		/// Number of samples in each input dimension.
+		size: Vec<u32>,
+		bits_per_sample: u8,
+		/// Samples packed in big-endian order, with the first dimension
+		/// varying fastest.
+		samples: Vec<u8>,
+	},
+	/// Exponential interpolation between two values (`/FunctionType 2`).
+	Exponential {
+		/// Output at the input 0.
+		c0: Vec<f64>,
+		/// Output at the input 1.
+		c1: Vec<f64>,
+		/// Interpolation exponent.
+		n: f64,
+	},
+	/// Combination of one-input functions over subdomains (`/FunctionType 3`).
+	Stitching {
+		functions: Vec<Function>,
+		/// Boundaries between the subdomains, in increasing order.
+		bounds: Vec<f64>,
+		/// Interval each subdomain is mapped onto for its function.
+		encode: Vec<[f64; 2]>,
+	},
+	/// Program in the subset of the PostScript language (`/FunctionType 4`).
+	PostScript {
+		code: String,
+	},
+}
+
+/// Function with its domain, and range of the outputs.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Function {
+	/// Interval of each input, clipping the input values.
+	pub domain: Vec<[f64; 2]>,
+	/// Interval of each output, clipping the output values. It's required by
+	/// sampled and PostScript functions.
+	pub range: Option<Vec<[f64; 2]>>,
+	pub kind: FunctionKind,
+}
+
+fn intervals(intervals: &[[f64; 2]]) -> PdfObject {
+	return PdfObject::Array(intervals.iter().flatten().map(|&value| PdfObject::Real(value)).collect());
+}
+
+fn reals(values: &[f64]) -> PdfObject {
+	return PdfObject::Array(values.iter().map(|&value| PdfObject::Real(value)).collect());
+}
+
+impl Function {
+	/// Creates new sampled function with the given range, for which the
+	/// domain of every input is `[0 1]`.
+	pub fn sampled(size: Vec<u32>, bits_per_sample: u8, range: Vec<[f64; 2]>, samples: Vec<u8>) -> Self {
+		return Self {
+			domain: std::vec![[0.0, 1.0]; size.len()],
+			range: Some(range),
+			kind: FunctionKind::Sampled {
+				size,
+				bits_per_sample,
+				samples,
+			},
+		};
+	}
+
+	/// Creates new exponential function over the domain `[0 1]`.
+	pub fn exponential(c0: Vec<f64>, c1: Vec<f64>, n: f64) -> Self {
+		return Self {
+			domain: std::vec![[0.0, 1.0]],
+			range: None,
+			kind: FunctionKind::Exponential {
+				c0,
+				c1,
+				n,
+			},
+		};
+	}
+
+	/// Creates new stitching function over the domain `[0 1]`. There has to be
+	/// one bound less than there are functions.
+	pub fn stitching(functions: Vec<Function>, bounds: Vec<f64>, encode: Vec<[f64; 2]>) -> Self {
+		return Self {
+			domain: std::vec![[0.0, 1.0]],
+			range: None,
+			kind: FunctionKind::Stitching {
+				functions,
+				bounds,
+				encode,
+			},
+		};
+	}
+
+	/// Creates new PostScript calculator function. The braces enclosing the
+	/// program are added if they're missing.
+	pub fn postscript(domain: Vec<[f64; 2]>, range: Vec<[f64; 2]>, code: &str) -> Self {
+		let code = code.trim();
+		let code = match code.starts_with('{') && code.ends_with('}') {
+			true => code.to_string(),
+			false => std::format!("{{ {} }}", code),
+		};
+		return Self {
+			domain,
+			range: Some(range),
+			kind: FunctionKind::PostScript {
+				code,
+			},
+		};
+	}
+
+	/// Sets the domain of the inputs.
+	pub fn with_domain(mut self, domain: Vec<[f64; 2]>) -> Self {
+		self.domain = domain;
+		return self;
+	}
+
+	/// Sets the range of the outputs.
+	pub fn with_range(mut self, range: Vec<[f64; 2]>) -> Self {
+		self.range = Some(range);
+		return self;
+	}
+
+	/// Writes the function into the document, returning the object to be
+	/// used e.g. in a shading dictionary: the dictionary of exponential and
+	/// stitching functions, or a reference to the stream of the other ones.
+	pub fn write_to(&self, doc: &mut Document) -> PdfObject {
+		let function_type = match self.kind {
+			FunctionKind::Sampled { .. } => 0,
+			FunctionKind::Exponential { .. } => 2,
+			FunctionKind::Stitching { .. } => 3,
+			FunctionKind::PostScript { .. } => 4,
+		};
+		let mut dict = Dictionary::new();
+		dict.set("FunctionType", function_type);
+		dict.set("Domain", intervals(&self.domain));
+		if let Some(range) = &self.range {
+			dict.set("Range", intervals(range));
+		}
+
+		match &self.kind {
+			FunctionKind::Sampled { size, bits_per_sample, samples } => {
+				dict.set("Size", size.iter().map(|&size| PdfObject::Integer(size as i64)).collect::<Vec<_>>());
+				dict.set("BitsPerSample", *bits_per_sample as i64);
+				dict.set("Filter", PdfObject::name("FlateDecode"));
+				let stream = Stream::new(dict, filters::flate::encode(samples));
+				return doc.add_object(stream).into();
+			},
+			FunctionKind::Exponential { c0, c1, n } => {
+				dict.set("C0", reals(c0));
+				dict.set("C1", reals(c1));
+				dict.set("N", *n);
+			},
+			FunctionKind::Stitching { functions, bounds, encode } => {
+				dict.set("Functions", functions.iter().map(|function| function.write_to(doc)).collect::<Vec<_>>());
+				dict.set("Bounds", reals(bounds));
+				dict.set("Encode", intervals(encode));
+			},
+			FunctionKind::PostScript { code } => {
+				let stream = Stream::new(dict, code.clone().into_bytes());
+				return doc.add_object(stream).into();
+			},
+		}
+		return PdfObject::Dictionary(dict);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::*;
+
+	#[test]
+	fn stitching() {
+		let red = Function::exponential(std::vec![0.0, 0.0, 0.0], std::vec![1.0, 0.0, 0.0], 1.0);
+		let blue = Function::exponential(std::vec![1.0, 0.0, 0.0], std::vec![0.0, 0.0, 1.0], 2.0);
+		let function = Function::stitching(std::vec![red, blue], std::vec![0.25], std::vec![[0.0, 1.0], [1.0, 0.0]]);
+
+		let mut doc = Document::new();
+		let object = function.write_to(&mut doc);
+		let id = doc.add_object(object);
+		let mut output = Vec::new();
+		doc.object(id).unwrap().write_to(&mut Writer::new(&mut output)).unwrap();
+		assert_eq!(
+			String::from_utf8(output).unwrap(),
+			concat!(
+				"<<\n/FunctionType 3\n/Domain [0 1]\n",
+				"/Functions [<< /FunctionType 2 /Domain [0 1] /C0 [0 0 0] /C1 [1 0 0] /N 1 >> << /FunctionType 2 /Domain [0 1] /C0 [1 0 0] /C1 [0 0 1] /N 2 >>]\n",
+				"/Bounds [0.25]\n/Encode [0 1 1 0]\n>>",
+			),
+		);
+
+		let function = Function::postscript(std::vec![[0.0, 1.0]], std::vec![[0.0, 1.0]; 2], "dup 0.5 mul");
+		let PdfObject::Reference(id) = function.write_to(&mut doc) else {
+			panic!("expected a reference to the stream");
+		};
+		let stream = doc.object(id).and_then(PdfObject::as_stream).unwrap();
+		assert_eq!(stream.data, b"{ dup 0.5 mul }");
+		assert_eq!(stream.dict.get("FunctionType"), Some(&PdfObject::Integer(4)));
+		assert_eq!(stream.dict.get("Range").and_then(PdfObject::as_array).map(Vec::len), Some(4));
+
+		let function = Function::sampled(std::vec![2], 8, std::vec![[0.0, 1.0]], std::vec![0, 255]);
+		let PdfObject::Reference(id) = function.write_to(&mut doc) else {
+			panic!("expected a reference to the stream");
+		};
+		assert_eq!(filters::decode(doc.object(id).and_then(PdfObject::as_stream).unwrap()).unwrap(), [0, 255]);
+	}
+}
@@ -0,0 +1,283 @@
+//! Graphics state parameter dictionaries, set with the `gs` operator.
+
+use crate::{Dictionary, Document, ObjectId, PdfError, PdfObject};
+
+/// Blend mode used for compositing painted objects with the backdrop.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BlendMode {
+	Normal,
+	Multiply,
+	Screen,
+	Overlay,
+	Darken,
+	Lighten,
+	ColorDodge,
+	ColorBurn,
+	HardLight,
+	SoftLight,
+	Difference,
+	Exclusion,
+	Hue,
+	Saturation,
+	Color,
+	Luminosity,
+}
+
+impl BlendMode {
+	fn name(self) -> &'static str {
+		return match self {
+			Self::Normal => "Normal",
+			Self::Multiply => "Multiply",
+			Self::Screen => "Screen",
+			Self::Overlay => "Overlay",
+			Self::Darken => "Darken",
+			Self::Lighten => "Lighten",
+			Self::ColorDodge => "ColorDodge",
+			Self::ColorBurn => "ColorBurn",
+			Self::HardLight => "HardLight",
+			Self::SoftLight => "SoftLight",
+			Self::Difference => "Difference",
+			Self::Exclusion => "Exclusion",
+			Self::Hue => "Hue",
+			Self::Saturation => "Saturation",
+			Self::Color => "Color",
+			Self::Luminosity => "Luminosity",
+		};
+	}
+}
+
+/// Values of the soft mask group used as the mask (`/S`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SoftMaskSubtype {
+	/// Luminosity of the colours painted by the group, so masking is done
+	/// e.g. with a gradient from black to white.
+	Luminosity,
+	/// Opacity of the colours painted by the group.
+	Alpha,
+}
+
+impl SoftMaskSubtype {
+	fn name(self) -> &'static str {
+		return match self {
+			Self::Luminosity => "Luminosity",
+			Self::Alpha => "Alpha",
+		};
+	}
+}
+
+/// Parameters of a graphics state dictionary. Parameters which are not set
+/// are left out, so they keep their current values when the state is used.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ExtGState {
+	/// Constant opacity used for stroking (`/CA`).
+	pub stroke_alpha: Option<f64>,
+	/// Constant opacity used for everything else (`/ca`).
+	pub fill_alpha: Option<f64>,
+	pub blend_mode: Option<BlendMode>,
+	/// Whether stroking in one colourant of a separation leaves the others
+	/// unchanged (`/OP`). It also applies to filling, unless set separately.
+	pub stroke_overprint: Option<bool>,
+	/// Whether filling overprints (`/op`).
+	pub fill_overprint: Option<bool>,
+	/// Overprint mode (`/OPM`), where 1 makes zero CMYK components leave the
+	/// previous colour unchanged, and 0 makes them erase it.
+	pub overprint_mode: Option<u8>,
+	/// Transfer function adjusting the colour components for the output
+	/// device (`/TR2`), i.e. the object returned by
+	/// [`Function::write_to`](crate::Function::write_to), an array of four
+	/// of them, one per colourant, or the name `/Identity` or `/Default`.
+	pub transfer: Option<PdfObject>,
+	/// Halftone screen (`/HT`), i.e. a reference to the halftone dictionary
+	/// or stream, or the name `/Default`.
+	pub halftone: Option<PdfObject>,
+}
+
+impl ExtGState {
+	/// Creates new graphics state without any parameters.
+	#[inline]
+	pub fn new() -> Self {
+		return Self::default();
+	}
+
+	/// Creates the graphics state parameter dictionary.
+	pub fn to_dict(&self) -> Dictionary {
+		let mut dict = Dictionary::new();
+		dict.set("Type", PdfObject::name("ExtGState"));
+		if let Some(alpha) = self.stroke_alpha {
+			dict.set("CA", alpha);
+		}
+		if let Some(alpha) = self.fill_alpha {
+			dict.set("ca", alpha);
+		}
+		if let Some(mode) = self.blend_mode {
+			dict.set("BM", PdfObject::name(mode.name()));
+		}
+		if let Some(overprint) = self.stroke_overprint {
+			dict.set("OP", overprint);
+		}
+		if let Some(overprint) = self.fill_overprint {
+			dict.set("op", overprint);
+		}
+		if let Some(mode) = self.overprint_mode {
+			dict.set("OPM", mode as i64);
+		}
+		if let Some(transfer) = &self.transfer {
+			// Readers older than PDF 1.3 only know `/TR`, which can't be
+			// `/Default`.
+			if transfer.as_name() != Some(b"Default") {
+				dict.set("TR", transfer.clone());
+			}
+			dict.set("TR2", transfer.clone());
+		}
+		if let Some(halftone) = &self.halftone {
+			dict.set("HT", halftone.clone());
+		}
+		return dict;
+	}
+}
+
+impl Document {
+	/// Adds the graphics state parameter dictionary, returning its identifier,
+	/// which can be used with e.g.
+	/// [`ContentStream::use_graphics_state`](crate::ContentStream::use_graphics_state).
+	pub fn add_ext_gstate(&mut self, state: &ExtGState) -> ObjectId {
+		// Transparency needs PDF 1.4.
+		if state.stroke_alpha.is_some() || state.fill_alpha.is_some() || state.blend_mode.is_some() {
+			self.require_version((1, 4));
+		}
+		return self.add_object(state.to_dict());
+	}
+
+	/// Attempts to add the graphics state parameter dictionary which masks
+	/// everything painted after it's set with the given form XObject. The
+	/// form becomes a transparency group, if it isn't one already.
+	pub fn add_soft_mask_group(&mut self, group: impl Into<ObjectId>, subtype: SoftMaskSubtype) -> Result<ObjectId, PdfError> {
+		let group = group.into();
+		let Some(PdfObject::Stream(form)) = self.object_mut(group) else {
+			return Err(PdfError::MissingObject(group));
+		};
+		if form.dict.get_name("Subtype") != Some(b"Form") {
+			return Err(PdfError::InvalidArgument("soft mask group is not a form XObject".to_string()));
+		}
+		if !form.dict.contains_key("Group") {
+			let mut attributes = Dictionary::new();
+			attributes.set("S", PdfObject::name("Transparency"));
+			// Luminosity is computed in the colour space of the group, which
+			// is required for the mask to not depend on the page.
+			if subtype == SoftMaskSubtype::Luminosity {
+				attributes.set("CS", PdfObject::name("DeviceRGB"));
+			}
+			form.dict.set("Group", attributes);
+		}
+
+		self.require_version((1, 4));
+		let mut mask = Dictionary::new();
+		mask.set("S", PdfObject::name(subtype.name()));
+		mask.set("G", group);
+		let mut dict = ExtGState::new().to_dict();
+		dict.set("SMask", mask);
+		return Ok(self.add_object(dict));
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::*;
+
+	#[test]
+	fn blend_modes() {
+		let mut doc = Document::new();
+		let multiply = doc.add_ext_gstate(&ExtGState {
+			fill_alpha: Some(0.5),
+			blend_mode: Some(BlendMode::Multiply),
+			..ExtGState::new()
+		});
+		let normal = doc.add_ext_gstate(&ExtGState {
+			blend_mode: Some(BlendMode::Normal),
+			..ExtGState::new()
+		});
+		let default = doc.add_ext_gstate(&ExtGState::new());
+
+		let dict = |id| doc.object(id).and_then(PdfObject::as_dict).unwrap();
+		assert_eq!(dict(multiply).get_name("BM"), Some(&b"Multiply"[..]));
+		assert_eq!(dict(multiply).get("ca"), Some(&PdfObject::Real(0.5)));
+		assert_eq!(dict(normal).get_name("BM"), Some(&b"Normal"[..]));
+		assert_eq!(dict(default).get("BM"), None);
+
+		let mut output = Vec::new();
+		doc.write(&mut Writer::new(&mut output)).unwrap();
+		assert!(String::from_utf8_lossy(&output).contains("<<\n/Type /ExtGState\n/ca 0.5\n/BM /Multiply\n>>"));
+	}
+
+	#[test]
+	fn soft_masks() {
+		let mut doc = Document::new();
+		let mut dict = Dictionary::new();
+		dict.set("Type", PdfObject::name("XObject"));
+		dict.set("Subtype", PdfObject::name("Form"));
+		dict.set("BBox", Rectangle::new(0.0, 0.0, 100.0, 100.0).to_pdf_array());
+		let group = doc.add_object(Stream::new(dict, b"0.5 g\n0 0 100 100 re\nf\n".to_vec()));
+		let image = doc.add_image(&Image::new(1, 1, 1, std::vec![0]));
+		assert!(doc.add_soft_mask_group(image, SoftMaskSubtype::Alpha).is_err());
+		let state = doc.add_soft_mask_group(group, SoftMaskSubtype::Luminosity).unwrap();
+
+		let mut content = ContentStream::new();
+		let name = content.use_graphics_state(state);
+		content.set_graphics_state(&name).rect(Rectangle::new(0.0, 0.0, 100.0, 100.0)).fill();
+		doc.add_page(612.0, 792.0).set_contents(content);
+
+		let mut output = Vec::new();
+		doc.write(&mut Writer::new(&mut output)).unwrap();
+		let output = String::from_utf8_lossy(&output);
+		assert!(output.contains(&std::format!("<<\n/Type /ExtGState\n/SMask << /S /Luminosity /G {} {} R >>\n>>", group.number, group.generation)));
+		assert!(output.contains("/Group << /S /Transparency /CS /DeviceRGB >>\n"));
+	}
+
+	#[test]
+	fn overprint() {
+		let mut doc = Document::new();
+		let state = doc.add_ext_gstate(&ExtGState {
+			stroke_overprint: Some(true),
+			fill_overprint: Some(true),
+			overprint_mode: Some(1),
+			..ExtGState::new()
+		});
+		let mut content = ContentStream::new();
+		let name = content.use_graphics_state(state);
+		content.set_graphics_state(&name).set_fill_color(Color::Cmyk(0.0, 1.0, 0.0, 0.0)).rect(Rectangle::new(0.0, 0.0, 10.0, 10.0)).fill();
+		doc.add_page(612.0, 792.0).set_contents(content);
+
+		let mut output = Vec::new();
+		doc.write(&mut Writer::new(&mut output)).unwrap();
+		let output = String::from_utf8_lossy(&output);
+		assert!(output.contains("<<\n/Type /ExtGState\n/OP true\n/op true\n/OPM 1\n>>"));
+		assert!(output.contains(&std::format!("/ExtGState << /GS0 {} {} R >>", state.number, state.generation)));
+		assert!(output.contains("/GS0 gs\n"));
+	}
+
+	#[test]
+	fn transfer_functions() {
+		let mut doc = Document::new();
+		let function = Function::sampled(std::vec![2], 8, std::vec![[0.0, 1.0]], std::vec![0, 255]).write_to(&mut doc);
+		let function_id = function.as_reference().unwrap();
+		let state = doc.add_ext_gstate(&ExtGState {
+			transfer: Some(function.clone()),
+			halftone: Some(PdfObject::name("Default")),
+			..ExtGState::new()
+		});
+		let default = doc.add_ext_gstate(&ExtGState {
+			transfer: Some(PdfObject::name("Default")),
+			..ExtGState::new()
+		});
+
+		let dict = |id| doc.object(id).and_then(PdfObject::as_dict).unwrap();
+		assert_eq!(dict(state).get("TR2"), Some(&function));
+		assert_eq!(dict(state).get("TR"), Some(&function));
+		assert_eq!(dict(state).get_name("HT"), Some(&b"Default"[..]));
+		assert_eq!(dict(default).get("TR"), None);
+
+		let mut output = Vec::new();
+		doc.write(&mut Writer::new(&mut output)).unwrap();
+		assert!(String::from_utf8_lossy(&output).contains(&std::format!("/TR2 {} {} R\n", function_id.number, function_id.generation)));
+	}
+}
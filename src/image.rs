@@ -0,0 +1,557 @@
+//! Raster images, embedded as image XObjects, and their extraction.
+
+use std::collections::BTreeSet;
+
+use crate::{filters, Dictionary, Document, ObjectId, Page, PdfError, PdfObject, Reader, Stream};
+
+/// Finds the dimensions and the number of colour components of a JPEG image,
+/// in its start of frame segment.
+fn jpeg_info(data: &[u8]) -> Option<(u32, u32, u8)> {
+	if !data.starts_with(&[0xFF, 0xD8]) {
+		return None;
+	}
+
+	let mut pos = 2;
+	loop {
+		// Markers may be preceded by any number of fill bytes.
+		while data.get(pos) == Some(&0xFF) && data.get(pos + 1) == Some(&0xFF) {
+			pos += 1;
+		}
+		let [0xFF, marker, high, low, ..] = *data.get(pos..)? else {
+			return None;
+		};
+		let length = u16::from_be_bytes([high, low]) as usize;
+		match marker {
+			// Start of frame markers, apart from DHT, JPG, and DAC.
+			0xC0..=0xCF if !matches!(marker, 0xC4 | 0xC8 | 0xCC) => {
+				let segment = data.get(pos + 4..pos + 2 + length)?;
+				let [_, h0, h1, w0, w1, components, ..] = *segment else {
+					return None;
+				};
+				return Some((u16::from_be_bytes([w0, w1]) as u32, u16::from_be_bytes([h0, h1]) as u32, components));
+			},
+			// Start of scan, or end of image before any frame.
+			0xDA | 0xD9 => return None,
+			_ => pos += 2 + length,
+		}
+	}
+}
+
+/// Uncompressed image with 8 bits per colour component, stored row by row
+/// starting with the top one.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Image {
+	pub width: u32,
+	pub height: u32,
+	/// Number of colour components, i.e. 1 for gray, 3 for RGB, and 4 for
+	/// CMYK images.
+	pub components: u8,
+	pub data: Vec<u8>,
+}
+
+impl Image {
+	/// Creates new image from the pixel data.
+	pub fn new(width: u32, height: u32, components: u8, data: Vec<u8>) -> Self {
+		assert!(matches!(components, 1 | 3 | 4), "unsupported number of colour components");
+		assert_eq!(data.len(), width as usize * height as usize * components as usize, "image data does not match its dimensions");
+		return Self {
+			width,
+			height,
+			components,
+			data,
+		};
+	}
+
+	fn color_space(&self) -> &'static str {
+		return match self.components {
+			1 => "DeviceGray",
+			3 => "DeviceRGB",
+			_ => "DeviceCMYK",
+		};
+	}
+
+	/// Creates the image XObject, with the pixel data compressed.
+	pub fn to_stream(&self) -> Stream {
+		let mut dict = Dictionary::new();
+		dict.set("Type", PdfObject::name("XObject"));
+		dict.set("Subtype", PdfObject::name("Image"));
+		dict.set("Width", self.width as i64);
+		dict.set("Height", self.height as i64);
+		dict.set("ColorSpace", PdfObject::name(self.color_space()));
+		dict.set("BitsPerComponent", 8);
+		dict.set("Filter", PdfObject::name("FlateDecode"));
+		return Stream::new(dict, filters::flate::encode(&self.data));
+	}
+}
+
+/// Finds the JPEG 2000 codestream, either in a JP2 file or on its own.
+fn jpx_codestream(data: &[u8]) -> Option<&[u8]> {
+	if data.starts_with(&[0xFF, 0x4F]) {
+		return Some(data);
+	}
+
+	let mut pos = 0;
+	while pos + 8 <= data.len() {
+		let (length, kind) = (u32::from_be_bytes(data[pos..pos + 4].try_into().unwrap()) as usize, &data[pos + 4..pos + 8]);
+		let (header, length) = match length {
+			// Length of the box follows its type as a 64-bit number.
+			1 => (16, u64::from_be_bytes(data.get(pos + 8..pos + 16)?.try_into().unwrap()) as usize),
+			// Box which extends to the end of the file.
+			0 => (8, data.len() - pos),
+			length => (8, length),
+		};
+		if length < header {
+			return None;
+		}
+		let end = pos.checked_add(length)?;
+		if kind == b"jp2c" {
+			return data.get(pos + header..end);
+		}
+		pos = end;
+	}
+	return None;
+}
+
+/// Finds the dimensions and the number of components of a JPEG 2000 image,
+/// in the SIZ marker segment following the start of the codestream.
+fn jpx_info(data: &[u8]) -> Option<(u32, u32, u16)> {
+	let codestream = jpx_codestream(data)?;
+	let siz = codestream.strip_prefix(&[0xFF, 0x4F, 0xFF, 0x51])?;
+	let u32_at = |pos: usize| siz.get(pos..pos + 4).map(|bytes| u32::from_be_bytes(bytes.try_into().unwrap()));
+	// Fields after the segment length and capabilities.
+	let (width, height, x_offset, y_offset) = (u32_at(4)?, u32_at(8)?, u32_at(12)?, u32_at(16)?);
+	let components = u16::from_be_bytes(siz.get(36..38)?.try_into().unwrap());
+	return Some((width.checked_sub(x_offset)?, height.checked_sub(y_offset)?, components));
+}
+
+impl Document {
+	/// Adds the image XObject, returning its identifier, which can be used
+	/// with e.g. [`ContentStream::use_image`](crate::ContentStream::use_image).
+	pub fn add_image(&mut self, image: &Image) -> ObjectId {
+		return self.add_object(image.to_stream());
+	}
+
+	/// Attempts to add the image XObject, like [`Document::add_image`], with
+	/// the optional `/Decode` array, which maps the samples of every component
+	/// onto the given interval, e.g. `[1 0]` inverts a grayscale image, and
+	/// the optional colour key `/Mask`, which has an interval of samples for
+	/// every component. Pixels with all the samples in the intervals are left
+	/// unpainted.
+	pub fn embed_image(&mut self, image: &Image, decode: Option<&[f64]>, color_key_mask: Option<&[u8]>) -> Result<ObjectId, PdfError> {
+		let components = image.components as usize * 2;
+		let mut stream = image.to_stream();
+		if let Some(decode) = decode {
+			if decode.len() != components {
+				return Err(PdfError::InvalidArgument(std::format!("decode array has {} values instead of {}", decode.len(), components)));
+			}
+			stream.dict.set("Decode", decode.iter().map(|&value| PdfObject::Real(value)).collect::<Vec<_>>());
+		}
+		if let Some(mask) = color_key_mask {
+			if mask.len() != components {
+				return Err(PdfError::InvalidArgument(std::format!("colour key mask has {} values instead of {}", mask.len(), components)));
+			}
+			if mask.chunks(2).any(|range| range[0] > range[1]) {
+				return Err(PdfError::InvalidArgument("colour key mask has an empty interval".to_string()));
+			}
+			stream.dict.set("Mask", mask.iter().map(|&value| PdfObject::Integer(value as i64)).collect::<Vec<_>>());
+		}
+		return Ok(self.add_object(stream));
+	}
+
+	/// Attempts to add the JPEG image as an image XObject. The image data is
+	/// embedded as it is, using the `DCTDecode` filter.
+	pub fn embed_jpeg(&mut self, jpeg: &[u8]) -> Result<ObjectId, PdfError> {
+		let Some((width, height, components)) = jpeg_info(jpeg) else {
+			return Err(PdfError::filter("DCTDecode", "missing JPEG start of frame"));
+		};
+		let color_space = match components {
+			1 => "DeviceGray",
+			3 => "DeviceRGB",
+			4 => "DeviceCMYK",
+			_ => return Err(PdfError::filter("DCTDecode", std::format!("unsupported number of colour components {}", components))),
+		};
+
+		let mut dict = Dictionary::new();
+		dict.set("Type", PdfObject::name("XObject"));
+		dict.set("Subtype", PdfObject::name("Image"));
+		dict.set("Width", width as i64);
+		dict.set("Height", height as i64);
+		dict.set("ColorSpace", PdfObject::name(color_space));
+		dict.set("BitsPerComponent", 8);
+		dict.set("Filter", PdfObject::name("DCTDecode"));
+		return Ok(self.add_object(Stream::new(dict, jpeg.to_vec())));
+	}
+
+	/// Attempts to add the JPEG 2000 image, either a JP2 file or a bare
+	/// codestream, as an image XObject. The data is embedded as it is, using
+	/// the `JPXDecode` filter, and the colour space is taken from the image.
+	pub fn embed_jp2(&mut self, jp2: &[u8]) -> Result<ObjectId, PdfError> {
+		let Some((width, height, components)) = jpx_info(jp2) else {
+			return Err(PdfError::filter("JPXDecode", "missing JPEG 2000 image size"));
+		};
+		if width == 0 || height == 0 || components == 0 {
+			return Err(PdfError::filter("JPXDecode", "empty JPEG 2000 image"));
+		}
+
+		let mut dict = Dictionary::new();
+		dict.set("Type", PdfObject::name("XObject"));
+		dict.set("Subtype", PdfObject::name("Image"));
+		dict.set("Width", width as i64);
+		dict.set("Height", height as i64);
+		dict.set("Filter", PdfObject::name("JPXDecode"));
+		return Ok(self.add_object(Stream::new(dict, jp2.to_vec())));
+	}
+
+	/// Attempts to render every page at the given scale, and attach the
+	/// rendering to the page as its thumbnail.
+	pub fn generate_thumbnails(&mut self, scale: f64) -> Result<(), PdfError> {
+		for index in 0..self.page_count() {
+			let mut page = self.page_mut(index).unwrap();
+			let thumbnail = page.render(scale)?.to_image();
+			page.set_thumbnail(&thumbnail);
+		}
+		return Ok(());
+	}
+}
+
+impl Page<'_> {
+	/// Sets the thumbnail image of the page, shown by some viewers, returning
+	/// the identifier of the image XObject.
+	pub fn set_thumbnail(&mut self, image: &Image) -> ObjectId {
+		let id = self.doc.add_image(image);
+		self.dict_mut().set("Thumb", id);
+		return id;
+	}
+}
+
+/// Format of the data of an extracted image.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ImageFormat {
+	/// JPEG file, taken from the PDF file as it is.
+	Jpeg,
+	/// JPEG 2000 file or codestream, taken from the PDF file as it is.
+	Jpeg2000,
+	/// Decoded samples, row by row, with the given number of bits per
+	/// component. The `/Decode` array of the image is already applied.
+	Raw,
+	/// Data still encoded with the filters in [`ExtractedImage::filters`],
+	/// starting with one which is not supported, e.g. `CCITTFaxDecode`.
+	Encoded,
+}
+
+/// Image XObject found in a PDF file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExtractedImage {
+	pub id: ObjectId,
+	pub width: u32,
+	pub height: u32,
+	pub bits_per_component: u8,
+	/// Name of the colour space family, e.g. `DeviceRGB` or `ICCBased`, or
+	/// `None` for image masks.
+	pub color_space: Option<String>,
+	pub format: ImageFormat,
+	/// Names of the filters the data is still encoded with, in the order
+	/// they're decoded, for the images in the [`ImageFormat::Encoded`]
+	/// format.
+	pub filters: Vec<String>,
+	pub data: Vec<u8>,
+}
+
+/// Gets the stream dictionary with only the given number of its first
+/// filters, and their decoding parameters.
+fn first_filters(dict: &Dictionary, count: usize) -> Dictionary {
+	let mut dict = dict.clone();
+	// The decoded length is the one of the data decoded by all the filters.
+	dict.remove("DL");
+	if let Some(PdfObject::Array(names)) = dict.get("Filter") {
+		let names = names[..count.min(names.len())].to_vec();
+		dict.set("Filter", names);
+	}
+	if let Some(PdfObject::Array(params)) = dict.get("DecodeParms") {
+		let params = params[..count.min(params.len())].to_vec();
+		dict.set("DecodeParms", params);
+	}
+	return dict;
+}
+
+impl Reader<'_> {
+	/// Adds the images among the XObjects of the resources, including the
+	/// ones used by form XObjects, to the list.
+	fn collect_images(&self, resources: &PdfObject, visited: &mut BTreeSet<ObjectId>, images: &mut Vec<ExtractedImage>) -> Result<(), PdfError> {
+		let resources = self.resolve_object(resources)?;
+		let Some(xobjects) = resources.as_dict().and_then(|resources| resources.get("XObject")) else {
+			return Ok(());
+		};
+		let xobjects = self.resolve_object(xobjects)?;
+
+		for (_, xobject) in xobjects.as_dict().into_iter().flat_map(Dictionary::iter) {
+			let Some(id) = xobject.as_reference() else {
+				continue;
+			};
+			if !visited.insert(id) {
+				continue;
+			}
+			let Some(stream) = self.resolve(id)?.as_stream().cloned() else {
+				continue;
+			};
+
+			match stream.dict.get_name("Subtype") {
+				Some(b"Image") => images.push(self.extract_image(id, &stream)?),
+				Some(b"Form") => {
+					if let Some(resources) = stream.dict.get("Resources") {
+						self.collect_images(resources, visited, images)?;
+					}
+				},
+				_ => {},
+			}
+		}
+		return Ok(());
+	}
+
+	/// Maps the samples through the `/Decode` array, and back onto the range
+	/// of the samples of the colour space, e.g. inverting them for `[1 0]`.
+	/// Indexed and Lab images are left unchanged, as their samples don't have
+	/// the range `[0 1]`.
+	fn apply_decode(&self, stream: &Stream, data: &mut [u8], color_space: Option<&[u8]>) -> Result<(), PdfError> {
+		let Some(decode) = stream.dict.get("Decode") else {
+			return Ok(());
+		};
+		let decode: Vec<f64> = self.resolve_object(decode)?.as_array().into_iter().flatten().filter_map(PdfObject::as_number).collect();
+		let bits = stream.dict.get("BitsPerComponent").and_then(PdfObject::as_integer).unwrap_or(1);
+		let width = stream.dict.get("Width").and_then(PdfObject::as_integer).unwrap_or(0).max(0) as usize;
+		if matches!(color_space, Some(b"Indexed" | b"Lab")) || decode.is_empty() || !decode.len().is_multiple_of(2) || !matches!(bits, 1 | 2 | 4 | 8) {
+			return Ok(());
+		}
+		if decode.chunks(2).all(|range| range == [0.0, 1.0]) {
+			return Ok(());
+		}
+
+		let (bits, components) = (bits as usize, decode.len() / 2);
+		let max = ((1 << bits) - 1) as f64;
+		// Rows start at byte boundaries.
+		let row = (width * components * bits).div_ceil(8);
+		for line in data.chunks_mut(row.max(1)) {
+			for sample in 0..width * components {
+				let (byte, shift) = (sample * bits / 8, 8 - bits - sample * bits % 8);
+				let Some(value) = line.get_mut(byte) else {
+					break;
+				};
+				let mask = (max as u8) << shift;
+				let [low, high] = [decode[sample % components * 2], decode[sample % components * 2 + 1]];
+				let decoded = low + ((*value & mask) >> shift) as f64 * (high - low) / max;
+				let encoded = (decoded.clamp(0.0, 1.0) * max).round() as u8;
+				*value = (*value & !mask) | (encoded << shift);
+			}
+		}
+		return Ok(());
+	}
+
+	fn extract_image(&self, id: ObjectId, stream: &Stream) -> Result<ExtractedImage, PdfError> {
+		let number = |key| stream.dict.get(key).and_then(PdfObject::as_integer).unwrap_or(0);
+		let color_space = match stream.dict.get("ColorSpace") {
+			Some(color_space) => match self.resolve_object(color_space)? {
+				PdfObject::Name(name) => Some(name),
+				PdfObject::Array(array) => array.first().and_then(PdfObject::as_name).map(<[u8]>::to_vec),
+				_ => None,
+			},
+			None => None,
+		};
+
+		let names: Vec<Vec<u8>> = match stream.dict.get("Filter") {
+			Some(PdfObject::Array(names)) => names.iter().filter_map(PdfObject::as_name).map(<[u8]>::to_vec).collect(),
+			Some(PdfObject::Name(name)) => std::vec![name.clone()],
+			_ => Vec::new(),
+		};
+		// The data is decoded up to the filter of an image format, which is
+		// kept as it is, or the first filter which is not supported.
+		let filters = self.filter_registry();
+		let end = names.iter().position(|name| matches!(&name[..], b"DCTDecode" | b"JPXDecode") || filters.get(name).is_none()).unwrap_or(names.len());
+		let mut data = match end {
+			0 => stream.data.clone(),
+			end if end == names.len() => self.decode(id, stream)?,
+			end => self.decode(id, &Stream::new(first_filters(&stream.dict, end), stream.data.clone()))?,
+		};
+		let format = match &names[end..] {
+			[] => {
+				self.apply_decode(stream, &mut data, color_space.as_deref())?;
+				ImageFormat::Raw
+			},
+			[name] if name == b"DCTDecode" => ImageFormat::Jpeg,
+			[name] if name == b"JPXDecode" => ImageFormat::Jpeg2000,
+			_ => ImageFormat::Encoded,
+		};
+		let remaining = match format {
+			ImageFormat::Encoded => names[end..].iter().map(|name| String::from_utf8_lossy(name).into_owned()).collect(),
+			_ => Vec::new(),
+		};
+		return Ok(ExtractedImage {
+			id,
+			width: number("Width") as u32,
+			height: number("Height") as u32,
+			// Image masks have a single bit per sample, even without the entry.
+			bits_per_component: stream.dict.get("BitsPerComponent").and_then(PdfObject::as_integer).unwrap_or(1) as u8,
+			color_space: color_space.map(|name| String::from_utf8_lossy(&name).into_owned()),
+			format,
+			filters: remaining,
+			data,
+		});
+	}
+
+	/// Attempts to extract all the images used by the pages, in the order of
+	/// their first use. Images used several times are extracted only once.
+	/// Images with filters which are not supported are extracted with their
+	/// data still encoded, in the [`ImageFormat::Encoded`] format.
+	pub fn images(&self) -> Result<Vec<ExtractedImage>, PdfError> {
+		let mut images = Vec::new();
+		let mut visited = BTreeSet::new();
+		for index in 0..self.page_count()? {
+			if let Some(resources) = self.page(index)?.get("Resources")? {
+				self.collect_images(&resources, &mut visited, &mut images)?;
+			}
+		}
+		return Ok(images);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::*;
+
+	#[test]
+	fn thumbnails() {
+		let mut doc = Document::new();
+		let mut content = ContentStream::new();
+		content.set_fill_color(Color::Gray(0.5)).rect(Rectangle::new(72.0, 72.0, 468.0, 648.0)).fill();
+		doc.add_page(612.0, 792.0).set_contents(content);
+		doc.add_page(300.0, 300.0).set_thumbnail(&Image::new(2, 1, 1, std::vec![0, 0xFF]));
+		doc.generate_thumbnails(0.1).unwrap();
+
+		let mut data = Vec::new();
+		doc.write(&mut Writer::new(&mut data)).unwrap();
+		let mut doc = Document::load(&data).unwrap();
+		for (index, size) in [(0, (62, 80)), (1, (30, 30))] {
+			let page = doc.page_mut(index).unwrap();
+			let thumb = page.dict().get("Thumb").and_then(PdfObject::as_reference).unwrap();
+			let image = doc.object(thumb).and_then(PdfObject::as_stream).unwrap();
+			assert_eq!(image.dict.get_name("Subtype"), Some(&b"Image"[..]));
+			assert_eq!((image.dict.get("Width"), image.dict.get("Height")), (Some(&PdfObject::Integer(size.0)), Some(&PdfObject::Integer(size.1))));
+		}
+
+		let thumb = doc.page_mut(0).unwrap().dict().get("Thumb").and_then(PdfObject::as_reference).unwrap();
+		let pixels = filters::flate::decode(&doc.object(thumb).and_then(PdfObject::as_stream).unwrap().data).unwrap();
+		assert_eq!(pixels.len(), 62 * 80 * 3);
+		// Pixels at the middle of the page are covered by the gray rectangle.
+		assert_eq!(&pixels[(40 * 62 + 31) * 3..][..3], &[0x80; 3]);
+		assert_eq!(&pixels[..3], &[0xFF; 3]);
+	}
+
+	#[test]
+	fn extract_images() {
+		// Start of image, baseline start of frame of a 32x16 RGB image, and end of image.
+		let jpeg = [
+			0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x04, 0x00, 0x00, 0xFF, 0xC0, 0x00, 0x11, 0x08, 0x00, 0x10, 0x00, 0x20, 0x03, 0x01, 0x22, 0x00, 0x02, 0x11, 0x01, 0x03, 0x11, 0x01, 0xFF, 0xD9,
+		];
+		let mut doc = Document::new();
+		assert!(doc.embed_jpeg(&jpeg[..4]).is_err());
+		let photo = doc.embed_jpeg(&jpeg).unwrap();
+		let gray = doc.add_image(&Image::new(2, 2, 1, std::vec![0, 64, 128, 255]));
+		for image in [photo, gray, photo] {
+			let mut content = ContentStream::new();
+			let name = content.use_image(image);
+			content.draw_xobject(&name);
+			doc.add_page(612.0, 792.0).set_contents(content);
+		}
+
+		let mut data = Vec::new();
+		doc.write(&mut Writer::new(&mut data)).unwrap();
+		let images = Reader::new(&data).unwrap().images().unwrap();
+		assert_eq!(images.len(), 2);
+		assert_eq!((images[0].id, images[0].format, images[0].width, images[0].height), (photo, ImageFormat::Jpeg, 32, 16));
+		assert_eq!(images[0].color_space.as_deref(), Some("DeviceRGB"));
+		assert_eq!(images[0].data, jpeg);
+		assert_eq!((images[1].format, images[1].bits_per_component), (ImageFormat::Raw, 8));
+		assert_eq!(images[1].data, [0, 64, 128, 255]);
+	}
+
+	#[test]
+	fn unsupported_filters() {
+		let jpeg = [0xFF, 0xD8, 0xFF, 0xC0, 0x00, 0x0B, 0x08, 0x00, 0x01, 0x00, 0x01, 0x01, 0x01, 0x11, 0x00, 0xFF, 0xD9];
+		let mut doc = Document::new();
+		let fax = doc.add_image(&Image::new(1, 1, 1, std::vec![0]));
+		let photo = doc.embed_jpeg(&jpeg).unwrap();
+		let chained = doc.add_image(&Image::new(1, 1, 1, std::vec![0]));
+		let filters = [(fax, std::vec!["CCITTFaxDecode", "FlateDecode"], std::vec![1, 2, 3]), (photo, std::vec!["FlateDecode", "DCTDecode"], filters::flate::encode(&jpeg))];
+		for (id, names, data) in filters {
+			let Some(PdfObject::Stream(stream)) = doc.object_mut(id) else {
+				unreachable!();
+			};
+			stream.dict.set("Filter", names.iter().map(|&name| PdfObject::name(name)).collect::<Vec<_>>());
+			stream.data = data;
+		}
+		for image in [fax, photo, chained] {
+			let mut content = ContentStream::new();
+			let name = content.use_image(image);
+			content.draw_xobject(&name);
+			doc.add_page(612.0, 792.0).set_contents(content);
+		}
+
+		let mut data = Vec::new();
+		doc.write(&mut Writer::new(&mut data)).unwrap();
+		let images = Reader::new(&data).unwrap().images().unwrap();
+		assert_eq!((images[0].format, &images[0].filters[..], &images[0].data[..]), (ImageFormat::Encoded, &["CCITTFaxDecode".to_string(), "FlateDecode".to_string()][..], &[1, 2, 3][..]));
+		assert_eq!((images[1].format, &images[1].data[..]), (ImageFormat::Jpeg, &jpeg[..]));
+		assert!(images[1].filters.is_empty());
+		assert_eq!((images[2].format, &images[2].data[..]), (ImageFormat::Raw, &[0][..]));
+	}
+
+	#[test]
+	fn decode_and_color_key_mask() {
+		let mut doc = Document::new();
+		let gray = Image::new(2, 1, 1, std::vec![0, 64]);
+		assert!(doc.embed_image(&gray, Some(&[1.0, 0.0, 1.0, 0.0]), None).is_err());
+		let inverted = doc.embed_image(&gray, Some(&[1.0, 0.0]), None).unwrap();
+		let rgb = Image::new(1, 1, 3, std::vec![255, 255, 255]);
+		assert!(doc.embed_image(&rgb, None, Some(&[250, 240, 0, 255, 0, 255])).is_err());
+		let keyed = doc.embed_image(&rgb, None, Some(&[240, 255, 0, 255, 250, 255])).unwrap();
+		for image in [inverted, keyed] {
+			let mut content = ContentStream::new();
+			let name = content.use_image(image);
+			content.draw_xobject(&name);
+			doc.add_page(612.0, 792.0).set_contents(content);
+		}
+
+		let mut data = Vec::new();
+		doc.write(&mut Writer::new(&mut data)).unwrap();
+		assert!(String::from_utf8_lossy(&data).contains("/Mask [240 255 0 255 250 255]"));
+		let images = Reader::new(&data).unwrap().images().unwrap();
+		assert_eq!(images[0].data, [255, 191]);
+		assert_eq!(images[1].data, [255, 255, 255]);
+	}
+
+	#[test]
+	fn jpeg_2000_embedding() {
+		// Signature and file type boxes, followed by a codestream with the
+		// SIZ marker of a 3 component, 40x30 image with a 10x5 offset.
+		let mut jp2 = std::vec![0, 0, 0, 12, b'j', b'P', b' ', b' ', 0x0D, 0x0A, 0x87, 0x0A];
+		jp2.extend_from_slice(&[0, 0, 0, 20, b'f', b't', b'y', b'p', b'j', b'p', b'2', b' ', 0, 0, 0, 0, b'j', b'p', b'2', b' ']);
+		let mut codestream = std::vec![0xFF, 0x4F, 0xFF, 0x51, 0, 47, 0, 0];
+		for value in [50u32, 35, 10, 5, 50, 35, 0, 0] {
+			codestream.extend_from_slice(&value.to_be_bytes());
+		}
+		codestream.extend_from_slice(&[0, 3, 7, 1, 1, 7, 1, 1, 7, 1, 1, 0xFF, 0xD9]);
+		jp2.extend_from_slice(&(codestream.len() as u32 + 8).to_be_bytes());
+		jp2.extend_from_slice(b"jp2c");
+		jp2.extend_from_slice(&codestream);
+
+		let mut doc = Document::new();
+		assert!(doc.embed_jp2(&jp2[..32]).is_err());
+		let image = doc.embed_jp2(&jp2).unwrap();
+		let bare = doc.embed_jp2(&codestream).unwrap();
+		for id in [image, bare] {
+			let dict = &doc.object(id).and_then(PdfObject::as_stream).unwrap().dict;
+			assert_eq!((dict.get("Width"), dict.get("Height")), (Some(&PdfObject::Integer(40)), Some(&PdfObject::Integer(30))));
+			assert_eq!(dict.get_name("Filter"), Some(&b"JPXDecode"[..]));
+		}
+		assert_eq!(doc.object(image).and_then(PdfObject::as_stream).unwrap().data, jp2);
+	}
+}
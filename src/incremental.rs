@@ -0,0 +1,283 @@
+//! Incremental updates, which append the changes to the original file.
+
+use std::collections::BTreeMap;
+use std::hash::{DefaultHasher, Hasher};
+
+use crate::document::{write_indirect, write_trailer, XrefEntries};
+use crate::{Document, ObjectId, PdfError, PdfObject, Reader, Writer};
+
+/// Hashes the object as it's written. Objects are modified in place in too
+/// many ways to track every change, so the changed ones are found by their
+/// hashes instead.
+pub(crate) fn object_hash(object: &PdfObject) -> u64 {
+	let mut data = Vec::new();
+	// Writing into memory can't fail.
+	let _ = object.write_to(&mut Writer::new(&mut data));
+	let mut hasher = DefaultHasher::new();
+	hasher.write(&data);
+	return hasher.finish();
+}
+
+impl Document {
+	/// Gets whether the object differs from the one loaded with the same
+	/// identifier, or wasn't loaded at all.
+	fn is_changed(&self, id: ObjectId, object: &PdfObject) -> bool {
+		return self.loaded.get(&id) != Some(&object_hash(object));
+	}
+
+	/// Attempts to write the document loaded from the given file as its
+	/// incremental update. The original file is written unchanged, followed by
+	/// the objects changed since the document was loaded, and a new
+	/// cross-reference section, which refers to the previous one for the rest,
+	/// and frees the objects removed since.
+	/// The permanent file identifier is kept, while the one of the revision is
+	/// generated anew.
+	pub fn write_update(&mut self, original: &[u8], writer: &mut Writer<'_>) -> Result<(), PdfError> {
+		let reader = Reader::new(original)?;
+		let prev = reader.startxref()?;
+		// The header can't change, so a newer version goes into the catalog.
+		if self.effective_version() > reader.pdf_version() {
+			self.set_catalog_version(self.effective_version());
+		}
+		self.merge_resources();
+		self.build_name_trees();
+		let compressed = self.compressed_contents();
+
+		writer.write(original)?;
+		if !original.ends_with(b"\n") {
+			writer.write(b"\n")?;
+		}
+
+		// Entries of the written objects, by their number, with the offset,
+		// the generation, and the type of the entry.
+		let mut entries: BTreeMap<u32, (usize, u16, u8)> = BTreeMap::new();
+		let roots = [(self.catalog_id, self.catalog_dict()), (self.pages_id, self.page_tree_dict())];
+		for (id, dict) in roots {
+			let object = PdfObject::Dictionary(dict);
+			if self.is_changed(id, &object) {
+				entries.insert(id.number, (writer.pos(), id.generation, b'n'));
+				write_indirect(writer, id, &object, self.write_style)?;
+			}
+		}
+		for (&id, object) in &self.objects {
+			if !self.is_changed(id, object) {
+				continue;
+			}
+			entries.insert(id.number, (writer.pos(), id.generation, b'n'));
+			write_indirect(writer, id, compressed.get(&id).unwrap_or(object), self.write_style)?;
+		}
+
+		// Objects deleted since the document was loaded are freed, with the
+		// next generation, for the object which may take their number. Free
+		// entries form a list, starting at the object 0, where each one has
+		// the number of the next free object in place of the offset, so the
+		// deleted objects are linked into the list of the original file, which
+		// is written anew, without the numbers taken by the written objects.
+		let deleted: Vec<ObjectId> = self.loaded.keys().copied().filter(|id| !self.objects.contains_key(id) && ![self.catalog_id, self.pages_id].contains(id) && !entries.contains_key(&id.number)).collect();
+		if !deleted.is_empty() {
+			let mut free: BTreeMap<u32, u16> = reader.free_ids().filter(|id| !entries.contains_key(&id.number)).map(|id| (id.number, id.generation)).collect();
+			free.extend(deleted.iter().map(|id| (id.number, id.generation.saturating_add(1))));
+			let free: Vec<(u32, u16)> = free.into_iter().collect();
+			let next = |index: usize| free.get(index).map_or(0, |&(number, _)| number as usize);
+			entries.insert(0, (next(0), 65535, b'f'));
+			for (index, &(number, generation)) in free.iter().enumerate() {
+				entries.insert(number, (next(index + 1), generation, b'f'));
+			}
+		}
+
+		// Only the written objects are listed, in subsections of consecutive
+		// object numbers.
+		let xref_pos = writer.pos();
+		writer.write(b"xref\n")?;
+		let entries: Vec<(u32, (usize, u16, u8))> = entries.into_iter().collect();
+		let mut xref = XrefEntries::new();
+		for subsection in entries.chunk_by(|(a, _), (b, _)| a + 1 == *b) {
+			writer.write(std::format!("{} {}\n", subsection[0].0, subsection.len()).as_bytes())?;
+			for &(_, (offset, generation, kind)) in subsection {
+				xref.push(writer, offset, generation, kind)?;
+			}
+			xref.flush(writer)?;
+		}
+
+		// The original file is the authority on the permanent identifier.
+		if let Some([PdfObject::String(permanent, _), PdfObject::String(revision, _)]) = reader.trailer().get("ID").and_then(PdfObject::as_array).map(Vec::as_slice) {
+			self.file_id = Some([permanent.clone(), revision.clone()]);
+		}
+		let size = reader.trailer().get("Size").and_then(PdfObject::as_integer).map_or(0, |size| size.clamp(0, u32::MAX as i64) as u32).max(self.next_number);
+		let (root, info) = (self.catalog_id, self.info);
+		let id = self.next_file_id();
+		write_trailer(writer, size, root, info, Some(id), Some(prev), xref_pos)?;
+		return Ok(());
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::*;
+
+	#[test]
+	fn file_identifiers() {
+		let mut doc = Document::new();
+		doc.add_page(612.0, 792.0);
+		let mut original = Vec::new();
+		doc.write(&mut Writer::new(&mut original)).unwrap();
+
+		let text = String::from_utf8_lossy(&original).into_owned();
+		let start = text.find("/ID [<").unwrap() + 6;
+		let [first, second] = text[start..text.find(">]").unwrap()].split("> <").collect::<Vec<_>>()[..] else {
+			panic!("expected two identifiers");
+		};
+		assert!(!first.is_empty() && first.len() == second.len());
+		assert!(first.bytes().all(|byte| byte.is_ascii_hexdigit()));
+		let id = doc.file_id().unwrap().clone();
+		assert_eq!(id[0], id[1]);
+
+		let mut doc = Document::load(&original).unwrap();
+		assert_eq!(doc.file_id(), Some(&id));
+		let font = doc.add_base_font("Helvetica");
+		doc.page_mut(0).unwrap().add_font_resource("F1", font);
+		let mut updated = Vec::new();
+		doc.write_update(&original, &mut Writer::new(&mut updated)).unwrap();
+		assert!(updated.starts_with(&original));
+
+		let reader = Reader::new(&updated).unwrap();
+		let trailer = reader.trailer();
+		assert!(trailer.get("Prev").is_some());
+		let updated_id = trailer.get("ID").and_then(PdfObject::as_array).unwrap();
+		assert_eq!(updated_id[0].as_string(), Some(&id[0][..]));
+		assert_eq!(updated_id[1].as_string().map(<[u8]>::len), Some(id[1].len()));
+		assert_eq!(reader.resolve(font.0).unwrap().as_dict().unwrap().get_name("BaseFont"), Some(&b"Helvetica"[..]));
+		let mut doc = Document::load(&updated).unwrap();
+		assert!(doc.page_mut(0).unwrap().dict().get("Resources").is_some());
+
+		// Deterministic identifiers only depend on the objects.
+		let write = || {
+			let mut doc = Document::new();
+			doc.set_deterministic_id(true);
+			doc.add_page(612.0, 792.0);
+			doc.write(&mut Writer::new(&mut Vec::new())).unwrap();
+			return doc.file_id().unwrap().clone();
+		};
+		assert_eq!(write(), write());
+	}
+
+	#[test]
+	fn updated_pages() {
+		let mut doc = Document::new();
+		let font = doc.add_base_font("Helvetica");
+		for text in ["First", "Second"] {
+			let mut content = ContentStream::new();
+			let name = content.use_font(font);
+			content.begin_text().set_font(&name, 12.0).move_text(72.0, 720.0).show_text(text.as_bytes()).end_text();
+			doc.add_page(612.0, 792.0).set_contents(content);
+		}
+		let mut original = Vec::new();
+		doc.write(&mut Writer::new(&mut original)).unwrap();
+
+		let mut doc = Document::load(&original).unwrap();
+		let mut content = ContentStream::new();
+		let name = content.use_font(font);
+		content.begin_text().set_font(&name, 12.0).move_text(72.0, 720.0).show_text(b"Updated").end_text();
+		doc.page_mut(0).unwrap().set_contents(content);
+		let mut updated = Vec::new();
+		doc.write_update(&original, &mut Writer::new(&mut updated)).unwrap();
+
+		let reader = Reader::new(&updated).unwrap();
+		assert_eq!(reader.page_count().unwrap(), 2);
+		assert_eq!(reader.page(0).unwrap().extract_text().unwrap().trim(), "Updated");
+		assert_eq!(reader.page(1).unwrap().extract_text().unwrap().trim(), "Second");
+		assert_eq!(Reader::new(&original).unwrap().page(0).unwrap().extract_text().unwrap().trim(), "First");
+	}
+
+	#[test]
+	fn deleted_objects() {
+		let mut doc = Document::new();
+		doc.add_page(612.0, 792.0);
+		let (first, second) = (doc.add_base_font("Helvetica"), doc.add_base_font("Courier"));
+		let mut original = Vec::new();
+		doc.write(&mut Writer::new(&mut original)).unwrap();
+
+		let mut doc = Document::load(&original).unwrap();
+		doc.remove_object(first.0).unwrap();
+		doc.remove_object(second.0).unwrap();
+		let mut updated = Vec::new();
+		doc.write_update(&original, &mut Writer::new(&mut updated)).unwrap();
+
+		let update = String::from_utf8_lossy(&updated[original.len()..]).into_owned();
+		let entry = |next: u32, generation: u16| std::format!("{:010} {:05} f", next, generation);
+		let (first_entry, second_entry) = (entry(second.0.number, 1), entry(0, 1));
+		assert!(update.contains(&std::format!("0 1\n{}", entry(first.0.number, 65535))));
+		assert!(update.contains(&std::format!("{} 2\n{}\r\n{}\r\n", first.0.number, first_entry, second_entry)));
+
+		// Older sections of the file don't have the freed objects in use.
+		let reader = Reader::new(&updated).unwrap();
+		assert!(reader.resolve(first.0).is_err() && reader.resolve(second.0).is_err());
+		assert!(Reader::new(&original).unwrap().resolve(first.0).is_ok());
+		let doc = Document::load(&updated).unwrap();
+		assert!(doc.object(first.0).is_none() && doc.object(second.0).is_none());
+		assert_eq!(doc.page_count(), 1);
+	}
+
+	#[test]
+	fn deleted_objects_linked() {
+		let mut doc = Document::new();
+		doc.add_page(612.0, 792.0);
+		let fonts = [doc.add_base_font("Helvetica"), doc.add_base_font("Courier"), doc.add_base_font("Symbol")];
+		let mut original = Vec::new();
+		doc.write(&mut Writer::new(&mut original)).unwrap();
+
+		let mut doc = Document::load(&original).unwrap();
+		doc.remove_object(fonts[0].0).unwrap();
+		let mut first = Vec::new();
+		doc.write_update(&original, &mut Writer::new(&mut first)).unwrap();
+
+		// Objects freed by the previous update stay on the free list.
+		let mut doc = Document::load(&first).unwrap();
+		doc.remove_object(fonts[2].0).unwrap();
+		let mut second = Vec::new();
+		doc.write_update(&first, &mut Writer::new(&mut second)).unwrap();
+
+		let update = String::from_utf8_lossy(&second[first.len()..]).into_owned();
+		let entry = |next: u32, generation: u16| std::format!("{:010} {:05} f", next, generation);
+		assert!(update.contains(&std::format!("0 1\n{}", entry(fonts[0].0.number, 65535))));
+		assert!(update.contains(&std::format!("{} 1\n{}", fonts[0].0.number, entry(fonts[2].0.number, 1))));
+		assert!(update.contains(&std::format!("{} 1\n{}", fonts[2].0.number, entry(0, 1))));
+
+		let reader = Reader::new(&second).unwrap();
+		assert!(reader.resolve(fonts[0].0).is_err() && reader.resolve(fonts[2].0).is_err());
+		assert!(reader.resolve(fonts[1].0).is_ok());
+	}
+
+	#[test]
+	fn changed_objects_only() {
+		let mut doc = Document::new();
+		let font = doc.add_base_font("Helvetica");
+		for index in 0..10 {
+			let mut content = ContentStream::new();
+			let name = content.use_font(font);
+			content.begin_text().set_font(&name, 12.0).move_text(72.0, 720.0).show_text(std::format!("Page {}", index).as_bytes()).end_text();
+			doc.add_page(612.0, 792.0).set_contents(content);
+		}
+		let mut original = Vec::new();
+		doc.write(&mut Writer::new(&mut original)).unwrap();
+
+		let mut doc = Document::load(&original).unwrap();
+		let contents = doc.page_mut(3).unwrap().dict().get("Contents").and_then(PdfObject::as_reference).unwrap();
+		let Some(PdfObject::Stream(stream)) = doc.object_mut(contents) else {
+			panic!("page contents are not a stream");
+		};
+		stream.data = b"BT /F0 12 Tf 72 720 Td (Edited) Tj ET".to_vec();
+		stream.dict.remove("Filter");
+		let mut updated = Vec::new();
+		doc.write_update(&original, &mut Writer::new(&mut updated)).unwrap();
+
+		let update = String::from_utf8_lossy(&updated[original.len()..]).into_owned();
+		assert_eq!(update.matches(" obj").count(), 1);
+		assert!(update.contains(&std::format!("{} {} obj", contents.number, contents.generation)));
+		assert!(update.contains(&std::format!("xref\n{} 1\n", contents.number)));
+		let reader = Reader::new(&updated).unwrap();
+		assert_eq!(reader.page_count().unwrap(), 10);
+		assert_eq!(reader.page(3).unwrap().extract_text().unwrap(), "Edited");
+		assert_eq!(reader.page(4).unwrap().extract_text().unwrap(), "Page 4");
+	}
+}
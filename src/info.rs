@@ -0,0 +1,118 @@
+//! Document information dictionary (`/Info` of the trailer).
+
+use crate::{Dictionary, Document, ObjectId, PdfDate, PdfObject, PdfString, Reader};
+
+/// Metadata of the document, shown by viewers in the document properties.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DocumentInfo {
+	pub title: Option<String>,
+	pub author: Option<String>,
+	pub subject: Option<String>,
+	pub keywords: Option<String>,
+	/// Application which created the original document.
+	pub creator: Option<String>,
+	/// Application which converted the document to PDF.
+	pub producer: Option<String>,
+	pub creation_date: Option<PdfDate>,
+	pub modification_date: Option<PdfDate>,
+}
+
+impl DocumentInfo {
+	const TEXT_KEYS: [&'static str; 6] = ["Title", "Author", "Subject", "Keywords", "Creator", "Producer"];
+
+	fn texts(&self) -> [&Option<String>; 6] {
+		return [&self.title, &self.author, &self.subject, &self.keywords, &self.creator, &self.producer];
+	}
+
+	/// Creates the information dictionary, with only the entries which are
+	/// set.
+	pub(crate) fn to_dict(&self) -> Dictionary {
+		let mut dict = Dictionary::new();
+		for (key, text) in Self::TEXT_KEYS.into_iter().zip(self.texts()) {
+			if let Some(text) = text {
+				dict.set(key, PdfObject::text_string(text));
+			}
+		}
+		if let Some(date) = self.creation_date {
+			dict.set("CreationDate", date);
+		}
+		if let Some(date) = self.modification_date {
+			dict.set("ModDate", date);
+		}
+		return dict;
+	}
+
+	/// Reads the information dictionary, decoding the text strings and
+	/// leaving out the entries which are not strings or valid dates.
+	pub(crate) fn from_dict(dict: &Dictionary) -> Self {
+		let text = |key: &str| dict.get(key).and_then(PdfObject::as_string).map(|bytes| PdfString::from_bytes(bytes).to_text());
+		let date = |key: &str| text(key).and_then(|date| PdfDate::parse(&date).ok());
+		return Self {
+			title: text("Title"),
+			author: text("Author"),
+			subject: text("Subject"),
+			keywords: text("Keywords"),
+			creator: text("Creator"),
+			producer: text("Producer"),
+			creation_date: date("CreationDate"),
+			modification_date: date("ModDate"),
+		};
+	}
+}
+
+impl Document {
+	/// Sets the document information dictionary, replacing the previous one.
+	pub fn set_info(&mut self, info: &DocumentInfo) {
+		match self.info {
+			Some(id) => self.set_object(id, info.to_dict()),
+			None => self.info = Some(self.add_object(info.to_dict())),
+		}
+	}
+
+	/// Gets the document information dictionary, if the document has one.
+	pub fn info(&self) -> Option<DocumentInfo> {
+		return self.object(self.info?).and_then(PdfObject::as_dict).map(DocumentInfo::from_dict);
+	}
+}
+
+impl Reader<'_> {
+	/// Gets the identifier of the document information dictionary.
+	pub(crate) fn info_id(&self) -> Option<ObjectId> {
+		return self.trailer().get("Info").and_then(PdfObject::as_reference);
+	}
+
+	/// Gets the document information dictionary, if the trailer has a valid
+	/// one.
+	pub fn info(&self) -> Option<DocumentInfo> {
+		let info = self.resolve_object(self.trailer().get("Info")?).ok()?;
+		return info.as_dict().map(DocumentInfo::from_dict);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::*;
+
+	#[test]
+	fn read_info() {
+		let info = DocumentInfo {
+			title: Some("Café ☕".to_string()),
+			author: Some("Aodhnait".to_string()),
+			creation_date: Some(PdfDate::from_parts(2020, 6, 15, 12, 0, 0, Some(120)).unwrap()),
+			..DocumentInfo::default()
+		};
+		let mut doc = Document::new();
+		doc.add_page(612.0, 792.0);
+		doc.set_info(&DocumentInfo::default());
+		doc.set_info(&info);
+		assert_eq!(doc.info(), Some(info.clone()));
+
+		let mut output = Vec::new();
+		doc.write(&mut Writer::new(&mut output)).unwrap();
+		let reader = Reader::new(&output).unwrap();
+		let title = reader.resolve(reader.info_id().unwrap()).unwrap().as_dict().unwrap().get("Title").and_then(PdfObject::as_string).unwrap().to_vec();
+		assert!(title.starts_with(&[0xfe, 0xff]));
+		assert_eq!(reader.info(), Some(info.clone()));
+		assert_eq!(Document::load(&output).unwrap().info(), Some(info));
+	}
+}
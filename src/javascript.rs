@@ -0,0 +1,56 @@
+//! JavaScript actions, run by the viewer, e.g. to calculate form fields.
+
+use crate::{Dictionary, Document, PdfError, PdfObject};
+
+/// Creates the action running the script.
+pub(crate) fn javascript_action(code: &str) -> Dictionary {
+	let mut action = Dictionary::new();
+	action.set("S", PdfObject::name("JavaScript"));
+	action.set("JS", PdfObject::text_string(code));
+	return action;
+}
+
+impl Document {
+	/// Attempts to add the document-level script, which is run when the
+	/// document is opened, e.g. to define the functions used by the form
+	/// fields. Setting the script with the same name again replaces it.
+	pub fn set_document_javascript(&mut self, name: &str, code: &str) -> Result<(), PdfError> {
+		return self.insert_into_name_tree("JavaScript", name.as_bytes(), javascript_action(code).into());
+	}
+
+	/// Sets the script run when the document is opened, as its open action.
+	pub fn set_open_javascript(&mut self, code: &str) {
+		self.catalog.set("OpenAction", javascript_action(code));
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::*;
+
+	#[test]
+	fn document_scripts() {
+		let mut doc = Document::new();
+		doc.add_page(612.0, 792.0);
+		doc.set_document_javascript("init", "var total = 0;").unwrap();
+		doc.set_document_javascript("helpers", "function f() {}").unwrap();
+		doc.set_document_javascript("init", "var total = 1;").unwrap();
+		doc.set_open_javascript("app.alert('Hello');");
+
+		let mut output = Vec::new();
+		doc.write(&mut Writer::new(&mut output)).unwrap();
+		let reader = Reader::new(&output).unwrap();
+		let init = reader.name_tree_value("JavaScript", b"init").unwrap().unwrap();
+		let init = init.as_dict().unwrap();
+		assert_eq!(init.get_name("S"), Some(&b"JavaScript"[..]));
+		assert_eq!(init.get("JS").and_then(PdfObject::as_string), Some(&b"var total = 1;"[..]));
+		assert_eq!(reader.name_tree_value("JavaScript", b"missing").unwrap(), None);
+
+		let catalog = reader.catalog().unwrap();
+		let tree = catalog.get("Names").and_then(PdfObject::as_dict).and_then(|names| names.get("JavaScript")).and_then(PdfObject::as_dict).unwrap();
+		assert_eq!(tree.get("Names").and_then(PdfObject::as_array).map(Vec::len), Some(4));
+		let open = catalog.get("OpenAction").and_then(PdfObject::as_dict).unwrap();
+		assert_eq!(open.get("JS").and_then(PdfObject::as_string), Some(&b"app.alert('Hello');"[..]));
+		assert!(String::from_utf8_lossy(&output).contains("/S /JavaScript /JS (var total = 1;)"));
+	}
+}
@@ -0,0 +1,562 @@
+//! Tokeniser for the PDF syntax, shared by the file and content stream parsers.
+
+use std::simd::cmp::SimdPartialEq;
+use std::simd::Simd;
+use std::sync::OnceLock;
+
+use crate::PdfError;
+
+/// Whitespace characters, as defined in the section 3.1.1 of the reference.
+#[inline]
+pub(crate) fn is_whitespace(byte: u8) -> bool {
+	return matches!(byte, 0x00 | 0x09 | 0x0a | 0x0c | 0x0d | 0x20);
+}
+
+#[inline]
+pub(crate) fn is_delimiter(byte: u8) -> bool {
+	return matches!(byte, b'(' | b')' | b'<' | b'>' | b'[' | b']' | b'{' | b'}' | b'/' | b'%');
+}
+
+/// Regular characters are all the characters which are neither whitespace nor
+/// delimiters. Numbers, names, and keywords are sequences of these.
+#[inline]
+pub(crate) fn is_regular(byte: u8) -> bool {
+	return !is_whitespace(byte) && !is_delimiter(byte);
+}
+
+#[inline(always)]
+fn whitespace_bits<const N: usize>(chunk: Simd<u8, N>) -> u64 {
+	let mask = chunk.simd_eq(Simd::splat(0x00))
+		| chunk.simd_eq(Simd::splat(0x09))
+		| chunk.simd_eq(Simd::splat(0x0a))
+		| chunk.simd_eq(Simd::splat(0x0c))
+		| chunk.simd_eq(Simd::splat(0x0d))
+		| chunk.simd_eq(Simd::splat(0x20));
+	return mask.to_bitmask();
+}
+
+#[inline(always)]
+fn delimiter_bits<const N: usize>(chunk: Simd<u8, N>) -> u64 {
+	let mask = chunk.simd_eq(Simd::splat(b'('))
+		| chunk.simd_eq(Simd::splat(b')'))
+		| chunk.simd_eq(Simd::splat(b'<'))
+		| chunk.simd_eq(Simd::splat(b'>'))
+		| chunk.simd_eq(Simd::splat(b'['))
+		| chunk.simd_eq(Simd::splat(b']'))
+		| chunk.simd_eq(Simd::splat(b'{'))
+		| chunk.simd_eq(Simd::splat(b'}'))
+		| chunk.simd_eq(Simd::splat(b'/'))
+		| chunk.simd_eq(Simd::splat(b'%'));
+	return mask.to_bitmask();
+}
+
+/// Scans the data `N` bytes at a time, finishing the last, partial chunk one
+/// byte at a time.
+#[inline(always)]
+fn skip_whitespace_simd<const N: usize>(data: &[u8], mut pos: usize) -> usize {
+	let all = u64::MAX >> (64 - N);
+	while pos + N <= data.len() {
+		let bits = whitespace_bits(Simd::<u8, N>::from_slice(&data[pos..pos + N]));
+		if bits != all {
+			return pos + (!bits).trailing_zeros() as usize;
+		}
+		pos += N;
+	}
+
+	return skip_whitespace_scalar(data, pos);
+}
+
+#[inline(always)]
+fn scan_regular_simd<const N: usize>(data: &[u8], mut pos: usize) -> usize {
+	while pos + N <= data.len() {
+		let chunk = Simd::<u8, N>::from_slice(&data[pos..pos + N]);
+		let bits = whitespace_bits(chunk) | delimiter_bits(chunk);
+		if bits != 0 {
+			return pos + bits.trailing_zeros() as usize;
+		}
+		pos += N;
+	}
+
+	return scan_regular_scalar(data, pos);
+}
+
+fn skip_whitespace_scalar(data: &[u8], mut pos: usize) -> usize {
+	while pos < data.len() && is_whitespace(data[pos]) {
+		pos += 1;
+	}
+	return pos;
+}
+
+fn scan_regular_scalar(data: &[u8], mut pos: usize) -> usize {
+	while pos < data.len() && is_regular(data[pos]) {
+		pos += 1;
+	}
+	return pos;
+}
+
+// The 32-byte variants are compiled with AVX2 enabled, so that they use it
+// even when the rest of the crate targets the baseline x86-64 CPU.
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2")]
+fn skip_whitespace_avx2(data: &[u8], pos: usize) -> usize {
+	return skip_whitespace_simd::<32>(data, pos);
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2")]
+fn scan_regular_avx2(data: &[u8], pos: usize) -> usize {
+	return scan_regular_simd::<32>(data, pos);
+}
+
+/// Number of bytes examined at once by the scanning routines.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Lanes {
+	Scalar,
+	/// SSE2 or NEON registers.
+	Sixteen,
+	/// AVX2 registers.
+	ThirtyTwo,
+}
+
+impl Lanes {
+	/// Detects the widest registers supported by the CPU, once.
+	pub fn detect() -> Self {
+		static LANES: OnceLock<Lanes> = OnceLock::new();
+		return *LANES.get_or_init(|| {
+			#[cfg(target_arch = "x86_64")]
+			{
+				if std::is_x86_feature_detected!("avx2") {
+					return Lanes::ThirtyTwo;
+				}
+				if std::is_x86_feature_detected!("sse2") {
+					return Lanes::Sixteen;
+				}
+			}
+			#[cfg(target_arch = "aarch64")]
+			{
+				if std::arch::is_aarch64_feature_detected!("neon") {
+					return Lanes::Sixteen;
+				}
+			}
+			return Lanes::Scalar;
+		});
+	}
+
+	/// Returns the position of the first non-whitespace byte at or after `pos`.
+	pub fn skip_whitespace(self, data: &[u8], pos: usize) -> usize {
+		return match self {
+			Self::Scalar => skip_whitespace_scalar(data, pos),
+			Self::Sixteen => skip_whitespace_simd::<16>(data, pos),
+			#[cfg(target_arch = "x86_64")]
+			// SAFETY: The variant is only detected when AVX2 is available.
+			Self::ThirtyTwo if std::is_x86_feature_detected!("avx2") => unsafe { skip_whitespace_avx2(data, pos) },
+			Self::ThirtyTwo => skip_whitespace_simd::<32>(data, pos),
+		};
+	}
+
+	/// Returns the position of the first non-regular byte at or after `pos`.
+	pub fn scan_regular(self, data: &[u8], pos: usize) -> usize {
+		return match self {
+			Self::Scalar => scan_regular_scalar(data, pos),
+			Self::Sixteen => scan_regular_simd::<16>(data, pos),
+			#[cfg(target_arch = "x86_64")]
+			// SAFETY: The variant is only detected when AVX2 is available.
+			Self::ThirtyTwo if std::is_x86_feature_detected!("avx2") => unsafe { scan_regular_avx2(data, pos) },
+			Self::ThirtyTwo => scan_regular_simd::<32>(data, pos),
+		};
+	}
+}
+
+/// Returns the position of the first non-whitespace byte at or after `pos`,
+/// using the widest registers available.
+#[inline]
+pub(crate) fn skip_whitespace(data: &[u8], pos: usize) -> usize {
+	return Lanes::detect().skip_whitespace(data, pos);
+}
+
+/// Returns the position of the first non-regular byte at or after `pos`.
+#[inline]
+pub(crate) fn scan_regular(data: &[u8], pos: usize) -> usize {
+	return Lanes::detect().scan_regular(data, pos);
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Token<'a> {
+	Integer(i64),
+	Real(f64),
+	String(Vec<u8>),
+	HexString(Vec<u8>),
+	/// Name with the `#XX` escapes already decoded, without the leading slash.
+	Name(Vec<u8>),
+	ArrayStart,
+	ArrayEnd,
+	DictStart,
+	DictEnd,
+	ProcStart,
+	ProcEnd,
+	/// Any other sequence of regular characters, e.g. `obj` or `Tj`.
+	Keyword(&'a [u8]),
+}
+
+pub(crate) struct Lexer<'a> {
+	data: &'a [u8],
+	pos: usize,
+}
+
+impl<'a> Lexer<'a> {
+	/// Creates new lexer starting at the given position of the data.
+	#[inline]
+	pub fn new(data: &'a [u8], pos: usize) -> Self {
+		return Self {
+			data,
+			pos,
+		};
+	}
+
+	#[inline]
+	pub fn data(&self) -> &'a [u8] {
+		return self.data;
+	}
+
+	#[inline]
+	pub fn pos(&self) -> usize {
+		return self.pos;
+	}
+
+	#[inline]
+	pub fn set_pos(&mut self, pos: usize) {
+		self.pos = pos;
+	}
+
+	/// Skips whitespace and comments.
+	pub fn skip_whitespace(&mut self) {
+		loop {
+			self.pos = skip_whitespace(self.data, self.pos);
+			if self.data.get(self.pos) != Some(&b'%') {
+				return;
+			}
+
+			while self.pos < self.data.len() && !matches!(self.data[self.pos], b'\r' | b'\n') {
+				self.pos += 1;
+			}
+		}
+	}
+
+	/// Returns the next token, or `None` at the end of the data.
+	pub fn next(&mut self) -> Result<Option<Token<'a>>, PdfError> {
+		self.skip_whitespace();
+
+		let start = self.pos;
+		let Some(&byte) = self.data.get(start) else {
+			return Ok(None);
+		};
+
+		let token = match byte {
+			b'[' => {
+				self.pos += 1;
+				Token::ArrayStart
+			},
+			b']' => {
+				self.pos += 1;
+				Token::ArrayEnd
+			},
+			b'{' => {
+				self.pos += 1;
+				Token::ProcStart
+			},
+			b'}' => {
+				self.pos += 1;
+				Token::ProcEnd
+			},
+			b'<' if self.data.get(start + 1) == Some(&b'<') => {
+				self.pos += 2;
+				Token::DictStart
+			},
+			b'>' if self.data.get(start + 1) == Some(&b'>') => {
+				self.pos += 2;
+				Token::DictEnd
+			},
+			b'<' => self.hex_string()?,
+			b'(' => self.literal_string()?,
+			b'/' => self.name(),
+			b')' | b'>' => return Err(PdfError::syntax(start, "unexpected delimiter")),
+			_ => {
+				self.pos = scan_regular(self.data, start);
+				let word = &self.data[start..self.pos];
+				parse_number(word).unwrap_or(Token::Keyword(word))
+			},
+		};
+
+		return Ok(Some(token));
+	}
+
+	fn name(&mut self) -> Token<'a> {
+		let start = self.pos + 1;
+		self.pos = scan_regular(self.data, start);
+
+		let raw = &self.data[start..self.pos];
+		let mut name = Vec::with_capacity(raw.len());
+		let mut i = 0;
+		while i < raw.len() {
+			if raw[i] == b'#' && i + 2 < raw.len() {
+				if let (Some(hi), Some(lo)) = (hex_value(raw[i + 1]), hex_value(raw[i + 2])) {
+					name.push(hi << 4 | lo);
+					i += 3;
+					continue;
+				}
+			}
+			name.push(raw[i]);
+			i += 1;
+		}
+
+		return Token::Name(name);
+	}
+
+	fn hex_string(&mut self) -> Result<Token<'a>, PdfError> {
+		let start = self.pos;
+		self.pos += 1;
+
+		let mut bytes = Vec::new();
+		let mut high = None;
+		loop {
+			let Some(&byte) = self.data.get(self.pos) else {
+				return Err(PdfError::syntax(start, "unterminated hexadecimal string"));
+			};
+			self.pos += 1;
+
+			if byte == b'>' {
+				break;
+			}
+
+			if is_whitespace(byte) {
+				continue;
+			}
+
+			let Some(value) = hex_value(byte) else {
+				return Err(PdfError::syntax(self.pos - 1, "invalid character in hexadecimal string"));
+			};
+
+			match high.take() {
+				Some(high) => bytes.push(high << 4 | value),
+				None => high = Some(value),
+			}
+		}
+
+		// Missing final digit is assumed to be zero.
+		if let Some(high) = high {
+			bytes.push(high << 4);
+		}
+
+		return Ok(Token::HexString(bytes));
+	}
+
+	fn literal_string(&mut self) -> Result<Token<'a>, PdfError> {
+		let start = self.pos;
+		self.pos += 1;
+
+		let mut bytes = Vec::new();
+		let mut depth = 0usize;
+		loop {
+			let Some(&byte) = self.data.get(self.pos) else {
+				return Err(PdfError::syntax(start, "unterminated literal string"));
+			};
+			self.pos += 1;
+
+			match byte {
+				b'(' => {
+					depth += 1;
+					bytes.push(byte);
+				},
+				b')' if depth == 0 => break,
+				b')' => {
+					depth -= 1;
+					bytes.push(byte);
+				},
+				b'\\' => self.escape(&mut bytes),
+				// All end-of-line markers within strings are treated as a single
+				// line feed.
+				b'\r' => {
+					if self.data.get(self.pos) == Some(&b'\n') {
+						self.pos += 1;
+					}
+					bytes.push(b'\n');
+				},
+				_ => bytes.push(byte),
+			}
+		}
+
+		return Ok(Token::String(bytes));
+	}
+
+	fn escape(&mut self, bytes: &mut Vec<u8>) {
+		let Some(&byte) = self.data.get(self.pos) else {
+			return;
+		};
+		self.pos += 1;
+
+		match byte {
+			b'n' => bytes.push(b'\n'),
+			b'r' => bytes.push(b'\r'),
+			b't' => bytes.push(b'\t'),
+			b'b' => bytes.push(0x08),
+			b'f' => bytes.push(0x0c),
+			b'0'..=b'7' => {
+				let mut value = (byte - b'0') as u32;
+				for _ in 0..2 {
+					match self.data.get(self.pos) {
+						Some(&digit @ b'0'..=b'7') => {
+							value = value * 8 + (digit - b'0') as u32;
+							self.pos += 1;
+						},
+						_ => break,
+					}
+				}
+				// High-order overflow is ignored.
+				bytes.push(value as u8);
+			},
+			// Backslash at the end of a line continues the string on the next line.
+			b'\r' => {
+				if self.data.get(self.pos) == Some(&b'\n') {
+					self.pos += 1;
+				}
+			},
+			b'\n' => {},
+			// Backslashes before any other character are ignored.
+			_ => bytes.push(byte),
+		}
+	}
+}
+
+#[inline]
+fn hex_value(byte: u8) -> Option<u8> {
+	return match byte {
+		b'0'..=b'9' => Some(byte - b'0'),
+		b'a'..=b'f' => Some(byte - b'a' + 10),
+		b'A'..=b'F' => Some(byte - b'A' + 10),
+		_ => None,
+	};
+}
+
+/// Parses the sequence of regular characters as a number, if it is one.
+fn parse_number(word: &[u8]) -> Option<Token<'static>> {
+	let first = *word.first()?;
+	if !first.is_ascii_digit() && !matches!(first, b'+' | b'-' | b'.') {
+		return None;
+	}
+
+	let text = std::str::from_utf8(word).ok()?;
+	if !word.contains(&b'.') {
+		return text.parse().ok().map(Token::Integer);
+	}
+
+	// Rust accepts exponents and special values, which are not valid numbers
+	// in the PDF syntax.
+	if !word.iter().all(|&b| b.is_ascii_digit() || matches!(b, b'+' | b'-' | b'.')) {
+		return None;
+	}
+
+	return text.parse().ok().map(Token::Real);
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::lexer::*;
+
+	fn tokens(data: &[u8]) -> Vec<Token<'_>> {
+		let mut lexer = Lexer::new(data, 0);
+		let mut tokens = Vec::new();
+		while let Some(token) = lexer.next().expect("failed to tokenise") {
+			tokens.push(token);
+		}
+		return tokens;
+	}
+
+	#[test]
+	fn basic_tokens() {
+		let data = b"<< /Type /Page /Kids [3 0 R] >> % comment\n(He\\(llo\\)\\041) <48 65 6C6> -.5 +17 4. Tj";
+		assert_eq!(tokens(data), vec![
+			Token::DictStart,
+			Token::Name(b"Type".to_vec()),
+			Token::Name(b"Page".to_vec()),
+			Token::Name(b"Kids".to_vec()),
+			Token::ArrayStart,
+			Token::Integer(3),
+			Token::Integer(0),
+			Token::Keyword(b"R"),
+			Token::ArrayEnd,
+			Token::DictEnd,
+			Token::String(b"He(llo)!".to_vec()),
+			Token::HexString(b"Hel`".to_vec()),
+			Token::Real(-0.5),
+			Token::Integer(17),
+			Token::Real(4.0),
+			Token::Keyword(b"Tj"),
+		]);
+	}
+
+	#[test]
+	fn name_escapes() {
+		assert_eq!(tokens(b"/A#20B#2f"), vec![Token::Name(b"A B/".to_vec())]);
+	}
+
+	#[test]
+	fn line_endings() {
+		let data = b"1\r2\n3\r\n%comment\r\n4 \r\n5";
+		let mut lexer = Lexer::new(data, 0);
+		let mut positions = Vec::new();
+		while let Some(token) = lexer.next().unwrap() {
+			positions.push((token, lexer.pos()));
+		}
+		assert_eq!(positions, vec![
+			(Token::Integer(1), 1),
+			(Token::Integer(2), 3),
+			(Token::Integer(3), 5),
+			(Token::Integer(4), 18),
+			(Token::Integer(5), 22),
+		]);
+		assert_eq!(tokens(b"(a\r\nb\rc\nd)"), vec![Token::String(b"a\nb\nc\nd".to_vec())]);
+	}
+
+	#[test]
+	fn lane_widths_agree() {
+		let mut data = Vec::new();
+		for i in 0..300u32 {
+			let byte = match i * 7919 % 23 {
+				0..=5 => b' ',
+				6 => b'\n',
+				7 => b'\r',
+				8 => b'/',
+				9 => b'(',
+				10 => b']',
+				11 => 0,
+				_ => b'a' + (i % 26) as u8,
+			};
+			data.push(byte);
+			// Long runs of the same class make the wider paths take effect.
+			if i % 50 == 0 {
+				data.extend_from_slice(&[if i % 100 == 0 { b' ' } else { b'x' }; 40]);
+			}
+		}
+
+		for pos in 0..=data.len() {
+			let whitespace = Lanes::Scalar.skip_whitespace(&data, pos);
+			let regular = Lanes::Scalar.scan_regular(&data, pos);
+			for lanes in [Lanes::Sixteen, Lanes::ThirtyTwo] {
+				assert_eq!(lanes.skip_whitespace(&data, pos), whitespace, "{:?} at {}", lanes, pos);
+				assert_eq!(lanes.scan_regular(&data, pos), regular, "{:?} at {}", lanes, pos);
+			}
+		}
+		assert_eq!(skip_whitespace(&data, 0), Lanes::Scalar.skip_whitespace(&data, 0));
+	}
+
+	#[test]
+	fn long_runs_cross_lanes() {
+		let mut data = vec![b' '; 37];
+		data.extend_from_slice(b"averyveryverylongkeywordthatspanslanes/x");
+		assert_eq!(tokens(&data), vec![
+			Token::Keyword(b"averyveryverylongkeywordthatspanslanes"),
+			Token::Name(b"x".to_vec()),
+		]);
+	}
+}
@@ -1,6 +1,80 @@
 //! SIMD-accelerated PDF parser, reader, and writer.
 
-#![feature(try_blocks)]
+#![feature(portable_simd)]
+#![allow(clippy::needless_return)]
+
+mod acroform;
+mod annotation;
+mod article;
+mod collection;
+mod color_space;
+mod content;
+mod date;
+mod destination;
+mod document;
+mod embedded_file;
+mod error;
+pub mod filters;
+mod font;
+mod form;
+mod function;
+mod graphics_state;
+mod image;
+mod info;
+mod incremental;
+mod javascript;
+mod lexer;
+mod names;
+mod object;
+mod object_stream;
+mod optional_content;
+mod output_intent;
+mod pages;
+mod parallel;
+mod pdfa;
+mod parser;
+mod raster;
+mod reader;
+mod redact;
+mod resources;
+mod streaming;
+mod structure;
+mod string;
+mod text;
+mod transition;
+mod usage_rights;
+mod viewer;
+
+pub use acroform::{parse_default_appearance, DefaultAppearance, FormField};
+pub use annotation::{appearance_matrix, Annotation, fit_appearance, AnnotationFlags, LineEnding, MarkupKind, Quad, TextIcon};
+pub use collection::CollectionView;
+pub use color_space::CsRef;
+pub use content::{parse_operations, write_operations, ContentStream, Operation, PropertyList, StateGuard, WritingMode};
+pub use date::PdfDate;
+pub use document::{CompressionPolicy, Document, FontRef, Page};
+pub use error::PdfError;
+pub use font::{FontFileKind, PathSegment, TrueTypeFont};
+pub use form::XObjectRef;
+pub use function::{Function, FunctionKind};
+pub use graphics_state::{BlendMode, ExtGState, SoftMaskSubtype};
+pub use image::{ExtractedImage, Image, ImageFormat};
+pub use info::DocumentInfo;
+pub use names::{NameTree, NumberTree};
+pub use object::{Color, Dictionary, Matrix, ObjectId, PdfObject, Point, Rectangle, Stream, StringFormat, WriteStyle};
+pub use optional_content::{LayerRef, OcmdRef, VisibilityPolicy};
+pub use output_intent::OutputIntentSubtype;
+pub use pages::extract_pages;
+pub use pdfa::PdfAVersion;
+pub use raster::Bitmap;
+pub use reader::{Diagnostic, ParseMode, Reader, ReaderPage};
+pub use resources::ResourceManager;
+pub use streaming::StreamingDocument;
+pub use structure::StructRef;
+pub use string::PdfString;
+pub use text::PositionedText;
+pub use transition::TransitionStyle;
+pub use usage_rights::UsageRights;
+pub use viewer::{PageLayout, PageMode};
 
 // For the reference used in this project, see:
 // https://www.adobe.com/content/dam/acom/en/devnet/pdf/pdfs/pdf_reference_1-7.pdf
@@ -40,7 +114,7 @@ impl<'a> Writer<'a> {
 
 impl Writer<'_> {
 	/// Attempts to write the entire buffer into the underlying stream.
-	#[must_use]
+	#[must_use = "the buffer may not have been written, and the offset not advanced"]
 	pub fn write(&mut self, buf: &[u8]) -> std::io::Result<()> {
 		self.stream.write_all(buf)?;
 		// Only advance the offset if we wrote everything successfully.
@@ -48,6 +122,28 @@ impl Writer<'_> {
 		return Ok(());
 	}
 
+	/// Attempts to write the byte the given number of times, e.g. for padding,
+	/// without allocating a buffer for all of them.
+	pub fn write_repeated(&mut self, byte: u8, count: usize) -> std::io::Result<()> {
+		let chunk = [byte; 512];
+		let mut left = count;
+		while left > 0 {
+			let len = left.min(chunk.len());
+			self.write(&chunk[..len])?;
+			left -= len;
+		}
+		return Ok(());
+	}
+
+	/// Attempts to write the formatted text, so that the writer can be used
+	/// with the `write!` macros, keeping track of the position.
+	pub fn write_fmt(&mut self, args: std::fmt::Arguments<'_>) -> std::io::Result<()> {
+		return match args.as_str() {
+			Some(text) => self.write(text.as_bytes()),
+			None => self.write(std::fmt::format(args).as_bytes()),
+		};
+	}
+
 	/// Gets the current position in the stream.
 	#[inline]
 	pub fn pos(&self) -> usize {
@@ -55,122 +151,58 @@ impl Writer<'_> {
 	}
 }
 
-pub struct Document;
-
-impl Document {
-	/// Attempts to write the entire document using the provided [`Writer`].
-	pub fn write(&mut self, writer: &mut Writer<'_>) -> std::io::Result<()> {
-		const NEWLINE: u8 = 0x0a;
-		const PERCENT: u8 = 0x25;
-
-		// Write PDF header.
-		writer.write(b"%PDF-1.7\n")?;
-		// Mark the file as containing binary data, because we want to be able to
-		// embed fonts and images.
-		writer.write(&[PERCENT, 0x80, 0x81, 0x82, 0x83, NEWLINE])?;
-
-		let catalog_pos = writer.pos();
-		writer.write(b"1 0 obj\n")?;
-		writer.write(b"<<\n")?;
-		writer.write(b"/Type /Catalog\n")?;
-		writer.write(b"/Pages 2 0 R\n")?;
-		writer.write(b">>\n")?;
-		writer.write(b"endobj\n")?;
-
-		let page_tree_pos = writer.pos();
-		writer.write(b"2 0 obj\n")?;
-		writer.write(b"<<\n")?;
-		writer.write(b"/Type /Pages\n")?;
-		writer.write(b"/Kids [3 0 R]\n")?;
-		writer.write(b"/Count 1\n")?;
-		writer.write(b">>\n")?;
-		writer.write(b"endobj\n")?;
-
-		let page1_pos = writer.pos();
-		writer.write(b"3 0 obj\n")?;
-		writer.write(b"<<\n")?;
-		writer.write(b"/Type /Page\n")?;
-		writer.write(b"/Parent 2 0 R\n")?;
-		writer.write(b"/MediaBox [0.0 0.0 612.0 792.0]\n")?;
-		writer.write(b"/Contents 4 0 R\n")?;
-		writer.write(b"/Resources << /Font << /F13 5 0 R >> >>\n")?;
-		writer.write(b">>\n")?;
-		writer.write(b"endobj\n")?;
-
-		// Small overview of the text rendering facilities in PDF 1.7:
-		//   Each rendered text needs to be a stream object. The stream starts with
-		// command "BT", which stands for "Begin Text", and ends with command "ET",
-		// for "End Text". Between these you select font and size using "Tf" command,
-		// which accepts font name ("/F13") and size in points/units. Text position
-		// is provided using "Td" command, which accepts offset from the "0,0" point
-		// (lower left corner) in units. "Tj" command draws the text provided as a
-		// string using the currently set options (colour, size, font, position.)
-
-		let page1_contents_pos = writer.pos();
-		writer.write(b"4 0 obj\n")?;
-		writer.write(b"<<\n")?;
-		// TODO: How can we get that length programatically?
-		writer.write(b"/Length 39\n")?;
-		writer.write(b">>\n")?;
-		writer.write(b"stream\n")?;
-		writer.write(b"BT\n")?;
-		writer.write(b"/F13 10 Tf\n")?;
-		writer.write(b"12 775 Td\n")?;
-		writer.write(b"(Hello!) Tj\n")?;
-		writer.write(b"ET\n")?;
-		writer.write(b"endstream\n")?;
-		writer.write(b"endobj\n")?;
-
-		// TODO: We want to use our own fonts instead of the built-in ones... That
-		// would probably require compressing them using DEFLATE, which is going to
-		// be a pain in Rust, since I don't want to pull in entire huge dependency
-		// just for that... I would rather implement it myself.
-		let font_pos = writer.pos();
-		writer.write(b"5 0 obj\n")?;
-		writer.write(b"<<\n")?;
-		writer.write(b"/Type /Font\n")?;
-		writer.write(b"/Subtype /Type1\n")?;
-		writer.write(b"/Name /F13\n")?;
-		writer.write(b"/BaseFont /Times-Roman\n")?;
-		writer.write(b"/Encoding /MacRomanEncoding\n")?;
-		writer.write(b">>\n")?;
-		writer.write(b"endobj\n")?;
-
-		let xref_pos = writer.pos();
-		writer.write(b"xref\n")?;
-		writer.write(b"0 6\n")?;
-		std::write!(writer.stream, "0000000000 65535 f\r\n")?;
-		std::write!(writer.stream, "{:0>10} 00000 n\r\n", catalog_pos)?;
-		std::write!(writer.stream, "{:0>10} 00000 n\r\n", page_tree_pos)?;
-		std::write!(writer.stream, "{:0>10} 00000 n\r\n", page1_pos)?;
-		std::write!(writer.stream, "{:0>10} 00000 n\r\n", page1_contents_pos)?;
-		std::write!(writer.stream, "{:0>10} 00000 n\r\n", font_pos)?;
-
-		// Write the document trailer.
-		writer.write(b"trailer\n")?;
-		writer.write(b"<<\n")?;
-		writer.write(b"/Size 6\n")?;
-		writer.write(b"/Root 1 0 R\n")?;
-		writer.write(b">>\n")?;
-
-		writer.write(b"startxref\n")?;
-		std::write!(writer.stream, "{}\n", xref_pos)?;
-		writer.write(b"%%EOF\n")?;
-
-		return Ok(());
-	}
-}
-
 #[cfg(test)]
 mod tests {
 	use crate::*;
 
 	#[test]
 	fn it_works() {
-		let mut file = std::fs::File::create("sample.pdf").expect("failed to create a file");
-		let mut writer = Writer::new(&mut file);
+		let mut output = Vec::new();
+		let mut writer = Writer::new(&mut output);
+
+		let mut doc = Document::new();
+		let font = doc.add_base_font("Times-Roman");
+		let mut page = doc.add_page(612.0, 792.0);
+		page.add_font_resource("F13", font);
+		let mut content = ContentStream::new();
+		content.begin_text().set_font("F13", 10.0).move_text(12.0, 775.0).show_text(b"Hello!").end_text();
+		page.set_contents(content);
+
+		doc.write(&mut writer).expect("failed to write document");
+		assert!(output.starts_with(b"%PDF-1.7\n"));
+		assert!(output.ends_with(b"%%EOF\n"));
+	}
 
-		let mut doc = Document;
-		doc.write(&mut writer).expect("failed to write document to file");
+	#[test]
+	fn formatted_positions() {
+		let mut doc = Document::new();
+		doc.add_page(612.0, 792.0);
+		doc.set_info(&DocumentInfo {
+			title: Some("Positions".to_string()),
+			..DocumentInfo::default()
+		});
+
+		let mut output = Vec::new();
+		let mut writer = Writer::new(&mut output);
+		doc.write(&mut writer).unwrap();
+		std::writeln!(writer, "% {} bytes", 42).unwrap();
+		let pos = writer.pos();
+		assert_eq!(pos, output.len());
+		assert!(output.ends_with(b"%%EOF\n% 42 bytes\n"));
+	}
+
+	#[test]
+	fn repeated_bytes() {
+		let mut output = Vec::new();
+		let mut writer = Writer::new(&mut output);
+		writer.write(b"<").unwrap();
+		writer.write_repeated(0, 100000).unwrap();
+		writer.write_repeated(b' ', 0).unwrap();
+		writer.write(b">").unwrap();
+		assert_eq!(writer.pos(), 100002);
+		let mut naive = b"<".to_vec();
+		naive.extend_from_slice(&std::vec![0; 100000]);
+		naive.push(b'>');
+		assert_eq!(output, naive);
 	}
 }
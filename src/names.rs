@@ -0,0 +1,362 @@
+//! Name trees of the `/Names` dictionary of the catalog, which map strings
+//! to objects, e.g. named destinations or document-level scripts.
+
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+
+use crate::{Dictionary, Document, ObjectId, PdfError, PdfObject, Reader};
+
+/// Calls the closure with the dictionary under the key, which may be an
+/// indirect object, creating an empty one if it's missing. The objects are
+/// passed along, so that the dictionaries nested in it can be reached too.
+pub(crate) fn with_entry_dict<R>(objects: &mut BTreeMap<ObjectId, PdfObject>, dict: &mut Dictionary, key: &str, f: impl FnOnce(&mut BTreeMap<ObjectId, PdfObject>, &mut Dictionary) -> R) -> R {
+	if let Some(id) = dict.get(key).and_then(PdfObject::as_reference) {
+		if let Some(PdfObject::Dictionary(mut entry)) = objects.remove(&id) {
+			let result = f(objects, &mut entry);
+			objects.insert(id, PdfObject::Dictionary(entry));
+			return result;
+		}
+	}
+	if !dict.get(key).is_some_and(|entry| entry.as_dict().is_some()) {
+		dict.set(key, Dictionary::new());
+	}
+	return f(objects, dict.get_mut(key).and_then(PdfObject::as_dict_mut).expect("entry is not a dictionary"));
+}
+
+/// Largest number of entries of a leaf, and of kids of an intermediate node,
+/// in the trees built here.
+const MAX_NODE_SIZE: usize = 32;
+
+/// Builder of a name tree, which maps strings to objects, keeping the keys
+/// sorted. Large trees are split into balanced nodes.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct NameTree {
+	pub(crate) entries: BTreeMap<Vec<u8>, PdfObject>,
+}
+
+impl NameTree {
+	/// Creates new empty name tree.
+	#[inline]
+	pub fn new() -> Self {
+		return Self::default();
+	}
+
+	/// Sets the value of the key, returning the previous one.
+	pub fn insert(&mut self, key: &[u8], value: impl Into<PdfObject>) -> Option<PdfObject> {
+		return self.entries.insert(key.to_vec(), value.into());
+	}
+
+	/// Gets the value of the key.
+	pub fn get(&self, key: &[u8]) -> Option<&PdfObject> {
+		return self.entries.get(key);
+	}
+
+	#[inline]
+	pub fn len(&self) -> usize {
+		return self.entries.len();
+	}
+
+	#[inline]
+	pub fn is_empty(&self) -> bool {
+		return self.entries.is_empty();
+	}
+
+	/// Adds the nodes of the tree to the document, returning the root.
+	pub fn write_to(&self, doc: &mut Document) -> ObjectId {
+		let root = build_tree(doc, "Names", self.entries(), &mut std::iter::empty(), &mut Vec::new());
+		return doc.add_object(root);
+	}
+
+	fn entries(&self) -> Vec<(PdfObject, PdfObject)> {
+		return self.entries.iter().map(|(key, value)| (PdfObject::string(key.clone()), value.clone())).collect();
+	}
+}
+
+/// Builder of a number tree, which maps integers to objects, e.g. the page
+/// labels, keeping the keys sorted. Large trees are split into balanced nodes.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct NumberTree {
+	entries: BTreeMap<i64, PdfObject>,
+}
+
+impl NumberTree {
+	/// Creates new empty number tree.
+	#[inline]
+	pub fn new() -> Self {
+		return Self::default();
+	}
+
+	/// Sets the value of the key, returning the previous one.
+	pub fn insert(&mut self, key: i64, value: impl Into<PdfObject>) -> Option<PdfObject> {
+		return self.entries.insert(key, value.into());
+	}
+
+	/// Gets the value of the key.
+	pub fn get(&self, key: i64) -> Option<&PdfObject> {
+		return self.entries.get(&key);
+	}
+
+	#[inline]
+	pub fn len(&self) -> usize {
+		return self.entries.len();
+	}
+
+	#[inline]
+	pub fn is_empty(&self) -> bool {
+		return self.entries.is_empty();
+	}
+
+	/// Adds the nodes of the tree to the document, returning the root.
+	pub fn write_to(&self, doc: &mut Document) -> ObjectId {
+		let entries = self.entries.iter().map(|(&key, value)| (PdfObject::Integer(key), value.clone())).collect();
+		let root = build_tree(doc, "Nums", entries, &mut std::iter::empty(), &mut Vec::new());
+		return doc.add_object(root);
+	}
+}
+
+/// Splits the items into as few chunks of at most [`MAX_NODE_SIZE`] items as
+/// possible, which differ in length by one at most.
+fn even_chunks<T>(items: &[T]) -> std::slice::Chunks<'_, T> {
+	let count = items.len().div_ceil(MAX_NODE_SIZE).max(1);
+	return items.chunks(items.len().div_ceil(count).max(1));
+}
+
+/// Adds the nodes of the tree with the sorted entries to the document, with
+/// the entries under the given key of the leaves, i.e. `/Names` or `/Nums`.
+/// The given identifiers are used first, and all the used ones are collected.
+/// Returns the root, which is left to the caller to add, so that it can be a
+/// direct object.
+fn build_tree(doc: &mut Document, key: &str, entries: Vec<(PdfObject, PdfObject)>, free: &mut impl Iterator<Item = ObjectId>, used: &mut Vec<ObjectId>) -> Dictionary {
+	let mut add_node = |doc: &mut Document, node: Dictionary| -> ObjectId {
+		let id = free.next().unwrap_or_else(|| doc.reserve_id());
+		doc.objects.insert(id, PdfObject::Dictionary(node));
+		used.push(id);
+		return id;
+	};
+	let flatten = |entries: &[(PdfObject, PdfObject)]| entries.iter().flat_map(|(key, value)| [key.clone(), value.clone()]).collect::<Vec<_>>();
+
+	if entries.len() <= MAX_NODE_SIZE {
+		let mut root = Dictionary::new();
+		root.set(key, flatten(&entries));
+		return root;
+	}
+
+	// Every node but the root has the limits of the keys under it, so that
+	// the lookups can skip it.
+	let mut level: Vec<(ObjectId, PdfObject, PdfObject)> = Vec::new();
+	for leaf in even_chunks(&entries) {
+		let mut node = Dictionary::new();
+		node.set(key, flatten(leaf));
+		let limits = (leaf[0].0.clone(), leaf[leaf.len() - 1].0.clone());
+		node.set("Limits", std::vec![limits.0.clone(), limits.1.clone()]);
+		level.push((add_node(doc, node), limits.0, limits.1));
+	}
+	while level.len() > MAX_NODE_SIZE {
+		let mut parents = Vec::new();
+		for kids in even_chunks(&level) {
+			let mut node = Dictionary::new();
+			node.set("Kids", kids.iter().map(|(id, _, _)| PdfObject::Reference(*id)).collect::<Vec<_>>());
+			let limits = (kids[0].1.clone(), kids[kids.len() - 1].2.clone());
+			node.set("Limits", std::vec![limits.0.clone(), limits.1.clone()]);
+			parents.push((add_node(doc, node), limits.0, limits.1));
+		}
+		level = parents;
+	}
+
+	let mut root = Dictionary::new();
+	root.set("Kids", level.iter().map(|(id, _, _)| PdfObject::Reference(*id)).collect::<Vec<_>>());
+	return root;
+}
+
+impl Document {
+	/// Attempts to set the value of the key in the name tree under the given
+	/// entry of the `/Names` dictionary, e.g. `/Dests`. The tree is built
+	/// when the document is written, starting from the entries of the one the
+	/// document already has.
+	pub fn insert_into_name_tree(&mut self, tree: &str, key: &[u8], value: PdfObject) -> Result<(), PdfError> {
+		if !self.name_trees.contains_key(tree) {
+			let existing = self.existing_name_tree(tree)?;
+			self.name_trees.insert(tree.to_string(), existing);
+		}
+		self.name_trees.get_mut(tree).expect("name tree is missing").insert(key, value);
+		return Ok(());
+	}
+
+	/// Attempts to collect the entries of the name tree under the given entry
+	/// of the `/Names` dictionary, if the document has it, keeping the
+	/// identifiers of its nodes to be used again.
+	fn existing_name_tree(&mut self, tree: &str) -> Result<NameTree, PdfError> {
+		let names = match self.catalog.get("Names") {
+			Some(PdfObject::Reference(id)) => self.objects.get(id).and_then(PdfObject::as_dict),
+			names => names.and_then(PdfObject::as_dict),
+		};
+		let mut entries = NameTree::new();
+		let mut pending: Vec<PdfObject> = names.and_then(|names| names.get(tree)).cloned().into_iter().collect();
+		while let Some(node) = pending.pop() {
+			let node = match node {
+				PdfObject::Reference(id) => {
+					if self.name_tree_nodes.contains(&id) {
+						return Err(PdfError::InvalidArgument("name tree contains a loop".to_string()));
+					}
+					self.name_tree_nodes.push(id);
+					self.objects.get(&id).cloned().unwrap_or(PdfObject::Null)
+				},
+				node => node,
+			};
+			let Some(node) = node.as_dict() else {
+				return Err(PdfError::InvalidArgument("name tree node is not a dictionary".to_string()));
+			};
+			for pair in node.get("Names").and_then(PdfObject::as_array).map_or(&[][..], Vec::as_slice).chunks_exact(2) {
+				if let Some(key) = pair[0].as_string() {
+					entries.insert(key, pair[1].clone());
+				}
+			}
+			pending.extend(node.get("Kids").and_then(PdfObject::as_array).into_iter().flatten().cloned());
+		}
+		return Ok(entries);
+	}
+
+	/// Adds the nodes of the name trees to the document, replacing the ones
+	/// added before, and sets their roots in the `/Names` dictionary, as
+	/// direct objects.
+	pub(crate) fn build_name_trees(&mut self) {
+		let mut free = std::mem::take(&mut self.name_tree_nodes).into_iter();
+		for id in free.as_slice() {
+			self.objects.remove(id);
+		}
+
+		let trees = std::mem::take(&mut self.name_trees);
+		let mut used = Vec::new();
+		for (name, tree) in &trees {
+			let root = build_tree(self, "Names", tree.entries(), &mut free, &mut used);
+			with_entry_dict(&mut self.objects, &mut self.catalog, "Names", |_, names| names.set(name, root));
+		}
+		self.name_trees = trees;
+		self.name_tree_nodes = used;
+	}
+}
+
+impl Reader<'_> {
+	/// Attempts to find the value of the key in the name tree under the given
+	/// entry of the `/Names` dictionary, e.g. `/Dests`.
+	pub fn name_tree_value(&self, tree: &str, key: &[u8]) -> Result<Option<PdfObject>, PdfError> {
+		let Some(names) = self.catalog()?.get("Names").cloned() else {
+			return Ok(None);
+		};
+		let Some(root) = self.resolve_object(&names)?.as_dict().and_then(|names| names.get(tree)).cloned() else {
+			return Ok(None);
+		};
+		return self.find_in_tree(self.resolve_object(&root)?, "Names", |entry| entry.as_string().map(|entry| entry.cmp(key)));
+	}
+
+	/// Attempts to find the value of the key in the number tree with the
+	/// given root node, which can be a reference, e.g. the `/PageLabels` of
+	/// the catalog.
+	pub fn number_tree_value(&self, root: &PdfObject, key: i64) -> Result<Option<PdfObject>, PdfError> {
+		return self.find_in_tree(self.resolve_object(root)?, "Nums", |entry| entry.as_integer().map(|entry| entry.cmp(&key)));
+	}
+
+	/// Attempts to find the value in the tree with the given root node, whose
+	/// leaves have the entries under the given key, i.e. `/Names` or `/Nums`.
+	/// The keys are compared to the one searched for with the closure, which
+	/// gives `None` for the keys of another type.
+	fn find_in_tree(&self, root: PdfObject, leaves: &str, compare: impl Fn(&PdfObject) -> Option<Ordering>) -> Result<Option<PdfObject>, PdfError> {
+		let mut visited = Vec::new();
+		let mut pending = std::vec![root];
+		while let Some(node) = pending.pop() {
+			let Some(node) = node.as_dict() else {
+				return Err(PdfError::syntax(0, "tree node is not a dictionary"));
+			};
+			// Limits of the intermediate nodes and leaves let us skip the
+			// subtrees which cannot contain the key.
+			if let Some([low, high]) = node.get("Limits").and_then(PdfObject::as_array).map(Vec::as_slice) {
+				if compare(low) == Some(Ordering::Greater) || compare(high) == Some(Ordering::Less) {
+					continue;
+				}
+			}
+
+			for pair in node.get(leaves).and_then(PdfObject::as_array).map_or(&[][..], Vec::as_slice).chunks_exact(2) {
+				if compare(&pair[0]) == Some(Ordering::Equal) {
+					return Ok(Some(pair[1].clone()));
+				}
+			}
+			for kid in node.get("Kids").and_then(PdfObject::as_array).into_iter().flatten() {
+				let Some(kid) = kid.as_reference() else {
+					return Err(PdfError::syntax(0, "tree kid is not a reference"));
+				};
+				if visited.contains(&kid) {
+					return Err(PdfError::syntax(0, "tree contains a loop"));
+				}
+				visited.push(kid);
+				pending.push(self.resolve(kid)?);
+			}
+		}
+		return Ok(None);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::*;
+
+	#[test]
+	fn balanced_trees() {
+		let mut doc = Document::new();
+		doc.add_page(612.0, 792.0);
+		for index in 0..100 {
+			doc.insert_into_name_tree("Dests", std::format!("key{:03}", index).as_bytes(), PdfObject::Integer(index)).unwrap();
+		}
+		let mut output = Vec::new();
+		doc.write(&mut Writer::new(&mut output)).unwrap();
+		let objects = doc.objects.len();
+		// Writing again uses the same nodes.
+		doc.write(&mut Writer::new(&mut Vec::new())).unwrap();
+		assert_eq!(doc.objects.len(), objects);
+
+		let reader = Reader::new(&output).unwrap();
+		for index in 0..100 {
+			assert_eq!(reader.name_tree_value("Dests", std::format!("key{:03}", index).as_bytes()).unwrap(), Some(PdfObject::Integer(index)));
+		}
+		assert_eq!(reader.name_tree_value("Dests", b"key100").unwrap(), None);
+
+		let names = reader.catalog().unwrap().get("Names").and_then(PdfObject::as_dict).unwrap().clone();
+		let root = names.get("Dests").and_then(PdfObject::as_dict).unwrap();
+		assert!(!root.contains_key("Limits"));
+		let kids = root.get("Kids").and_then(PdfObject::as_array).unwrap();
+		assert_eq!(kids.len(), 4);
+		for (index, kid) in kids.iter().enumerate() {
+			let leaf = reader.resolve_dict(kid.as_reference().unwrap()).unwrap();
+			assert_eq!(leaf.get("Names").and_then(PdfObject::as_array).map(Vec::len), Some(50));
+			let limits = [index * 25, index * 25 + 24].map(|key| PdfObject::string(std::format!("key{:03}", key)));
+			assert_eq!(leaf.get("Limits"), Some(&PdfObject::Array(limits.to_vec())));
+		}
+
+		// Loaded trees are extended with the new entries.
+		let mut doc = Document::load(&output).unwrap();
+		doc.insert_into_name_tree("Dests", b"extra", PdfObject::Integer(100)).unwrap();
+		let mut output = Vec::new();
+		doc.write(&mut Writer::new(&mut output)).unwrap();
+		let reader = Reader::new(&output).unwrap();
+		assert_eq!(reader.name_tree_value("Dests", b"key050").unwrap(), Some(PdfObject::Integer(50)));
+		assert_eq!(reader.name_tree_value("Dests", b"extra").unwrap(), Some(PdfObject::Integer(100)));
+
+		let mut numbers = NumberTree::new();
+		for key in 0..40 {
+			numbers.insert(key * 2, PdfObject::Integer(key));
+		}
+		let root = numbers.write_to(&mut doc);
+		let kids = doc.object(root).and_then(PdfObject::as_dict).and_then(|root| root.get("Kids")).and_then(PdfObject::as_array).unwrap().clone();
+		let limits: Vec<_> = kids.iter().map(|kid| doc.object(kid.as_reference().unwrap()).and_then(PdfObject::as_dict).and_then(|leaf| leaf.get("Limits")).unwrap().clone()).collect();
+		assert_eq!(limits, [PdfObject::Array(std::vec![0.into(), 38.into()]), PdfObject::Array(std::vec![40.into(), 78.into()])]);
+
+		let mut output = Vec::new();
+		doc.write(&mut Writer::new(&mut output)).unwrap();
+		let reader = Reader::new(&output).unwrap();
+		let root = PdfObject::Reference(root);
+		for key in 0..40 {
+			assert_eq!(reader.number_tree_value(&root, key * 2).unwrap(), Some(PdfObject::Integer(key)));
+			assert_eq!(reader.number_tree_value(&root, key * 2 + 1).unwrap(), None);
+		}
+		assert_eq!(reader.number_tree_value(&root, -1).unwrap(), None);
+	}
+}
@@ -0,0 +1,836 @@
+//! Basic PDF object model, and its serialisation into the file syntax.
+
+use crate::lexer::is_regular;
+use crate::{PdfString, Writer};
+
+/// Identifier of an indirect object, i.e. its object number and generation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ObjectId {
+	pub number: u32,
+	pub generation: u16,
+}
+
+impl ObjectId {
+	/// Creates new object identifier with the given object number and generation.
+	#[inline]
+	pub const fn new(number: u32, generation: u16) -> Self {
+		return Self {
+			number,
+			generation,
+		};
+	}
+}
+
+/// How a string object should be written out.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StringFormat {
+	/// Literal string, enclosed in parentheses, e.g. `(Hello!)`.
+	Literal,
+	/// Hexadecimal string, enclosed in angle brackets, e.g. `<48656C6C6F21>`.
+	Hex,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum PdfObject {
+	Null,
+	Boolean(bool),
+	Integer(i64),
+	Real(f64),
+	String(Vec<u8>, StringFormat),
+	Name(Vec<u8>),
+	Array(Vec<PdfObject>),
+	Dictionary(Dictionary),
+	Stream(Stream),
+	Reference(ObjectId),
+}
+
+impl PdfObject {
+	/// Creates new name object from the given string, without the leading slash.
+	#[inline]
+	pub fn name(name: &str) -> Self {
+		return Self::Name(name.as_bytes().to_vec());
+	}
+
+	/// Creates new literal string object.
+	#[inline]
+	pub fn string(bytes: impl Into<Vec<u8>>) -> Self {
+		return Self::String(bytes.into(), StringFormat::Literal);
+	}
+
+	/// Creates new hexadecimal string object.
+	#[inline]
+	pub fn hex_string(bytes: impl Into<Vec<u8>>) -> Self {
+		return Self::String(bytes.into(), StringFormat::Hex);
+	}
+
+	/// Creates new text string, which is written in PDFDocEncoding if possible,
+	/// and as UTF-16BE prefixed with a byte order mark otherwise.
+	pub fn text_string(text: &str) -> Self {
+		return PdfString::new(text).into();
+	}
+
+	/// Returns the contained dictionary, or the dictionary of a stream.
+	pub fn as_dict(&self) -> Option<&Dictionary> {
+		return match self {
+			Self::Dictionary(dict) => Some(dict),
+			Self::Stream(stream) => Some(&stream.dict),
+			_ => None,
+		};
+	}
+
+	/// Returns the contained dictionary, or the dictionary of a stream.
+	pub fn as_dict_mut(&mut self) -> Option<&mut Dictionary> {
+		return match self {
+			Self::Dictionary(dict) => Some(dict),
+			Self::Stream(stream) => Some(&mut stream.dict),
+			_ => None,
+		};
+	}
+
+	#[inline]
+	pub fn as_array(&self) -> Option<&Vec<PdfObject>> {
+		return match self {
+			Self::Array(array) => Some(array),
+			_ => None,
+		};
+	}
+
+	#[inline]
+	pub fn as_name(&self) -> Option<&[u8]> {
+		return match self {
+			Self::Name(name) => Some(name),
+			_ => None,
+		};
+	}
+
+	/// Returns the value of an integer or real number object.
+	#[inline]
+	pub fn as_number(&self) -> Option<f64> {
+		return match self {
+			Self::Integer(value) => Some(*value as f64),
+			Self::Real(value) => Some(*value),
+			_ => None,
+		};
+	}
+
+	#[inline]
+	pub fn as_integer(&self) -> Option<i64> {
+		return match self {
+			Self::Integer(value) => Some(*value),
+			_ => None,
+		};
+	}
+
+	#[inline]
+	pub fn as_string(&self) -> Option<&[u8]> {
+		return match self {
+			Self::String(bytes, _) => Some(bytes),
+			_ => None,
+		};
+	}
+
+	#[inline]
+	pub fn as_reference(&self) -> Option<ObjectId> {
+		return match self {
+			Self::Reference(id) => Some(*id),
+			_ => None,
+		};
+	}
+
+	#[inline]
+	pub fn as_stream(&self) -> Option<&Stream> {
+		return match self {
+			Self::Stream(stream) => Some(stream),
+			_ => None,
+		};
+	}
+
+	/// Calls the function for every reference within the object, including
+	/// the nested ones.
+	pub(crate) fn for_each_reference(&self, f: &mut impl FnMut(ObjectId)) {
+		match self {
+			Self::Reference(id) => f(*id),
+			Self::Array(array) => array.iter().for_each(|object| object.for_each_reference(f)),
+			Self::Dictionary(dict) => dict.iter().for_each(|(_, object)| object.for_each_reference(f)),
+			Self::Stream(stream) => stream.dict.iter().for_each(|(_, object)| object.for_each_reference(f)),
+			_ => {},
+		}
+	}
+
+	/// Calls the function for every object within the object, including the
+	/// object itself, allowing it to be replaced, e.g. to renumber references.
+	pub(crate) fn visit_mut(&mut self, f: &mut impl FnMut(&mut PdfObject)) {
+		f(self);
+		match self {
+			Self::Array(array) => array.iter_mut().for_each(|object| object.visit_mut(f)),
+			Self::Dictionary(dict) => dict.values_mut().for_each(|object| object.visit_mut(f)),
+			Self::Stream(stream) => stream.dict.values_mut().for_each(|object| object.visit_mut(f)),
+			_ => {},
+		}
+	}
+
+	/// Attempts to write the object using the PDF file syntax.
+	pub fn write_to(&self, writer: &mut Writer<'_>) -> std::io::Result<()> {
+		return self.write_nested(writer, 0);
+	}
+
+	/// Attempts to write the object with the given layout of the whitespace.
+	pub fn write_styled(&self, writer: &mut Writer<'_>, style: WriteStyle) -> std::io::Result<()> {
+		return match style {
+			WriteStyle::Default => self.write_nested(writer, 0),
+			WriteStyle::Compact => {
+				let mut buf = Vec::new();
+				self.write_compact(&mut buf);
+				writer.write(&buf)
+			},
+			WriteStyle::Pretty => self.write_pretty(writer, 0),
+		};
+	}
+
+	fn write_compact(&self, buf: &mut Vec<u8>) {
+		// Tokens starting with a regular character have to be separated from
+		// the previous one, if it ends with one too, e.g. in `/Count 3`.
+		let regular = matches!(self, Self::Null | Self::Boolean(_) | Self::Integer(_) | Self::Real(_) | Self::Reference(_));
+		if regular && buf.last().is_some_and(|&byte| is_regular(byte)) {
+			buf.push(b' ');
+		}
+		match self {
+			Self::Array(array) => {
+				buf.push(b'[');
+				for item in array {
+					item.write_compact(buf);
+				}
+				buf.push(b']');
+			},
+			Self::Dictionary(dict) => dict.write_compact(buf),
+			Self::Stream(stream) => {
+				let mut dict = stream.dict.clone();
+				dict.set("Length", stream.data.len() as i64);
+				dict.write_compact(buf);
+				buf.extend_from_slice(b"stream\n");
+				buf.extend_from_slice(&stream.data);
+				buf.extend_from_slice(b"\nendstream");
+			},
+			// Writing into a vector never fails.
+			_ => drop(self.write_nested(&mut Writer::new(buf), 1)),
+		}
+	}
+
+	fn write_pretty(&self, writer: &mut Writer<'_>, depth: usize) -> std::io::Result<()> {
+		match self {
+			Self::Array(array) if array.iter().any(|item| matches!(item, Self::Array(_) | Self::Dictionary(_))) => {
+				writer.write(b"[\n")?;
+				for item in array {
+					writer.write_repeated(b' ', (depth + 1) * 2)?;
+					item.write_pretty(writer, depth + 1)?;
+					writer.write(b"\n")?;
+				}
+				writer.write_repeated(b' ', depth * 2)?;
+				writer.write(b"]")?;
+			},
+			Self::Dictionary(dict) => dict.write_pretty(writer, depth)?,
+			Self::Stream(stream) => {
+				let mut dict = stream.dict.clone();
+				dict.set("Length", stream.data.len() as i64);
+				dict.write_pretty(writer, depth)?;
+				writer.write(b"\nstream\n")?;
+				writer.write(&stream.data)?;
+				writer.write(b"\nendstream")?;
+			},
+			_ => self.write_nested(writer, depth + 1)?,
+		}
+		return Ok(());
+	}
+
+	/// Attempts to write the object on a single line, e.g. as an operand.
+	pub(crate) fn write_inline(&self, writer: &mut Writer<'_>) -> std::io::Result<()> {
+		return self.write_nested(writer, 1);
+	}
+
+	// Top-level dictionaries have every entry on a separate line, while the
+	// nested ones are written inline, e.g. `/Resources << /Font << ... >> >>`.
+	fn write_nested(&self, writer: &mut Writer<'_>, depth: usize) -> std::io::Result<()> {
+		match self {
+			Self::Null => writer.write(b"null")?,
+			Self::Boolean(true) => writer.write(b"true")?,
+			Self::Boolean(false) => writer.write(b"false")?,
+			Self::Integer(value) => writer.write(value.to_string().as_bytes())?,
+			Self::Real(value) => writer.write(format_real(*value).as_bytes())?,
+			Self::String(bytes, StringFormat::Literal) => write_literal_string(writer, bytes)?,
+			Self::String(bytes, StringFormat::Hex) => write_hex_string(writer, bytes)?,
+			Self::Name(name) => write_name(writer, name)?,
+			Self::Array(array) => {
+				writer.write(b"[")?;
+				for (i, item) in array.iter().enumerate() {
+					if i > 0 {
+						writer.write(b" ")?;
+					}
+					item.write_nested(writer, depth + 1)?;
+				}
+				writer.write(b"]")?;
+			},
+			Self::Dictionary(dict) => dict.write_nested(writer, depth)?,
+			Self::Stream(stream) => {
+				let mut dict = stream.dict.clone();
+				dict.set("Length", stream.data.len() as i64);
+				dict.write_nested(writer, depth)?;
+				writer.write(b"\nstream\n")?;
+				writer.write(&stream.data)?;
+				writer.write(b"\nendstream")?;
+			},
+			Self::Reference(id) => {
+				writer.write(format!("{} {} R", id.number, id.generation).as_bytes())?;
+			},
+		}
+
+		return Ok(());
+	}
+}
+
+/// Formats a real number without using the scientific notation, which is not
+/// allowed by the PDF syntax.
+pub(crate) fn format_real(value: f64) -> String {
+	// Neither NaN nor infinities can be represented, so we write them as zero.
+	if !value.is_finite() || value == 0.0 {
+		return "0".to_string();
+	}
+
+	// Display implementation for floats never uses the exponent form, and
+	// writes the shortest representation that round-trips.
+	return value.to_string();
+}
+
+/// Writes the name with the leading slash. Bytes which are not regular
+/// characters, `#`, and bytes outside of the printable ASCII range are written
+/// as `#XX` escapes, so that any name is read back unchanged.
+pub(crate) fn write_name(writer: &mut Writer<'_>, name: &[u8]) -> std::io::Result<()> {
+	const HEX: &[u8; 16] = b"0123456789ABCDEF";
+
+	let needs_escape = |byte: u8| !(0x21..=0x7e).contains(&byte) || byte == b'#' || !is_regular(byte);
+	writer.write(b"/")?;
+	if !name.iter().any(|&byte| needs_escape(byte)) {
+		return writer.write(name);
+	}
+
+	let mut escaped = Vec::with_capacity(name.len() + 8);
+	for &byte in name {
+		if needs_escape(byte) {
+			escaped.extend_from_slice(&[b'#', HEX[(byte >> 4) as usize], HEX[(byte & 0xf) as usize]]);
+		} else {
+			escaped.push(byte);
+		}
+	}
+	return writer.write(&escaped);
+}
+
+pub(crate) fn write_literal_string(writer: &mut Writer<'_>, bytes: &[u8]) -> std::io::Result<()> {
+	writer.write(b"(")?;
+	for &byte in bytes {
+		match byte {
+			b'(' | b')' | b'\\' => writer.write(&[b'\\', byte])?,
+			b'\n' => writer.write(b"\\n")?,
+			b'\r' => writer.write(b"\\r")?,
+			_ => writer.write(&[byte])?,
+		}
+	}
+	writer.write(b")")?;
+	return Ok(());
+}
+
+fn write_hex_string(writer: &mut Writer<'_>, bytes: &[u8]) -> std::io::Result<()> {
+	const HEX: &[u8; 16] = b"0123456789ABCDEF";
+
+	writer.write(b"<")?;
+	for &byte in bytes {
+		writer.write(&[HEX[(byte >> 4) as usize], HEX[(byte & 0xf) as usize]])?;
+	}
+	writer.write(b">")?;
+	return Ok(());
+}
+
+impl From<bool> for PdfObject {
+	#[inline]
+	fn from(value: bool) -> Self {
+		return Self::Boolean(value);
+	}
+}
+
+impl From<i64> for PdfObject {
+	#[inline]
+	fn from(value: i64) -> Self {
+		return Self::Integer(value);
+	}
+}
+
+impl From<i32> for PdfObject {
+	#[inline]
+	fn from(value: i32) -> Self {
+		return Self::Integer(value as i64);
+	}
+}
+
+impl From<usize> for PdfObject {
+	#[inline]
+	fn from(value: usize) -> Self {
+		return Self::Integer(value as i64);
+	}
+}
+
+impl From<f64> for PdfObject {
+	#[inline]
+	fn from(value: f64) -> Self {
+		return Self::Real(value);
+	}
+}
+
+impl From<ObjectId> for PdfObject {
+	#[inline]
+	fn from(id: ObjectId) -> Self {
+		return Self::Reference(id);
+	}
+}
+
+impl From<Vec<PdfObject>> for PdfObject {
+	#[inline]
+	fn from(array: Vec<PdfObject>) -> Self {
+		return Self::Array(array);
+	}
+}
+
+impl From<Dictionary> for PdfObject {
+	#[inline]
+	fn from(dict: Dictionary) -> Self {
+		return Self::Dictionary(dict);
+	}
+}
+
+impl From<Stream> for PdfObject {
+	#[inline]
+	fn from(stream: Stream) -> Self {
+		return Self::Stream(stream);
+	}
+}
+
+/// Dictionary object, which keeps its entries in the insertion order, so that
+/// the output is deterministic.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Dictionary {
+	entries: Vec<(Vec<u8>, PdfObject)>,
+}
+
+impl Dictionary {
+	/// Creates new empty dictionary.
+	#[inline]
+	pub fn new() -> Self {
+		return Self {
+			entries: Vec::new(),
+		};
+	}
+
+	/// Sets the value for the given key, replacing the previous one if any.
+	pub fn set(&mut self, key: &str, value: impl Into<PdfObject>) {
+		self.set_raw(key.as_bytes(), value.into());
+	}
+
+	/// Sets the value for the given key, which may contain arbitrary bytes.
+	pub fn set_raw(&mut self, key: &[u8], value: PdfObject) {
+		match self.entries.iter_mut().find(|(k, _)| k == key) {
+			Some((_, v)) => *v = value,
+			None => self.entries.push((key.to_vec(), value)),
+		}
+	}
+
+	#[inline]
+	pub fn get(&self, key: &str) -> Option<&PdfObject> {
+		return self.get_raw(key.as_bytes());
+	}
+
+	pub fn get_raw(&self, key: &[u8]) -> Option<&PdfObject> {
+		return self.entries.iter().find(|(k, _)| k == key).map(|(_, v)| v);
+	}
+
+	pub fn get_mut(&mut self, key: &str) -> Option<&mut PdfObject> {
+		return self.entries.iter_mut().find(|(k, _)| k == key.as_bytes()).map(|(_, v)| v);
+	}
+
+	/// Removes the entry with the given key, returning its value.
+	pub fn remove(&mut self, key: &str) -> Option<PdfObject> {
+		let index = self.entries.iter().position(|(k, _)| k == key.as_bytes())?;
+		return Some(self.entries.remove(index).1);
+	}
+
+	#[inline]
+	pub fn contains_key(&self, key: &str) -> bool {
+		return self.get(key).is_some();
+	}
+
+	/// Returns the name stored under the given key.
+	pub fn get_name(&self, key: &str) -> Option<&[u8]> {
+		return self.get(key).and_then(PdfObject::as_name);
+	}
+
+	#[inline]
+	pub fn len(&self) -> usize {
+		return self.entries.len();
+	}
+
+	#[inline]
+	pub fn is_empty(&self) -> bool {
+		return self.entries.is_empty();
+	}
+
+	pub(crate) fn values_mut(&mut self) -> impl Iterator<Item = &mut PdfObject> {
+		return self.entries.iter_mut().map(|(_, value)| value);
+	}
+
+	pub fn iter(&self) -> impl Iterator<Item = (&[u8], &PdfObject)> {
+		return self.entries.iter().map(|(k, v)| (k.as_slice(), v));
+	}
+
+	fn write_compact(&self, buf: &mut Vec<u8>) {
+		buf.extend_from_slice(b"<<");
+		for (key, value) in &self.entries {
+			// Writing into a vector never fails.
+			let _ = write_name(&mut Writer::new(buf), key);
+			value.write_compact(buf);
+		}
+		buf.extend_from_slice(b">>");
+	}
+
+	fn write_pretty(&self, writer: &mut Writer<'_>, depth: usize) -> std::io::Result<()> {
+		writer.write(b"<<\n")?;
+		for (key, value) in &self.entries {
+			writer.write_repeated(b' ', (depth + 1) * 2)?;
+			write_name(writer, key)?;
+			writer.write(b" ")?;
+			value.write_pretty(writer, depth + 1)?;
+			writer.write(b"\n")?;
+		}
+		writer.write_repeated(b' ', depth * 2)?;
+		writer.write(b">>")?;
+		return Ok(());
+	}
+
+	fn write_nested(&self, writer: &mut Writer<'_>, depth: usize) -> std::io::Result<()> {
+		let separator: &[u8] = if depth == 0 { b"\n" } else { b" " };
+
+		writer.write(b"<<")?;
+		writer.write(separator)?;
+		for (key, value) in &self.entries {
+			write_name(writer, key)?;
+			writer.write(b" ")?;
+			value.write_nested(writer, depth + 1)?;
+			writer.write(separator)?;
+		}
+		writer.write(b">>")?;
+		return Ok(());
+	}
+}
+
+/// Layout of the whitespace in the written objects.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum WriteStyle {
+	/// Top-level dictionaries have every entry on a separate line, while the
+	/// nested ones are written inline.
+	#[default]
+	Default,
+	/// Only the whitespace needed to separate the tokens, for smaller files.
+	Compact,
+	/// Entries of all dictionaries, and items of arrays holding dictionaries or
+	/// arrays, are written on separate lines, indented by their depth.
+	Pretty,
+}
+
+/// Stream object, i.e. a dictionary followed by a sequence of bytes. The
+/// `/Length` entry is filled in automatically when the stream is written.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Stream {
+	pub dict: Dictionary,
+	pub data: Vec<u8>,
+}
+
+impl Stream {
+	/// Creates new stream with the given dictionary and data.
+	#[inline]
+	pub fn new(dict: Dictionary, data: Vec<u8>) -> Self {
+		return Self {
+			dict,
+			data,
+		};
+	}
+}
+
+/// Point in the user space coordinates.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Point {
+	pub x: f64,
+	pub y: f64,
+}
+
+impl Point {
+	#[inline]
+	pub const fn new(x: f64, y: f64) -> Self {
+		return Self {
+			x,
+			y,
+		};
+	}
+}
+
+/// Rectangle given by its lower left and upper right corners.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Rectangle {
+	pub llx: f64,
+	pub lly: f64,
+	pub urx: f64,
+	pub ury: f64,
+}
+
+impl Rectangle {
+	#[inline]
+	pub const fn new(llx: f64, lly: f64, urx: f64, ury: f64) -> Self {
+		return Self {
+			llx,
+			lly,
+			urx,
+			ury,
+		};
+	}
+
+	/// Returns the smallest rectangle containing all the given points.
+	pub fn bounding(points: impl IntoIterator<Item = Point>) -> Self {
+		let mut rect = Self::new(f64::INFINITY, f64::INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY);
+		for point in points {
+			rect.llx = rect.llx.min(point.x);
+			rect.lly = rect.lly.min(point.y);
+			rect.urx = rect.urx.max(point.x);
+			rect.ury = rect.ury.max(point.y);
+		}
+
+		if rect.llx > rect.urx {
+			return Self::default();
+		}
+
+		return rect;
+	}
+
+	/// Returns the rectangle grown by the given amount on each side, or shrunk
+	/// if the amount is negative.
+	#[inline]
+	pub fn inflate(&self, amount: f64) -> Self {
+		return Self::new(self.llx - amount, self.lly - amount, self.urx + amount, self.ury + amount);
+	}
+
+	/// Returns whether the rectangles overlap.
+	#[inline]
+	pub fn intersects(&self, other: &Self) -> bool {
+		return self.llx < other.urx && other.llx < self.urx && self.lly < other.ury && other.lly < self.ury;
+	}
+
+	#[inline]
+	pub fn width(&self) -> f64 {
+		return self.urx - self.llx;
+	}
+
+	#[inline]
+	pub fn height(&self) -> f64 {
+		return self.ury - self.lly;
+	}
+
+	/// Reads the rectangle from a PDF array of four numbers, normalizing it so
+	/// that the lower left corner comes first.
+	pub fn from_pdf_array(object: &PdfObject) -> Option<Self> {
+		let [x1, y1, x2, y2] = object.as_array()?.as_slice() else {
+			return None;
+		};
+		let (x1, y1, x2, y2) = (x1.as_number()?, y1.as_number()?, x2.as_number()?, y2.as_number()?);
+		return Some(Self::new(x1.min(x2), y1.min(y2), x1.max(x2), y1.max(y2)));
+	}
+
+	/// Converts the rectangle into a PDF array `[llx lly urx ury]`.
+	pub fn to_pdf_array(&self) -> PdfObject {
+		return PdfObject::Array(vec![
+			self.llx.into(),
+			self.lly.into(),
+			self.urx.into(),
+			self.ury.into(),
+		]);
+	}
+}
+
+/// Affine transformation matrix `[a b c d e f]`, mapping a point `(x, y)` to
+/// `(a*x + c*y + e, b*x + d*y + f)`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Matrix {
+	pub a: f64,
+	pub b: f64,
+	pub c: f64,
+	pub d: f64,
+	pub e: f64,
+	pub f: f64,
+}
+
+impl Matrix {
+	pub const IDENTITY: Self = Self::new(1.0, 0.0, 0.0, 1.0, 0.0, 0.0);
+
+	#[inline]
+	pub const fn new(a: f64, b: f64, c: f64, d: f64, e: f64, f: f64) -> Self {
+		return Self {
+			a,
+			b,
+			c,
+			d,
+			e,
+			f,
+		};
+	}
+
+	#[inline]
+	pub const fn translate(x: f64, y: f64) -> Self {
+		return Self::new(1.0, 0.0, 0.0, 1.0, x, y);
+	}
+
+	#[inline]
+	pub const fn scale(x: f64, y: f64) -> Self {
+		return Self::new(x, 0.0, 0.0, y, 0.0, 0.0);
+	}
+
+	/// Returns the matrix which applies this transformation first, and the
+	/// other one afterwards. This is the order used by the `cm` operator, i.e.
+	/// `new CTM = M × CTM` is written as `m.then(&ctm)`.
+	pub fn then(&self, other: &Self) -> Self {
+		return Self::new(
+			self.a * other.a + self.b * other.c,
+			self.a * other.b + self.b * other.d,
+			self.c * other.a + self.d * other.c,
+			self.c * other.b + self.d * other.d,
+			self.e * other.a + self.f * other.c + other.e,
+			self.e * other.b + self.f * other.d + other.f,
+		);
+	}
+
+	#[inline]
+	pub fn transform(&self, point: Point) -> Point {
+		return Point::new(
+			self.a * point.x + self.c * point.y + self.e,
+			self.b * point.x + self.d * point.y + self.f,
+		);
+	}
+
+	/// Converts the matrix into a PDF array `[a b c d e f]`.
+	pub fn to_pdf_array(&self) -> PdfObject {
+		return PdfObject::Array([self.a, self.b, self.c, self.d, self.e, self.f].into_iter().map(PdfObject::Real).collect());
+	}
+}
+
+impl Default for Matrix {
+	fn default() -> Self {
+		return Self::IDENTITY;
+	}
+}
+
+/// Colour in one of the device colour spaces.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Color {
+	Gray(f64),
+	Rgb(f64, f64, f64),
+	Cmyk(f64, f64, f64, f64),
+}
+
+impl Color {
+	/// Returns the colour components, as used by annotations `/C` entry.
+	pub fn components(&self) -> Vec<f64> {
+		return match *self {
+			Self::Gray(g) => vec![g],
+			Self::Rgb(r, g, b) => vec![r, g, b],
+			Self::Cmyk(c, m, y, k) => vec![c, m, y, k],
+		};
+	}
+
+	/// Converts the colour into a PDF array of its components.
+	pub fn to_pdf_array(&self) -> PdfObject {
+		return PdfObject::Array(self.components().into_iter().map(PdfObject::Real).collect());
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::*;
+	use crate::object::format_real;
+
+	fn serialise(object: &PdfObject) -> String {
+		let mut buf = Vec::new();
+		let mut writer = Writer::new(&mut buf);
+		object.write_to(&mut writer).expect("failed to write object");
+		return String::from_utf8(buf).expect("object is not valid utf-8");
+	}
+
+	#[test]
+	fn nested_dictionaries_are_inline() {
+		let mut font = Dictionary::new();
+		font.set("F13", ObjectId::new(5, 0));
+		let mut resources = Dictionary::new();
+		resources.set("Font", font);
+		let mut page = Dictionary::new();
+		page.set("Type", PdfObject::name("Page"));
+		page.set("Resources", resources);
+
+		let output = serialise(&PdfObject::Dictionary(page));
+		assert_eq!(output, "<<\n/Type /Page\n/Resources << /Font << /F13 5 0 R >> >>\n>>");
+	}
+
+	#[test]
+	fn reals_have_no_exponent() {
+		assert_eq!(format_real(612.0), "612");
+		assert_eq!(format_real(0.1), "0.1");
+		assert_eq!(format_real(1e-7), "0.0000001");
+		assert_eq!(format_real(f64::NAN), "0");
+	}
+
+	#[test]
+	fn strings_are_escaped() {
+		assert_eq!(serialise(&PdfObject::string(&b"a(b)\\"[..])), "(a\\(b\\)\\\\)");
+		assert_eq!(serialise(&PdfObject::hex_string(vec![0xfe, 0xff])), "<FEFF>");
+	}
+
+	#[test]
+	fn names_are_escaped() {
+		let name = PdfObject::Name(b"A B/C#\x80(".to_vec());
+		assert_eq!(serialise(&name).as_bytes(), b"/A#20B#2FC#23#80#28");
+		assert_eq!(serialise(&PdfObject::name("Type")), "/Type");
+
+		let mut dict = Dictionary::new();
+		dict.set_raw(b"Key with space", name.clone());
+		let output = serialise(&PdfObject::Dictionary(dict.clone()));
+		assert_eq!(crate::parser::Parser::new(output.as_bytes(), 0).parse_object().unwrap(), PdfObject::Dictionary(dict));
+		assert_eq!(crate::parser::Parser::new(serialise(&name).as_bytes(), 0).parse_object().unwrap(), name);
+	}
+
+	#[test]
+	fn write_styles() {
+		let mut font = Dictionary::new();
+		font.set("F13", ObjectId::new(5, 0));
+		let mut resources = Dictionary::new();
+		resources.set("Font", font);
+		let mut page = Dictionary::new();
+		page.set("Type", PdfObject::name("Page"));
+		page.set("MediaBox", [0, 0, 612, 792].map(PdfObject::Integer).to_vec());
+		page.set("Count", 3);
+		page.set("Kids", std::vec![PdfObject::Dictionary(Dictionary::new()), PdfObject::Null, PdfObject::Boolean(true)]);
+		page.set("Resources", resources);
+		let page = PdfObject::Dictionary(page);
+
+		let styled = |style: WriteStyle| {
+			let mut buf = Vec::new();
+			page.write_styled(&mut Writer::new(&mut buf), style).unwrap();
+			return String::from_utf8(buf).unwrap();
+		};
+		let (compact, pretty) = (styled(WriteStyle::Compact), styled(WriteStyle::Pretty));
+		assert_eq!(compact, "<</Type/Page/MediaBox[0 0 612 792]/Count 3/Kids[<<>>null true]/Resources<</Font<</F13 5 0 R>>>>>>");
+		assert_eq!(pretty, "<<\n  /Type /Page\n  /MediaBox [0 0 612 792]\n  /Count 3\n  /Kids [\n    <<\n    >>\n    null\n    true\n  ]\n  /Resources <<\n    /Font <<\n      /F13 5 0 R\n    >>\n  >>\n>>");
+		assert_eq!(styled(WriteStyle::Default), serialise(&page));
+		for output in [compact, pretty] {
+			assert_eq!(crate::parser::Parser::new(output.as_bytes(), 0).parse_object().unwrap(), page);
+		}
+	}
+}
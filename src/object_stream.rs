@@ -0,0 +1,168 @@
+//! Object streams, which store the objects other than streams compressed
+//! together, along with a cross-reference stream (PDF 1.5).
+
+use std::collections::BTreeMap;
+
+use crate::document::write_indirect;
+use crate::{filters, Dictionary, Document, ObjectId, PdfObject, Stream, Writer};
+
+/// Largest number of objects stored in a single object stream, so that
+/// readers don't have to decode a huge stream to get any of them.
+const OBJECTS_PER_STREAM: usize = 100;
+
+/// Location of an object, as listed in a cross-reference stream.
+#[derive(Clone, Copy)]
+enum Location {
+	Offset(usize, u16),
+	/// Object stored in the object stream with the given number, at the index.
+	Compressed(u32, usize),
+}
+
+impl Document {
+	/// Sets whether the objects other than streams are compressed together in
+	/// object streams when the document is written, which makes the file
+	/// smaller, e.g. for documents with many annotations. This needs PDF 1.5,
+	/// so the version written is raised to it. Incremental updates always
+	/// use a cross-reference table.
+	pub fn set_object_streams(&mut self, enabled: bool) {
+		self.object_streams = enabled;
+		if enabled {
+			self.require_version((1, 5));
+		}
+	}
+
+	/// Attempts to write the objects, with the compressed copies of the page
+	/// contents in place of the ones which have them, and the catalog and page
+	/// tree root given separately, storing the ones which can be stored in
+	/// object streams, and finishes the file with a cross-reference stream
+	/// instead of a table.
+	pub(crate) fn write_object_streams(&mut self, writer: &mut Writer<'_>, roots: [(ObjectId, PdfObject); 2], contents: &BTreeMap<ObjectId, PdfObject>) -> std::io::Result<()> {
+		let style = self.write_style;
+		let mut locations = BTreeMap::new();
+		let mut compressed = Vec::new();
+		for (id, object) in roots.iter().map(|(id, object)| (*id, object)).chain(self.objects.iter().map(|(&id, object)| (id, contents.get(&id).unwrap_or(object)))) {
+			// Streams can't be stored in object streams, and neither can the
+			// objects which are not of the first generation.
+			if object.as_stream().is_some() || id.generation != 0 {
+				locations.insert(id.number, Location::Offset(writer.pos(), id.generation));
+				write_indirect(writer, id, object, style)?;
+			} else {
+				compressed.push((id, object));
+			}
+		}
+
+		// Object streams and the cross-reference stream are numbered after the
+		// objects of the document.
+		let mut number = self.next_number;
+		for objects in compressed.chunks(OBJECTS_PER_STREAM) {
+			let mut header = Vec::new();
+			let mut body = Vec::new();
+			for (index, (id, object)) in objects.iter().enumerate() {
+				std::write!(Writer::new(&mut header), "{} {} ", id.number, body.len())?;
+				object.write_styled(&mut Writer::new(&mut body), style)?;
+				body.push(b'\n');
+				locations.insert(id.number, Location::Compressed(number, index));
+			}
+
+			let mut dict = Dictionary::new();
+			dict.set("Type", PdfObject::name("ObjStm"));
+			dict.set("N", objects.len());
+			dict.set("First", header.len());
+			header.extend_from_slice(&body);
+			let data = self.filter_registry().encode_stream(&mut dict, &header, &["FlateDecode"]).map_err(std::io::Error::other)?;
+			let stream = Stream::new(dict, data);
+			locations.insert(number, Location::Offset(writer.pos(), 0));
+			write_indirect(writer, ObjectId::new(number, 0), &PdfObject::Stream(stream), style)?;
+			number += 1;
+		}
+
+		let xref_pos = writer.pos();
+		locations.insert(number, Location::Offset(xref_pos, 0));
+		let size = number + 1;
+		// Every entry has the type, the offset or the number of the object
+		// stream, and the generation or the index in the object stream.
+		let mut rows = Vec::with_capacity(size as usize * 7);
+		for number in 0..size {
+			let (kind, field, last) = match locations.get(&number) {
+				Some(&Location::Offset(offset, generation)) => (1, offset, generation as usize),
+				Some(&Location::Compressed(stream, index)) => (2, stream as usize, index),
+				None => (0, 0, if number == 0 { 65535 } else { 1 }),
+			};
+			let (Ok(field), Ok(last)) = (u32::try_from(field), u16::try_from(last)) else {
+				return Err(std::io::Error::other("offset does not fit in a cross-reference stream entry"));
+			};
+			rows.push(kind);
+			rows.extend_from_slice(&field.to_be_bytes());
+			rows.extend_from_slice(&last.to_be_bytes());
+		}
+
+		let mut dict = Dictionary::new();
+		dict.set("Type", PdfObject::name("XRef"));
+		dict.set("Size", size as i64);
+		dict.set("W", std::vec![PdfObject::Integer(1), PdfObject::Integer(4), PdfObject::Integer(2)]);
+		dict.set("Root", self.catalog_id);
+		if let Some(info) = self.info {
+			dict.set("Info", info);
+		}
+		let [first, second] = self.next_file_id().clone();
+		dict.set("ID", std::vec![PdfObject::hex_string(first), PdfObject::hex_string(second)]);
+		dict.set("Filter", PdfObject::name("FlateDecode"));
+		write_indirect(writer, ObjectId::new(number, 0), &PdfObject::Stream(Stream::new(dict, filters::flate::encode(&rows))), style)?;
+
+		writer.write(b"startxref\n")?;
+		std::writeln!(writer, "{}", xref_pos)?;
+		writer.write(b"%%EOF\n")?;
+		return Ok(());
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::*;
+
+	#[test]
+	fn version_bump() {
+		let mut doc = Document::new();
+		doc.set_version((1, 4));
+		let font = doc.add_base_font("Helvetica");
+		for text in ["First", "Second"] {
+			let mut content = ContentStream::new();
+			let name = content.use_font(font);
+			content.begin_text().set_font(&name, 12.0).move_text(72.0, 720.0).show_text(text.as_bytes()).end_text();
+			doc.add_page(612.0, 792.0).set_contents(content);
+		}
+		assert_eq!(doc.effective_version(), (1, 4));
+		doc.set_object_streams(true);
+		assert_eq!(doc.version(), (1, 4));
+		assert_eq!(doc.effective_version(), (1, 5));
+
+		let mut output = Vec::new();
+		doc.write(&mut Writer::new(&mut output)).unwrap();
+		assert!(output.starts_with(b"%PDF-1.5\n"));
+		let text = String::from_utf8_lossy(&output);
+		assert!(text.contains("/Type /ObjStm") && text.contains("/Type /XRef") && !text.contains("\nxref\n"));
+
+		let reader = Reader::with_mode(&output, ParseMode::Strict).unwrap();
+		reader.verify_offsets().unwrap();
+		assert_eq!(reader.pdf_version(), (1, 5));
+		assert_eq!(reader.page_count().unwrap(), 2);
+		assert_eq!(reader.page(1).unwrap().extract_text().unwrap(), "Second");
+		assert_eq!(reader.resolve(font.0).unwrap().as_dict().unwrap().get_name("BaseFont"), Some(&b"Helvetica"[..]));
+		let id = reader.trailer().get("ID").and_then(PdfObject::as_array).unwrap();
+		assert_eq!(id[0].as_string(), doc.file_id().map(|id| &id[0][..]));
+
+		// Transparency needs PDF 1.4, which doesn't lower the version.
+		doc.add_ext_gstate(&ExtGState {
+			fill_alpha: Some(0.5),
+			..ExtGState::new()
+		});
+		assert_eq!(doc.effective_version(), (1, 5));
+		let mut doc = Document::new();
+		doc.set_version((1, 3));
+		doc.add_ext_gstate(&ExtGState {
+			blend_mode: Some(BlendMode::Multiply),
+			..ExtGState::new()
+		});
+		assert_eq!(doc.effective_version(), (1, 4));
+	}
+}
@@ -0,0 +1,113 @@
+//! Optional content, i.e. layers of content which viewers can show or hide.
+
+use crate::{Dictionary, Document, ObjectId, PdfObject};
+
+/// Reference to an optional content group, i.e. a single layer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct LayerRef(pub ObjectId);
+
+/// Reference to an optional content membership dictionary, which makes
+/// content visible based on the visibility of several layers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct OcmdRef(pub ObjectId);
+
+impl From<LayerRef> for ObjectId {
+	#[inline]
+	fn from(layer: LayerRef) -> Self {
+		return layer.0;
+	}
+}
+
+impl From<OcmdRef> for ObjectId {
+	#[inline]
+	fn from(membership: OcmdRef) -> Self {
+		return membership.0;
+	}
+}
+
+/// Visibility policy of a membership dictionary, telling which of its layers
+/// have to be visible for the content to be visible.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum VisibilityPolicy {
+	AllOn,
+	#[default]
+	AnyOn,
+	AnyOff,
+	AllOff,
+}
+
+impl VisibilityPolicy {
+	fn name(self) -> &'static str {
+		return match self {
+			Self::AllOn => "AllOn",
+			Self::AnyOn => "AnyOn",
+			Self::AnyOff => "AnyOff",
+			Self::AllOff => "AllOff",
+		};
+	}
+}
+
+impl Document {
+	/// Adds a layer with the given name, shown in the viewer user interface,
+	/// which is visible by default.
+	pub fn add_layer(&mut self, name: &str) -> LayerRef {
+		let mut dict = Dictionary::new();
+		dict.set("Type", PdfObject::name("OCG"));
+		dict.set("Name", PdfObject::text_string(name));
+		let id = self.add_object(dict);
+
+		if !self.catalog.contains_key("OCProperties") {
+			let mut config = Dictionary::new();
+			config.set("Order", Vec::new());
+			let mut properties = Dictionary::new();
+			properties.set("OCGs", Vec::new());
+			properties.set("D", config);
+			self.catalog.set("OCProperties", properties);
+		}
+
+		let properties = self.catalog.get_mut("OCProperties").and_then(PdfObject::as_dict_mut).expect("optional content properties are not a dictionary");
+		if let Some(PdfObject::Array(groups)) = properties.get_mut("OCGs") {
+			groups.push(id.into());
+		}
+		if let Some(PdfObject::Array(order)) = properties.get_mut("D").and_then(PdfObject::as_dict_mut).and_then(|config| config.get_mut("Order")) {
+			order.push(id.into());
+		}
+		return LayerRef(id);
+	}
+
+	/// Adds a membership dictionary, making content visible according to the
+	/// policy applied to the given layers.
+	pub fn add_oc_membership(&mut self, layers: &[LayerRef], policy: VisibilityPolicy) -> OcmdRef {
+		let mut dict = Dictionary::new();
+		dict.set("Type", PdfObject::name("OCMD"));
+		dict.set("OCGs", layers.iter().map(|layer| PdfObject::Reference(layer.0)).collect::<Vec<_>>());
+		dict.set("P", PdfObject::name(policy.name()));
+		return OcmdRef(self.add_object(dict));
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::*;
+
+	#[test]
+	fn membership_of_two_layers() {
+		let mut doc = Document::new();
+		let (l1, l2) = (doc.add_layer("L1"), doc.add_layer("L2"));
+		let both = doc.add_oc_membership(&[l1, l2], VisibilityPolicy::AllOn);
+
+		let mut content = ContentStream::new();
+		content.begin_layer(l1).rect(Rectangle::new(0.0, 0.0, 10.0, 10.0)).fill().end_layer();
+		content.begin_layer(both).rect(Rectangle::new(20.0, 0.0, 30.0, 10.0)).fill().end_layer();
+		doc.add_page(612.0, 792.0).set_contents(content);
+
+		let mut output = Vec::new();
+		doc.write(&mut Writer::new(&mut output)).unwrap();
+		let output = String::from_utf8_lossy(&output);
+		let layers = std::format!("[{} 0 R {} 0 R]", l1.0.number, l2.0.number);
+		assert!(output.contains(&std::format!("<<\n/Type /OCMD\n/OCGs {}\n/P /AllOn\n>>", layers)));
+		assert!(output.contains(&std::format!("/OCProperties << /OCGs {} /D << /Order {} >> >>", layers, layers)));
+		assert!(output.contains(&std::format!("/Properties << /OC0 {} 0 R /OC1 {} 0 R >>", l1.0.number, both.0.number)));
+		assert!(output.contains("/OC /OC1 BDC\n20 0 10 10 re\nf\nEMC\n"));
+	}
+}
@@ -0,0 +1,101 @@
+//! Output intents, describing the colour characteristics of the device the
+//! document is meant to be reproduced on.
+
+use crate::{filters, Dictionary, Document, ObjectId, PdfError, PdfObject, Stream};
+
+/// Standard the output intent is meant for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputIntentSubtype {
+	/// PDF/A archival documents (`/GTS_PDFA1`).
+	PdfA,
+	/// PDF/X print production (`/GTS_PDFX`).
+	PdfX,
+}
+
+impl OutputIntentSubtype {
+	fn name(self) -> &'static str {
+		return match self {
+			Self::PdfA => "GTS_PDFA1",
+			Self::PdfX => "GTS_PDFX",
+		};
+	}
+}
+
+/// Gets the number of colour components of the ICC profile, from the colour
+/// space signature in its header.
+pub(crate) fn icc_components(profile: &[u8]) -> Option<i64> {
+	if profile.len() < 128 || &profile[36..40] != b"acsp" {
+		return None;
+	}
+	return match &profile[16..20] {
+		b"GRAY" => Some(1),
+		b"RGB " | b"Lab " => Some(3),
+		b"CMYK" => Some(4),
+		_ => None,
+	};
+}
+
+impl Document {
+	/// Attempts to add the output intent with the given ICC profile to the
+	/// catalog, returning the identifier of the intent dictionary. The
+	/// identifier names the output condition, e.g. `sRGB IEC61966-2.1`.
+	pub fn set_output_intent(&mut self, subtype: OutputIntentSubtype, icc_profile: &[u8], identifier: &str) -> Result<ObjectId, PdfError> {
+		let Some(components) = icc_components(icc_profile) else {
+			return Err(PdfError::InvalidArgument("output intent profile is not a supported ICC profile".to_string()));
+		};
+
+		let mut profile = Stream::new(Dictionary::new(), filters::flate::encode(icc_profile));
+		profile.dict.set("N", components);
+		profile.dict.set("Filter", PdfObject::name("FlateDecode"));
+		let profile = self.add_object(profile);
+
+		let mut intent = Dictionary::new();
+		intent.set("Type", PdfObject::name("OutputIntent"));
+		intent.set("S", PdfObject::name(subtype.name()));
+		intent.set("OutputConditionIdentifier", PdfObject::text_string(identifier));
+		intent.set("DestOutputProfile", profile);
+		let intent = self.add_object(intent);
+
+		match self.catalog.get_mut("OutputIntents") {
+			Some(PdfObject::Array(intents)) => intents.push(intent.into()),
+			_ => self.catalog.set("OutputIntents", std::vec![PdfObject::Reference(intent)]),
+		}
+		return Ok(intent);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::*;
+
+	/// Header of an sRGB profile, enough to be recognised.
+	fn profile() -> Vec<u8> {
+		let mut profile = std::vec![0; 128];
+		profile[16..20].copy_from_slice(b"RGB ");
+		profile[36..40].copy_from_slice(b"acsp");
+		profile.extend_from_slice(b"tag data");
+		return profile;
+	}
+
+	#[test]
+	fn output_intent() {
+		let mut doc = Document::new();
+		doc.add_page(612.0, 792.0);
+		assert!(matches!(doc.set_output_intent(OutputIntentSubtype::PdfA, b"not a profile", "sRGB"), Err(PdfError::InvalidArgument(_))));
+		doc.set_output_intent(OutputIntentSubtype::PdfA, &profile(), "sRGB IEC61966-2.1").unwrap();
+
+		let mut output = Vec::new();
+		doc.write(&mut Writer::new(&mut output)).unwrap();
+		let reader = Reader::new(&output).unwrap();
+		let intents = reader.catalog().unwrap().get("OutputIntents").and_then(PdfObject::as_array).cloned().unwrap();
+		assert_eq!(intents.len(), 1);
+
+		let intent = reader.resolve_object(&intents[0]).unwrap();
+		let intent = intent.as_dict().unwrap();
+		assert_eq!(intent.get_name("S"), Some(&b"GTS_PDFA1"[..]));
+		let profile_stream = reader.resolve_object(intent.get("DestOutputProfile").unwrap()).unwrap();
+		let profile_stream = profile_stream.as_stream().unwrap();
+		assert_eq!(profile_stream.dict.get("N"), Some(&PdfObject::Integer(3)));
+		assert_eq!(filters::decode(profile_stream).unwrap(), profile());
+	}
+}
@@ -0,0 +1,321 @@
+//! Restructuring of the pages of a document.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{Document, ObjectId, PdfError, PdfObject, Writer};
+
+impl Document {
+	/// Attempts to rearrange the pages, so that the page at `new_order[i]`
+	/// becomes the page at index `i`. The order has to be a permutation of all
+	/// page indices.
+	pub fn reorder_pages(&mut self, new_order: &[usize]) -> Result<(), PdfError> {
+		let count = self.pages.len();
+		if new_order.len() != count {
+			return Err(PdfError::InvalidArgument(std::format!("page order has {} entries, document has {} pages", new_order.len(), count)));
+		}
+
+		let mut seen = std::vec![false; count];
+		for &index in new_order {
+			if index >= count {
+				return Err(PdfError::PageOutOfRange { index, count });
+			}
+			if std::mem::replace(&mut seen[index], true) {
+				return Err(PdfError::InvalidArgument(std::format!("page {} appears more than once", index)));
+			}
+		}
+
+		self.pages = new_order.iter().map(|&index| self.pages[index]).collect();
+		return Ok(());
+	}
+
+	/// Replaces every reference to the given objects with null, e.g. for the
+	/// destinations pointing at removed pages.
+	pub(crate) fn drop_references(&mut self, removed: &[ObjectId]) {
+		let mut drop = |object: &mut PdfObject| {
+			if let PdfObject::Reference(id) = object {
+				if removed.contains(id) {
+					*object = PdfObject::Null;
+				}
+			}
+		};
+
+		for object in self.objects.values_mut() {
+			object.visit_mut(&mut drop);
+		}
+		for object in self.catalog.values_mut() {
+			object.visit_mut(&mut drop);
+		}
+		for object in self.name_trees.values_mut().flat_map(|tree| tree.entries.values_mut()) {
+			object.visit_mut(&mut drop);
+		}
+	}
+
+	/// Finds the objects which can be reached from the given ones, including them.
+	fn reachable(&self, mut pending: Vec<ObjectId>) -> BTreeSet<ObjectId> {
+		let mut reachable = BTreeSet::new();
+		while let Some(id) = pending.pop() {
+			if !reachable.insert(id) {
+				continue;
+			}
+			if let Some(object) = self.objects.get(&id) {
+				object.for_each_reference(&mut |id| pending.push(id));
+			}
+		}
+		return reachable;
+	}
+
+	/// Finds the objects used by the document, i.e. which can be reached from
+	/// the catalog, the pages, including the resources added to them when
+	/// they're written, the name trees built when the document is written, or
+	/// the information dictionary.
+	pub(crate) fn used_objects(&self) -> BTreeSet<ObjectId> {
+		let mut roots = self.pages.clone();
+		roots.extend(self.info);
+		for (_, object) in self.catalog.iter() {
+			object.for_each_reference(&mut |id| roots.push(id));
+		}
+		for resources in self.resources.values() {
+			PdfObject::Dictionary(resources.to_dict()).for_each_reference(&mut |id| roots.push(id));
+		}
+		for object in self.name_trees.values().flat_map(|tree| tree.entries.values()) {
+			object.for_each_reference(&mut |id| roots.push(id));
+		}
+		return self.reachable(roots);
+	}
+
+	/// Removes the objects which can't be reached from the catalog or the pages.
+	pub(crate) fn collect_garbage(&mut self) {
+		let used = self.used_objects();
+		self.objects.retain(|id, _| used.contains(id));
+	}
+
+	/// Attempts to remove the page, together with its contents, annotations,
+	/// and other objects which are not used anywhere else. References to the
+	/// page, e.g. from destinations, are replaced with null.
+	pub fn remove_page(&mut self, index: usize) -> Result<(), PdfError> {
+		let count = self.pages.len();
+		if index >= count {
+			return Err(PdfError::PageOutOfRange { index, count });
+		}
+		if count == 1 {
+			return Err(PdfError::InvalidArgument("a document has to have at least one page".to_string()));
+		}
+
+		let id = self.pages.remove(index);
+		let owned = self.reachable(std::vec![id]);
+		self.resources.remove(&id);
+		self.drop_references(&[id]);
+
+		let used = self.used_objects();
+		self.objects.retain(|id, _| !owned.contains(id) || used.contains(id));
+		return Ok(());
+	}
+
+	/// Renumbers the objects consecutively, starting with the catalog and the
+	/// page tree root, and updates all references to them.
+	pub(crate) fn renumber(&mut self) {
+		let mut map = BTreeMap::new();
+		map.insert(self.catalog_id, ObjectId::new(1, 0));
+		map.insert(self.pages_id, ObjectId::new(2, 0));
+		for (index, id) in self.objects.keys().copied().filter(|id| !map.contains_key(id)).collect::<Vec<_>>().into_iter().enumerate() {
+			map.insert(id, ObjectId::new(index as u32 + 3, 0));
+		}
+
+		let mut remap = |object: &mut PdfObject| {
+			if let PdfObject::Reference(id) = object {
+				if let Some(&new) = map.get(id) {
+					*id = new;
+				}
+			}
+		};
+
+		let objects = std::mem::take(&mut self.objects);
+		for (id, mut object) in objects {
+			object.visit_mut(&mut remap);
+			self.objects.insert(map[&id], object);
+		}
+		for object in self.catalog.values_mut() {
+			object.visit_mut(&mut remap);
+		}
+		for object in self.name_trees.values_mut().flat_map(|tree| tree.entries.values_mut()) {
+			object.visit_mut(&mut remap);
+		}
+
+		for page in &mut self.pages {
+			*page = map[page];
+		}
+		self.info = self.info.and_then(|info| map.get(&info).copied());
+		self.page_tree_nodes = std::mem::take(&mut self.page_tree_nodes).into_iter().filter_map(|id| map.get(&id).copied()).collect();
+		self.struct_elements = std::mem::take(&mut self.struct_elements).into_iter().filter_map(|id| map.get(&id).copied()).collect();
+		self.name_tree_nodes = std::mem::take(&mut self.name_tree_nodes).into_iter().filter_map(|id| map.get(&id).copied()).collect();
+		// The loaded objects are now under other numbers.
+		self.loaded.clear();
+		self.resources = std::mem::take(&mut self.resources).into_iter().filter_map(|(id, resources)| Some((*map.get(&id)?, resources))).collect();
+		self.catalog_id = ObjectId::new(1, 0);
+		self.pages_id = ObjectId::new(2, 0);
+		self.next_number = self.objects.len() as u32 + 3;
+	}
+}
+
+/// Attempts to create a new PDF file with only the pages of the given range,
+/// with zero-based page indices. Only the objects used by these pages are
+/// carried along, and they're renumbered.
+pub fn extract_pages(pdf: &[u8], range: std::ops::RangeInclusive<usize>) -> Result<Vec<u8>, PdfError> {
+	let mut doc = Document::load(pdf)?;
+	let count = doc.page_count();
+	if range.is_empty() || *range.end() >= count {
+		return Err(PdfError::PageOutOfRange {
+			index: *range.end(),
+			count,
+		});
+	}
+
+	let removed: Vec<ObjectId> = doc.pages.iter().enumerate().filter(|(index, _)| !range.contains(index)).map(|(_, &id)| id).collect();
+	doc.pages.retain(|id| !removed.contains(id));
+	doc.drop_references(&removed);
+	doc.collect_garbage();
+	doc.renumber();
+
+	let mut output = Vec::new();
+	doc.write(&mut Writer::new(&mut output))?;
+	return Ok(output);
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::*;
+
+	#[test]
+	fn extract_page_range() {
+		let mut doc = Document::new();
+		for number in 1..=5 {
+			let font = doc.add_base_font(&std::format!("Font{}", number));
+			let mut content = ContentStream::new();
+			let name = content.use_font(font);
+			content.begin_text().set_font(&name, 12.0).show_text(std::format!("Page {}", number).as_bytes()).end_text();
+			doc.add_page(612.0, 792.0).set_contents(content);
+		}
+		let mut data = Vec::new();
+		doc.write(&mut Writer::new(&mut data)).unwrap();
+
+		let extracted = extract_pages(&data, 1..=2).unwrap();
+		let mut doc = Document::load(&extracted).unwrap();
+		assert_eq!(doc.page_count(), 2);
+		// Catalog, page tree, and a font, contents, and page object for each page.
+		assert_eq!(Reader::new(&extracted).unwrap().object_ids().count(), 2 + 2 * 3);
+
+		for (index, text) in [(0, "Page 2"), (1, "Page 3")] {
+			let page = doc.page_mut(index).unwrap();
+			assert_eq!(page.dict().get("Parent"), Some(&PdfObject::Reference(ObjectId::new(2, 0))));
+			assert_eq!(page.extract_text().unwrap(), text);
+		}
+
+		let reader = Reader::new(&extracted).unwrap();
+		let kids = reader.resolve(ObjectId::new(2, 0)).unwrap();
+		assert_eq!(kids.as_dict().unwrap().get("Count"), Some(&PdfObject::Integer(2)));
+		assert!(matches!(extract_pages(&data, 4..=5), Err(PdfError::PageOutOfRange { index: 5, count: 5 })));
+	}
+
+	fn numbered_pages(count: usize) -> Document {
+		let mut doc = Document::new();
+		for number in 1..=count {
+			let mut content = ContentStream::new();
+			content.begin_text().set_font("F1", 12.0).show_text(std::format!("Page {}", number).as_bytes()).end_text();
+			doc.add_page(612.0, 792.0).set_contents(content);
+		}
+		return doc;
+	}
+
+	fn texts(doc: &mut Document) -> Vec<String> {
+		return (0..doc.page_count()).map(|index| doc.page_mut(index).unwrap().extract_text().unwrap()).collect();
+	}
+
+	#[test]
+	fn reorder() {
+		let mut doc = numbered_pages(3);
+		assert!(matches!(doc.reorder_pages(&[0, 1]), Err(PdfError::InvalidArgument(_))));
+		assert!(matches!(doc.reorder_pages(&[0, 0, 1]), Err(PdfError::InvalidArgument(_))));
+		assert!(matches!(doc.reorder_pages(&[0, 1, 3]), Err(PdfError::PageOutOfRange { index: 3, count: 3 })));
+		doc.reorder_pages(&[2, 0, 1]).unwrap();
+		assert_eq!(texts(&mut doc), ["Page 3", "Page 1", "Page 2"]);
+
+		let mut data = Vec::new();
+		doc.write(&mut Writer::new(&mut data)).unwrap();
+		let mut doc = Document::load(&data).unwrap();
+		doc.reorder_pages(&[2, 0, 1]).unwrap();
+		assert_eq!(texts(&mut doc), ["Page 2", "Page 3", "Page 1"]);
+
+		let mut data = Vec::new();
+		doc.write(&mut Writer::new(&mut data)).unwrap();
+		let reader = Reader::new(&data).unwrap();
+		let pages = reader.resolve(doc.pages_id()).unwrap();
+		assert_eq!(pages.as_dict().unwrap().get("Count"), Some(&PdfObject::Integer(3)));
+	}
+
+	#[test]
+	fn remove_page() {
+		let mut doc = numbered_pages(3);
+		let font = doc.add_base_font("Helvetica");
+		for index in 0..3 {
+			doc.page_mut(index).unwrap().add_font_resource("F1", font);
+		}
+		let mut data = Vec::new();
+		doc.write(&mut Writer::new(&mut data)).unwrap();
+
+		let mut doc = Document::load(&data).unwrap();
+		let first = doc.page_mut(0).unwrap().id();
+		let contents = doc.page_mut(0).unwrap().content_ids()[0];
+		doc.remove_page(0).unwrap();
+		assert_eq!(texts(&mut doc), ["Page 2", "Page 3"]);
+		assert!(doc.object(first).is_none() && doc.object(contents).is_none());
+		// The font is still used by the remaining pages.
+		assert!(doc.object(font.0).is_some());
+
+		let mut data = Vec::new();
+		doc.write(&mut Writer::new(&mut data)).unwrap();
+		let reader = Reader::new(&data).unwrap();
+		let pages = reader.resolve(doc.pages_id()).unwrap();
+		assert_eq!(pages.as_dict().unwrap().get("Count"), Some(&PdfObject::Integer(2)));
+		for id in pages.as_dict().unwrap().get("Kids").unwrap().as_array().unwrap() {
+			let page = reader.resolve(id.as_reference().unwrap()).unwrap();
+			let parent = page.as_dict().unwrap().get("Parent").and_then(PdfObject::as_reference).unwrap();
+			assert_eq!(reader.resolve(parent).unwrap().as_dict().unwrap().get_name("Type"), Some(&b"Pages"[..]));
+		}
+
+		doc.remove_page(1).unwrap();
+		assert!(matches!(doc.remove_page(0), Err(PdfError::InvalidArgument(_))));
+	}
+
+	#[test]
+	fn remove_page_keeps_name_trees() {
+		let mut doc = numbered_pages(2);
+		let spec = doc.add_embedded_file("notes.txt", b"notes", None).unwrap();
+		// The attachment annotation refers to the file specification, which the
+		// name tree lists as well.
+		let mut annot = Dictionary::new();
+		annot.set("Subtype", PdfObject::name("FileAttachment"));
+		annot.set("FS", spec);
+		let annot = doc.add_object(annot);
+		let first = doc.page_mut(0).unwrap().id();
+		doc.object_mut(first).and_then(PdfObject::as_dict_mut).unwrap().set("Annots", std::vec![PdfObject::Reference(annot)]);
+
+		doc.remove_page(0).unwrap();
+		assert!(doc.object(annot).is_none());
+		assert!(doc.object(spec).is_some());
+	}
+
+	#[test]
+	fn remove_page_with_named_destination() {
+		let mut doc = numbered_pages(2);
+		doc.add_named_destination("first", 0, false).unwrap();
+		doc.add_named_destination("second", 1, false).unwrap();
+		doc.remove_page(1).unwrap();
+
+		let mut data = Vec::new();
+		doc.write(&mut Writer::new(&mut data)).unwrap();
+		let reader = Reader::new(&data).unwrap();
+		assert_eq!(reader.named_destination(b"second").unwrap(), Some(std::vec![PdfObject::Null, PdfObject::name("Fit")]));
+		let first = reader.named_destination(b"first").unwrap().unwrap();
+		assert_eq!(first[0], PdfObject::Reference(doc.page_mut(0).unwrap().id()));
+	}
+}
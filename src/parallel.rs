@@ -0,0 +1,123 @@
+//! Generation of page contents on several threads.
+//!
+//! The threads are scoped threads of the standard library, rather than the
+//! pool of rayon behind a feature, so that the crate keeps having no
+//! dependencies. They work as a pool of their own, though: instead of a thread
+//! per page, each of them takes the next page which isn't built yet, until
+//! there are none left, so a few slow pages don't hold up a whole range.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::{ContentStream, Document, ObjectId};
+
+/// Largest number of threads building the contents at once, even if the
+/// system has more of them available.
+const MAX_THREADS: usize = 16;
+
+impl Document {
+	/// Appends `count` pages with the given media box dimensions, whose
+	/// content streams are built by the closure, given the index of the page
+	/// among the new ones. The closure is run on several threads at once,
+	/// while the pages are added in order afterwards, so the object numbers
+	/// are the same as if the pages were added one by one. Returns the
+	/// identifiers of the new pages.
+	///
+	/// There are at most as many threads as the available parallelism, and
+	/// at most 16 of them, each building the next page left whenever it's
+	/// done with one.
+	///
+	/// Objects used by the contents, e.g. fonts, have to be added beforehand.
+	pub fn add_pages_parallel<F>(&mut self, count: usize, width: f64, height: f64, content: F) -> Vec<ObjectId>
+	where
+		F: Fn(usize) -> ContentStream + Sync,
+	{
+		let threads = std::thread::available_parallelism().map_or(1, |threads| threads.get()).min(MAX_THREADS).min(count).max(1);
+		let next = AtomicUsize::new(0);
+		let mut contents: Vec<(usize, ContentStream)> = std::thread::scope(|scope| {
+			let (content, next) = (&content, &next);
+			let workers: Vec<_> = (0..threads).map(|_| {
+				return scope.spawn(move || {
+					let mut built = Vec::new();
+					loop {
+						let index = next.fetch_add(1, Ordering::Relaxed);
+						if index >= count {
+							return built;
+						}
+						built.push((index, content(index)));
+					}
+				});
+			}).collect();
+			return workers.into_iter().flat_map(|worker| worker.join().unwrap_or_else(|panic| std::panic::resume_unwind(panic))).collect();
+		});
+		// Pages are added in order, whichever thread built them.
+		contents.sort_unstable_by_key(|&(index, _)| index);
+
+		return contents.into_iter().map(|(_, content)| {
+			let mut page = self.add_page(width, height);
+			page.set_contents(content);
+			return page.id();
+		}).collect();
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::parallel::MAX_THREADS;
+	use crate::*;
+
+	#[test]
+	fn parallel_pages() {
+		let build = |parallel: bool| {
+			let mut doc = Document::new();
+			doc.set_deterministic_id(true);
+			let font = doc.add_base_font("Helvetica");
+			let content = |index: usize| {
+				let mut content = ContentStream::new();
+				let name = content.use_font(font);
+				for line in 0..(index % 7 + 1) {
+					content.begin_text().set_font(&name, 12.0).move_text(72.0, 720.0 - line as f64 * 14.0).show_text(std::format!("Page {}, line {}", index + 1, line).as_bytes()).end_text();
+				}
+				return content;
+			};
+			let pages = match parallel {
+				true => doc.add_pages_parallel(100, 612.0, 792.0, content),
+				false => (0..100).map(|index| {
+					let mut page = doc.add_page(612.0, 792.0);
+					page.set_contents(content(index));
+					return page.id();
+				}).collect(),
+			};
+			let mut output = Vec::new();
+			doc.write(&mut Writer::new(&mut output)).unwrap();
+			return (pages, output);
+		};
+
+		let (pages, output) = build(true);
+		assert_eq!(pages.len(), 100);
+		assert_eq!((pages, output), build(false));
+		assert!(Document::new().add_pages_parallel(0, 612.0, 792.0, |_| ContentStream::new()).is_empty());
+
+		// Every page is built once, by one of the threads.
+		let built = std::sync::atomic::AtomicUsize::new(0);
+		let pages = Document::new().add_pages_parallel(MAX_THREADS * 3 + 1, 612.0, 792.0, |_| {
+			built.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+			return ContentStream::new();
+		});
+		assert_eq!((pages.len(), built.into_inner()), (MAX_THREADS * 3 + 1, MAX_THREADS * 3 + 1));
+
+		// Slow pages don't change the order of the others.
+		let mut doc = Document::new();
+		let pages = doc.add_pages_parallel(20, 612.0, 792.0, |index| {
+			if index % 5 == 0 {
+				std::thread::sleep(std::time::Duration::from_millis(5));
+			}
+			let mut content = ContentStream::new();
+			content.begin_text().set_font("F1", 12.0).show_text(std::format!("Page {}", index).as_bytes()).end_text();
+			return content;
+		});
+		for (index, &page) in pages.iter().enumerate() {
+			assert_eq!(doc.pages[index], page);
+			assert_eq!(doc.page_mut(index).unwrap().extract_text().unwrap(), std::format!("Page {}", index));
+		}
+	}
+}
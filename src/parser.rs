@@ -0,0 +1,177 @@
+//! Parser turning tokens into objects.
+
+use crate::lexer::{Lexer, Token};
+use crate::{Dictionary, ObjectId, ParseMode, PdfError, PdfObject};
+
+pub(crate) struct Parser<'a> {
+	pub(crate) lexer: Lexer<'a>,
+	/// Whether `N G R` sequences should be parsed as indirect references. That
+	/// is never the case in content streams.
+	references: bool,
+	mode: ParseMode,
+}
+
+impl<'a> Parser<'a> {
+	/// Creates new parser for objects in the file body.
+	#[inline]
+	pub fn new(data: &'a [u8], pos: usize) -> Self {
+		return Self {
+			lexer: Lexer::new(data, pos),
+			references: true,
+			mode: ParseMode::Strict,
+		};
+	}
+
+	/// Creates new parser for operands within content streams.
+	#[inline]
+	pub fn content(data: &'a [u8]) -> Self {
+		return Self {
+			lexer: Lexer::new(data, 0),
+			references: false,
+			mode: ParseMode::Strict,
+		};
+	}
+
+	/// Sets how deviations from the syntax are handled.
+	#[inline]
+	pub fn with_mode(mut self, mode: ParseMode) -> Self {
+		self.mode = mode;
+		return self;
+	}
+
+	#[inline]
+	pub fn pos(&self) -> usize {
+		return self.lexer.pos();
+	}
+
+	/// Attempts to parse the next object.
+	pub fn parse_object(&mut self) -> Result<PdfObject, PdfError> {
+		let pos = self.lexer.pos();
+		let Some(token) = self.lexer.next()? else {
+			return Err(PdfError::syntax(pos, "unexpected end of data"));
+		};
+
+		return self.parse_from(token, pos);
+	}
+
+	/// Attempts to parse an object starting with the already read token.
+	pub fn parse_from(&mut self, token: Token<'a>, pos: usize) -> Result<PdfObject, PdfError> {
+		return match token {
+			Token::Integer(number) => Ok(self.reference_or_integer(number)?),
+			Token::Real(value) => Ok(PdfObject::Real(value)),
+			Token::String(bytes) => Ok(PdfObject::string(bytes)),
+			Token::HexString(bytes) => Ok(PdfObject::hex_string(bytes)),
+			Token::Name(name) => Ok(PdfObject::Name(name)),
+			Token::ArrayStart => self.array(),
+			Token::DictStart => Ok(PdfObject::Dictionary(self.dictionary()?)),
+			Token::Keyword(b"true") => Ok(PdfObject::Boolean(true)),
+			Token::Keyword(b"false") => Ok(PdfObject::Boolean(false)),
+			Token::Keyword(b"null") => Ok(PdfObject::Null),
+			Token::Keyword(word) if is_malformed_number(word) => match self.mode {
+				ParseMode::Strict => Err(PdfError::syntax(pos, "malformed number")),
+				ParseMode::Lenient => Ok(recover_number(word)),
+			},
+			_ => Err(PdfError::syntax(pos, "unexpected token")),
+		};
+	}
+
+	fn reference_or_integer(&mut self, number: i64) -> Result<PdfObject, PdfError> {
+		if !self.references || number < 0 || number > u32::MAX as i64 {
+			return Ok(PdfObject::Integer(number));
+		}
+
+		let pos = self.lexer.pos();
+		if let Ok(Some(Token::Integer(generation))) = self.lexer.next() {
+			if (0..=u16::MAX as i64).contains(&generation) {
+				if let Ok(Some(Token::Keyword(b"R"))) = self.lexer.next() {
+					return Ok(PdfObject::Reference(ObjectId::new(number as u32, generation as u16)));
+				}
+			}
+		}
+
+		self.lexer.set_pos(pos);
+		return Ok(PdfObject::Integer(number));
+	}
+
+	fn array(&mut self) -> Result<PdfObject, PdfError> {
+		let mut array = Vec::new();
+		loop {
+			let pos = self.lexer.pos();
+			match self.lexer.next()? {
+				Some(Token::ArrayEnd) => break,
+				Some(token) => array.push(self.parse_from(token, pos)?),
+				None => return Err(PdfError::syntax(pos, "unterminated array")),
+			}
+		}
+
+		return Ok(PdfObject::Array(array));
+	}
+
+	/// Attempts to parse the entries of a dictionary, after the opening `<<`.
+	pub fn dictionary(&mut self) -> Result<Dictionary, PdfError> {
+		let mut dict = Dictionary::new();
+		loop {
+			let pos = self.lexer.pos();
+			let key = match self.lexer.next()? {
+				Some(Token::DictEnd) => break,
+				Some(Token::Name(name)) => name,
+				Some(_) => return Err(PdfError::syntax(pos, "dictionary key is not a name")),
+				None => return Err(PdfError::syntax(pos, "unterminated dictionary")),
+			};
+
+			let value = self.parse_object()?;
+			// Entries with null values are equivalent to absent ones.
+			if value != PdfObject::Null {
+				dict.set_raw(&key, value);
+			}
+		}
+
+		return Ok(dict);
+	}
+}
+
+/// Checks whether the keyword was meant to be a number, e.g. `--5` or `1.2.3`.
+fn is_malformed_number(word: &[u8]) -> bool {
+	return word.iter().all(|&byte| byte.is_ascii_digit() || matches!(byte, b'+' | b'-' | b'.')) && word.iter().any(u8::is_ascii_digit);
+}
+
+/// Reads the number the way most readers do: repeated signs are the same as a
+/// single one, and everything after a second sign or decimal point is ignored.
+fn recover_number(word: &[u8]) -> PdfObject {
+	let start = word.iter().position(|&byte| !matches!(byte, b'+' | b'-')).unwrap_or(word.len());
+	let negative = word[..start].contains(&b'-');
+	let mut end = start;
+	let mut point = false;
+	while end < word.len() {
+		match word[end] {
+			b'0'..=b'9' => {},
+			b'.' if !point => point = true,
+			_ => break,
+		}
+		end += 1;
+	}
+
+	let text = std::str::from_utf8(&word[start..end]).unwrap_or_default();
+	let sign = if negative { -1 } else { 1 };
+	if !point {
+		return PdfObject::Integer(text.parse::<i64>().unwrap_or(0) * sign);
+	}
+	return PdfObject::Real(text.parse::<f64>().unwrap_or(0.0) * sign as f64);
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::parser::*;
+
+	#[test]
+	fn references_and_nesting() {
+		let mut parser = Parser::new(b"<< /Kids [3 0 R 4 0 R] /Count 2 /Skip null /Box [0 0 612.5 792] >>", 0);
+		let object = parser.parse_object().unwrap();
+
+		let mut expected = Dictionary::new();
+		expected.set("Kids", vec![PdfObject::Reference(ObjectId::new(3, 0)), PdfObject::Reference(ObjectId::new(4, 0))]);
+		expected.set("Count", 2);
+		expected.set("Box", vec![0.into(), 0.into(), 612.5.into(), 792.into()]);
+		assert_eq!(object, PdfObject::Dictionary(expected));
+	}
+}
@@ -0,0 +1,338 @@
+//! Conformance with the PDF/A standard for archival documents.
+
+use crate::{Dictionary, Document, DocumentInfo, PdfDate, PdfError, PdfObject, Stream};
+
+/// Part and conformance level of the PDF/A standard.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PdfAVersion {
+	/// PDF/A-1b, i.e. ISO 19005-1 with the basic conformance level, which
+	/// ensures the visual appearance is preserved.
+	A1b,
+}
+
+impl PdfAVersion {
+	/// Gets the PDF version the part is based on.
+	fn pdf_version(self) -> (u8, u8) {
+		return match self {
+			Self::A1b => (1, 4),
+		};
+	}
+
+	/// Gets the part and conformance level, as written in the metadata.
+	fn identification(self) -> (u8, &'static str) {
+		return match self {
+			Self::A1b => (1, "B"),
+		};
+	}
+
+	/// Gets the output intent subtype required by the part.
+	fn output_intent(self) -> &'static [u8] {
+		return match self {
+			Self::A1b => b"GTS_PDFA1",
+		};
+	}
+}
+
+impl Document {
+	/// Makes the document conform to the given part of the PDF/A standard.
+	/// The constraints which can't be met automatically are checked whenever
+	/// the document is written, which fails if any of them is not met:
+	///
+	/// - all fonts have their programs embedded,
+	/// - the catalog has an output intent with an ICC profile, added with
+	///   [`Document::set_output_intent`],
+	/// - nothing uses transparency, i.e. soft masks (`/SMask`), constant
+	///   opacities (`/CA` and `/ca`) below 1, or blend modes (`/BM`) other
+	///   than `Normal`.
+	///
+	/// The XMP metadata identifying the part, with the entries of the
+	/// document information dictionary, and the file identifier in the trailer
+	/// are added when writing. The documents are never encrypted.
+	pub fn set_pdfa_mode(&mut self, version: PdfAVersion) {
+		self.pdfa = Some(version);
+		self.set_version(version.pdf_version());
+	}
+
+	/// Gets the object, resolving it if it's a reference.
+	fn pdfa_resolve<'a>(&'a self, object: &'a PdfObject) -> &'a PdfObject {
+		return match object {
+			PdfObject::Reference(id) => self.objects.get(id).unwrap_or(&PdfObject::Null),
+			object => object,
+		};
+	}
+
+	/// Checks whether the font dictionary has its font program embedded.
+	fn is_font_embedded(&self, font: &Dictionary) -> bool {
+		return match font.get_name("Subtype") {
+			// Glyphs of Type 3 fonts are content streams within the font.
+			Some(b"Type3") => true,
+			Some(b"Type0") => {
+				let descendants = font.get("DescendantFonts").map(|fonts| self.pdfa_resolve(fonts));
+				descendants.and_then(PdfObject::as_array).is_some_and(|fonts| fonts.iter().all(|font| self.pdfa_resolve(font).as_dict().is_some_and(|font| self.is_font_embedded(font))))
+			},
+			_ => {
+				let descriptor = font.get("FontDescriptor").map(|descriptor| self.pdfa_resolve(descriptor)).and_then(PdfObject::as_dict);
+				descriptor.is_some_and(|descriptor| ["FontFile", "FontFile2", "FontFile3"].iter().any(|key| descriptor.contains_key(key)))
+			},
+		};
+	}
+
+	/// Finds the entry of the object, or the objects within it, which uses
+	/// transparency, forbidden by PDF/A-1.
+	fn find_transparency(object: &PdfObject) -> Option<&'static str> {
+		let dict = match object {
+			PdfObject::Array(array) => return array.iter().find_map(Self::find_transparency),
+			PdfObject::Dictionary(dict) => dict,
+			PdfObject::Stream(stream) => &stream.dict,
+			_ => return None,
+		};
+		if dict.get("SMask").is_some_and(|mask| mask.as_name() != Some(b"None")) {
+			return Some("SMask");
+		}
+		for key in ["CA", "ca"] {
+			if dict.get(key).and_then(PdfObject::as_number).is_some_and(|alpha| alpha < 1.0) {
+				return Some(key);
+			}
+		}
+		let separable = |mode: &PdfObject| matches!(mode.as_name(), Some(b"Normal" | b"Compatible"));
+		let blend_mode = match dict.get("BM") {
+			Some(PdfObject::Array(modes)) => modes.iter().all(separable),
+			Some(mode) => separable(mode),
+			None => true,
+		};
+		if !blend_mode {
+			return Some("BM");
+		}
+		return dict.iter().find_map(|(_, value)| Self::find_transparency(value));
+	}
+
+	/// Attempts to check the constraints of the PDF/A part, and adds the
+	/// metadata identifying it.
+	pub(crate) fn prepare_pdfa(&mut self, version: PdfAVersion) -> Result<(), PdfError> {
+		if self.effective_version() > version.pdf_version() {
+			let (major, minor) = self.effective_version();
+			return Err(PdfError::Conformance(std::format!("document uses features of PDF {}.{}", major, minor)));
+		}
+		if !self.binary_marker.is_some_and(|marker| marker.iter().all(|&byte| byte >= 0x80)) {
+			return Err(PdfError::Conformance("header is not followed by a binary comment".to_string()));
+		}
+		for object in self.objects.values() {
+			let Some(font) = object.as_dict().filter(|dict| dict.get_name("Type") == Some(b"Font")) else {
+				continue;
+			};
+			// Descendant fonts are checked together with their parents.
+			if font.get_name("Subtype").is_some_and(|subtype| subtype.starts_with(b"CIDFont")) {
+				continue;
+			}
+			if !self.is_font_embedded(font) {
+				let name = String::from_utf8_lossy(font.get_name("BaseFont").unwrap_or(b"unnamed")).into_owned();
+				return Err(PdfError::Conformance(std::format!("font /{} is not embedded", name)));
+			}
+		}
+
+		for (id, object) in &self.objects {
+			if let Some(key) = Self::find_transparency(object) {
+				return Err(PdfError::Conformance(std::format!("object {} {} R uses transparency (/{})", id.number, id.generation, key)));
+			}
+		}
+
+		let intents = self.catalog.get("OutputIntents").map(|intents| self.pdfa_resolve(intents)).and_then(PdfObject::as_array);
+		let has_intent = intents.into_iter().flatten().filter_map(|intent| self.pdfa_resolve(intent).as_dict()).any(|intent| {
+			return intent.get_name("S") == Some(version.output_intent()) && intent.get("DestOutputProfile").is_some_and(|profile| self.pdfa_resolve(profile).as_stream().is_some());
+		});
+		if !has_intent {
+			let subtype = String::from_utf8_lossy(version.output_intent()).into_owned();
+			return Err(PdfError::Conformance(std::format!("document has no /{} output intent with an ICC profile", subtype)));
+		}
+
+		let id = match self.catalog.get("Metadata") {
+			Some(&PdfObject::Reference(id)) => id,
+			_ => self.reserve_id(),
+		};
+		let info = self.info().unwrap_or_default();
+		self.set_object(id, metadata_stream(version, &info));
+		self.catalog.set("Metadata", id);
+		return Ok(());
+	}
+}
+
+/// Escapes the text for XML character data.
+fn escape_xml(text: &str) -> String {
+	return text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;");
+}
+
+/// Formats the date as in XMP, e.g. `2024-01-02T03:04:05+01:00`.
+fn xmp_date(date: &PdfDate) -> String {
+	let offset = match date.utc_offset {
+		None => String::new(),
+		Some(0) => "Z".to_string(),
+		Some(offset) => std::format!("{}{:02}:{:02}", if offset < 0 { '-' } else { '+' }, offset.unsigned_abs() / 60, offset.unsigned_abs() % 60),
+	};
+	return std::format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}{}", date.year, date.month, date.day, date.hour, date.minute, date.second, offset);
+}
+
+/// Creates the XMP properties equivalent to the entries of the document
+/// information dictionary, which PDF/A requires to be the same.
+fn info_properties(info: &DocumentInfo) -> String {
+	let mut properties = String::new();
+	let alternative = |text: &str| std::format!("<rdf:Alt><rdf:li xml:lang=\"x-default\">{}</rdf:li></rdf:Alt>", escape_xml(text));
+	let texts = [
+		("dc:title", info.title.as_deref().map(alternative)),
+		("dc:creator", info.author.as_deref().map(|author| std::format!("<rdf:Seq><rdf:li>{}</rdf:li></rdf:Seq>", escape_xml(author)))),
+		("dc:description", info.subject.as_deref().map(alternative)),
+		("pdf:Keywords", info.keywords.as_deref().map(escape_xml)),
+		("xmp:CreatorTool", info.creator.as_deref().map(escape_xml)),
+		("pdf:Producer", info.producer.as_deref().map(escape_xml)),
+		("xmp:CreateDate", info.creation_date.as_ref().map(xmp_date)),
+		("xmp:ModifyDate", info.modification_date.as_ref().map(xmp_date)),
+	];
+	for (property, value) in texts {
+		if let Some(value) = value {
+			properties.push_str(&std::format!("<{}>{}</{}>\n", property, value, property));
+		}
+	}
+	return properties;
+}
+
+/// Creates the XMP metadata stream with the PDF/A identification schema, and
+/// the entries of the document information dictionary. It's not compressed,
+/// as PDF/A-1 requires it to be readable by tools which don't understand PDF.
+fn metadata_stream(version: PdfAVersion, info: &DocumentInfo) -> Stream {
+	let (part, conformance) = version.identification();
+	let packet = std::format!(
+		concat!(
+			"<?xpacket begin=\"\u{feff}\" id=\"W5M0MpCehiHzreSzNTczkc9d\"?>\n",
+			"<x:xmpmeta xmlns:x=\"adobe:ns:meta/\">\n",
+			"<rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\">\n",
+			"<rdf:Description rdf:about=\"\" xmlns:pdfaid=\"http://www.aiim.org/pdfa/ns/id/\">\n",
+			"<pdfaid:part>{}</pdfaid:part>\n",
+			"<pdfaid:conformance>{}</pdfaid:conformance>\n",
+			"</rdf:Description>\n",
+			"<rdf:Description rdf:about=\"\" xmlns:dc=\"http://purl.org/dc/elements/1.1/\" xmlns:xmp=\"http://ns.adobe.com/xap/1.0/\" xmlns:pdf=\"http://ns.adobe.com/pdf/1.3/\">\n",
+			"{}",
+			"</rdf:Description>\n",
+			"</rdf:RDF>\n",
+			"</x:xmpmeta>\n",
+			"<?xpacket end=\"w\"?>",
+		),
+		part,
+		conformance,
+		info_properties(info),
+	);
+
+	let mut dict = Dictionary::new();
+	dict.set("Type", PdfObject::name("Metadata"));
+	dict.set("Subtype", PdfObject::name("XML"));
+	return Stream::new(dict, packet.into_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::pdfa::*;
+	use crate::*;
+
+	#[test]
+	fn pdfa_1b() {
+		let mut doc = Document::new();
+		doc.set_pdfa_mode(PdfAVersion::A1b);
+		let font = doc.add_base_font("Helvetica");
+		doc.add_page(612.0, 792.0).add_font_resource("F1", font);
+
+		let mut output = Vec::new();
+		let error = doc.write(&mut Writer::new(&mut output)).unwrap_err();
+		assert!(matches!(error.get_ref().and_then(|error| error.downcast_ref()), Some(PdfError::Conformance(message)) if message.contains("Helvetica")));
+		assert!(output.is_empty());
+
+		// Makes the font look embedded, and adds a bare output intent.
+		let mut descriptor = Dictionary::new();
+		descriptor.set("FontFile", doc.add_object(Stream::new(Dictionary::new(), b"%!PS".to_vec())));
+		let descriptor = doc.add_object(descriptor);
+		doc.object_mut(font.0).unwrap().as_dict_mut().unwrap().set("FontDescriptor", descriptor);
+		let error = doc.write(&mut Writer::new(&mut output)).unwrap_err();
+		assert!(error.to_string().contains("output intent"));
+
+		let mut intent = Dictionary::new();
+		intent.set("Type", PdfObject::name("OutputIntent"));
+		intent.set("S", PdfObject::name("GTS_PDFA1"));
+		intent.set("DestOutputProfile", doc.add_object(Stream::new(Dictionary::new(), b"icc".to_vec())));
+		doc.catalog.set("OutputIntents", std::vec![PdfObject::Dictionary(intent)]);
+		doc.write(&mut Writer::new(&mut output)).unwrap();
+		assert!(output.starts_with(b"%PDF-1.4\n"));
+
+		let reader = Reader::new(&output).unwrap();
+		let metadata = reader.resolve(doc.catalog.get("Metadata").and_then(PdfObject::as_reference).unwrap()).unwrap();
+		let packet = String::from_utf8(metadata.as_stream().unwrap().data.clone()).unwrap();
+		assert!(packet.contains("<pdfaid:part>1</pdfaid:part>") && packet.contains("<pdfaid:conformance>B</pdfaid:conformance>"));
+		let id = reader.trailer().get("ID").and_then(PdfObject::as_array).unwrap();
+		assert_eq!(id.len(), 2);
+
+		// The metadata is replaced, rather than added again.
+		let count = doc.objects.len();
+		doc.write(&mut Writer::new(&mut Vec::new())).unwrap();
+		assert_eq!(doc.objects.len(), count);
+
+		doc.set_binary_marker(Some(*b"text"));
+		assert!(doc.write(&mut Writer::new(&mut Vec::new())).unwrap_err().to_string().contains("binary comment"));
+	}
+
+	/// Creates PDF/A-1b document with an embedded font and an output intent,
+	/// which only fails the constraints set up by the test.
+	fn conforming() -> Document {
+		let mut doc = Document::new();
+		doc.set_pdfa_mode(PdfAVersion::A1b);
+		let mut intent = Dictionary::new();
+		intent.set("S", PdfObject::name("GTS_PDFA1"));
+		intent.set("DestOutputProfile", doc.add_object(Stream::new(Dictionary::new(), b"icc".to_vec())));
+		doc.catalog.set("OutputIntents", std::vec![PdfObject::Dictionary(intent)]);
+		doc.add_page(612.0, 792.0);
+		return doc;
+	}
+
+	#[test]
+	fn transparency() {
+		let states = [
+			ExtGState { fill_alpha: Some(0.5), ..ExtGState::new() },
+			ExtGState { stroke_alpha: Some(0.0), ..ExtGState::new() },
+			ExtGState { blend_mode: Some(BlendMode::Multiply), ..ExtGState::new() },
+		];
+		for (state, key) in states.iter().zip(["/ca", "/CA", "/BM"]) {
+			let mut doc = conforming();
+			doc.add_ext_gstate(state);
+			let error = doc.write(&mut Writer::new(&mut Vec::new())).unwrap_err().to_string();
+			assert!(error.contains(&std::format!("uses transparency ({})", key)), "{}", error);
+		}
+
+		let mut doc = conforming();
+		let mut dict = Dictionary::new();
+		dict.set("Subtype", PdfObject::name("Form"));
+		let form = doc.add_object(Stream::new(dict, Vec::new()));
+		doc.add_soft_mask_group(form, SoftMaskSubtype::Alpha).unwrap();
+		assert!(doc.write(&mut Writer::new(&mut Vec::new())).unwrap_err().to_string().contains("(/SMask)"));
+
+		let mut doc = conforming();
+		doc.add_ext_gstate(&ExtGState { fill_alpha: Some(1.0), blend_mode: Some(BlendMode::Normal), ..ExtGState::new() });
+		doc.write(&mut Writer::new(&mut Vec::new())).unwrap();
+	}
+
+	#[test]
+	fn metadata_info() {
+		let mut doc = conforming();
+		doc.set_info(&DocumentInfo {
+			title: Some("Q&A <draft>".to_string()),
+			author: Some("Aodhnait".to_string()),
+			producer: Some("pdf-rs".to_string()),
+			creation_date: Some(PdfDate::from_parts(2024, 1, 2, 3, 4, 5, Some(90)).unwrap()),
+			modification_date: Some(PdfDate::from_parts(2024, 2, 3, 4, 5, 6, Some(0)).unwrap()),
+			..DocumentInfo::default()
+		});
+		doc.write(&mut Writer::new(&mut Vec::new())).unwrap();
+		let metadata = doc.catalog.get("Metadata").and_then(PdfObject::as_reference).unwrap();
+		let packet = String::from_utf8(doc.object(metadata).and_then(PdfObject::as_stream).unwrap().data.clone()).unwrap();
+		assert!(packet.contains("<dc:title><rdf:Alt><rdf:li xml:lang=\"x-default\">Q&amp;A &lt;draft&gt;</rdf:li></rdf:Alt></dc:title>"));
+		assert!(packet.contains("<dc:creator><rdf:Seq><rdf:li>Aodhnait</rdf:li></rdf:Seq></dc:creator>"));
+		assert!(packet.contains("<pdf:Producer>pdf-rs</pdf:Producer>"));
+		assert!(packet.contains("<xmp:CreateDate>2024-01-02T03:04:05+01:30</xmp:CreateDate>"));
+		assert!(packet.contains("<xmp:ModifyDate>2024-02-03T04:05:06Z</xmp:ModifyDate>"));
+		assert!(!packet.contains("dc:description"));
+	}
+}
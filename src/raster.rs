@@ -0,0 +1,421 @@
+//! Simple rasterizer of page contents, good enough for e.g. thumbnails.
+//!
+//! Only paths are painted properly, with solid colours and without
+//! anti-aliasing. Text is drawn as bars covering the runs of text, and images,
+//! shadings, and clipping are ignored.
+
+use crate::content::Operation;
+use crate::{filters, text, Image, Matrix, Page, PdfError, PdfObject, Point, Rectangle};
+
+/// Number of line segments every Bézier curve is flattened into.
+const CURVE_SEGMENTS: usize = 16;
+
+/// RGB bitmap with 8 bits per component, stored row by row starting with the
+/// top one.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Bitmap {
+	pub width: u32,
+	pub height: u32,
+	pub data: Vec<u8>,
+}
+
+impl Bitmap {
+	/// Creates new white bitmap.
+	pub fn new(width: u32, height: u32) -> Self {
+		return Self {
+			width,
+			height,
+			data: std::vec![0xFF; width as usize * height as usize * 3],
+		};
+	}
+
+	/// Gets the colour of the pixel, where `(0, 0)` is the top left corner.
+	pub fn pixel(&self, x: u32, y: u32) -> [u8; 3] {
+		let index = (y as usize * self.width as usize + x as usize) * 3;
+		return [self.data[index], self.data[index + 1], self.data[index + 2]];
+	}
+
+	/// Converts the bitmap into an RGB image.
+	pub fn to_image(&self) -> Image {
+		return Image::new(self.width, self.height, 3, self.data.clone());
+	}
+
+	/// Encodes the bitmap as a PNG image, e.g. to save a rendered page.
+	pub fn to_png(&self) -> Vec<u8> {
+		let mut header = Vec::with_capacity(13);
+		header.extend_from_slice(&self.width.to_be_bytes());
+		header.extend_from_slice(&self.height.to_be_bytes());
+		// 8 bits per component, truecolour, and no interlacing.
+		header.extend_from_slice(&[8, 2, 0, 0, 0]);
+
+		// Every row starts with its filter type, which is always none.
+		let row = self.width as usize * 3;
+		let mut scanlines = Vec::with_capacity((row + 1) * self.height as usize);
+		for line in self.data.chunks(row.max(1)).take(self.height as usize) {
+			scanlines.push(0);
+			scanlines.extend_from_slice(line);
+		}
+
+		let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+		write_chunk(&mut png, b"IHDR", &header);
+		write_chunk(&mut png, b"IDAT", &filters::flate::encode(&scanlines));
+		write_chunk(&mut png, b"IEND", &[]);
+		return png;
+	}
+
+	/// Fills the span of pixels whose centres lie between the two positions.
+	fn fill_span(&mut self, row: u32, x0: f64, x1: f64, color: [u8; 3]) {
+		let start = (x0 - 0.5).ceil().max(0.0) as usize;
+		let end = ((x1 - 0.5).ceil().max(0.0) as usize).min(self.width as usize);
+		let offset = row as usize * self.width as usize;
+		for x in start..end {
+			let index = (offset + x) * 3;
+			self.data[index..index + 3].copy_from_slice(&color);
+		}
+	}
+
+	/// Fills the area enclosed by the polygons, given in device coordinates,
+	/// using either the nonzero winding number or the even-odd rule.
+	fn fill(&mut self, polygons: &[Vec<Point>], even_odd: bool, color: [u8; 3]) {
+		let mut crossings = Vec::new();
+		for row in 0..self.height {
+			let y = row as f64 + 0.5;
+			crossings.clear();
+			for polygon in polygons {
+				for (index, &p) in polygon.iter().enumerate() {
+					let q = polygon[(index + 1) % polygon.len()];
+					if (p.y <= y) == (q.y <= y) {
+						continue;
+					}
+					let x = p.x + (y - p.y) / (q.y - p.y) * (q.x - p.x);
+					crossings.push((x, if q.y > p.y { 1 } else { -1 }));
+				}
+			}
+			crossings.sort_by(|a: &(f64, i32), b| a.0.total_cmp(&b.0));
+
+			let mut winding = 0;
+			for (index, pair) in crossings.windows(2).enumerate() {
+				winding += pair[0].1;
+				let inside = if even_odd { index % 2 == 0 } else { winding != 0 };
+				if inside {
+					self.fill_span(row, pair[0].0, pair[1].0, color);
+				}
+			}
+		}
+	}
+}
+
+/// Subpath of the current path, in device coordinates.
+#[derive(Clone, Debug, Default)]
+struct Subpath {
+	points: Vec<Point>,
+	closed: bool,
+}
+
+/// Parameters of the graphics state which affect painting.
+#[derive(Clone, Copy, Debug)]
+struct State {
+	ctm: Matrix,
+	fill: [u8; 3],
+	stroke: [u8; 3],
+	line_width: f64,
+}
+
+/// Computes the CRC-32 checksum used by PNG chunks.
+fn crc32(bytes: &[u8]) -> u32 {
+	let mut crc = !0u32;
+	for &byte in bytes {
+		crc ^= byte as u32;
+		for _ in 0..8 {
+			crc = if crc & 1 != 0 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+		}
+	}
+	return !crc;
+}
+
+/// Appends the PNG chunk with its length and checksum.
+fn write_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+	png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+	let start = png.len();
+	png.extend_from_slice(kind);
+	png.extend_from_slice(data);
+	let crc = crc32(&png[start..]);
+	png.extend_from_slice(&crc.to_be_bytes());
+}
+
+/// Converts the colour operands to RGB, based on their number.
+fn color(operation: &Operation) -> Option<[u8; 3]> {
+	let components: Vec<f64> = operation.operands.iter().filter_map(PdfObject::as_number).map(|value| value.clamp(0.0, 1.0)).collect();
+	let rgb = match *components.as_slice() {
+		[gray] => [gray; 3],
+		[r, g, b] => [r, g, b],
+		[c, m, y, k] => [(1.0 - c) * (1.0 - k), (1.0 - m) * (1.0 - k), (1.0 - y) * (1.0 - k)],
+		_ => return None,
+	};
+	return Some(rgb.map(|value| (value * 255.0).round() as u8));
+}
+
+/// Flattens the cubic Bézier curve, appending the points after its start.
+fn flatten_curve(points: &mut Vec<Point>, p0: Point, p1: Point, p2: Point, p3: Point) {
+	for step in 1..=CURVE_SEGMENTS {
+		let t = step as f64 / CURVE_SEGMENTS as f64;
+		let u = 1.0 - t;
+		let (a, b, c, d) = (u * u * u, 3.0 * u * u * t, 3.0 * u * t * t, t * t * t);
+		points.push(Point::new(a * p0.x + b * p1.x + c * p2.x + d * p3.x, a * p0.y + b * p1.y + c * p2.y + d * p3.y));
+	}
+}
+
+struct Rasterizer {
+	bitmap: Bitmap,
+	/// Transformation from the default user space to the bitmap pixels.
+	device: Matrix,
+	state: State,
+	stack: Vec<State>,
+	path: Vec<Subpath>,
+}
+
+impl Rasterizer {
+	/// Transforms the point from the user space into device coordinates.
+	fn point(&self, x: f64, y: f64) -> Point {
+		return self.state.ctm.then(&self.device).transform(Point::new(x, y));
+	}
+
+	fn current(&mut self) -> &mut Subpath {
+		if self.path.is_empty() {
+			self.path.push(Subpath::default());
+		}
+		return self.path.last_mut().unwrap();
+	}
+
+	fn current_point(&self) -> Option<Point> {
+		return self.path.last().and_then(|subpath| subpath.points.last().copied());
+	}
+
+	fn move_to(&mut self, point: Point) {
+		self.path.push(Subpath {
+			points: std::vec![point],
+			closed: false,
+		});
+	}
+
+	fn curve_to(&mut self, p1: Point, p2: Point, p3: Point) {
+		let Some(p0) = self.current_point() else {
+			return;
+		};
+		flatten_curve(&mut self.current().points, p0, p1, p2, p3);
+	}
+
+	fn close_path(&mut self) {
+		let Some(start) = self.path.last().and_then(|subpath| subpath.points.first().copied()) else {
+			return;
+		};
+		self.current().closed = true;
+		self.move_to(start);
+	}
+
+	/// Paints the current path and starts a new one.
+	fn paint(&mut self, fill: Option<bool>, stroke: bool) {
+		let path = std::mem::take(&mut self.path);
+		if let Some(even_odd) = fill {
+			let polygons: Vec<Vec<Point>> = path.iter().filter(|subpath| subpath.points.len() > 2).map(|subpath| subpath.points.clone()).collect();
+			self.bitmap.fill(&polygons, even_odd, self.state.fill);
+		}
+		if stroke {
+			self.stroke(&path);
+		}
+	}
+
+	/// Strokes the path by filling a quadrilateral for each of its segments.
+	fn stroke(&mut self, path: &[Subpath]) {
+		let matrix = self.state.ctm.then(&self.device);
+		let scale = (matrix.a * matrix.d - matrix.b * matrix.c).abs().sqrt();
+		// Zero width lines are the thinnest lines which can be rendered.
+		let half_width = (self.state.line_width * scale / 2.0).max(0.5);
+
+		for subpath in path {
+			let points = &subpath.points;
+			let count = if subpath.closed { points.len() } else { points.len().saturating_sub(1) };
+			for index in 0..count {
+				let (p, q) = (points[index], points[(index + 1) % points.len()]);
+				let (dx, dy) = (q.x - p.x, q.y - p.y);
+				let length = (dx * dx + dy * dy).sqrt();
+				if length == 0.0 {
+					continue;
+				}
+				let (nx, ny) = (-dy / length * half_width, dx / length * half_width);
+				let quad = std::vec![
+					Point::new(p.x + nx, p.y + ny),
+					Point::new(q.x + nx, q.y + ny),
+					Point::new(q.x - nx, q.y - ny),
+					Point::new(p.x - nx, p.y - ny),
+				];
+				self.bitmap.fill(&[quad], false, self.state.stroke);
+			}
+		}
+	}
+
+	/// Paints a bar covering the run of text, in a lighter shade of the fill
+	/// colour.
+	fn greek(&mut self, bbox: Rectangle) {
+		let corners = [
+			Point::new(bbox.llx, bbox.lly),
+			Point::new(bbox.urx, bbox.lly),
+			Point::new(bbox.urx, bbox.ury),
+			Point::new(bbox.llx, bbox.ury),
+		];
+		let polygon = corners.iter().map(|&corner| self.device.transform(corner)).collect();
+		let color = self.state.fill.map(|value| ((value as u16 + 0xFF) / 2) as u8);
+		self.bitmap.fill(&[polygon], false, color);
+	}
+
+	fn run(&mut self, operations: &[Operation]) {
+		let runs = text::text_runs(operations);
+		let mut runs = runs.iter().peekable();
+
+		for (index, operation) in operations.iter().enumerate() {
+			let n = |i| operation.number(i);
+			match operation.operator.as_slice() {
+				b"q" => self.stack.push(self.state),
+				b"Q" => {
+					if let Some(state) = self.stack.pop() {
+						self.state = state;
+					}
+				},
+				b"cm" => {
+					let matrix = Matrix::new(n(0), n(1), n(2), n(3), n(4), n(5));
+					self.state.ctm = matrix.then(&self.state.ctm);
+				},
+				b"w" => self.state.line_width = n(0),
+				b"g" | b"rg" | b"k" | b"sc" | b"scn" => {
+					if let Some(color) = color(operation) {
+						self.state.fill = color;
+					}
+				},
+				b"G" | b"RG" | b"K" | b"SC" | b"SCN" => {
+					if let Some(color) = color(operation) {
+						self.state.stroke = color;
+					}
+				},
+				b"m" => self.move_to(self.point(n(0), n(1))),
+				b"l" => {
+					let point = self.point(n(0), n(1));
+					self.current().points.push(point);
+				},
+				b"c" => self.curve_to(self.point(n(0), n(1)), self.point(n(2), n(3)), self.point(n(4), n(5))),
+				b"v" => {
+					if let Some(p1) = self.current_point() {
+						self.curve_to(p1, self.point(n(0), n(1)), self.point(n(2), n(3)));
+					}
+				},
+				b"y" => {
+					let p3 = self.point(n(2), n(3));
+					self.curve_to(self.point(n(0), n(1)), p3, p3);
+				},
+				b"h" => self.close_path(),
+				b"re" => {
+					let (x, y, width, height) = (n(0), n(1), n(2), n(3));
+					self.path.push(Subpath {
+						points: std::vec![self.point(x, y), self.point(x + width, y), self.point(x + width, y + height), self.point(x, y + height)],
+						closed: true,
+					});
+				},
+				b"f" | b"F" => self.paint(Some(false), false),
+				b"f*" => self.paint(Some(true), false),
+				b"S" => self.paint(None, true),
+				b"s" => {
+					self.close_path();
+					self.paint(None, true);
+				},
+				b"B" => self.paint(Some(false), true),
+				b"B*" => self.paint(Some(true), true),
+				b"b" => {
+					self.close_path();
+					self.paint(Some(false), true);
+				},
+				b"b*" => {
+					self.close_path();
+					self.paint(Some(true), true);
+				},
+				b"n" => self.path.clear(),
+				_ => {},
+			}
+
+			while let Some(run) = runs.next_if(|run| run.operation == index) {
+				self.greek(run.bbox);
+			}
+		}
+	}
+}
+
+impl Page<'_> {
+	/// Attempts to render the page into a bitmap, where one unit of the
+	/// default user space becomes `scale` pixels.
+	pub fn render(&self, scale: f64) -> Result<Bitmap, PdfError> {
+		if !(scale.is_finite() && scale > 0.0) {
+			return Err(PdfError::InvalidArgument(std::format!("invalid rendering scale {}", scale)));
+		}
+		let Some(media_box) = self.media_box() else {
+			return Err(PdfError::InvalidArgument("page has no valid /MediaBox".to_string()));
+		};
+
+		let size = |length: f64| (length * scale).ceil().max(1.0) as u32;
+		let mut rasterizer = Rasterizer {
+			bitmap: Bitmap::new(size(media_box.width()), size(media_box.height())),
+			device: Matrix::translate(-media_box.llx, -media_box.ury).then(&Matrix::scale(scale, -scale)),
+			state: State {
+				ctm: Matrix::IDENTITY,
+				fill: [0; 3],
+				stroke: [0; 3],
+				line_width: 1.0,
+			},
+			stack: Vec::new(),
+			path: Vec::new(),
+		};
+		rasterizer.run(&self.operations()?);
+		return Ok(rasterizer.bitmap);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::*;
+
+	#[test]
+	fn render_paths() {
+		let mut doc = Document::new();
+		let mut content = ContentStream::new();
+		content.set_fill_color(Color::Rgb(1.0, 0.0, 0.0)).rect(Rectangle::new(0.0, 0.0, 50.0, 50.0)).fill();
+		content.set_stroke_color(Color::Gray(0.0)).set_line_width(4.0).move_to(Point::new(0.0, 90.0)).line_to(Point::new(100.0, 90.0)).stroke();
+		doc.add_page(100.0, 100.0).set_contents(content);
+
+		let bitmap = doc.page_mut(0).unwrap().render(0.5).unwrap();
+		assert_eq!((bitmap.width, bitmap.height), (50, 50));
+		// The rectangle covers the bottom left quarter, and the line is near the top.
+		assert_eq!(bitmap.pixel(5, 45), [0xFF, 0, 0]);
+		assert_eq!(bitmap.pixel(30, 45), [0xFF; 3]);
+		assert_eq!(bitmap.pixel(30, 4), [0; 3]);
+		assert_eq!(bitmap.pixel(30, 10), [0xFF; 3]);
+	}
+
+	#[test]
+	fn png_output() {
+		let mut doc = Document::new();
+		let mut content = ContentStream::new();
+		content.set_fill_color(Color::Rgb(0.0, 0.0, 1.0)).rect(Rectangle::new(0.0, 0.0, 20.0, 10.0)).fill();
+		doc.add_page(40.0, 30.0).set_contents(content);
+
+		let bitmap = doc.page_mut(0).unwrap().render(1.0).unwrap();
+		let png = bitmap.to_png();
+		assert!(png.starts_with(b"\x89PNG\r\n\x1a\n"));
+		assert_eq!(&png[8..16], b"\0\0\0\x0dIHDR");
+		assert_eq!((u32::from_be_bytes(png[16..20].try_into().unwrap()), u32::from_be_bytes(png[20..24].try_into().unwrap())), (bitmap.width, bitmap.height));
+		// The checksum of the empty end chunk is well known.
+		assert!(png.ends_with(&[0, 0, 0, 0, b'I', b'E', b'N', b'D', 0xAE, 0x42, 0x60, 0x82]));
+
+		let length = u32::from_be_bytes(png[33..37].try_into().unwrap()) as usize;
+		assert_eq!(&png[37..41], b"IDAT");
+		let scanlines = filters::flate::decode(&png[41..41 + length]).unwrap();
+		assert_eq!(scanlines.len(), (40 * 3 + 1) * 30);
+		assert_eq!(&scanlines[29 * 121..29 * 121 + 4], &[0, 0, 0, 0xFF]);
+	}
+}
@@ -0,0 +1,1569 @@
+//! Reader of existing PDF files.
+
+use std::borrow::Cow;
+use std::cell::OnceCell;
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::content::{parse_operations, pretty_print, Operation};
+use crate::lexer::{self, Token};
+use crate::parser::Parser;
+use crate::filters::FilterRegistry;
+use crate::{text, Dictionary, ObjectId, PdfError, PdfObject, Rectangle, Stream};
+
+/// Page attributes which are inherited from the ancestor page tree nodes,
+/// whenever the page itself doesn't have them.
+pub(crate) const INHERITABLE: [&str; 4] = ["Resources", "MediaBox", "CropBox", "Rotate"];
+
+/// Number of bytes after the end-of-file marker which are ignored in the
+/// lenient mode.
+const MAX_TRAILING_JUNK: usize = 1024;
+
+/// Location of an object in use, taken from the cross-reference table.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum XrefEntry {
+	/// Object at the given offset in the file.
+	Offset { offset: usize, generation: u16 },
+	/// Object stored in the object stream with the given number, which always
+	/// has the generation 0.
+	Compressed { stream: u32, index: usize },
+}
+
+impl XrefEntry {
+	#[inline]
+	pub fn generation(&self) -> u16 {
+		return match self {
+			Self::Offset { generation, .. } => *generation,
+			Self::Compressed { .. } => 0,
+		};
+	}
+}
+
+/// How the reader handles files which don't follow the specification.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ParseMode {
+	/// Any deviation is an error.
+	Strict,
+	/// Common mistakes are recovered from, e.g. the cross-reference table is
+	/// rebuilt by scanning the file when it's broken.
+	#[default]
+	Lenient,
+}
+
+/// Event reported while reading a file, showing where a broken file was
+/// recovered from, or why it failed to be read.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Diagnostic {
+	/// Cross-reference table or stream was read at the offset.
+	XrefSection { offset: usize },
+	/// Cross-reference table was rebuilt by scanning the file for objects,
+	/// after failing to be read with the error.
+	XrefRebuilt { error: String, objects: usize },
+	/// Malformed object was recovered from, e.g. found at another offset.
+	ObjectRecovered { id: ObjectId, message: String },
+	/// Data of the stream object could not be decoded.
+	FilterFailed { id: ObjectId, message: String },
+}
+
+/// Reader of a PDF file held in memory. Objects are parsed lazily, whenever
+/// they're resolved.
+pub struct Reader<'a> {
+	data: &'a [u8],
+	mode: ParseMode,
+	/// Callback which receives the diagnostics.
+	diagnostics: Option<&'a dyn Fn(&Diagnostic)>,
+	/// Objects in use, by their number. Entries of the newest section of the
+	/// file take precedence over the older ones.
+	xref: BTreeMap<u32, XrefEntry>,
+	/// Older versions of the objects, which have another generation than
+	/// the newest one, e.g. objects regenerated by incremental updates.
+	versions: BTreeMap<ObjectId, XrefEntry>,
+	/// Objects freed by a newer section of the file than the ones which have
+	/// them in use, e.g. deleted by an incremental update, with the generation
+	/// of their free entry.
+	freed: BTreeMap<u32, u16>,
+	trailer: Dictionary,
+	/// Filters used to decode the streams, other than the built-in ones.
+	filters: Option<&'a FilterRegistry>,
+	/// Page objects, in order, once the page tree has been walked.
+	pages: OnceCell<Vec<ObjectId>>,
+}
+
+impl<'a> Reader<'a> {
+	/// Attempts to read the cross-reference table and the trailer of the file,
+	/// recovering from common mistakes.
+	#[inline]
+	pub fn new(data: &'a [u8]) -> Result<Self, PdfError> {
+		return Self::with_mode(data, ParseMode::Lenient);
+	}
+
+	/// Attempts to read the cross-reference table and the trailer of the file,
+	/// in the given mode.
+	pub fn with_mode(data: &'a [u8], mode: ParseMode) -> Result<Self, PdfError> {
+		return Self::read(data, mode, None);
+	}
+
+	/// Attempts to read the cross-reference table and the trailer of the file,
+	/// in the given mode, reporting what happens while reading the file to
+	/// the callback, e.g. the objects which were recovered from.
+	pub fn with_diagnostics(data: &'a [u8], mode: ParseMode, callback: &'a dyn Fn(&Diagnostic)) -> Result<Self, PdfError> {
+		return Self::read(data, mode, Some(callback));
+	}
+
+	fn read(data: &'a [u8], mode: ParseMode, diagnostics: Option<&'a dyn Fn(&Diagnostic)>) -> Result<Self, PdfError> {
+		let mut reader = Self {
+			data,
+			mode,
+			diagnostics,
+			xref: BTreeMap::new(),
+			versions: BTreeMap::new(),
+			freed: BTreeMap::new(),
+			trailer: Dictionary::new(),
+			filters: None,
+			pages: OnceCell::new(),
+		};
+
+		match (reader.read_xref(), mode) {
+			(Ok(()), _) => {},
+			(Err(error), ParseMode::Strict) => return Err(error),
+			(Err(error), ParseMode::Lenient) => {
+				reader.rebuild_xref();
+				reader.report(Diagnostic::XrefRebuilt {
+					error: error.to_string(),
+					objects: reader.xref.len(),
+				});
+				if !reader.trailer.contains_key("Root") {
+					return Err(error);
+				}
+			},
+		}
+		return Ok(reader);
+	}
+
+	/// Attempts to read the chain of cross-reference sections, starting with
+	/// the newest one.
+	fn read_xref(&mut self) -> Result<(), PdfError> {
+		let mut next = Some(self.startxref()?);
+		let mut visited = Vec::new();
+		while let Some(offset) = next {
+			// Broken files may have loops in their chain of sections.
+			if visited.contains(&offset) {
+				return Err(PdfError::syntax(offset, "cross-reference sections form a loop"));
+			}
+			visited.push(offset);
+
+			let trailer = self.xref_section(offset)?;
+			self.report(Diagnostic::XrefSection { offset });
+			next = match trailer.get("Prev") {
+				Some(prev) => match prev.as_integer() {
+					Some(prev) if prev >= 0 => Some(prev as usize),
+					_ => return Err(PdfError::syntax(offset, "invalid /Prev in trailer")),
+				},
+				None => None,
+			};
+
+			if self.trailer.is_empty() {
+				self.trailer = trailer;
+			}
+		}
+
+		if self.mode == ParseMode::Strict {
+			if !self.trailer.get("Size").is_some_and(|size| size.as_integer().is_some()) {
+				return Err(PdfError::syntax(0, "trailer has no /Size"));
+			}
+			if !self.trailer.contains_key("Root") {
+				return Err(PdfError::syntax(0, "trailer has no /Root"));
+			}
+		}
+
+		// Broken files may have entries for objects beyond the declared size,
+		// which is then grown, so that objects added by updates don't take
+		// their numbers.
+		let size = self.trailer.get("Size").and_then(PdfObject::as_integer);
+		if let (Some(size), Some((&highest, entry))) = (size, self.xref.last_key_value()) {
+			if highest as i64 >= size {
+				if self.mode == ParseMode::Strict {
+					return Err(PdfError::syntax(0, std::format!("cross-reference entry for object {} is beyond /Size {}", highest, size)));
+				}
+				self.report(Diagnostic::ObjectRecovered {
+					id: ObjectId::new(highest, entry.generation()),
+					message: std::format!("object is beyond /Size {}", size),
+				});
+				self.trailer.set("Size", highest as i64 + 1);
+			}
+		}
+		return Ok(());
+	}
+
+	/// Rebuilds the cross-reference table by scanning the whole file for the
+	/// object headers. Objects found later in the file take precedence, as
+	/// they come from incremental updates.
+	fn rebuild_xref(&mut self) {
+		self.xref.clear();
+		self.versions.clear();
+		self.freed.clear();
+		self.trailer = Dictionary::new();
+		let objects = scan_objects(self.data);
+		for &(number, generation, offset) in &objects {
+			if let Some(older) = self.xref.insert(number, XrefEntry::Offset { offset, generation }).filter(|older| older.generation() != generation) {
+				self.versions.insert(ObjectId::new(number, older.generation()), older);
+			}
+		}
+
+		// The trailer is taken from the newest trailer or cross-reference
+		// stream which has the catalog, or made up if there are none.
+		let mut pos = self.data.len();
+		while let Some(found) = self.data[..pos].windows(7).rposition(|window| window == b"trailer") {
+			pos = found;
+			let mut parser = self.parser(found + 7);
+			if let (Ok(Some(Token::DictStart)), Ok(trailer)) = (parser.lexer.next(), parser.dictionary()) {
+				if trailer.contains_key("Root") {
+					self.trailer = trailer;
+					break;
+				}
+			}
+		}
+
+		let mut catalog = None;
+		for &(number, generation, offset) in objects.iter().rev() {
+			let id = ObjectId::new(number, generation);
+			let Ok(PdfObject::Dictionary(dict)) = self.object_parser(id, offset).and_then(|mut parser| parser.parse_object()) else {
+				continue;
+			};
+			match dict.get_name("Type") {
+				Some(b"XRef") if self.trailer.is_empty() && dict.contains_key("Root") => self.trailer = dict,
+				Some(b"Catalog") if catalog.is_none() => catalog = Some(id),
+				Some(b"ObjStm") => self.scan_object_stream(number),
+				_ => {},
+			}
+		}
+		if let (true, Some(catalog)) = (self.trailer.is_empty(), catalog) {
+			self.trailer.set("Root", catalog);
+		}
+		self.trailer.remove("Prev");
+		self.trailer.remove("XRefStm");
+	}
+
+	/// Adds the objects stored in the object stream, which are not already
+	/// known, to the cross-reference table.
+	fn scan_object_stream(&mut self, stream: u32) {
+		let Ok(PdfObject::Stream(container)) = self.resolve(ObjectId::new(stream, 0)) else {
+			return;
+		};
+		let (Some(count), Ok(data)) = (container.dict.get("N").and_then(PdfObject::as_integer), self.decode(ObjectId::new(stream, 0), &container)) else {
+			return;
+		};
+
+		let mut parser = Parser::new(&data, 0);
+		for index in 0..count.max(0) as usize {
+			let (Ok(Some(Token::Integer(number))), Ok(Some(Token::Integer(_)))) = (parser.lexer.next(), parser.lexer.next()) else {
+				return;
+			};
+			if let Ok(number) = u32::try_from(number) {
+				self.xref.entry(number).or_insert(XrefEntry::Compressed { stream, index });
+			}
+		}
+	}
+
+	/// Reports the diagnostic to the callback, if there is one.
+	#[inline]
+	fn report(&self, diagnostic: Diagnostic) {
+		if let Some(callback) = &self.diagnostics {
+			callback(&diagnostic);
+		}
+	}
+
+	/// Attempts to decode the data of the stream object, reporting the
+	/// failure.
+	#[inline]
+	pub(crate) fn decode(&self, id: ObjectId, stream: &Stream) -> Result<Vec<u8>, PdfError> {
+		return self.decode_data(id, &stream.dict, &stream.data);
+	}
+
+	/// Sets the filters used to decode the streams, e.g. with a custom filter
+	/// registered. Cross-reference streams, which are read along with the
+	/// file, are always decoded with the built-in filters.
+	pub fn set_filters(&mut self, filters: &'a FilterRegistry) {
+		self.filters = Some(filters);
+	}
+
+	/// Gets the filters used to decode the streams.
+	pub(crate) fn filter_registry(&self) -> &FilterRegistry {
+		return match self.filters {
+			Some(filters) => filters,
+			None => FilterRegistry::builtin(),
+		};
+	}
+
+	/// Attempts to decode the data of the stream object with the given
+	/// dictionary, reporting the failure.
+	fn decode_data(&self, id: ObjectId, dict: &Dictionary, raw: &[u8]) -> Result<Vec<u8>, PdfError> {
+		let filters = self.filter_registry();
+		// Filters don't have access to the file, so the objects their
+		// parameters refer to are resolved for them.
+		let dict = match dict.get("DecodeParms") {
+			Some(params) => {
+				let mut dict = dict.clone();
+				dict.set("DecodeParms", self.resolve_params(params)?);
+				Cow::Owned(dict)
+			},
+			None => Cow::Borrowed(dict),
+		};
+		return filters.decode_with_mode(&dict, raw, self.mode).inspect_err(|error| {
+			self.report(Diagnostic::FilterFailed {
+				id,
+				message: error.to_string(),
+			});
+		});
+	}
+
+	/// Attempts to resolve the decoding parameters, either a dictionary or an
+	/// array of them, and the values of the dictionaries.
+	fn resolve_params(&self, params: &PdfObject) -> Result<PdfObject, PdfError> {
+		let resolve_dict = |params: PdfObject| -> Result<PdfObject, PdfError> {
+			let PdfObject::Dictionary(params) = params else {
+				return Ok(params);
+			};
+			let mut resolved = Dictionary::new();
+			for (key, value) in params.iter() {
+				resolved.set_raw(key, self.resolve_object(value)?);
+			}
+			return Ok(PdfObject::Dictionary(resolved));
+		};
+		return match self.resolve_object(params)? {
+			PdfObject::Array(params) => Ok(PdfObject::Array(params.iter().map(|params| resolve_dict(self.resolve_object(params)?)).collect::<Result<_, _>>()?)),
+			params => resolve_dict(params),
+		};
+	}
+
+	/// Creates parser for objects in the file body, starting at the given
+	/// position.
+	#[inline]
+	fn parser(&self, pos: usize) -> Parser<'a> {
+		return Parser::new(self.data, pos).with_mode(self.mode);
+	}
+
+	/// Gets the mode in which the file is read.
+	#[inline]
+	pub fn mode(&self) -> ParseMode {
+		return self.mode;
+	}
+
+	/// Gets the whole file data.
+	#[inline]
+	pub fn data(&self) -> &'a [u8] {
+		return self.data;
+	}
+
+	/// Gets the trailer of the newest section of the file.
+	#[inline]
+	pub fn trailer(&self) -> &Dictionary {
+		return &self.trailer;
+	}
+
+	/// Gets the identifiers of all objects in use, in ascending order.
+	pub fn object_ids(&self) -> impl Iterator<Item = ObjectId> + '_ {
+		return self.xref.iter().map(|(&number, entry)| ObjectId::new(number, entry.generation()));
+	}
+
+	/// Gets the objects on the free list of the file, other than the object
+	/// 0, with the generation their numbers are to be reused with, in
+	/// ascending order.
+	pub(crate) fn free_ids(&self) -> impl Iterator<Item = ObjectId> + '_ {
+		return self.freed.iter().filter(|&(&number, _)| number != 0).map(|(&number, &generation)| ObjectId::new(number, generation));
+	}
+
+	/// Iterates over all objects in use, in ascending order of their
+	/// identifiers, resolving the ones in object streams. Objects which can't
+	/// be read are returned as errors, without ending the iteration.
+	pub fn objects(&self) -> impl Iterator<Item = Result<(ObjectId, PdfObject), PdfError>> + '_ {
+		return self.object_ids().map(|id| self.resolve(id).map(|object| (id, object)));
+	}
+
+	/// Attempts to check that the offsets of the cross-reference entries point
+	/// exactly at the headers of their objects, e.g. in a file just written.
+	pub(crate) fn verify_offsets(&self) -> Result<(), PdfError> {
+		let entries = self.xref.iter().map(|(&number, &entry)| (number, entry)).chain(self.versions.iter().map(|(id, &entry)| (id.number, entry)));
+		for (number, entry) in entries {
+			let XrefEntry::Offset { offset, generation } = entry else {
+				continue;
+			};
+			let header = std::format!("{} {} obj", number, generation);
+			if !self.data.get(offset..).is_some_and(|data| data.starts_with(header.as_bytes())) {
+				return Err(PdfError::syntax(offset, std::format!("cross-reference entry of object {} {} R does not point at its header", number, generation)));
+			}
+		}
+		return Ok(());
+	}
+
+	/// Finds the offset of the last cross-reference section, which is given
+	/// just before the end-of-file marker.
+	pub(crate) fn startxref(&self) -> Result<usize, PdfError> {
+		let mut end = self.data.iter().rposition(|byte| !byte.is_ascii_whitespace()).map_or(0, |pos| pos + 1);
+		if !self.data[..end].ends_with(b"%%EOF") {
+			// Some writers, or the transfers of the files, leave junk after the
+			// end-of-file marker, which is skipped as long as there isn't much
+			// of it.
+			let start = end.saturating_sub(MAX_TRAILING_JUNK + 5);
+			match self.data[start..end].windows(5).rposition(|window| window == b"%%EOF") {
+				Some(pos) if self.mode == ParseMode::Lenient => end = start + pos + 5,
+				_ => return Err(PdfError::syntax(end, "missing end-of-file marker")),
+			}
+		}
+
+		let Some(pos) = self.data[..end].windows(9).rposition(|window| window == b"startxref") else {
+			return Err(PdfError::syntax(end, "missing startxref"));
+		};
+
+		let mut parser = self.parser(pos + 9);
+		return match parser.parse_object()? {
+			PdfObject::Integer(offset) if offset >= 0 && (offset as usize) < self.data.len() => Ok(offset as usize),
+			_ => Err(PdfError::syntax(pos, "invalid startxref offset")),
+		};
+	}
+
+	/// Adds the entry from an older section than the ones read so far, which
+	/// is either an older version of a known object, or a new object.
+	fn add_entry(&mut self, number: u32, entry: XrefEntry) {
+		if self.freed.contains_key(&number) {
+			return;
+		}
+		match self.xref.get(&number) {
+			None => {
+				self.xref.insert(number, entry);
+			},
+			Some(newest) if newest.generation() != entry.generation() => {
+				self.versions.entry(ObjectId::new(number, entry.generation())).or_insert(entry);
+			},
+			Some(_) => {},
+		}
+	}
+
+	/// Marks the objects with free entries as freed, unless they're in use
+	/// according to a newer section of the file, so that the older sections
+	/// don't have them in use.
+	fn free_entries(&mut self, entries: Vec<(u32, u16)>) {
+		for (number, generation) in entries {
+			if !self.xref.contains_key(&number) {
+				self.freed.entry(number).or_insert(generation);
+			}
+		}
+	}
+
+	/// Reads the cross-reference table or stream at the given offset, adding
+	/// the entries which are not already known, and returns the trailer.
+	fn xref_section(&mut self, offset: usize) -> Result<Dictionary, PdfError> {
+		let mut parser = self.parser(offset);
+		if parser.lexer.next()? != Some(Token::Keyword(b"xref")) {
+			let mut free = Vec::new();
+			let dict = self.xref_stream(offset, &mut free)?;
+			self.free_entries(free);
+			return Ok(dict);
+		}
+
+		let (mut entries, mut free) = (Vec::new(), Vec::new());
+		loop {
+			let pos = parser.pos();
+			let (start, count) = match parser.lexer.next()? {
+				Some(Token::Keyword(b"trailer")) => break,
+				Some(Token::Integer(start)) => match parser.lexer.next()? {
+					Some(Token::Integer(count)) if start >= 0 && count >= 0 => (start as u32, count as u32),
+					_ => return Err(PdfError::syntax(pos, "invalid cross-reference subsection")),
+				},
+				_ => return Err(PdfError::syntax(pos, "invalid cross-reference subsection")),
+			};
+
+			for number in start..start.saturating_add(count) {
+				let pos = parser.pos();
+				let (Some(Token::Integer(offset)), Some(Token::Integer(generation)), Some(Token::Keyword(kind))) = (parser.lexer.next()?, parser.lexer.next()?, parser.lexer.next()?) else {
+					return Err(PdfError::syntax(pos, "invalid cross-reference entry"));
+				};
+
+				match kind {
+					b"n" if self.mode == ParseMode::Strict && !(0..self.data.len() as i64).contains(&offset) => {
+						return Err(PdfError::syntax(pos, "cross-reference entry points outside of the file"));
+					},
+					b"n" => {
+						let entry = XrefEntry::Offset {
+							offset: offset as usize,
+							generation: generation as u16,
+						};
+						entries.push((number, entry));
+					},
+					b"f" => free.push((number, generation as u16)),
+					_ => return Err(PdfError::syntax(pos, "invalid cross-reference entry type")),
+				}
+			}
+		}
+
+		let pos = parser.pos();
+		let trailer = match parser.lexer.next()? {
+			Some(Token::DictStart) => parser.dictionary()?,
+			_ => return Err(PdfError::syntax(pos, "trailer is not a dictionary")),
+		};
+
+		// Hybrid-reference files list the objects which older readers don't
+		// need to know about, e.g. the ones in object streams, only in a
+		// cross-reference stream. Its entries take precedence over the table.
+		if let Some(stream) = trailer.get("XRefStm") {
+			match stream.as_integer() {
+				Some(stream) if stream >= 0 && (stream as usize) < self.data.len() => self.xref_stream(stream as usize, &mut free)?,
+				_ => return Err(PdfError::syntax(offset, "invalid /XRefStm in trailer")),
+			};
+		}
+		for (number, entry) in entries {
+			self.add_entry(number, entry);
+		}
+		// Hybrid-reference files list the objects in object streams as free
+		// in the table, so the objects are only freed once both are read.
+		self.free_entries(free);
+		return Ok(trailer);
+	}
+
+	/// Reads the cross-reference stream at the given offset, adding the
+	/// entries which are not already known, and the numbers of the free ones
+	/// to the list, and returns its dictionary, which serves as the trailer.
+	fn xref_stream(&mut self, offset: usize, free: &mut Vec<(u32, u16)>) -> Result<Dictionary, PdfError> {
+		let mut parser = self.parser(offset);
+		let header = (parser.lexer.next()?, parser.lexer.next()?, parser.lexer.next()?);
+		let (Some(Token::Integer(number)), Some(Token::Integer(generation)), Some(Token::Keyword(b"obj"))) = header else {
+			return Err(PdfError::syntax(offset, "expected cross-reference table or stream"));
+		};
+		let (PdfObject::Dictionary(dict), Some(Token::Keyword(b"stream"))) = (parser.parse_object()?, parser.lexer.next()?) else {
+			return Err(PdfError::syntax(offset, "expected cross-reference stream"));
+		};
+		if dict.get_name("Type") != Some(b"XRef") {
+			return Err(PdfError::syntax(offset, "cross-reference stream has no /Type /XRef"));
+		}
+
+		let raw = self.stream_data(&dict, parser.pos())?;
+		let data = self.decode_data(ObjectId::new(number.clamp(0, u32::MAX as i64) as u32, generation.clamp(0, u16::MAX as i64) as u16), &dict, raw)?;
+
+		let widths: Vec<usize> = match dict.get("W").and_then(PdfObject::as_array).map(Vec::as_slice) {
+			Some([w0, w1, w2]) => [w0, w1, w2].into_iter().filter_map(PdfObject::as_integer).filter(|width| (0..=8).contains(width)).map(|width| width as usize).collect(),
+			_ => Vec::new(),
+		};
+		if widths.len() != 3 || widths[1] == 0 {
+			return Err(PdfError::syntax(offset, "invalid /W in cross-reference stream"));
+		}
+
+		let Some(size) = dict.get("Size").and_then(PdfObject::as_integer) else {
+			return Err(PdfError::syntax(offset, "cross-reference stream has no /Size"));
+		};
+		let index = match dict.get("Index") {
+			Some(index) => index.as_array().into_iter().flatten().filter_map(PdfObject::as_integer).collect(),
+			None => std::vec![0, size],
+		};
+		if index.len() % 2 != 0 || index.iter().any(|&value| value < 0) {
+			return Err(PdfError::syntax(offset, "invalid /Index in cross-reference stream"));
+		}
+
+		let mut rows = data.chunks_exact(widths.iter().sum());
+		for subsection in index.chunks(2) {
+			let (start, count) = (subsection[0] as u32, subsection[1] as u32);
+			for number in start..start.saturating_add(count) {
+				let Some(row) = rows.next() else {
+					return Err(PdfError::syntax(offset, "cross-reference stream is too short"));
+				};
+
+				let mut fields = [0usize; 3];
+				let mut bytes = row.iter();
+				for (field, &width) in fields.iter_mut().zip(&widths) {
+					*field = bytes.by_ref().take(width).fold(0, |value, &byte| value << 8 | byte as usize);
+				}
+				// The type defaults to 1 when its field is left out.
+				if widths[0] == 0 {
+					fields[0] = 1;
+				}
+
+				let entry = match fields {
+					[1, offset, generation] => XrefEntry::Offset {
+						offset,
+						generation: generation as u16,
+					},
+					[2, stream, index] => XrefEntry::Compressed {
+						stream: stream as u32,
+						index,
+					},
+					[0, _, generation] => {
+						free.push((number, generation as u16));
+						continue;
+					},
+					// Unknown types, which are to be treated as references to
+					// the null object.
+					_ => continue,
+				};
+				self.add_entry(number, entry);
+			}
+		}
+		return Ok(dict);
+	}
+
+	/// Creates parser positioned just after the header of the object with the
+	/// given identifier, found at the given offset.
+	fn object_parser(&self, id: ObjectId, offset: usize) -> Result<Parser<'a>, PdfError> {
+		let mut parser = self.parser(offset);
+		let header = (parser.lexer.next()?, parser.lexer.next()?, parser.lexer.next()?);
+		let (Some(Token::Integer(number)), Some(Token::Integer(generation)), Some(Token::Keyword(b"obj"))) = header else {
+			return Err(PdfError::syntax(offset, "missing object header"));
+		};
+		if number != id.number as i64 {
+			return Err(PdfError::syntax(offset, std::format!("expected object {}, found {}", id.number, number)));
+		}
+		if generation != id.generation as i64 {
+			return Err(PdfError::syntax(offset, std::format!("expected generation {} of object {}, found {}", id.generation, id.number, generation)));
+		}
+
+		return Ok(parser);
+	}
+
+	/// Finds the cross-reference entry of the object with the given number
+	/// and generation, returning it with the identifier of the object it's
+	/// for. In the lenient mode, a reference with the wrong generation is
+	/// taken as one to the newest version of the object.
+	fn entry(&self, id: ObjectId) -> Result<(ObjectId, XrefEntry), PdfError> {
+		if let Some(&entry) = self.versions.get(&id) {
+			return Ok((id, entry));
+		}
+		return match self.xref.get(&id.number) {
+			Some(&entry) if entry.generation() == id.generation => Ok((id, entry)),
+			Some(&entry) if self.mode == ParseMode::Lenient => {
+				let newest = ObjectId::new(id.number, entry.generation());
+				self.report(Diagnostic::ObjectRecovered {
+					id,
+					message: std::format!("no generation {}, using generation {} instead", id.generation, newest.generation),
+				});
+				Ok((newest, entry))
+			},
+			_ => Err(PdfError::MissingObject(id)),
+		};
+	}
+
+	/// Attempts to parse the object with the given identifier. Its generation
+	/// has to match the one in the cross-reference table, so that older
+	/// versions of the objects, e.g. from before an incremental update, are
+	/// resolved as well.
+	pub fn resolve(&self, id: ObjectId) -> Result<PdfObject, PdfError> {
+		return Ok(match self.resolve_borrowed(id)? {
+			(PdfObject::Dictionary(dict), Some(data)) => PdfObject::Stream(Stream::new(dict, data.to_vec())),
+			(object, _) => object,
+		});
+	}
+
+	/// Attempts to get the still encoded data of the stream object, borrowed
+	/// from the file, e.g. to copy a JPEG image as it is.
+	pub fn stream_raw(&self, id: ObjectId) -> Result<&'a [u8], PdfError> {
+		return match self.resolve_borrowed(id)? {
+			(_, Some(data)) => Ok(data),
+			_ => Err(PdfError::InvalidArgument(std::format!("object {} {} R is not a stream", id.number, id.generation))),
+		};
+	}
+
+	/// Attempts to get the decoded data of the stream object, which is only
+	/// copied out of the file if the stream has filters to apply.
+	pub fn stream_decoded(&self, id: ObjectId) -> Result<Cow<'a, [u8]>, PdfError> {
+		let (PdfObject::Dictionary(dict), Some(data)) = self.resolve_borrowed(id)? else {
+			return Err(PdfError::InvalidArgument(std::format!("object {} {} R is not a stream", id.number, id.generation)));
+		};
+		if !dict.contains_key("Filter") {
+			return Ok(Cow::Borrowed(data));
+		}
+		return Ok(Cow::Owned(self.decode_data(id, &dict, data)?));
+	}
+
+	/// Attempts to parse the object, like [`Reader::resolve`], but returns the
+	/// dictionary of a stream separately from its data, borrowed from the
+	/// file.
+	fn resolve_borrowed(&self, id: ObjectId) -> Result<(PdfObject, Option<&'a [u8]>), PdfError> {
+		let (id, offset) = match self.entry(id)? {
+			(id, XrefEntry::Offset { offset, .. }) => (id, offset),
+			// Objects in object streams cannot be streams themselves.
+			(id, XrefEntry::Compressed { stream, index }) => return Ok((self.resolve_compressed(id, stream, index)?, None)),
+		};
+
+		let mut parser = match self.object_parser(id, offset) {
+			Ok(parser) => parser,
+			// The offsets are often wrong, e.g. after the file was edited by
+			// hand, while the object itself is fine.
+			Err(error) if self.mode == ParseMode::Lenient => match scan_objects(self.data).into_iter().rev().find(|&(number, generation, _)| number == id.number && generation == id.generation) {
+				Some((_, _, found)) => {
+					let parser = self.object_parser(id, found)?;
+					self.report(Diagnostic::ObjectRecovered {
+						id,
+						message: std::format!("found at offset {} instead of {}: {}", found, offset, error),
+					});
+					parser
+				},
+				None => return Err(error),
+			},
+			Err(error) => return Err(error),
+		};
+		let object = parser.parse_object()?;
+		let pos = parser.pos();
+		return match parser.lexer.next()? {
+			Some(Token::Keyword(b"endobj")) => Ok((object, None)),
+			Some(Token::Keyword(b"stream")) => {
+				let PdfObject::Dictionary(dict) = object else {
+					return Err(PdfError::syntax(pos, "stream without a dictionary"));
+				};
+				let data = self.stream_data(&dict, parser.pos())?;
+				Ok((PdfObject::Dictionary(dict), Some(data)))
+			},
+			// The next object often starts without the previous one ending.
+			_ if self.mode == ParseMode::Lenient => {
+				self.report(Diagnostic::ObjectRecovered {
+					id,
+					message: std::format!("missing endobj at offset {}", pos),
+				});
+				Ok((object, None))
+			},
+			_ => Err(PdfError::syntax(pos, "expected endobj")),
+		};
+	}
+
+	/// Attempts to parse the object stored at the given index of an object
+	/// stream.
+	fn resolve_compressed(&self, id: ObjectId, stream: u32, index: usize) -> Result<PdfObject, PdfError> {
+		let stream_id = ObjectId::new(stream, 0);
+		// Object streams can't be stored in object streams themselves, which
+		// also rules out loops.
+		let Some(&XrefEntry::Offset { offset, .. }) = self.xref.get(&stream) else {
+			return Err(PdfError::MissingObject(stream_id));
+		};
+		let Some(container) = self.resolve(stream_id)?.as_stream().cloned() else {
+			return Err(PdfError::syntax(offset, "object stream is not a stream"));
+		};
+		if container.dict.get_name("Type") != Some(b"ObjStm") {
+			return Err(PdfError::syntax(offset, "object stream has no /Type /ObjStm"));
+		}
+
+		let (Some(count), Some(first)) = (container.dict.get("N").and_then(PdfObject::as_integer), container.dict.get("First").and_then(PdfObject::as_integer)) else {
+			return Err(PdfError::syntax(offset, "object stream has no valid /N or /First"));
+		};
+		if index as i64 >= count || first < 0 {
+			return Err(PdfError::syntax(offset, std::format!("object stream has no object at index {}", index)));
+		}
+
+		let data = self.decode(stream_id, &container)?;
+		// The data starts with pairs of object numbers and their offsets,
+		// relative to the first object.
+		let mut parser = Parser::new(&data, 0).with_mode(self.mode);
+		for _ in 0..index * 2 {
+			parser.lexer.next()?;
+		}
+		let (Some(Token::Integer(number)), Some(Token::Integer(relative))) = (parser.lexer.next()?, parser.lexer.next()?) else {
+			return Err(PdfError::syntax(offset, "invalid object stream header"));
+		};
+		if number != id.number as i64 || relative < 0 {
+			return Err(PdfError::syntax(offset, std::format!("expected object {} in object stream, found {}", id.number, number)));
+		}
+
+		return Parser::new(&data, (first + relative) as usize).with_mode(self.mode).parse_object();
+	}
+
+	/// Gets the length of a stream, which is often written as a separate
+	/// object after the stream itself. Any problem with it is not an error, as
+	/// the length can be recovered from the data.
+	fn stream_length(&self, dict: &Dictionary) -> Option<usize> {
+		let length = match dict.get("Length")? {
+			// Only the object itself is parsed, as it might as well be a stream
+			// with the reference to its own length.
+			PdfObject::Reference(id) => match self.entry(*id).ok()? {
+				(id, XrefEntry::Offset { offset, .. }) => self.object_parser(id, offset).ok()?.parse_object().ok()?.as_integer()?,
+				(id, XrefEntry::Compressed { .. }) => self.resolve(id).ok()?.as_integer()?,
+			},
+			object => object.as_integer()?,
+		};
+		return usize::try_from(length).ok();
+	}
+
+	/// Gets the data of a stream, which starts after the end-of-line marker
+	/// following the `stream` keyword. The marker is either CRLF or LF, but
+	/// never a lone CR, which is the first byte of the data instead. In the
+	/// lenient mode, a lone CR is still skipped if the `/Length` says so.
+	///
+	/// When the `/Length` is missing or wrong, i.e. it's not followed by the
+	/// `endstream` keyword, the data extends up to the first such keyword,
+	/// unless in the strict mode. Both ways, a stream with no data between the
+	/// keywords is empty.
+	fn stream_data(&self, dict: &Dictionary, mut pos: usize) -> Result<&'a [u8], PdfError> {
+		let length = self.stream_length(dict);
+		let fits = |start: usize| {
+			let end = length.and_then(|length| start.checked_add(length))?;
+			if end <= self.data.len() && self.data[lexer::skip_whitespace(self.data, end)..].starts_with(b"endstream") {
+				return Some(end);
+			}
+			return None;
+		};
+
+		if self.data[pos..].starts_with(b"\r\n") {
+			pos += 2;
+		} else if self.data.get(pos) == Some(&b'\n') {
+			pos += 1;
+		} else if self.data.get(pos) == Some(&b'\r') {
+			match self.mode {
+				ParseMode::Strict => return Err(PdfError::syntax(pos, "stream keyword is followed by a lone CR")),
+				ParseMode::Lenient if fits(pos).is_none() && fits(pos + 1).is_some() => pos += 1,
+				ParseMode::Lenient => {},
+			}
+		}
+
+		if let Some(end) = fits(pos) {
+			return Ok(&self.data[pos..end]);
+		}
+		if self.mode == ParseMode::Strict {
+			return Err(PdfError::syntax(pos, "stream /Length is missing or wrong"));
+		}
+
+		let Some(offset) = self.data[pos..].windows(9).position(|window| window == b"endstream") else {
+			return Err(PdfError::syntax(pos, "missing endstream"));
+		};
+		let mut data = &self.data[pos..pos + offset];
+		// Nothing but whitespace between the keywords, e.g. `stream
+		// endstream`, is an empty stream, rather than one with the whitespace.
+		if data.iter().all(|&b| lexer::is_whitespace(b)) {
+			return Ok(&[]);
+		}
+		// The end-of-line marker before the keyword is not a part of the data.
+		if let Some(rest) = data.strip_suffix(b"\r\n") {
+			data = rest;
+		} else if let Some(rest) = data.strip_suffix(b"\n").or_else(|| data.strip_suffix(b"\r")) {
+			data = rest;
+		}
+		return Ok(data);
+	}
+
+	/// Attempts to resolve the object as a dictionary.
+	pub(crate) fn resolve_dict(&self, id: ObjectId) -> Result<Dictionary, PdfError> {
+		return match self.resolve(id)? {
+			PdfObject::Dictionary(dict) => Ok(dict),
+			PdfObject::Stream(stream) => Ok(stream.dict),
+			_ => Err(PdfError::syntax(0, std::format!("object {} {} R is not a dictionary", id.number, id.generation))),
+		};
+	}
+
+	/// Attempts to get the document catalog, i.e. the root of the objects.
+	pub fn catalog(&self) -> Result<Dictionary, PdfError> {
+		let Some(root) = self.trailer.get("Root").and_then(PdfObject::as_reference) else {
+			return Err(PdfError::syntax(0, "trailer has no /Root"));
+		};
+		return self.resolve_dict(root);
+	}
+
+	/// Gets the version of the file, which is given in the header, unless the
+	/// catalog overrides it with a newer one. Files without the header are
+	/// assumed to be PDF 1.0.
+	pub fn pdf_version(&self) -> (u8, u8) {
+		// The header may be preceded by some junk, which most readers allow
+		// within the first kilobyte.
+		let start = &self.data[..self.data.len().min(1024)];
+		let header = start.windows(5).position(|window| window == b"%PDF-").map(|pos| &start[pos + 5..]);
+		let header = header.and_then(|rest| parse_version(&rest[..lexer::scan_regular(rest, 0)])).unwrap_or((1, 0));
+
+		let catalog = self.catalog().ok().and_then(|catalog| catalog.get_name("Version").and_then(parse_version));
+		return match catalog {
+			Some(version) if version > header => version,
+			_ => header,
+		};
+	}
+
+	/// Attempts to find the page objects of the document, in order, by walking
+	/// the page tree.
+	#[inline]
+	pub fn pages(&self) -> Result<Vec<ObjectId>, PdfError> {
+		return Ok(self.page_ids()?.to_vec());
+	}
+
+	/// Attempts to find the page objects of the document, walking the page
+	/// tree only the first time.
+	fn page_ids(&self) -> Result<&[ObjectId], PdfError> {
+		if let Some(pages) = self.pages.get() {
+			return Ok(pages);
+		}
+		let Some(node) = self.catalog()?.get("Pages").and_then(PdfObject::as_reference) else {
+			return Err(PdfError::syntax(0, "catalog has no /Pages"));
+		};
+
+		let mut pages = Vec::new();
+		let mut visited = BTreeSet::new();
+		let mut pending = std::vec![node];
+		while let Some(id) = pending.pop() {
+			if !visited.insert(id) {
+				return Err(PdfError::syntax(0, "page tree contains a loop"));
+			}
+
+			let dict = self.resolve_dict(id)?;
+			if dict.get_name("Type") == Some(b"Page") || !dict.contains_key("Kids") {
+				pages.push(id);
+				continue;
+			}
+			// Kids are pushed in reverse, so that they're visited in order.
+			for kid in dict.get("Kids").and_then(PdfObject::as_array).into_iter().flatten().rev() {
+				let Some(kid) = kid.as_reference() else {
+					return Err(PdfError::syntax(0, "page tree kid is not a reference"));
+				};
+				pending.push(kid);
+			}
+		}
+		return Ok(self.pages.get_or_init(|| pages));
+	}
+
+	/// Attempts to count the pages of the document.
+	pub fn page_count(&self) -> Result<usize, PdfError> {
+		return Ok(self.page_ids()?.len());
+	}
+
+	/// Attempts to get the page with the given index.
+	pub fn page(&self, index: usize) -> Result<ReaderPage<'_, 'a>, PdfError> {
+		let pages = self.page_ids()?;
+		let Some(&id) = pages.get(index) else {
+			return Err(PdfError::PageOutOfRange {
+				index,
+				count: pages.len(),
+			});
+		};
+		return Ok(ReaderPage {
+			reader: self,
+			id,
+			dict: self.resolve_dict(id)?,
+		});
+	}
+
+	/// Attempts to get the attribute of the page. Inheritable attributes which
+	/// the page doesn't have are taken from its nearest ancestor having them.
+	pub fn page_attribute(&self, page: ObjectId, key: &str) -> Result<Option<PdfObject>, PdfError> {
+		let mut visited = Vec::new();
+		let mut node = page;
+		loop {
+			let dict = self.resolve_dict(node)?;
+			if let Some(value) = dict.get(key) {
+				return Ok(Some(value.clone()));
+			}
+			if !INHERITABLE.contains(&key) {
+				return Ok(None);
+			}
+
+			visited.push(node);
+			node = match dict.get("Parent").and_then(PdfObject::as_reference) {
+				Some(parent) if !visited.contains(&parent) => parent,
+				Some(_) => return Err(PdfError::syntax(0, "page tree contains a loop")),
+				None => return Ok(None),
+			};
+		}
+	}
+
+	/// Resolves the object if it's a reference, or returns it as it is.
+	pub fn resolve_object(&self, object: &PdfObject) -> Result<PdfObject, PdfError> {
+		return match object {
+			PdfObject::Reference(id) => self.resolve(*id),
+			object => Ok(object.clone()),
+		};
+	}
+}
+
+/// Parses the version written as `major.minor`.
+pub(crate) fn parse_version(text: &[u8]) -> Option<(u8, u8)> {
+	let text = std::str::from_utf8(text).ok()?;
+	let (major, minor) = text.split_once('.')?;
+	return Some((major.parse().ok()?, minor.parse().ok()?));
+}
+
+/// Finds the headers of all objects in the data, i.e. the `N G obj` sequences,
+/// in order. Returns their numbers, generations, and offsets.
+fn scan_objects(data: &[u8]) -> Vec<(u32, u16, usize)> {
+	let mut objects = Vec::new();
+	let mut pos = 0;
+	while let Some(found) = data[pos..].windows(3).position(|window| window == b"obj") {
+		let keyword = pos + found;
+		pos = keyword + 3;
+		if data.get(pos).is_some_and(|&byte| lexer::is_regular(byte)) {
+			continue;
+		}
+
+		// Walks back over the generation and the number, each preceded by
+		// whitespace.
+		let mut fields = [0u64; 2];
+		let mut start = keyword;
+		for field in fields.iter_mut().rev() {
+			let end = start;
+			while start > 0 && lexer::is_whitespace(data[start - 1]) {
+				start -= 1;
+			}
+			let digits = start;
+			while start > 0 && data[start - 1].is_ascii_digit() {
+				start -= 1;
+			}
+			if start == end || digits == end || start == digits {
+				start = usize::MAX;
+				break;
+			}
+			*field = std::str::from_utf8(&data[start..digits]).ok().and_then(|text| text.parse().ok()).unwrap_or(u64::MAX);
+		}
+
+		if start == usize::MAX || (start > 0 && lexer::is_regular(data[start - 1])) {
+			continue;
+		}
+		if let (Ok(number), Ok(generation)) = (u32::try_from(fields[0]), u16::try_from(fields[1])) {
+			objects.push((number, generation, start));
+		}
+	}
+	return objects;
+}
+
+/// Page of a file opened with a [`Reader`].
+pub struct ReaderPage<'r, 'a> {
+	reader: &'r Reader<'a>,
+	id: ObjectId,
+	dict: Dictionary,
+}
+
+impl ReaderPage<'_, '_> {
+	/// Gets the identifier of the page object.
+	#[inline]
+	pub fn id(&self) -> ObjectId {
+		return self.id;
+	}
+
+	/// Gets the page dictionary, without the inherited attributes.
+	#[inline]
+	pub fn dict(&self) -> &Dictionary {
+		return &self.dict;
+	}
+
+	/// Attempts to get the attribute of the page, including the ones inherited
+	/// from the page tree.
+	pub fn get(&self, key: &str) -> Result<Option<PdfObject>, PdfError> {
+		if let Some(value) = self.dict.get(key) {
+			return Ok(Some(value.clone()));
+		}
+		return self.reader.page_attribute(self.id, key);
+	}
+
+	/// Attempts to get the media box of the page, which may be inherited, and
+	/// whose numbers may be integers or reals, or indirect objects.
+	pub fn media_box(&self) -> Result<Option<Rectangle>, PdfError> {
+		let Some(media_box) = self.get("MediaBox")? else {
+			return Ok(None);
+		};
+		let PdfObject::Array(numbers) = self.reader.resolve_object(&media_box)? else {
+			return Ok(None);
+		};
+		let numbers = numbers.iter().map(|number| self.reader.resolve_object(number)).collect::<Result<Vec<_>, _>>()?;
+		return Ok(Rectangle::from_pdf_array(&PdfObject::Array(numbers)));
+	}
+
+	/// Attempts to get the decoded contents of the page. Contents made of
+	/// several streams are joined with a newline between them.
+	pub fn contents(&self) -> Result<Vec<u8>, PdfError> {
+		let streams = match self.dict.get("Contents") {
+			Some(PdfObject::Reference(id)) => match self.reader.resolve(*id)? {
+				PdfObject::Array(streams) => streams,
+				_ => std::vec![PdfObject::Reference(*id)],
+			},
+			Some(PdfObject::Array(streams)) => streams.clone(),
+			_ => Vec::new(),
+		};
+
+		let mut data = Vec::new();
+		for stream in streams {
+			let Some(id) = stream.as_reference() else {
+				return Err(PdfError::syntax(0, "page content stream is not an indirect object"));
+			};
+			let Some(stream) = self.reader.resolve(id)?.as_stream().cloned() else {
+				return Err(PdfError::syntax(0, "page contents are not a stream"));
+			};
+			if !data.is_empty() {
+				data.push(b'\n');
+			}
+			data.extend(self.reader.decode(id, &stream)?);
+		}
+		return Ok(data);
+	}
+
+	/// Attempts to parse the contents of the page into a sequence of
+	/// operations.
+	pub fn operations(&self) -> Result<Vec<Operation>, PdfError> {
+		return parse_operations(&self.contents()?);
+	}
+
+	/// Attempts to extract the text shown on the page. Text shown with the
+	/// composite fonts which have no `/ToUnicode` map is recovered through the
+	/// `cmap` of their embedded program, if they have one.
+	pub fn extract_text(&self) -> Result<String, PdfError> {
+		let fonts = self.reader.font_decoders(self.get("Resources")?.as_ref());
+		return Ok(text::extract_text_with_fonts(&self.operations()?, &fonts));
+	}
+
+	/// Attempts to extract the text shown on the page, like
+	/// [`ReaderPage::extract_text`], with the position of every shown string.
+	pub fn extract_text_positioned(&self) -> Result<Vec<text::PositionedText>, PdfError> {
+		let fonts = self.reader.font_decoders(self.get("Resources")?.as_ref());
+		return Ok(text::positioned_text(&self.operations()?, &fonts));
+	}
+
+	/// Attempts to format the contents of the page for debugging, like
+	/// [`ContentStream::to_pretty_string`].
+	///
+	/// [`ContentStream::to_pretty_string`]: crate::ContentStream::to_pretty_string
+	pub fn content_debug(&self) -> Result<String, PdfError> {
+		return Ok(pretty_print(&self.operations()?));
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::*;
+
+	#[test]
+	fn reads_written_document() {
+		let mut doc = Document::new();
+		let font = doc.add_base_font("Times-Roman");
+		let mut page = doc.add_page(612.0, 792.0);
+		page.add_font_resource("F13", font);
+		let mut content = ContentStream::new();
+		content.begin_text().set_font("F13", 10.0).move_text(12.0, 775.0).show_text(b"Hello!").end_text();
+		page.set_contents(content);
+
+		let mut data = Vec::new();
+		doc.write(&mut Writer::new(&mut data)).unwrap();
+
+		let reader = Reader::new(&data).unwrap();
+		let root = reader.trailer().get("Root").and_then(PdfObject::as_reference).unwrap();
+		let catalog = reader.resolve(root).unwrap();
+		assert_eq!(catalog.as_dict().unwrap().get_name("Type"), Some(&b"Catalog"[..]));
+		assert_eq!(reader.object_ids().count(), 5);
+
+		let contents = reader.resolve(ObjectId::new(5, 0)).unwrap();
+		assert_eq!(contents.as_stream().unwrap().data, b"BT\n/F13 10 Tf\n12 775 Td\n(Hello!) Tj\nET\n");
+		assert_eq!(reader.page(0).unwrap().content_debug().unwrap(), "BT\n  /F13 10 Tf\n  12 775 Td\n  (Hello!) Tj\nET\n");
+		assert!(matches!(reader.resolve(ObjectId::new(9, 0)), Err(PdfError::MissingObject(_))));
+
+		let strict = Reader::with_mode(&data, ParseMode::Strict).unwrap();
+		assert_eq!(strict.resolve(ObjectId::new(5, 0)).unwrap(), contents);
+	}
+
+	#[test]
+	fn inherited_page_attributes() {
+		let data = file(&[
+			b"<< /Type /Catalog /Pages 2 0 R >>",
+			b"<< /Type /Pages /Kids [3 0 R 4 0 R] /Count 3 /MediaBox [0 0 612 792] /Rotate 90 >>",
+			b"<< /Type /Page /Parent 2 0 R /Rotate 0 >>",
+			b"<< /Type /Pages /Parent 2 0 R /Kids [5 0 R 6 0 R] /Count 2 /CropBox [0 0 300 300] >>",
+			b"<< /Type /Page /Parent 4 0 R >>",
+			b"<< /Type /Page /Parent 4 0 R /MediaBox [0 0 400 400] >>",
+		]);
+		let reader = Reader::new(&data).unwrap();
+		let pages = reader.pages().unwrap();
+		assert_eq!(pages, [3, 5, 6].map(|number| ObjectId::new(number, 0)));
+
+		let attribute = |index: usize, key| reader.page_attribute(pages[index], key).unwrap();
+		let media_box = |index| attribute(index, "MediaBox").as_ref().and_then(Rectangle::from_pdf_array);
+		assert_eq!(media_box(0), Some(Rectangle::new(0.0, 0.0, 612.0, 792.0)));
+		assert_eq!(media_box(1), Some(Rectangle::new(0.0, 0.0, 612.0, 792.0)));
+		assert_eq!(media_box(2), Some(Rectangle::new(0.0, 0.0, 400.0, 400.0)));
+		assert_eq!(attribute(0, "Rotate"), Some(PdfObject::Integer(0)));
+		assert_eq!(attribute(2, "Rotate"), Some(PdfObject::Integer(90)));
+		assert_eq!(attribute(0, "CropBox"), None);
+		assert!(attribute(1, "CropBox").is_some());
+		// Only some of the attributes are inheritable.
+		assert_eq!(attribute(0, "Kids"), None);
+		assert_eq!(reader.page(1).unwrap().get("CropBox").unwrap(), attribute(1, "CropBox"));
+
+		let mut doc = Document::load(&data).unwrap();
+		assert_eq!(doc.page_mut(1).unwrap().dict().get("MediaBox"), attribute(1, "MediaBox").as_ref());
+	}
+
+	#[test]
+	fn page_tree_navigation() {
+		let data = file(&[
+			b"<< /Type /Catalog /Pages 2 0 R /PageMode /UseOutlines >>",
+			b"<< /Type /Pages /Kids [3 0 R 6 0 R] /Count 3 >>",
+			b"<< /Type /Pages /Parent 2 0 R /Kids [4 0 R 5 0 R] /Count 2 >>",
+			b"<< /Type /Page /Parent 3 0 R /MediaBox [0 0 100 100] >>",
+			b"<< /Type /Page /Parent 3 0 R /MediaBox [0 0 200 200] >>",
+			b"<< /Type /Page /Parent 2 0 R /MediaBox [0 0 300 300] >>",
+		]);
+		let reader = Reader::new(&data).unwrap();
+		assert_eq!(reader.catalog().unwrap().get_name("PageMode"), Some(&b"UseOutlines"[..]));
+		assert_eq!(reader.page_count().unwrap(), 3);
+
+		for (index, (number, size)) in [(4, 100.0), (5, 200.0), (6, 300.0)].into_iter().enumerate() {
+			let page = reader.page(index).unwrap();
+			assert_eq!(page.id(), ObjectId::new(number, 0));
+			assert_eq!(page.dict().get_name("Type"), Some(&b"Page"[..]));
+			assert_eq!(page.dict().get("MediaBox").and_then(Rectangle::from_pdf_array), Some(Rectangle::new(0.0, 0.0, size, size)));
+		}
+		assert!(matches!(reader.page(3), Err(PdfError::PageOutOfRange { index: 3, count: 3 })));
+		assert_eq!(reader.pages().unwrap(), [4, 5, 6].map(|number| ObjectId::new(number, 0)));
+
+		let data = file(&[
+			b"<< /Type /Catalog /Pages 2 0 R >>",
+			b"<< /Type /Pages /Kids [3 0 R] /Count 1 >>",
+			b"<< /Type /Pages /Parent 2 0 R /Kids [2 0 R] /Count 1 >>",
+		]);
+		let reader = Reader::new(&data).unwrap();
+		assert!(reader.page_count().is_err());
+		// Failures aren't cached, so they're reported every time.
+		assert!(reader.page(0).is_err());
+	}
+
+	/// Creates file with the given objects, numbered from 1, where the first
+	/// one is the catalog.
+	fn file(objects: &[&[u8]]) -> Vec<u8> {
+		let mut data = b"%PDF-1.7\n".to_vec();
+		let mut offsets = Vec::new();
+		for (index, object) in objects.iter().enumerate() {
+			offsets.push(data.len());
+			data.extend_from_slice(std::format!("{} 0 obj\n", index + 1).as_bytes());
+			data.extend_from_slice(object);
+			data.extend_from_slice(b"\nendobj\n");
+		}
+
+		let xref = data.len();
+		data.extend_from_slice(std::format!("xref\n0 {}\n0000000000 65535 f\r\n", objects.len() + 1).as_bytes());
+		for offset in offsets {
+			data.extend_from_slice(std::format!("{:010} 00000 n\r\n", offset).as_bytes());
+		}
+		data.extend_from_slice(std::format!("trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n", objects.len() + 1, xref).as_bytes());
+		return data;
+	}
+
+	#[test]
+	fn stream_lengths() {
+		let data = file(&[
+			b"<< /Type /Catalog >>",
+			b"<< /Length 3 0 R >>\nstream\nHello, world!\nendstream",
+			b"13",
+			b"<< /Length 5 >>\nstream\r\nHello, world!\r\nendstream",
+			b"<< /Length 6 0 R >>\nstream\nHello,\nworld!\nendstream",
+			b"<< /Length 6 0 R >>\nstream\nendstream",
+		]);
+		let reader = Reader::new(&data).unwrap();
+		let data = |number| reader.resolve(ObjectId::new(number, 0)).unwrap().as_stream().unwrap().data.clone();
+		assert_eq!(data(2), b"Hello, world!");
+		// The length is too small, it's not a number, and it's a reference to
+		// the stream itself.
+		assert_eq!(data(4), b"Hello, world!");
+		assert_eq!(data(5), b"Hello,\nworld!");
+		assert_eq!(data(6), b"");
+	}
+
+	#[test]
+	fn empty_streams() {
+		let objects: [&[u8]; 6] = [
+			b"<< /Type /Catalog >>",
+			b"<< /Length 0 >>\nstream\nendstream",
+			b"<< /Length 0 >>\nstream\r\n\nendstream",
+			b"<< >>\nstream\nendstream",
+			b"<< >>\nstream endstream",
+			b"(after)",
+		];
+		let data = file(&objects);
+		for mode in [ParseMode::Strict, ParseMode::Lenient] {
+			let reader = Reader::with_mode(&data, mode).unwrap();
+			for number in 2..4 {
+				let stream = reader.resolve(ObjectId::new(number, 0)).unwrap();
+				assert_eq!(stream.as_stream().unwrap().data, b"");
+				assert_eq!(reader.stream_raw(ObjectId::new(number, 0)).unwrap(), b"");
+			}
+			assert_eq!(reader.resolve(ObjectId::new(6, 0)).unwrap(), PdfObject::string("after"));
+		}
+
+		// Without the `/Length`, the data is found by the `endstream` keyword.
+		let reader = Reader::new(&data).unwrap();
+		for number in 4..6 {
+			assert_eq!(reader.resolve(ObjectId::new(number, 0)).unwrap().as_stream().unwrap().data, b"");
+		}
+		assert!(Reader::with_mode(&data, ParseMode::Strict).unwrap().resolve(ObjectId::new(4, 0)).is_err());
+	}
+
+	#[test]
+	fn parse_modes() {
+		let mut data = file(&[
+			b"<< /Type /Catalog /Pages 2 0 R >>",
+			b"<< /Type /Pages /Kids [3 0 R] /Count 1 >>",
+			b"<< /Type /Page /Parent 2 0 R /Rotate --90 /Contents 4 0 R >>",
+			b"<< /Length 3 >>\nstream\rq Q\nendstream",
+		]);
+		// Shifts everything, so that the offsets in the table are wrong.
+		data.splice(9..9, b"% edited\n".iter().copied());
+
+		assert!(matches!(Reader::with_mode(&data, ParseMode::Strict), Err(PdfError::Syntax { .. })));
+		let lenient = Reader::with_mode(&data, ParseMode::Lenient).unwrap();
+		let page = lenient.page(0).unwrap();
+		assert_eq!(page.get("Rotate").unwrap(), Some(PdfObject::Integer(-90)));
+		let contents = page.get("Contents").unwrap().unwrap().as_reference().unwrap();
+		assert_eq!(lenient.resolve(contents).unwrap().as_stream().unwrap().data, b"q Q");
+
+		// Without the table and the trailer, only the lenient mode can find
+		// the objects.
+		let end = data.windows(4).position(|window| window == b"xref").unwrap();
+		data.truncate(end);
+		data.extend_from_slice(b"%%EOF\n");
+		assert!(matches!(Reader::with_mode(&data, ParseMode::Strict), Err(PdfError::Syntax { .. })));
+		let lenient = Reader::new(&data).unwrap();
+		assert_eq!(lenient.trailer().get("Root"), Some(&PdfObject::Reference(ObjectId::new(1, 0))));
+		assert_eq!(lenient.page_count().unwrap(), 1);
+	}
+
+	#[test]
+	fn diagnostics() {
+		let mut data = file(&[b"<< /Type /Catalog /Pages 2 0 R >>", b"<< /Type /Pages /Kids [] /Count 0 >>"]);
+		let xref = data.windows(4).position(|window| window == b"xref").unwrap();
+		// Points the entry of the page tree at the catalog.
+		let entry = xref + b"xref\n0 3\n".len() + 2 * 20;
+		let catalog = data[entry - 20..entry - 10].to_vec();
+		data.splice(entry..entry + 10, catalog);
+
+		let events = std::cell::RefCell::new(Vec::new());
+		let collect = |event: &Diagnostic| events.borrow_mut().push(event.clone());
+		let reader = Reader::with_diagnostics(&data, ParseMode::Lenient, &collect).unwrap();
+		assert_eq!(reader.page_count().unwrap(), 0);
+		let events = events.take();
+		assert_eq!(events[0], Diagnostic::XrefSection { offset: xref });
+		assert!(matches!(&events[1], Diagnostic::ObjectRecovered { id, message } if *id == ObjectId::new(2, 0) && message.contains("expected object 2, found 1")));
+
+		data.truncate(xref);
+		data.extend_from_slice(b"%%EOF\n");
+		let events = std::cell::RefCell::new(Vec::new());
+		let collect = |event: &Diagnostic| events.borrow_mut().push(event.clone());
+		Reader::with_diagnostics(&data, ParseMode::Lenient, &collect).unwrap();
+		assert!(matches!(&events.borrow()[..], [Diagnostic::XrefRebuilt { objects: 2, error }] if error.contains("startxref")));
+	}
+
+	#[test]
+	fn generations() {
+		let mut data = file(&[b"<< /Type /Catalog /Pages 2 0 R >>", b"<< /Type /Pages /Kids [] /Count 0 >>", b"(old)"]);
+		let prev = data.windows(4).position(|window| window == b"xref").unwrap();
+		let offset = data.len();
+		data.extend_from_slice(b"3 1 obj\n(new)\nendobj\n");
+		let xref = data.len();
+		data.extend_from_slice(std::format!("xref\n3 1\n{:010} 00001 n\r\ntrailer\n<< /Size 4 /Root 1 0 R /Prev {} >>\nstartxref\n{}\n%%EOF\n", offset, prev, xref).as_bytes());
+
+		let strict = Reader::with_mode(&data, ParseMode::Strict).unwrap();
+		assert_eq!(strict.resolve(ObjectId::new(3, 0)).unwrap(), PdfObject::string(b"old".to_vec()));
+		assert_eq!(strict.resolve(ObjectId::new(3, 1)).unwrap(), PdfObject::string(b"new".to_vec()));
+		assert!(matches!(strict.resolve(ObjectId::new(3, 2)), Err(PdfError::MissingObject(_))));
+		assert!(strict.object_ids().any(|id| id == ObjectId::new(3, 1)));
+
+		let events = std::cell::RefCell::new(Vec::new());
+		let collect = |event: &Diagnostic| events.borrow_mut().push(event.clone());
+		let lenient = Reader::with_diagnostics(&data, ParseMode::Lenient, &collect).unwrap();
+		assert_eq!(lenient.resolve(ObjectId::new(3, 2)).unwrap(), PdfObject::string(b"new".to_vec()));
+		assert!(matches!(events.borrow().last(), Some(Diagnostic::ObjectRecovered { id, .. }) if *id == ObjectId::new(3, 2)));
+	}
+
+	#[test]
+	fn versions() {
+		let mut data = file(&[b"<< /Type /Catalog /Version /1.7 >>"]);
+		data[7] = b'4';
+		assert!(data.starts_with(b"%PDF-1.4\n"));
+		assert_eq!(Reader::new(&data).unwrap().pdf_version(), (1, 7));
+		let data = file(&[b"<< /Type /Catalog /Version /1.3 >>"]);
+		assert_eq!(Reader::new(&data).unwrap().pdf_version(), (1, 7));
+
+		let mut doc = Document::new();
+		doc.add_page(612.0, 792.0);
+		doc.set_version((1, 4));
+		let mut data = Vec::new();
+		doc.write(&mut Writer::new(&mut data)).unwrap();
+		assert!(data.starts_with(b"%PDF-1.4\n"));
+		assert_eq!(Reader::new(&data).unwrap().pdf_version(), (1, 4));
+
+		let mut doc = Document::load(&data).unwrap();
+		doc.set_catalog_version((1, 6));
+		doc.set_version((2, 0));
+		let mut data = Vec::new();
+		doc.write(&mut Writer::new(&mut data)).unwrap();
+		assert!(data.starts_with(b"%PDF-2.0\n"));
+		assert_eq!(Reader::new(&data).unwrap().catalog().unwrap().get_name("Version"), Some(&b"2.0"[..]));
+	}
+
+	#[test]
+	fn hybrid_reference_file() {
+		let mut data = b"%PDF-1.5\n".to_vec();
+		let mut offsets = Vec::new();
+		let mut object = |data: &mut Vec<u8>, number: usize, body: &[u8]| {
+			offsets.push(data.len());
+			data.extend_from_slice(std::format!("{} 0 obj\n", number).as_bytes());
+			data.extend_from_slice(body);
+			data.extend_from_slice(b"\nendobj\n");
+		};
+		object(&mut data, 1, b"<< /Type /Catalog /Pages 2 0 R >>");
+		object(&mut data, 2, b"<< /Type /Pages /Kids [3 0 R] /Count 1 >>");
+		object(&mut data, 3, b"<< /Type /Page /Parent 2 0 R /MediaBox [0 0 612 792] /Resources 4 0 R >>");
+		// Object 4 is only in the object stream, which is listed by the
+		// cross-reference stream alone.
+		object(&mut data, 5, b"<< /Type /ObjStm /N 1 /First 4 /Length 25 >>\nstream\n4 0 << /ProcSet [/PDF] >>\nendstream");
+		// Row for object 4, with the PNG Up filter: type 2, stream 5, index 0.
+		let rows = filters::flate::encode(&[2, 2, 0, 5, 0]);
+		let mut xref_stream = std::format!("<< /Type /XRef /Size 7 /W [1 2 1] /Index [4 1] /Filter /FlateDecode /DecodeParms << /Columns 4 /Predictor 12 >> /Length {} >>\nstream\n", rows.len()).into_bytes();
+		xref_stream.extend_from_slice(&rows);
+		xref_stream.extend_from_slice(b"\nendstream");
+		object(&mut data, 6, &xref_stream);
+
+		let xref = data.len();
+		data.extend_from_slice(b"xref\n0 7\n0000000000 65535 f\r\n");
+		for (index, offset) in offsets.iter().enumerate() {
+			data.extend_from_slice(std::format!("{:010} 00000 n\r\n", offset).as_bytes());
+			if index == 2 {
+				data.extend_from_slice(b"0000000000 00001 f\r\n");
+			}
+		}
+		data.extend_from_slice(std::format!("trailer\n<< /Size 7 /Root 1 0 R /XRefStm {} >>\nstartxref\n{}\n%%EOF\n", offsets[4], xref).as_bytes());
+
+		let reader = Reader::new(&data).unwrap();
+		assert_eq!(reader.object_ids().count(), 6);
+		let resources = reader.resolve(ObjectId::new(4, 0)).unwrap();
+		assert_eq!(resources.as_dict().unwrap().get("ProcSet"), Some(&PdfObject::Array(std::vec![PdfObject::name("PDF")])));
+		assert_eq!(reader.page_attribute(ObjectId::new(3, 0), "Resources").unwrap(), Some(PdfObject::Reference(ObjectId::new(4, 0))));
+
+		// The structure of the file is not carried over when it's loaded.
+		let doc = Document::load(&data).unwrap();
+		assert!(doc.object(ObjectId::new(4, 0)).is_some());
+		assert!(doc.object(ObjectId::new(5, 0)).is_none() && doc.object(ObjectId::new(6, 0)).is_none());
+	}
+
+	#[test]
+	fn stream_data_starting_with_newlines() {
+		let mut doc = Document::new();
+		let contents = b"\r\n\r\nq\r1 0 0 1 0 0 cm\nQ\r\n";
+		let id = doc.add_object(Stream::new(Dictionary::new(), contents.to_vec()));
+		doc.add_page(612.0, 792.0).dict_mut().set("Contents", id);
+
+		let mut data = Vec::new();
+		doc.write(&mut Writer::new(&mut data)).unwrap();
+		let reader = Reader::new(&data).unwrap();
+		assert_eq!(reader.resolve(id).unwrap().as_stream().unwrap().data, contents);
+
+		let mut doc = Document::load(&data).unwrap();
+		let operators: Vec<Vec<u8>> = doc.page_mut(0).unwrap().operations().unwrap().into_iter().map(|operation| operation.operator).collect();
+		assert_eq!(operators, [&b"q"[..], b"cm", b"Q"]);
+	}
+
+	#[test]
+	fn trailing_junk() {
+		let mut doc = Document::new();
+		doc.add_page(612.0, 792.0);
+		let mut data = Vec::new();
+		doc.write(&mut Writer::new(&mut data)).unwrap();
+		let startxref = Reader::new(&data).unwrap().startxref().unwrap();
+
+		let junk = |length: usize| (0..length).map(|i| b"junk %%EO\n\x80"[i % 11]).collect::<Vec<u8>>();
+		let rebuilt = std::cell::Cell::new(false);
+		let callback = |diagnostic: &Diagnostic| rebuilt.set(rebuilt.get() || matches!(diagnostic, Diagnostic::XrefRebuilt { .. }));
+		let with_junk = [data.clone(), junk(512)].concat();
+		let reader = Reader::with_diagnostics(&with_junk, ParseMode::Lenient, &callback).unwrap();
+		assert_eq!(reader.startxref().unwrap(), startxref);
+		assert_eq!(reader.page_count().unwrap(), 1);
+		assert!(!rebuilt.get());
+		assert!(Reader::with_mode(&with_junk, ParseMode::Strict).is_err());
+
+		// Junk after another end-of-file marker of an update is skipped too.
+		let twice = [data.clone(), b"%%EOF\n".to_vec(), junk(100)].concat();
+		assert_eq!(Reader::new(&twice).unwrap().startxref().unwrap(), startxref);
+		let too_much = [data, junk(2000)].concat();
+		assert!(Reader::new(&too_much).unwrap().startxref().is_err());
+	}
+
+	#[test]
+	fn entries_beyond_size() {
+		let mut data = file(&[b"<< /Type /Catalog /Pages 2 0 R >>", b"<< /Type /Pages /Kids [] /Count 0 >>"]);
+		let xref = data.windows(4).position(|window| window == b"xref").unwrap();
+		data.truncate(xref);
+		let offset = data.len();
+		data.extend_from_slice(b"5 0 obj\n(beyond)\nendobj\n");
+		let offsets: Vec<usize> = [b"1 0 obj", b"2 0 obj"].iter().map(|header| data.windows(7).position(|window| window == *header).unwrap()).collect();
+		let xref = data.len();
+		data.extend_from_slice(std::format!("xref\n0 3\n0000000000 65535 f\r\n{:010} 00000 n\r\n{:010} 00000 n\r\n5 1\n{:010} 00000 n\r\n", offsets[0], offsets[1], offset).as_bytes());
+		data.extend_from_slice(std::format!("trailer\n<< /Size 3 /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n", xref).as_bytes());
+
+		assert!(Reader::with_mode(&data, ParseMode::Strict).is_err());
+		let events = std::cell::RefCell::new(Vec::new());
+		let collect = |event: &Diagnostic| events.borrow_mut().push(event.clone());
+		let reader = Reader::with_diagnostics(&data, ParseMode::Lenient, &collect).unwrap();
+		assert_eq!(reader.resolve(ObjectId::new(5, 0)).unwrap(), PdfObject::string(b"beyond".to_vec()));
+		assert!(matches!(reader.resolve(ObjectId::new(4, 0)), Err(PdfError::MissingObject(_))));
+		assert_eq!(reader.trailer().get("Size"), Some(&PdfObject::Integer(6)));
+		assert!(matches!(events.borrow().last(), Some(Diagnostic::ObjectRecovered { id, .. }) if *id == ObjectId::new(5, 0)));
+
+		let mut doc = Document::load(&data).unwrap();
+		let added = doc.add_object(PdfObject::Integer(1));
+		assert!(added.number > 5);
+	}
+
+	#[test]
+	fn borrowed_stream_data() {
+		let jpeg = b"\xFF\xD8\xFF\xE0 not really a JPEG \xFF\xD9".to_vec();
+		let mut doc = Document::new();
+		let mut image = Stream::new(Dictionary::new(), jpeg.clone());
+		image.dict.set("Subtype", PdfObject::name("Image"));
+		image.dict.set("Filter", PdfObject::name("DCTDecode"));
+		let image = doc.add_object(image);
+		let plain = doc.add_object(Stream::new(Dictionary::new(), b"plain".to_vec()));
+		let mut compressed = Stream::new(Dictionary::new(), filters::flate::encode(b"compressed"));
+		compressed.dict.set("Filter", PdfObject::name("FlateDecode"));
+		let compressed = doc.add_object(compressed);
+		let page = doc.add_page(612.0, 792.0).id();
+
+		let mut data = Vec::new();
+		doc.write(&mut Writer::new(&mut data)).unwrap();
+		let reader = Reader::new(&data).unwrap();
+		let raw = reader.stream_raw(image).unwrap();
+		assert_eq!(raw, jpeg);
+		assert!(data.as_ptr_range().contains(&raw.as_ptr()));
+		assert!(reader.stream_raw(page).is_err());
+
+		assert!(matches!(reader.stream_decoded(plain).unwrap(), std::borrow::Cow::Borrowed(b"plain")));
+		assert!(matches!(reader.stream_decoded(compressed).unwrap(), std::borrow::Cow::Owned(data) if data == b"compressed"));
+	}
+
+	#[test]
+	fn all_objects() {
+		let mut doc = Document::new();
+		let font = doc.add_base_font("Times-Roman");
+		let mut page = doc.add_page(612.0, 792.0);
+		page.add_font_resource("F13", font);
+		let mut content = ContentStream::new();
+		content.begin_text().set_font("F13", 10.0).move_text(12.0, 775.0).show_text(b"Hello!").end_text();
+		page.set_contents(content);
+		let page = page.id();
+		let mut output = Vec::new();
+		doc.write(&mut Writer::new(&mut output)).unwrap();
+
+		let reader = Reader::new(&output).unwrap();
+		let objects: Vec<(ObjectId, PdfObject)> = reader.objects().collect::<Result<_, _>>().unwrap();
+		let kinds: Vec<(ObjectId, &str)> = objects.iter().map(|(id, object)| {
+			let kind = match object {
+				PdfObject::Stream(_) => "content",
+				object => match object.as_dict().and_then(|dict| dict.get_name("Type")) {
+					Some(b"Catalog") => "catalog",
+					Some(b"Pages") => "pages",
+					Some(b"Page") => "page",
+					Some(b"Font") => "font",
+					_ => "other",
+				},
+			};
+			return (*id, kind);
+		}).collect();
+		let contents = reader.resolve_dict(page).unwrap().get("Contents").and_then(PdfObject::as_reference).unwrap();
+		assert_eq!(kinds, [(ObjectId::new(1, 0), "catalog"), (ObjectId::new(2, 0), "pages"), (font.0, "font"), (page, "page"), (contents, "content")]);
+	}
+
+	#[test]
+	fn integer_media_boxes() {
+		let data = file(&[
+			b"<< /Type /Catalog /Pages 2 0 R >>",
+			b"<< /Type /Pages /Kids [3 0 R 4 0 R 5 0 R 6 0 R] /Count 4 /MediaBox [0 0 612 792] >>",
+			b"<< /Type /Page /Parent 2 0 R >>",
+			b"<< /Type /Page /Parent 2 0 R /MediaBox [0.0 0.0 612.0 792.0] >>",
+			b"<< /Type /Page /Parent 2 0 R /MediaBox [0 0.0 612 792.0] >>",
+			b"<< /Type /Page /Parent 2 0 R /MediaBox 7 0 R >>",
+			b"[0 0 8 0 R 792]",
+			b"612",
+		]);
+		let reader = Reader::new(&data).unwrap();
+		for index in 0..4 {
+			assert_eq!(reader.page(index).unwrap().media_box().unwrap(), Some(Rectangle::new(0.0, 0.0, 612.0, 792.0)));
+		}
+	}
+}
@@ -0,0 +1,648 @@
+//! Application of redaction annotations, i.e. removal of the marked content.
+
+use std::collections::HashMap;
+
+use crate::content::{parse_operations, write_operations, Operation};
+use crate::text::TextRun;
+use crate::{document, text};
+use crate::{Color, ContentStream, Dictionary, Document, Matrix, ObjectId, Page, PdfError, PdfObject, Point, Rectangle, Stream};
+
+/// Regions covered by a single redaction annotation.
+struct Redaction {
+	id: ObjectId,
+	rects: Vec<Rectangle>,
+	color: Option<Color>,
+}
+
+fn redactions(doc: &Document, page: ObjectId) -> Vec<Redaction> {
+	let Some(annots) = doc.object(page).and_then(PdfObject::as_dict).and_then(|page| page.get("Annots")).and_then(PdfObject::as_array) else {
+		return Vec::new();
+	};
+
+	let mut redactions = Vec::new();
+	for id in annots.iter().filter_map(PdfObject::as_reference) {
+		let Some(annot) = doc.object(id).and_then(PdfObject::as_dict) else {
+			continue;
+		};
+
+		if annot.get_name("Subtype") != Some(b"Redact") {
+			continue;
+		}
+
+		let numbers = |key| annot.get(key).and_then(PdfObject::as_array).map(|array| {
+			return array.iter().filter_map(PdfObject::as_number).collect::<Vec<_>>();
+		});
+
+		// Each quadrilateral is used through its bounding box, falling back to the
+		// annotation rect when there are no quads.
+		let mut rects = numbers("QuadPoints").unwrap_or_default().chunks_exact(8).map(|quad| {
+			return Rectangle::bounding(quad.chunks_exact(2).map(|p| Point::new(p[0], p[1])));
+		}).collect::<Vec<_>>();
+		if rects.is_empty() {
+			if let Some(&[llx, lly, urx, ury]) = numbers("Rect").as_deref() {
+				rects.push(Rectangle::new(llx, lly, urx, ury));
+			}
+		}
+
+		let color = match numbers("IC").as_deref() {
+			Some(&[g]) => Some(Color::Gray(g)),
+			Some(&[r, g, b]) => Some(Color::Rgb(r, g, b)),
+			Some(&[c, m, y, k]) => Some(Color::Cmyk(c, m, y, k)),
+			_ => None,
+		};
+
+		redactions.push(Redaction {
+			id,
+			rects,
+			color,
+		});
+	}
+
+	return redactions;
+}
+
+/// Smallest half width of stroked lines, in the page coordinates, as even
+/// the thinnest lines are painted.
+const HAIRLINE: f64 = 0.5;
+/// Deepest nesting of form XObjects which are redacted. Forms nested deeper,
+/// which meet the redacted regions, are removed as a whole.
+const MAX_FORM_DEPTH: usize = 16;
+
+/// Gets the strings and numbers shown by the text showing operation, as the
+/// glyphs of its run refer to them.
+fn shown_elements(operation: &Operation) -> &[PdfObject] {
+	return match operation.operator.as_slice() {
+		b"TJ" => operation.operands.first().and_then(PdfObject::as_array).map_or(&[], Vec::as_slice),
+		b"\"" => &operation.operands[operation.operands.len().min(2)..],
+		_ => &operation.operands,
+	};
+}
+
+/// Rebuilds the text showing operation, with the glyphs within any of the
+/// rects replaced by the numbers of a text showing array which move the text
+/// position as much, so that the other glyphs, and the text which follows,
+/// keep their positions.
+fn redact_run(operation: &Operation, run: &TextRun, rects: &[Rectangle]) -> Vec<Operation> {
+	let mut replacement = Vec::new();
+	match operation.operator.as_slice() {
+		b"'" => replacement.push(Operation::new("T*", Vec::new())),
+		b"\"" => {
+			if let [word_spacing, char_spacing, ..] = operation.operands.as_slice() {
+				replacement.push(Operation::new("Tw", std::vec![word_spacing.clone()]));
+				replacement.push(Operation::new("Tc", std::vec![char_spacing.clone()]));
+			}
+			replacement.push(Operation::new("T*", Vec::new()));
+		},
+		_ => {},
+	}
+
+	let mut array = Vec::new();
+	// Movement of the removed glyphs, and the numbers, not written yet.
+	let mut adjustment = None;
+	for (index, element) in shown_elements(operation).iter().enumerate() {
+		let PdfObject::String(bytes, format) = element else {
+			*adjustment.get_or_insert(0.0) += element.as_number().unwrap_or(0.0);
+			continue;
+		};
+
+		let mut kept = Vec::new();
+		for glyph in run.glyphs.iter().filter(|glyph| glyph.element == index) {
+			if rects.iter().any(|rect| rect.intersects(&glyph.bbox)) {
+				if !kept.is_empty() {
+					array.push(PdfObject::String(std::mem::take(&mut kept), *format));
+				}
+				*adjustment.get_or_insert(0.0) += glyph.adjustment;
+				continue;
+			}
+			if let Some(adjustment) = adjustment.take() {
+				array.push(PdfObject::Real(adjustment));
+			}
+			kept.extend_from_slice(&bytes[glyph.bytes.clone()]);
+		}
+		if !kept.is_empty() {
+			array.push(PdfObject::String(kept, *format));
+		}
+	}
+	array.extend(adjustment.map(PdfObject::Real));
+
+	replacement.push(Operation::new("TJ", std::vec![array.into()]));
+	return replacement;
+}
+
+/// Removes the glyphs shown within any of the rects, keeping the positions of
+/// the other glyphs, and of the text which follows, unchanged.
+fn remove_text(operations: Vec<Operation>, runs: &[TextRun], rects: &[Rectangle]) -> Vec<Operation> {
+	let mut replacements = HashMap::new();
+	for run in runs {
+		if run.glyphs.iter().any(|glyph| rects.iter().any(|rect| rect.intersects(&glyph.bbox))) {
+			replacements.insert(run.operation, redact_run(&operations[run.operation], run, rects));
+		}
+	}
+
+	let mut result = Vec::with_capacity(operations.len());
+	for (index, operation) in operations.into_iter().enumerate() {
+		match replacements.remove(&index) {
+			Some(replacement) => result.extend(replacement),
+			None => result.push(operation),
+		}
+	}
+
+	return result;
+}
+
+/// Gets the rectangle containing the rectangle transformed by the matrix.
+fn transform_rect(matrix: Matrix, rect: Rectangle) -> Rectangle {
+	let corners = [(rect.llx, rect.lly), (rect.urx, rect.lly), (rect.llx, rect.ury), (rect.urx, rect.ury)];
+	return Rectangle::bounding(corners.map(|(x, y)| matrix.transform(Point::new(x, y))));
+}
+
+/// Gets the part of the first rectangle which is within the other one.
+fn intersection(a: Rectangle, b: Rectangle) -> Rectangle {
+	return Rectangle::new(a.llx.max(b.llx), a.lly.max(b.lly), a.urx.min(b.urx), a.ury.min(b.ury));
+}
+
+/// Rectangle containing everything, i.e. the clipping region of the page
+/// before anything is clipped.
+const EVERYTHING: Rectangle = Rectangle::new(f64::NEG_INFINITY, f64::NEG_INFINITY, f64::INFINITY, f64::INFINITY);
+
+/// Parameters of the graphics state which affect the extent of what's
+/// painted, in the page coordinates.
+#[derive(Clone, Copy)]
+struct GraphicsState {
+	ctm: Matrix,
+	line_width: f64,
+	/// Bounding box of the clipping region.
+	clip: Rectangle,
+}
+
+/// Removal of everything painted within the redacted regions of a page.
+struct Redactor<'a> {
+	doc: &'a mut Document,
+	rects: Vec<Rectangle>,
+	/// Forms replaced with their redacted copies.
+	replaced: Vec<ObjectId>,
+	/// Names of the forms removed from the resources, which are no longer
+	/// painted.
+	removed: Vec<Vec<u8>>,
+}
+
+impl Redactor<'_> {
+	fn meets(&self, bbox: &Rectangle) -> bool {
+		return self.rects.iter().any(|rect| rect.intersects(bbox));
+	}
+
+	fn resolve(&self, object: &PdfObject) -> PdfObject {
+		return match object {
+			PdfObject::Reference(id) => self.doc.object(*id).cloned().unwrap_or(PdfObject::Null),
+			object => object.clone(),
+		};
+	}
+
+	/// Attempts to remove everything painted within the redacted regions by
+	/// the operations, starting with the given graphics state. The glyphs of
+	/// text are removed one by one, while paths, images, and shadings are
+	/// removed as a whole. Forms are redacted in copies, which are added to
+	/// the resources under new names.
+	fn redact(&mut self, operations: Vec<Operation>, mut state: GraphicsState, resources: &mut Dictionary, depth: usize) -> Result<Vec<Operation>, PdfError> {
+		let runs = text::text_runs_in(&operations, &self.doc.resource_font_decoders(resources), state.ctm);
+		let operations = remove_text(operations, &runs, &self.rects);
+
+		let mut result = Vec::with_capacity(operations.len());
+		let mut stack = Vec::new();
+		// Start of the current path among the result, and its points, in the
+		// page coordinates.
+		let mut path = None;
+		let mut points = Vec::new();
+		let mut clipping = false;
+		// Names of the forms replaced with their redacted copies.
+		let mut replaced = Vec::new();
+		for mut operation in operations {
+			let numbers: Vec<f64> = operation.operands.iter().filter_map(PdfObject::as_number).collect();
+			match operation.operator.as_slice() {
+				b"q" => stack.push(state),
+				b"Q" => {
+					if let Some(saved) = stack.pop() {
+						state = saved;
+					}
+				},
+				b"cm" => {
+					if let [a, b, c, d, e, f] = numbers[..] {
+						state.ctm = Matrix::new(a, b, c, d, e, f).then(&state.ctm);
+					}
+				},
+				b"w" => state.line_width = numbers.first().copied().unwrap_or(1.0),
+				b"re" => {
+					path.get_or_insert(result.len());
+					if let [x, y, width, height] = numbers[..] {
+						points.extend([(x, y), (x + width, y), (x, y + height), (x + width, y + height)].map(|(x, y)| state.ctm.transform(Point::new(x, y))));
+					}
+				},
+				b"m" | b"l" | b"c" | b"v" | b"y" | b"h" => {
+					path.get_or_insert(result.len());
+					// Control points are included, so the box may be larger than
+					// the curves.
+					points.extend(numbers.chunks_exact(2).map(|point| state.ctm.transform(Point::new(point[0], point[1]))));
+				},
+				b"W" | b"W*" => clipping = true,
+				b"S" | b"s" | b"f" | b"F" | b"f*" | b"B" | b"B*" | b"b" | b"b*" | b"n" => {
+					let start = path.take().unwrap_or(result.len());
+					let mut bbox = Rectangle::bounding(points.drain(..));
+					if std::mem::take(&mut clipping) {
+						state.clip = intersection(state.clip, bbox);
+						// The clipping path is kept for what follows, but it's no
+						// longer painted.
+						if operation.operator != b"n" && self.meets(&bbox) {
+							result.push(Operation::new("n", Vec::new()));
+							continue;
+						}
+					}
+					if operation.operator != b"n" {
+						if matches!(operation.operator.as_slice(), b"S" | b"s" | b"B" | b"B*" | b"b" | b"b*") {
+							let scale = state.ctm.a.hypot(state.ctm.b).max(state.ctm.c.hypot(state.ctm.d));
+							bbox = bbox.inflate((state.line_width * scale / 2.0).max(HAIRLINE));
+						}
+						if self.meets(&bbox) {
+							result.truncate(start);
+							continue;
+						}
+					}
+				},
+				// Shadings fill the clipping region.
+				b"sh" if self.meets(&state.clip) => continue,
+				// Images fill the unit square.
+				b"BI" if self.meets(&transform_rect(state.ctm, Rectangle::new(0.0, 0.0, 1.0, 1.0))) => continue,
+				b"Do" => {
+					let name = operation.operands.first().cloned();
+					if !self.redact_xobject(&mut operation.operands, state, resources, depth)? {
+						continue;
+					}
+					if operation.operands.first() != name.as_ref() {
+						replaced.extend(name.as_ref().and_then(PdfObject::as_name).map(<[u8]>::to_vec));
+					}
+				},
+				_ => {},
+			}
+			result.push(operation);
+		}
+
+		// The forms painted only within the redacted regions are no longer
+		// needed, and mustn't be left in the resources.
+		replaced.retain(|name| !result.iter().any(|operation| operation.operator == b"Do" && operation.operands.first().and_then(PdfObject::as_name) == Some(name)));
+		if let Some(PdfObject::Dictionary(xobjects)) = resources.get_mut("XObject") {
+			for name in &replaced {
+				xobjects.remove(&String::from_utf8_lossy(name));
+			}
+		}
+		self.removed.append(&mut replaced);
+		return Ok(result);
+	}
+
+	/// Attempts to redact the XObject painted by the `Do` operation with the
+	/// operands, returning whether the operation is kept. Images within the redacted regions are
+	/// removed, while forms are replaced with their redacted copies.
+	fn redact_xobject(&mut self, operands: &mut [PdfObject], state: GraphicsState, resources: &mut Dictionary, depth: usize) -> Result<bool, PdfError> {
+		let Some(name) = operands.first().and_then(PdfObject::as_name).map(<[u8]>::to_vec) else {
+			return Ok(true);
+		};
+		let mut xobjects = match resources.get("XObject").map(|xobjects| self.resolve(xobjects)) {
+			Some(PdfObject::Dictionary(xobjects)) => xobjects,
+			_ => return Ok(true),
+		};
+		let Some(id) = xobjects.get_raw(&name).and_then(PdfObject::as_reference) else {
+			return Ok(true);
+		};
+		let Some(PdfObject::Stream(form)) = self.doc.object(id).cloned() else {
+			return Ok(true);
+		};
+
+		if form.dict.get_name("Subtype") != Some(b"Form") {
+			return Ok(!self.meets(&transform_rect(state.ctm, Rectangle::new(0.0, 0.0, 1.0, 1.0))));
+		}
+		let matrix = match form.dict.get("Matrix").and_then(PdfObject::as_array).map(|matrix| matrix.iter().filter_map(PdfObject::as_number).collect::<Vec<_>>()).as_deref() {
+			Some(&[a, b, c, d, e, f]) => Matrix::new(a, b, c, d, e, f),
+			_ => Matrix::IDENTITY,
+		}.then(&state.ctm);
+		let clip = match form.dict.get("BBox").and_then(Rectangle::from_pdf_array) {
+			Some(bbox) => intersection(state.clip, transform_rect(matrix, bbox)),
+			None => state.clip,
+		};
+		if !self.meets(&clip) {
+			return Ok(true);
+		}
+		if depth == MAX_FORM_DEPTH {
+			return Ok(false);
+		}
+
+		let mut form_resources = match form.dict.get("Resources").map(|resources| self.resolve(resources)) {
+			Some(PdfObject::Dictionary(resources)) => resources,
+			_ => Dictionary::new(),
+		};
+		for value in form_resources.values_mut() {
+			*value = self.resolve(value);
+		}
+		let data = match form.dict.contains_key("Filter") {
+			true => self.doc.decode(&form)?,
+			false => form.data,
+		};
+		let state = GraphicsState {
+			ctm: matrix,
+			clip,
+			..state
+		};
+		let operations = self.redact(parse_operations(&data)?, state, &mut form_resources, depth + 1)?;
+
+		// The form may be painted elsewhere, so it's copied, rather than
+		// changed in place.
+		let mut dict = form.dict;
+		for key in ["Filter", "DecodeParms", "DL"] {
+			dict.remove(key);
+		}
+		dict.set("Resources", form_resources);
+		let copy = self.doc.add_object(Stream::new(dict, write_operations(&operations)));
+		self.replaced.push(id);
+
+		let name = String::from_utf8_lossy(&name).into_owned();
+		let name = (0..).map(|index| std::format!("{}R{}", name, index)).find(|name| !xobjects.contains_key(name)).unwrap();
+		xobjects.set(&name, copy);
+		resources.set("XObject", xobjects);
+		operands[0] = PdfObject::name(&name);
+		return Ok(true);
+	}
+}
+
+impl Document {
+	/// Applies all the redaction annotations, removing everything painted
+	/// within the marked regions from the page content, and filling them with
+	/// the annotation interior colour. Redaction annotations are removed
+	/// afterwards.
+	///
+	/// Glyphs of text are removed one by one, keeping the positions of the
+	/// others, while paths, images, and shadings which meet the regions are
+	/// removed as a whole. Form XObjects are redacted in copies, and the
+	/// content streams shared with other pages are copied as well, so the
+	/// other pages are left unchanged. The replaced objects are removed,
+	/// unless they're still used elsewhere.
+	pub fn apply_redactions(&mut self) -> Result<(), PdfError> {
+		let mut replaced = Vec::new();
+		for page in self.pages.clone() {
+			let redactions = redactions(self, page);
+			if redactions.is_empty() {
+				continue;
+			}
+
+			let page_handle = Page {
+				doc: self,
+				id: page,
+			};
+			let content_ids = page_handle.content_ids();
+			let operations = page_handle.operations()?;
+			let original = page_handle.resources();
+
+			let mut resources = original.clone();
+			let state = GraphicsState {
+				ctm: Matrix::IDENTITY,
+				line_width: 1.0,
+				clip: EVERYTHING,
+			};
+			let mut redactor = Redactor {
+				doc: self,
+				rects: redactions.iter().flat_map(|r| r.rects.iter().copied()).collect(),
+				replaced: Vec::new(),
+				removed: Vec::new(),
+			};
+			let mut operations = redactor.redact(operations, state, &mut resources, 0)?;
+			replaced.append(&mut redactor.replaced);
+			let removed = redactor.removed;
+			if let Some(pending) = self.resources.get_mut(&page) {
+				pending.remove("XObject", &removed);
+			}
+
+			let mut fill = ContentStream::new();
+			for redaction in &redactions {
+				fill.with_state(|fill| {
+					fill.set_fill_color(redaction.color.unwrap_or(Color::Gray(0.0)));
+					for &rect in &redaction.rects {
+						fill.rect(rect);
+					}
+					fill.fill();
+				});
+			}
+			operations.extend(parse_operations(fill.as_bytes())?);
+
+			if resources != original {
+				self.object_mut(page).and_then(PdfObject::as_dict_mut).expect("page object is not a dictionary").set("Resources", resources);
+			}
+
+			let data = write_operations(&operations);
+			let shared = self.pages.iter().filter(|&&other| other != page).filter_map(|&other| self.object(other).and_then(PdfObject::as_dict)).any(|other| {
+				return document::content_ids(other).iter().any(|id| content_ids.contains(id));
+			});
+			match (content_ids.as_slice(), shared) {
+				(&[id], false) if self.object(id).and_then(PdfObject::as_stream).is_some() => {
+					let Some(PdfObject::Stream(stream)) = self.object_mut(id) else {
+						unreachable!();
+					};
+					// The operations are written decoded, so the filters of the
+					// previous data no longer apply.
+					for key in ["Filter", "DecodeParms", "DL"] {
+						stream.dict.remove(key);
+					}
+					stream.data = data;
+				},
+				_ => {
+					// The redacted text must not be left in any of the previous
+					// streams, unless other pages show them.
+					replaced.extend(content_ids);
+					let id = self.add_object(Stream::new(Dictionary::new(), data));
+					self.object_mut(page).and_then(PdfObject::as_dict_mut).expect("page object is not a dictionary").set("Contents", id);
+				},
+			}
+
+			let removed = redactions.iter().map(|r| r.id).collect::<Vec<_>>();
+			if let Some(PdfObject::Array(annots)) = self.object_mut(page).and_then(PdfObject::as_dict_mut).and_then(|page| page.get_mut("Annots")) {
+				annots.retain(|annot| annot.as_reference().is_none_or(|id| !removed.contains(&id)));
+			}
+			for id in removed {
+				self.remove_object(id);
+			}
+		}
+
+		let used = self.used_objects();
+		for id in replaced {
+			if !used.contains(&id) {
+				self.remove_object(id);
+			}
+		}
+		return Ok(());
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::*;
+
+	#[test]
+	fn redaction_removes_text() {
+		let mut doc = Document::new();
+		let font = doc.add_base_font("Times-Roman");
+		let mut page = doc.add_page(612.0, 792.0);
+		page.add_font_resource("F13", font);
+		let mut content = ContentStream::new();
+		content.begin_text().set_font("F13", 10.0).move_text(12.0, 775.0).show_text(b"Hello!").show_text(b" World").end_text();
+		page.set_contents(content);
+		page.add_redaction(&[Rectangle::new(10.0, 770.0, 41.0, 785.0)]);
+
+		doc.apply_redactions().unwrap();
+
+		let page = doc.page_mut(0).unwrap();
+		assert_eq!(page.extract_text().unwrap(), " World");
+		assert!(page.dict().get("Annots").and_then(PdfObject::as_array).unwrap().is_empty());
+
+		let operations = page.operations().unwrap();
+		let data = content::write_operations(&operations);
+		assert!(!data.windows(5).any(|w| w == b"Hello"));
+		// Following text keeps its position.
+		let runs = text::text_runs(&operations);
+		assert_eq!(runs.last().map(|run| run.origin), Some(Point::new(42.0, 775.0)));
+		// Region is filled with black.
+		assert!(data.ends_with(b"q\n0 g\n10 770 31 15 re\nf\nQ\n"));
+	}
+
+	#[test]
+	fn compressed_redaction() {
+		let mut doc = Document::new();
+		doc.set_compression(CompressionPolicy::Threshold(0));
+		let font = doc.add_base_font("Times-Roman");
+		let mut page = doc.add_page(612.0, 792.0);
+		page.add_font_resource("F13", font);
+		let mut content = ContentStream::new();
+		content.begin_text().set_font("F13", 10.0).move_text(12.0, 775.0).show_text(b"Secret secret secret").end_text();
+		page.set_contents(content);
+		let mut original = Vec::new();
+		doc.write(&mut Writer::new(&mut original)).unwrap();
+
+		let mut doc = Document::load(&original).unwrap();
+		let contents = doc.page_mut(0).unwrap().content_ids()[0];
+		assert_eq!(doc.object(contents).and_then(PdfObject::as_stream).unwrap().dict.get_name("Filter"), Some(&b"FlateDecode"[..]));
+		doc.page_mut(0).unwrap().add_redaction(&[Rectangle::new(0.0, 0.0, 612.0, 792.0)]);
+		doc.apply_redactions().unwrap();
+
+		let stream = doc.object(contents).and_then(PdfObject::as_stream).unwrap();
+		assert!(!stream.dict.contains_key("Filter"));
+		assert!(stream.data.ends_with(b"f\nQ\n"));
+		assert_eq!(doc.page_mut(0).unwrap().extract_text().unwrap(), "");
+	}
+
+	#[test]
+	fn redaction_keeps_glyphs_outside() {
+		let mut doc = Document::new();
+		let font = doc.add_base_font("Times-Roman");
+		let mut page = doc.add_page(612.0, 792.0);
+		page.add_font_resource("F13", font);
+		let mut content = ContentStream::new();
+		content.begin_text().set_font("F13", 10.0).move_text(12.0, 775.0).show_text(b"Hello! World").end_text();
+		page.set_contents(content);
+		let runs = text::text_runs_with_fonts(&page.operations().unwrap(), &page.font_decoders());
+		let position = runs[0].glyphs[5].bbox;
+		page.add_redaction(&[Rectangle::new(10.0, 770.0, position.llx - 1.0, 785.0)]);
+
+		doc.apply_redactions().unwrap();
+
+		let page = doc.page_mut(0).unwrap();
+		let operations = page.operations().unwrap();
+		let data = content::write_operations(&operations);
+		assert!(!data.windows(5).any(|w| w == b"Hello"));
+		assert!(data.windows(7).any(|w| w == b"! World"));
+		// Kept glyphs don't move.
+		let runs = text::text_runs_with_fonts(&operations, &page.font_decoders());
+		assert_eq!(runs[0].text, "! World");
+		assert_eq!(runs[0].glyphs[0].bbox, position);
+	}
+
+	#[test]
+	fn redaction_removes_graphics() {
+		let mut doc = Document::new();
+		let image = doc.add_image(&Image::new(1, 1, 1, std::vec![0]));
+		let mut page = doc.add_page(612.0, 792.0);
+		let mut content = page.new_content();
+		let name = content.use_image(image);
+		content.rect(Rectangle::new(20.0, 20.0, 30.0, 30.0)).fill();
+		content.rect(Rectangle::new(300.0, 300.0, 310.0, 310.0)).fill();
+		content.move_to(Point::new(0.0, 60.0)).line_to(Point::new(100.0, 60.0)).stroke();
+		content.with_state(|content| {
+			content.transform(Matrix::new(10.0, 0.0, 0.0, 10.0, 40.0, 40.0)).draw_xobject(&name);
+		});
+		page.set_contents(content);
+		page.add_redaction(&[Rectangle::new(0.0, 0.0, 100.0, 100.0)]);
+
+		doc.apply_redactions().unwrap();
+
+		let data = content::write_operations(&doc.page_mut(0).unwrap().operations().unwrap());
+		let text = String::from_utf8(data).unwrap();
+		assert!(!text.contains("20 20 10 10 re"));
+		assert!(text.contains("300 300 10 10 re\nf\n"));
+		assert!(!text.contains(" l\nS\n"));
+		assert!(!text.contains("Do"));
+	}
+
+	#[test]
+	fn redaction_copies_forms() {
+		let mut doc = Document::new();
+		let font = doc.add_base_font("Helvetica");
+		let mut form = ContentStream::new();
+		let name = form.use_font(font);
+		form.begin_text().set_font(&name, 10.0).move_text(0.0, 0.0).show_text(b"Secret").end_text();
+		let form = doc.add_form_xobject(Rectangle::new(0.0, 0.0, 100.0, 20.0), form);
+		let mut page = doc.add_page(612.0, 792.0);
+		let mut content = page.new_content();
+		content.draw_form(form, Matrix::translate(100.0, 700.0));
+		page.set_contents(content);
+		page.add_redaction(&[Rectangle::new(90.0, 690.0, 200.0, 720.0)]);
+
+		doc.apply_redactions().unwrap();
+
+		assert!(doc.object(form.0).is_none());
+		let page = doc.page_mut(0).unwrap();
+		let xobjects = page.resources().get("XObject").and_then(PdfObject::as_dict).cloned().unwrap();
+		let (_, copy) = xobjects.iter().next().unwrap();
+		let copy = doc.object(copy.as_reference().unwrap()).and_then(PdfObject::as_stream).unwrap();
+		assert!(!copy.data.windows(6).any(|w| w == b"Secret"));
+	}
+
+	#[test]
+	fn redaction_copies_shared_contents() {
+		let mut doc = Document::new();
+		let font = doc.add_base_font("Times-Roman");
+		let mut page = doc.add_page(612.0, 792.0);
+		page.add_font_resource("F13", font);
+		let mut content = ContentStream::new();
+		content.begin_text().set_font("F13", 10.0).move_text(12.0, 775.0).show_text(b"Shared").end_text();
+		page.set_contents(content);
+		let contents = page.content_ids()[0];
+		let mut second = doc.add_page(612.0, 792.0);
+		second.add_font_resource("F13", font);
+		let second = second.id;
+		doc.object_mut(second).and_then(PdfObject::as_dict_mut).unwrap().set("Contents", contents);
+		doc.page_mut(1).unwrap().add_redaction(&[Rectangle::new(0.0, 0.0, 612.0, 792.0)]);
+
+		doc.apply_redactions().unwrap();
+
+		assert_eq!(doc.page_mut(0).unwrap().extract_text().unwrap(), "Shared");
+		assert_eq!(doc.page_mut(1).unwrap().extract_text().unwrap(), "");
+		assert_ne!(doc.page_mut(1).unwrap().content_ids(), std::vec![contents]);
+	}
+
+	#[test]
+	fn malformed_next_line_text() {
+		let mut doc = Document::new();
+		let font = doc.add_base_font("Times-Roman");
+		let mut page = doc.add_page(612.0, 792.0);
+		page.add_font_resource("F13", font);
+		let mut content = ContentStream::new();
+		content.raw(b"BT /F13 10 Tf 12 775 Td (Hi) \" ET\n");
+		page.set_contents(content);
+		page.add_redaction(&[Rectangle::new(0.0, 0.0, 612.0, 792.0)]);
+
+		doc.apply_redactions().unwrap();
+	}
+}
@@ -0,0 +1,137 @@
+//! Automatic naming of the resources used by content streams.
+
+use crate::{Dictionary, FontRef, ObjectId, PdfError};
+
+/// Resource used by a content stream, under its assigned name.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Resource {
+	/// Category of the resource dictionary, e.g. `Font`.
+	category: &'static str,
+	prefix: &'static str,
+	name: String,
+	id: ObjectId,
+}
+
+/// Assigns names to the resources as they are used, e.g. `/F0`, `/Im0`, or
+/// `/GS0`. Using the same object again gives back the same name.
+///
+/// Names are only unique among the resources of the same manager, so they
+/// shouldn't be mixed with names of the same form added manually.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ResourceManager {
+	resources: Vec<Resource>,
+}
+
+impl ResourceManager {
+	/// Creates new manager without any resources.
+	#[inline]
+	pub fn new() -> Self {
+		return Self::default();
+	}
+
+	#[inline]
+	pub fn is_empty(&self) -> bool {
+		return self.resources.is_empty();
+	}
+
+	fn assign(&mut self, category: &'static str, prefix: &'static str, id: ObjectId) -> String {
+		if let Some(resource) = self.resources.iter().find(|resource| resource.prefix == prefix && resource.id == id) {
+			return resource.name.clone();
+		}
+
+		// Names of the removed resources may be given again.
+		let name = (0..).map(|index| std::format!("{}{}", prefix, index)).find(|name| !self.resources.iter().any(|resource| resource.name == *name)).unwrap();
+		self.resources.push(Resource {
+			category,
+			prefix,
+			name: name.clone(),
+			id,
+		});
+		return name;
+	}
+
+	/// Gets the name of the font, assigning a new one on its first use.
+	pub fn font(&mut self, font: FontRef) -> String {
+		return self.assign("Font", "F", font.0);
+	}
+
+	/// Gets the name of the image XObject.
+	pub fn image(&mut self, image: ObjectId) -> String {
+		return self.assign("XObject", "Im", image);
+	}
+
+	/// Gets the name of the form XObject.
+	pub fn form(&mut self, form: ObjectId) -> String {
+		return self.assign("XObject", "Fm", form);
+	}
+
+	/// Gets the name of the graphics state parameter dictionary.
+	pub fn graphics_state(&mut self, state: ObjectId) -> String {
+		return self.assign("ExtGState", "GS", state);
+	}
+
+	/// Gets the name of the property list, e.g. an optional content group.
+	pub fn properties(&mut self, properties: ObjectId) -> String {
+		return self.assign("Properties", "OC", properties);
+	}
+
+	/// Attempts to add the resources of the other manager, e.g. of another
+	/// content stream of the same page. Fails if a name is used by both for
+	/// different objects.
+	pub(crate) fn merge(&mut self, other: ResourceManager) -> Result<(), PdfError> {
+		for resource in other.resources {
+			match self.resources.iter().find(|existing| existing.category == resource.category && existing.name == resource.name) {
+				Some(existing) if existing.id == resource.id => {},
+				Some(_) => return Err(PdfError::InvalidArgument(std::format!("resource name /{} is already used for another object", resource.name))),
+				None => self.resources.push(resource),
+			}
+		}
+		return Ok(());
+	}
+
+	/// Adds the resources into the resource dictionary, keeping the entries
+	/// which are already there.
+	/// Removes the resources, which are no longer used, from the given
+	/// category.
+	pub(crate) fn remove(&mut self, category: &str, names: &[Vec<u8>]) {
+		self.resources.retain(|resource| resource.category != category || !names.iter().any(|name| name == resource.name.as_bytes()));
+	}
+
+	pub(crate) fn merge_into(&self, dict: &mut Dictionary) {
+		for resource in &self.resources {
+			if !dict.contains_key(resource.category) {
+				dict.set(resource.category, Dictionary::new());
+			}
+			if let Some(category) = dict.get_mut(resource.category).and_then(|category| category.as_dict_mut()) {
+				category.set(&resource.name, resource.id);
+			}
+		}
+	}
+
+	/// Generates the resource dictionary.
+	pub fn to_dict(&self) -> Dictionary {
+		let mut dict = Dictionary::new();
+		self.merge_into(&mut dict);
+		return dict;
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::*;
+
+	#[test]
+	fn names_are_assigned_once() {
+		let mut resources = ResourceManager::new();
+		let (a, b) = (FontRef(ObjectId::new(3, 0)), FontRef(ObjectId::new(4, 0)));
+		assert_eq!(resources.font(a), "F0");
+		assert_eq!(resources.image(ObjectId::new(5, 0)), "Im0");
+		assert_eq!(resources.font(b), "F1");
+		assert_eq!(resources.font(a), "F0");
+
+		let dict = resources.to_dict();
+		let fonts = dict.get("Font").and_then(PdfObject::as_dict).unwrap();
+		assert_eq!(fonts.len(), 2);
+		assert_eq!(fonts.get("F1"), Some(&PdfObject::Reference(ObjectId::new(4, 0))));
+	}
+}
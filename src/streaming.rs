@@ -0,0 +1,171 @@
+//! Document writer which flushes every page as soon as it's complete.
+
+use crate::document::{write_header, write_indirect, write_xref_and_trailer, DEFAULT_BINARY_MARKER, DEFAULT_VERSION};
+use crate::{ContentStream, Dictionary, ObjectId, PdfObject, Rectangle, Stream, WriteStyle, Writer};
+
+/// Document written page by page, for documents too large to be kept in
+/// memory. Only the offsets of the written objects and the page identifiers
+/// are retained until [`finish`](Self::finish) writes the page tree.
+///
+/// Objects shared between pages, e.g. fonts, are written using
+/// [`add_object`](Self::add_object) before the pages using them.
+pub struct StreamingDocument<'a> {
+	writer: Writer<'a>,
+	/// Offsets of the written objects, indexed by the object number.
+	offsets: Vec<Option<usize>>,
+	pages: Vec<ObjectId>,
+	media_box: Rectangle,
+}
+
+impl<'a> StreamingDocument<'a> {
+	const CATALOG_ID: ObjectId = ObjectId::new(1, 0);
+	const PAGES_ID: ObjectId = ObjectId::new(2, 0);
+
+	/// Creates new document, written into the given writer.
+	pub fn new(writer: Writer<'a>) -> Self {
+		return Self {
+			writer,
+			offsets: std::vec![None; 3],
+			pages: Vec::new(),
+			media_box: Rectangle::new(0.0, 0.0, 612.0, 792.0),
+		};
+	}
+
+	/// Attempts to write the file header.
+	pub fn begin(&mut self) -> std::io::Result<()> {
+		return write_header(&mut self.writer, DEFAULT_VERSION, Some(DEFAULT_BINARY_MARKER));
+	}
+
+	/// Sets the size of the pages written from now on.
+	pub fn set_page_size(&mut self, width: f64, height: f64) {
+		self.media_box = Rectangle::new(0.0, 0.0, width, height);
+	}
+
+	/// Gets the number of pages written so far.
+	#[inline]
+	pub fn page_count(&self) -> usize {
+		return self.pages.len();
+	}
+
+	fn write_object(&mut self, object: &PdfObject) -> std::io::Result<ObjectId> {
+		let id = ObjectId::new(self.offsets.len() as u32, 0);
+		self.offsets.push(Some(self.writer.pos()));
+		write_indirect(&mut self.writer, id, object, WriteStyle::Default)?;
+		return Ok(id);
+	}
+
+	/// Attempts to write the object immediately, returning its identifier.
+	pub fn add_object(&mut self, object: impl Into<PdfObject>) -> std::io::Result<ObjectId> {
+		return self.write_object(&object.into());
+	}
+
+	/// Attempts to write the page with the given contents. The resources
+	/// requested from the content stream are added to the given ones.
+	pub fn write_page(&mut self, content: ContentStream, mut resources: Dictionary) -> std::io::Result<ObjectId> {
+		content.resources.merge_into(&mut resources);
+		let contents = self.write_object(&Stream::new(Dictionary::new(), content.finish()).into())?;
+
+		let mut dict = Dictionary::new();
+		dict.set("Type", PdfObject::name("Page"));
+		dict.set("Parent", Self::PAGES_ID);
+		dict.set("MediaBox", self.media_box.to_pdf_array());
+		dict.set("Resources", resources);
+		dict.set("Contents", contents);
+		let id = self.write_object(&dict.into())?;
+
+		self.pages.push(id);
+		return Ok(id);
+	}
+
+	/// Attempts to write the page tree, the catalog, and the cross-reference
+	/// table, finishing the file.
+	pub fn finish(mut self) -> std::io::Result<()> {
+		let mut page_tree = Dictionary::new();
+		page_tree.set("Type", PdfObject::name("Pages"));
+		page_tree.set("Kids", self.pages.iter().map(|&id| PdfObject::Reference(id)).collect::<Vec<_>>());
+		page_tree.set("Count", self.pages.len());
+		self.offsets[Self::PAGES_ID.number as usize] = Some(self.writer.pos());
+		write_indirect(&mut self.writer, Self::PAGES_ID, &page_tree.into(), WriteStyle::Default)?;
+
+		let mut catalog = Dictionary::new();
+		catalog.set("Type", PdfObject::name("Catalog"));
+		catalog.set("Pages", Self::PAGES_ID);
+		self.offsets[Self::CATALOG_ID.number as usize] = Some(self.writer.pos());
+		write_indirect(&mut self.writer, Self::CATALOG_ID, &catalog.into(), WriteStyle::Default)?;
+
+		let offsets = &self.offsets;
+		return write_xref_and_trailer(&mut self.writer, offsets.len() as u32, Self::CATALOG_ID, None, None, |number| offsets[number as usize].map(|offset| (offset, 0)));
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::*;
+
+	/// Sink which only remembers what is needed for the checks.
+	#[derive(Default)]
+	struct Summary {
+		written: usize,
+		tail: Vec<u8>,
+	}
+
+	impl std::io::Write for Summary {
+		fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+			self.written += buf.len();
+			self.tail.extend_from_slice(buf);
+			// Everything but the end of the file is dropped as it's written.
+			if self.tail.len() > 1 << 18 {
+				self.tail.drain(..self.tail.len() - (1 << 17));
+			}
+			return Ok(buf.len());
+		}
+
+		fn flush(&mut self) -> std::io::Result<()> {
+			return Ok(());
+		}
+	}
+
+	#[test]
+	fn thousand_pages() {
+		let mut summary = Summary::default();
+		let mut doc = StreamingDocument::new(Writer::new(&mut summary));
+		doc.begin().unwrap();
+
+		let mut font = Dictionary::new();
+		font.set("Type", PdfObject::name("Font"));
+		font.set("Subtype", PdfObject::name("Type1"));
+		font.set("BaseFont", PdfObject::name("Helvetica"));
+		let font = FontRef(doc.add_object(font).unwrap());
+
+		let mut flushed = doc.writer.pos();
+		for page in 0..1000 {
+			let mut content = ContentStream::new();
+			let name = content.use_font(font);
+			content.begin_text().set_font(&name, 12.0).move_text(72.0, 720.0).show_text(std::format!("Page {}", page + 1).as_bytes()).end_text();
+			doc.write_page(content, Dictionary::new()).unwrap();
+
+			// Every page is flushed as soon as it's written, keeping only the
+			// offsets of its two objects and its reference.
+			assert!(doc.writer.pos() > flushed);
+			flushed = doc.writer.pos();
+			assert_eq!(doc.offsets.len(), 3 + 1 + 2 * (page + 1));
+			assert_eq!(doc.pages.len(), page + 1);
+		}
+
+		// Only the offsets and page references stay in memory, which is far
+		// less than the pages written.
+		assert_eq!(doc.page_count(), 1000);
+		assert_eq!(doc.offsets.len(), 3 + 1 + 2000);
+		let retained = doc.offsets.capacity() * std::mem::size_of::<Option<usize>>() + doc.pages.capacity() * std::mem::size_of::<ObjectId>();
+		assert!(retained <= 1000 * 64, "{} bytes retained", retained);
+		assert!(retained * 2 < doc.writer.pos());
+		doc.finish().unwrap();
+
+		let tail = String::from_utf8_lossy(&summary.tail);
+		assert_eq!(tail.matches("/Type /Pages\n").count(), 1);
+		assert!(tail.contains("/Count 1000\n"));
+		assert!(tail.contains("xref\n0 2004\n"));
+		assert!(tail.ends_with("%%EOF\n"));
+		assert!(summary.written > 1000 * 100);
+	}
+}
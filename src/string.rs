@@ -0,0 +1,138 @@
+//! Text strings, which are encoded either in PDFDocEncoding, or in UTF-16BE
+//! prefixed with a byte order mark.
+
+use crate::{PdfObject, Writer};
+
+const BYTE_ORDER_MARK: [u8; 2] = [0xFE, 0xFF];
+/// Byte order mark of UTF-8 text strings, allowed since PDF 2.0.
+const UTF8_BYTE_ORDER_MARK: [u8; 3] = [0xEF, 0xBB, 0xBF];
+
+/// Characters of the PDFDocEncoding codes 0x18 to 0x1F.
+const DIACRITICS: [char; 8] = ['\u{02D8}', '\u{02C7}', '\u{02C6}', '\u{02D9}', '\u{02DD}', '\u{02DB}', '\u{02DA}', '\u{02DC}'];
+/// Characters of the PDFDocEncoding codes 0x80 to 0xA0, which differ from
+/// Latin-1. The code 0x9F is undefined.
+const SPECIALS: [char; 33] = [
+	'\u{2022}', '\u{2020}', '\u{2021}', '\u{2026}', '\u{2014}', '\u{2013}', '\u{0192}', '\u{2044}', '\u{2039}', '\u{203A}', '\u{2212}', '\u{2030}', '\u{201E}', '\u{201C}', '\u{201D}', '\u{2018}', '\u{2019}', '\u{201A}', '\u{2122}', '\u{FB01}', '\u{FB02}', '\u{0141}', '\u{0152}', '\u{0160}', '\u{0178}', '\u{017D}', '\u{0131}', '\u{0142}', '\u{0153}', '\u{0161}', '\u{017E}', '\u{FFFD}', '\u{20AC}',
+];
+
+/// Encodes the character in PDFDocEncoding, if it's representable.
+fn encode_pdf_doc(c: char) -> Option<u8> {
+	return match c as u32 {
+		0x09 | 0x0A | 0x0D | 0x20..=0x7E | 0xA1..=0xAC | 0xAE..=0xFF => Some(c as u8),
+		_ => {
+			if let Some(index) = DIACRITICS.iter().position(|&diacritic| diacritic == c) {
+				return Some(0x18 + index as u8);
+			}
+			SPECIALS.iter().position(|&special| special == c && c != '\u{FFFD}').map(|index| 0x80 + index as u8)
+		},
+	};
+}
+
+/// Decodes the PDFDocEncoding byte, replacing undefined codes.
+fn decode_pdf_doc(byte: u8) -> char {
+	return match byte {
+		0x18..=0x1F => DIACRITICS[(byte - 0x18) as usize],
+		0x80..=0xA0 => SPECIALS[(byte - 0x80) as usize],
+		0x7F | 0xAD => '\u{FFFD}',
+		_ => byte as char,
+	};
+}
+
+/// Text string, i.e. a string meant to be read by people, e.g. a document
+/// title or the contents of an annotation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PdfString {
+	bytes: Vec<u8>,
+}
+
+impl PdfString {
+	/// Creates new text string, encoded in PDFDocEncoding if all the
+	/// characters are representable in it, and in UTF-16BE otherwise.
+	pub fn new(text: &str) -> Self {
+		if let Some(bytes) = text.chars().map(encode_pdf_doc).collect::<Option<Vec<u8>>>() {
+			return Self {
+				bytes,
+			};
+		}
+
+		let mut bytes = BYTE_ORDER_MARK.to_vec();
+		for unit in text.encode_utf16() {
+			bytes.extend_from_slice(&unit.to_be_bytes());
+		}
+		return Self {
+			bytes,
+		};
+	}
+
+	/// Creates new text string with the bytes of an existing string.
+	#[inline]
+	pub fn from_bytes(bytes: impl Into<Vec<u8>>) -> Self {
+		return Self {
+			bytes: bytes.into(),
+		};
+	}
+
+	/// Gets the encoded bytes of the string.
+	#[inline]
+	pub fn as_bytes(&self) -> &[u8] {
+		return &self.bytes;
+	}
+
+	/// Returns whether the string is encoded in UTF-16BE.
+	#[inline]
+	pub fn is_unicode(&self) -> bool {
+		return self.bytes.starts_with(&BYTE_ORDER_MARK);
+	}
+
+	/// Decodes the string, replacing the invalid or undefined characters.
+	pub fn to_text(&self) -> String {
+		if self.is_unicode() {
+			let units: Vec<u16> = self.bytes[2..].chunks(2).map(|pair| u16::from_be_bytes([pair[0], *pair.get(1).unwrap_or(&0)])).collect();
+			return String::from_utf16_lossy(&units);
+		}
+		if let Some(bytes) = self.bytes.strip_prefix(&UTF8_BYTE_ORDER_MARK) {
+			return String::from_utf8_lossy(bytes).into_owned();
+		}
+		return self.bytes.iter().map(|&byte| decode_pdf_doc(byte)).collect();
+	}
+
+	/// Attempts to write the string as a literal string, escaping the bytes
+	/// where needed.
+	pub fn write_to(&self, writer: &mut Writer<'_>) -> std::io::Result<()> {
+		return PdfObject::string(self.bytes.clone()).write_to(writer);
+	}
+}
+
+impl From<PdfString> for PdfObject {
+	#[inline]
+	fn from(string: PdfString) -> Self {
+		return Self::string(string.bytes);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::parser::Parser;
+	use crate::*;
+
+	fn round_trip(text: &str) -> (Vec<u8>, String) {
+		let mut output = Vec::new();
+		PdfString::new(text).write_to(&mut Writer::new(&mut output)).unwrap();
+		let object = Parser::new(&output, 0).parse_object().unwrap();
+		let decoded = PdfString::from_bytes(object.as_string().unwrap()).to_text();
+		return (output, decoded);
+	}
+
+	#[test]
+	fn encodings() {
+		assert_eq!(round_trip("Hello (world)"), (b"(Hello \\(world\\))".to_vec(), "Hello (world)".to_string()));
+		assert_eq!(round_trip("café"), (b"(caf\xE9)".to_vec(), "café".to_string()));
+		assert_eq!(PdfString::new("\u{2022} \u{20AC}5").as_bytes(), b"\x80 \xA05");
+
+		let (output, decoded) = round_trip("(日本)");
+		assert_eq!(decoded, "(日本)");
+		assert!(PdfString::new("日本").is_unicode());
+		// Bytes of the parentheses are escaped even inside UTF-16BE code units.
+		assert_eq!(output, b"(\xFE\xFF\x00\\(\x65\xE5\x67\x2C\x00\\))");
+	}
+}
@@ -0,0 +1,184 @@
+//! Logical structure of tagged documents, i.e. the tree of structure elements
+//! like paragraphs and headings, which the marked content of the pages
+//! belongs to.
+
+use crate::{Dictionary, Document, ObjectId, PdfError, PdfObject};
+
+/// Reference to a structure element added to the document.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct StructRef(pub ObjectId);
+
+impl From<StructRef> for ObjectId {
+	#[inline]
+	fn from(element: StructRef) -> Self {
+		return element.0;
+	}
+}
+
+impl Document {
+	/// Gets the root of the structure tree, creating it if the document
+	/// doesn't have one yet, which also marks the document as tagged.
+	fn struct_tree_root(&mut self) -> ObjectId {
+		if let Some(&PdfObject::Reference(root)) = self.catalog.get("StructTreeRoot") {
+			if self.object(root).is_some_and(|root| root.as_dict().is_some()) {
+				return root;
+			}
+		}
+
+		let mut root = Dictionary::new();
+		root.set("Type", PdfObject::name("StructTreeRoot"));
+		root.set("K", Vec::<PdfObject>::new());
+		let root = self.add_object(root);
+		self.catalog.set("StructTreeRoot", root);
+		let mut mark_info = Dictionary::new();
+		mark_info.set("Marked", true);
+		self.catalog.set("MarkInfo", mark_info);
+		return root;
+	}
+
+	/// Begins the structure element of the given type, e.g. `P`, as the last
+	/// child of the innermost element which is not ended yet, or of the root
+	/// of the structure tree. The language, e.g. `fr-FR`, overrides the one of
+	/// the document for the element and its children.
+	pub fn begin_struct_element(&mut self, kind: &str, language: Option<&str>) -> StructRef {
+		let root = self.struct_tree_root();
+		let parent = self.struct_elements.last().copied().unwrap_or(root);
+
+		let mut dict = Dictionary::new();
+		dict.set("Type", PdfObject::name("StructElem"));
+		dict.set("S", PdfObject::name(kind));
+		dict.set("P", parent);
+		if let Some(language) = language {
+			dict.set("Lang", PdfObject::text_string(language));
+		}
+		let id = self.add_object(dict);
+
+		let parent = self.object_mut(parent).and_then(PdfObject::as_dict_mut).expect("structure element is not a dictionary");
+		match parent.get_mut("K") {
+			Some(PdfObject::Array(kids)) => kids.push(id.into()),
+			_ => parent.set("K", std::vec![PdfObject::Reference(id)]),
+		}
+		self.struct_elements.push(id);
+		return StructRef(id);
+	}
+
+	/// Attempts to end the structure element begun most recently.
+	pub fn end_struct_element(&mut self) -> Result<StructRef, PdfError> {
+		return match self.struct_elements.pop() {
+			Some(id) => Ok(StructRef(id)),
+			None => Err(PdfError::InvalidArgument("no structure element to end".to_string())),
+		};
+	}
+
+	/// Attempts to add the marked content of the page with the given
+	/// zero-based index to the innermost structure element which is not ended
+	/// yet. The content has to be marked with the `/MCID` property, e.g.
+	/// `/P << /MCID 0 >> BDC`, with the given identifier, unique in the page.
+	pub fn add_struct_content(&mut self, index: usize, mcid: u32) -> Result<(), PdfError> {
+		let Some(&element) = self.struct_elements.last() else {
+			return Err(PdfError::InvalidArgument("no structure element to add the content to".to_string()));
+		};
+		let count = self.pages.len();
+		let Some(&page) = self.pages.get(index) else {
+			return Err(PdfError::PageOutOfRange { index, count });
+		};
+
+		let mut reference = Dictionary::new();
+		reference.set("Type", PdfObject::name("MCR"));
+		reference.set("Pg", page);
+		reference.set("MCID", mcid as i64);
+		let dict = self.object_mut(element).and_then(PdfObject::as_dict_mut).expect("structure element is not a dictionary");
+		match dict.get_mut("K") {
+			Some(PdfObject::Array(kids)) => kids.push(reference.into()),
+			_ => dict.set("K", std::vec![PdfObject::Dictionary(reference)]),
+		}
+
+		// The parent tree maps the marked content of every page back to the
+		// elements, by the `/StructParents` key of the page.
+		let root = self.struct_tree_root();
+		let next_key = self.object(root).and_then(PdfObject::as_dict).and_then(|root| root.get("ParentTreeNextKey")).and_then(PdfObject::as_integer).unwrap_or(0);
+		let page = self.object_mut(page).and_then(PdfObject::as_dict_mut).expect("page object is not a dictionary");
+		let key = match page.get("StructParents").and_then(PdfObject::as_integer) {
+			Some(key) => key,
+			None => {
+				page.set("StructParents", next_key);
+				next_key
+			},
+		};
+
+		let root = self.object_mut(root).and_then(PdfObject::as_dict_mut).expect("structure tree root is not a dictionary");
+		if key == next_key {
+			root.set("ParentTreeNextKey", next_key + 1);
+		}
+		if !root.get("ParentTree").is_some_and(|tree| tree.as_dict().is_some()) {
+			let mut tree = Dictionary::new();
+			tree.set("Nums", Vec::<PdfObject>::new());
+			root.set("ParentTree", tree);
+		}
+		let Some(PdfObject::Array(nums)) = root.get_mut("ParentTree").and_then(PdfObject::as_dict_mut).and_then(|tree| tree.get_mut("Nums")) else {
+			return Err(PdfError::InvalidArgument("parent tree has no /Nums array".to_string()));
+		};
+		// Keys are assigned in increasing order, so the new ones go last.
+		let position = match nums.chunks(2).position(|pair| pair[0].as_integer() == Some(key)) {
+			Some(position) => position,
+			None => {
+				nums.extend([PdfObject::Integer(key), PdfObject::Array(Vec::new())]);
+				nums.len() / 2 - 1
+			},
+		};
+		let Some(PdfObject::Array(parents)) = nums.get_mut(position * 2 + 1) else {
+			return Err(PdfError::InvalidArgument(std::format!("parent tree entry {} is not an array", key)));
+		};
+		if parents.len() <= mcid as usize {
+			parents.resize(mcid as usize + 1, PdfObject::Null);
+		}
+		parents[mcid as usize] = element.into();
+		return Ok(());
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::*;
+
+	#[test]
+	fn element_languages() {
+		let mut doc = Document::new();
+		doc.set_language("en-US");
+		let mut page = doc.add_page(612.0, 792.0);
+		let mut content = ContentStream::new();
+		let mut mcid = Dictionary::new();
+		mcid.set("MCID", 0);
+		content.begin_marked_content("P", Some(PropertyList::Inline(mcid))).end_marked_content();
+		page.set_contents(content);
+
+		let document = doc.begin_struct_element("Document", None);
+		let french = doc.begin_struct_element("P", Some("fr-FR"));
+		doc.add_struct_content(0, 0).unwrap();
+		assert_eq!(doc.end_struct_element().unwrap(), french);
+		let english = doc.begin_struct_element("P", None);
+		doc.end_struct_element().unwrap();
+		assert_eq!(doc.end_struct_element().unwrap(), document);
+		assert!(doc.end_struct_element().is_err());
+		assert!(doc.add_struct_content(0, 1).is_err());
+
+		let mut output = Vec::new();
+		doc.write(&mut Writer::new(&mut output)).unwrap();
+		let reader = Reader::new(&output).unwrap();
+		let catalog = reader.catalog().unwrap();
+		assert_eq!(catalog.get("Lang"), Some(&PdfObject::text_string("en-US")));
+		assert_eq!(catalog.get("MarkInfo").and_then(PdfObject::as_dict).and_then(|info| info.get("Marked")), Some(&PdfObject::Boolean(true)));
+
+		let element = |element: StructRef| reader.resolve_dict(element.0).unwrap();
+		assert_eq!(element(french).get("Lang"), Some(&PdfObject::text_string("fr-FR")));
+		assert_eq!(element(english).get("Lang"), None);
+		assert_eq!(element(document).get("Lang"), None);
+		assert_eq!(element(document).get("K"), Some(&PdfObject::Array(std::vec![french.0.into(), english.0.into()])));
+
+		let root = reader.resolve_dict(catalog.get("StructTreeRoot").and_then(PdfObject::as_reference).unwrap()).unwrap();
+		assert_eq!(root.get("K"), Some(&PdfObject::Array(std::vec![document.0.into()])));
+		let nums = root.get("ParentTree").and_then(PdfObject::as_dict).and_then(|tree| tree.get("Nums")).unwrap();
+		assert_eq!(nums, &PdfObject::Array(std::vec![PdfObject::Integer(0), PdfObject::Array(std::vec![french.0.into()])]));
+		assert_eq!(reader.resolve_dict(reader.pages().unwrap()[0]).unwrap().get("StructParents"), Some(&PdfObject::Integer(0)));
+	}
+}
@@ -0,0 +1,504 @@
+//! Interpretation of the text operators in content streams, used for text
+//! extraction and redaction.
+
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+use crate::content::Operation;
+use crate::{Matrix, PdfObject, Point, Rectangle};
+
+/// Advance width used for the glyphs of fonts without widths, e.g. the
+/// standard 14 fonts, in thousandths of an em. We don't have their metrics,
+/// so this is an approximation of an average glyph.
+pub(crate) const DEFAULT_GLYPH_WIDTH: f64 = 500.0;
+/// Width of the glyphs of composite fonts without one given, in thousandths
+/// of an em, i.e. the default of `/DW`.
+pub(crate) const DEFAULT_CID_WIDTH: f64 = 1000.0;
+/// Vertical displacement of the glyphs of vertical fonts without one given,
+/// in thousandths of an em, i.e. the default of `/DW2`.
+pub(crate) const DEFAULT_VERTICAL_ADVANCE: f64 = -1000.0;
+/// Extent of glyphs below and above the baseline, relative to the font size.
+const DESCENT: f64 = -0.2;
+const ASCENT: f64 = 0.8;
+
+/// Run of text shown by a single text showing operator.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct TextRun {
+	/// Index of the operation which shows the text.
+	pub operation: usize,
+	pub text: String,
+	/// Position of the first glyph origin, in the page coordinates.
+	pub origin: Point,
+	/// Position just after the last glyph, in the page coordinates.
+	pub end: Point,
+	/// Bounding box of the glyphs, in the page coordinates.
+	pub bbox: Rectangle,
+	pub font_size: f64,
+	/// Advance of the run along the writing direction, in unscaled text space
+	/// units, which is vertical, and negative, for vertical fonts.
+	pub advance: f64,
+	/// Glyphs of the run, in the order they're shown.
+	pub glyphs: Vec<Glyph>,
+}
+
+/// Glyph of a run of text.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Glyph {
+	/// Index of the string among the elements shown by the operator, i.e. the
+	/// strings and numbers of a text showing array, and the range of its bytes
+	/// which encode the glyph.
+	pub element: usize,
+	pub bytes: Range<usize>,
+	/// Bounding box of the glyph, in the page coordinates.
+	pub bbox: Rectangle,
+	/// Number of a text showing array which moves the text position as much
+	/// as the glyph does.
+	pub adjustment: f64,
+}
+
+/// Text shown on a page, with its position.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PositionedText {
+	pub text: String,
+	/// Position of the first glyph origin, on the baseline, in the page
+	/// coordinates.
+	pub origin: Point,
+	/// Bounding box of the glyphs, in the page coordinates, computed from the
+	/// advance widths of the fonts. The widths are approximated for the fonts
+	/// which don't give them, e.g. the standard 14 fonts.
+	pub bbox: Rectangle,
+}
+
+/// Mapping of the character codes of a font to Unicode, for the fonts whose
+/// codes are not simply the characters, e.g. the glyph identifiers used by
+/// composite fonts with the `Identity-H` encoding, along with the widths of
+/// the glyphs.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct FontDecoder {
+	/// Whether the codes are two bytes long, rather than one.
+	pub two_byte: bool,
+	pub chars: BTreeMap<u16, char>,
+	/// Advance widths of the glyphs, by their codes, in thousandths of an em.
+	pub widths: BTreeMap<u16, f64>,
+	/// Width of the glyphs which are not listed.
+	pub default_width: f64,
+	/// Displacements of the glyphs of the fonts in the vertical writing mode,
+	/// or `None` for the horizontal ones.
+	pub vertical: Option<VerticalMetrics>,
+}
+
+/// Vertical displacements of the glyphs of a font in the vertical writing
+/// mode, in thousandths of an em, which are negative as the text goes down.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct VerticalMetrics {
+	/// Displacement of the glyphs which are not listed, from `/DW2`.
+	pub default: f64,
+	pub advances: BTreeMap<u16, f64>,
+}
+
+impl Default for FontDecoder {
+	fn default() -> Self {
+		return Self {
+			two_byte: false,
+			chars: BTreeMap::new(),
+			widths: BTreeMap::new(),
+			default_width: DEFAULT_GLYPH_WIDTH,
+			vertical: None,
+		};
+	}
+}
+
+impl FontDecoder {
+	/// Gets the advance width of the glyph with the code.
+	#[inline]
+	fn width(&self, code: u16) -> f64 {
+		return self.widths.get(&code).copied().unwrap_or(self.default_width);
+	}
+
+	/// Decodes the string into the characters, with their codes.
+	fn decode(&self, bytes: &[u8]) -> Vec<(u16, char)> {
+		if !self.two_byte {
+			return bytes.iter().map(|&byte| (byte as u16, self.chars.get(&(byte as u16)).copied().unwrap_or(byte as char))).collect();
+		}
+		return bytes.chunks(2).map(|pair| {
+			let code = u16::from_be_bytes([pair[0], pair.get(1).copied().unwrap_or(0)]);
+			return (code, self.chars.get(&code).copied().unwrap_or(char::REPLACEMENT_CHARACTER));
+		}).collect();
+	}
+}
+
+/// Parameters of the graphics state which affect text positioning.
+#[derive(Clone, Copy, Debug)]
+struct State<'f> {
+	ctm: Matrix,
+	/// Decoder of the selected font, if it needs one.
+	font: Option<&'f FontDecoder>,
+	font_size: f64,
+	char_spacing: f64,
+	word_spacing: f64,
+	horizontal_scaling: f64,
+	leading: f64,
+	rise: f64,
+}
+
+impl Default for State<'_> {
+	fn default() -> Self {
+		return Self {
+			ctm: Matrix::IDENTITY,
+			font: None,
+			font_size: 0.0,
+			char_spacing: 0.0,
+			word_spacing: 0.0,
+			horizontal_scaling: 1.0,
+			leading: 0.0,
+			rise: 0.0,
+		};
+	}
+}
+
+struct Interpreter<'f> {
+	state: State<'f>,
+	stack: Vec<State<'f>>,
+	text_matrix: Matrix,
+	line_matrix: Matrix,
+	runs: Vec<TextRun>,
+}
+
+impl Interpreter<'_> {
+	fn next_line(&mut self, x: f64, y: f64) {
+		self.line_matrix = Matrix::translate(x, y).then(&self.line_matrix);
+		self.text_matrix = self.line_matrix;
+	}
+
+	/// Advances the text matrix by the given amount of text space, vertically
+	/// for vertical fonts.
+	fn advance(&mut self, amount: f64, vertical: bool) {
+		let (tx, ty) = if vertical { (0.0, amount) } else { (amount, 0.0) };
+		self.text_matrix = Matrix::translate(tx, ty).then(&self.text_matrix);
+	}
+
+	fn show(&mut self, operation: usize, elements: &[PdfObject]) {
+		let start = self.text_matrix;
+		let state = self.state;
+		let vertical = state.font.and_then(|font| font.vertical.as_ref());
+		let mut text = String::new();
+		let mut advance = 0.0;
+		// Widest glyph, for the box of vertical text.
+		let mut widest: f64 = 0.0;
+		let mut glyphs = Vec::new();
+		let to_page = start.then(&state.ctm);
+		let (low, high) = (DESCENT * state.font_size + state.rise, ASCENT * state.font_size + state.rise);
+		// Text space units moved by a number of a text showing array of 1000.
+		let unit = match vertical {
+			Some(_) => state.font_size,
+			None => state.font_size * state.horizontal_scaling,
+		};
+
+		for (element, object) in elements.iter().enumerate() {
+			match object {
+				PdfObject::String(bytes, _) => {
+					let chars = match state.font {
+						Some(font) => font.decode(bytes),
+						None => bytes.iter().map(|&byte| (byte as u16, byte as char)).collect(),
+					};
+					let single_byte = !state.font.is_some_and(|font| font.two_byte);
+					let length = if single_byte { 1 } else { 2 };
+					for (index, (code, c)) in chars.into_iter().enumerate() {
+						text.push(c);
+						let width = state.font.map_or(DEFAULT_GLYPH_WIDTH, |font| font.width(code));
+						widest = widest.max(width);
+						let (amount, bbox) = match vertical {
+							Some(vertical) => {
+								// Horizontal scaling doesn't apply to vertical text.
+								let ty = vertical.advances.get(&code).copied().unwrap_or(vertical.default) / 1000.0 * state.font_size + state.char_spacing;
+								let half = width / 2000.0 * state.font_size;
+								(ty, [Point::new(-half, advance), Point::new(half, advance + ty)])
+							},
+							None => {
+								let mut tx = width / 1000.0 * state.font_size + state.char_spacing;
+								// Word spacing applies to the single-byte code 32 only.
+								if single_byte && code == 32 {
+									tx += state.word_spacing;
+								}
+								tx *= state.horizontal_scaling;
+								(tx, [Point::new(advance, low), Point::new(advance + tx, high)])
+							},
+						};
+						let [from, to] = bbox;
+						glyphs.push(Glyph {
+							element,
+							bytes: index * length..((index + 1) * length).min(bytes.len()),
+							bbox: Rectangle::bounding([from, to, Point::new(from.x, to.y), Point::new(to.x, from.y)].map(|point| to_page.transform(point))),
+							adjustment: if unit == 0.0 { 0.0 } else { -amount / unit * 1000.0 },
+						});
+						advance += amount;
+						self.advance(amount, vertical.is_some());
+					}
+				},
+				number => {
+					let amount = number.as_number().unwrap_or(0.0);
+					let scaling = if vertical.is_some() { 1.0 } else { state.horizontal_scaling };
+					let tx = -amount / 1000.0 * state.font_size * scaling;
+					// Large negative adjustments usually separate words.
+					if amount < -200.0 && !text.is_empty() && !text.ends_with(' ') {
+						text.push(' ');
+					}
+					advance += tx;
+					self.advance(tx, vertical.is_some());
+				},
+			}
+		}
+
+		if vertical.is_some() {
+			// Glyphs of vertical text are centred below their origins.
+			let half = widest / 2000.0 * state.font_size;
+			self.runs.push(TextRun {
+				operation,
+				text,
+				origin: to_page.transform(Point::new(0.0, 0.0)),
+				end: to_page.transform(Point::new(0.0, advance)),
+				bbox: Rectangle::bounding([
+					to_page.transform(Point::new(-half, 0.0)),
+					to_page.transform(Point::new(half, 0.0)),
+					to_page.transform(Point::new(-half, advance)),
+					to_page.transform(Point::new(half, advance)),
+				]),
+				font_size: state.font_size,
+				advance,
+				glyphs,
+			});
+			return;
+		}
+
+		let bbox = Rectangle::bounding([
+			to_page.transform(Point::new(0.0, low)),
+			to_page.transform(Point::new(advance, low)),
+			to_page.transform(Point::new(0.0, high)),
+			to_page.transform(Point::new(advance, high)),
+		]);
+
+		self.runs.push(TextRun {
+			operation,
+			text,
+			origin: to_page.transform(Point::new(0.0, state.rise)),
+			end: to_page.transform(Point::new(advance, state.rise)),
+			bbox,
+			font_size: state.font_size,
+			advance,
+			glyphs,
+		});
+	}
+}
+
+/// Finds all runs of text shown by the operations.
+#[inline]
+pub(crate) fn text_runs(operations: &[Operation]) -> Vec<TextRun> {
+	return text_runs_with_fonts(operations, &BTreeMap::new());
+}
+
+/// Finds all runs of text shown by the operations, decoding the strings
+/// shown with the given fonts, by their resource names.
+#[inline]
+pub(crate) fn text_runs_with_fonts(operations: &[Operation], fonts: &BTreeMap<Vec<u8>, FontDecoder>) -> Vec<TextRun> {
+	return text_runs_in(operations, fonts, Matrix::IDENTITY);
+}
+
+/// Finds all runs of text shown by the operations, like
+/// [`text_runs_with_fonts`], starting with the given transformation to the
+/// page coordinates, e.g. the one of a form XObject.
+pub(crate) fn text_runs_in(operations: &[Operation], fonts: &BTreeMap<Vec<u8>, FontDecoder>, ctm: Matrix) -> Vec<TextRun> {
+	let mut interpreter = Interpreter {
+		state: State {
+			ctm,
+			..State::default()
+		},
+		stack: Vec::new(),
+		text_matrix: Matrix::IDENTITY,
+		line_matrix: Matrix::IDENTITY,
+		runs: Vec::new(),
+	};
+
+	for (index, operation) in operations.iter().enumerate() {
+		let n = |i| operation.number(i);
+		match operation.operator.as_slice() {
+			b"q" => interpreter.stack.push(interpreter.state),
+			b"Q" => {
+				if let Some(state) = interpreter.stack.pop() {
+					interpreter.state = state;
+				}
+			},
+			b"cm" => {
+				let matrix = Matrix::new(n(0), n(1), n(2), n(3), n(4), n(5));
+				interpreter.state.ctm = matrix.then(&interpreter.state.ctm);
+			},
+			b"BT" => {
+				interpreter.text_matrix = Matrix::IDENTITY;
+				interpreter.line_matrix = Matrix::IDENTITY;
+			},
+			b"Tf" => {
+				interpreter.state.font = operation.operands.first().and_then(PdfObject::as_name).and_then(|name| fonts.get(name));
+				interpreter.state.font_size = n(1);
+			},
+			b"Tc" => interpreter.state.char_spacing = n(0),
+			b"Tw" => interpreter.state.word_spacing = n(0),
+			b"Tz" => interpreter.state.horizontal_scaling = n(0) / 100.0,
+			b"TL" => interpreter.state.leading = n(0),
+			b"Ts" => interpreter.state.rise = n(0),
+			b"Td" => interpreter.next_line(n(0), n(1)),
+			b"TD" => {
+				interpreter.state.leading = -n(1);
+				interpreter.next_line(n(0), n(1));
+			},
+			b"Tm" => {
+				interpreter.line_matrix = Matrix::new(n(0), n(1), n(2), n(3), n(4), n(5));
+				interpreter.text_matrix = interpreter.line_matrix;
+			},
+			b"T*" => interpreter.next_line(0.0, -interpreter.state.leading),
+			b"Tj" => interpreter.show(index, &operation.operands),
+			b"TJ" => {
+				if let Some(elements) = operation.operands.first().and_then(PdfObject::as_array) {
+					interpreter.show(index, elements);
+				}
+			},
+			b"'" => {
+				interpreter.next_line(0.0, -interpreter.state.leading);
+				interpreter.show(index, &operation.operands);
+			},
+			b"\"" => {
+				interpreter.state.word_spacing = n(0);
+				interpreter.state.char_spacing = n(1);
+				interpreter.next_line(0.0, -interpreter.state.leading);
+				interpreter.show(index, &operation.operands[operation.operands.len().min(2)..]);
+			},
+			_ => {},
+		}
+	}
+
+	return interpreter.runs;
+}
+
+/// Joins the runs of text into lines, based on their positions.
+pub(crate) fn join_runs(runs: &[TextRun]) -> String {
+	let mut text = String::new();
+	let mut previous: Option<&TextRun> = None;
+
+	for run in runs.iter().filter(|run| !run.text.is_empty()) {
+		if let Some(previous) = previous {
+			let size = previous.font_size.abs().max(1.0);
+			if (run.origin.y - previous.end.y).abs() > size / 2.0 {
+				text.push('\n');
+			} else if run.origin.x - previous.end.x > size * 0.15 && !text.ends_with(' ') {
+				text.push(' ');
+			}
+		}
+
+		text.push_str(&run.text);
+		previous = Some(run);
+	}
+
+	return text;
+}
+
+/// Extracts the text shown by the operations, decoding the strings shown
+/// with the given fonts.
+pub(crate) fn extract_text_with_fonts(operations: &[Operation], fonts: &BTreeMap<Vec<u8>, FontDecoder>) -> String {
+	return join_runs(&text_runs_with_fonts(operations, fonts));
+}
+
+/// Finds the text shown by the operations, one item per text showing
+/// operator, decoding the strings shown with the given fonts.
+pub(crate) fn positioned_text(operations: &[Operation], fonts: &BTreeMap<Vec<u8>, FontDecoder>) -> Vec<PositionedText> {
+	return text_runs_with_fonts(operations, fonts).into_iter().filter(|run| !run.text.is_empty()).map(|run| PositionedText {
+		text: run.text,
+		origin: run.origin,
+		bbox: run.bbox,
+	}).collect();
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::content::parse_operations;
+	use crate::text::*;
+
+	#[test]
+	fn lines_and_words() {
+		let data = b"BT /F1 10 Tf 12 775 Td (Hello,) Tj [(wor) -10 (ld)] TJ 0 -12 Td (Second) Tj 40 0 Td (line) Tj ET";
+		let operations = parse_operations(data).unwrap();
+		assert_eq!(extract_text_with_fonts(&operations, &BTreeMap::new()), "Hello,world\nSecond line");
+
+		let runs = text_runs(&operations);
+		assert_eq!(runs[0].origin, Point::new(12.0, 775.0));
+		assert_eq!(runs[0].end, Point::new(42.0, 775.0));
+	}
+
+	#[test]
+	fn positioned_strings() {
+		let mut doc = crate::Document::new();
+		let font = doc.add_base_font("Times-Roman");
+		let mut page = doc.add_page(612.0, 792.0);
+		page.add_font_resource("F13", font);
+		let mut content = crate::ContentStream::new();
+		content.begin_text().set_font("F13", 10.0).move_text(12.0, 775.0).show_text(b"Hello!").end_text();
+		page.set_contents(content);
+		let items = page.extract_text_positioned().unwrap();
+		assert_eq!(items.len(), 1);
+		assert_eq!(items[0].text, "Hello!");
+		assert!((items[0].origin.x - 12.0).abs() < 0.01 && (items[0].origin.y - 775.0).abs() < 0.01);
+		assert_eq!(items[0].bbox, Rectangle::new(12.0, 773.0, 42.0, 783.0));
+
+		let mut output = Vec::new();
+		doc.write(&mut crate::Writer::new(&mut output)).unwrap();
+		let reader = crate::Reader::new(&output).unwrap();
+		assert_eq!(reader.page(0).unwrap().extract_text_positioned().unwrap(), items);
+	}
+
+	#[test]
+	fn font_widths() {
+		let mut doc = crate::Document::new();
+		let font = doc.add_base_font("Times-Roman");
+		let dict = doc.object_mut(font.0).and_then(PdfObject::as_dict_mut).unwrap();
+		dict.set("FirstChar", PdfObject::Integer(65));
+		dict.set("Widths", PdfObject::Array(std::vec![PdfObject::Integer(722), PdfObject::Integer(667)]));
+		let mut page = doc.add_page(612.0, 792.0);
+		page.add_font_resource("F1", font);
+		let mut content = crate::ContentStream::new();
+		content.begin_text().set_font("F1", 10.0).move_text(12.0, 775.0).show_text(b"AB").show_text(b"BC").end_text();
+		page.set_contents(content);
+		let items = page.extract_text_positioned().unwrap();
+		// The codes past the widths have none, since there's no
+		// `/MissingWidth`.
+		let near = |a: f64, b: f64| (a - b).abs() < 1e-9;
+		assert!(near(items[0].bbox.urx, 12.0 + 13.89) && near(items[1].bbox.llx, 12.0 + 13.89));
+		assert!(near(items[1].bbox.urx, 12.0 + 13.89 + 6.67));
+
+		let mut output = Vec::new();
+		doc.write(&mut crate::Writer::new(&mut output)).unwrap();
+		let reader = crate::Reader::new(&output).unwrap();
+		assert_eq!(reader.page(0).unwrap().extract_text_positioned().unwrap(), items);
+	}
+
+	#[test]
+	fn rotated_text_matrix() {
+		let (cos, sin) = (std::f64::consts::FRAC_PI_4.cos(), std::f64::consts::FRAC_PI_4.sin());
+		let mut content = crate::ContentStream::new();
+		content.begin_text().set_font("F1", 10.0).move_text(50.0, 50.0);
+		content.set_text_matrix(Matrix::new(cos, sin, -sin, cos, 100.0, 100.0)).show_text(b"Hello");
+		content.move_text(0.0, -12.0).show_text(b"Next");
+		content.set_text_matrix(Matrix::translate(300.0, 400.0)).move_text(0.0, -12.0).show_text(b"Reset").end_text();
+		let runs = text_runs(&parse_operations(content.as_bytes()).unwrap());
+
+		let near = |a: Point, b: Point| (a.x - b.x).abs() < 1e-6 && (a.y - b.y).abs() < 1e-6;
+		assert!(near(runs[0].origin, Point::new(100.0, 100.0)));
+		assert!(near(runs[0].end, Point::new(100.0 + 25.0 * cos, 100.0 + 25.0 * sin)));
+		// The box of the rotated glyphs is wider than the run is long, and
+		// reaches left of the origin, with the ascent rotated over it.
+		let bbox = runs[0].bbox;
+		assert!(near(Point::new(bbox.llx, bbox.lly), Point::new(100.0 - 8.0 * sin, 100.0 - 2.0 * cos)));
+		assert!(near(Point::new(bbox.urx, bbox.ury), Point::new(100.0 + 25.0 * cos + 2.0 * sin, 100.0 + 25.0 * sin + 8.0 * cos)));
+
+		// Moving to the next line is relative to the rotated line matrix, and
+		// another text matrix replaces it, rather than adding to it.
+		assert!(near(runs[1].origin, Point::new(100.0 + 12.0 * sin, 100.0 - 12.0 * cos)));
+		assert!(near(runs[2].origin, Point::new(300.0, 388.0)));
+	}
+}
@@ -0,0 +1,121 @@
+//! Page transitions, used when presenting a document as slides.
+
+use crate::{Dictionary, Page, PdfObject};
+
+/// Style of the transition effect shown when moving to a page.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TransitionStyle {
+	Split,
+	Blinds,
+	Box,
+	Wipe,
+	Dissolve,
+	Glitter,
+	/// The new page simply replaces the old one.
+	Replace,
+	Fly,
+	Push,
+	Cover,
+	Uncover,
+	Fade,
+}
+
+impl TransitionStyle {
+	fn name(self) -> &'static str {
+		return match self {
+			Self::Split => "Split",
+			Self::Blinds => "Blinds",
+			Self::Box => "Box",
+			Self::Wipe => "Wipe",
+			Self::Dissolve => "Dissolve",
+			Self::Glitter => "Glitter",
+			Self::Replace => "R",
+			Self::Fly => "Fly",
+			Self::Push => "Push",
+			Self::Cover => "Cover",
+			Self::Uncover => "Uncover",
+			Self::Fade => "Fade",
+		};
+	}
+
+	/// Gets the version of PDF which introduced the style.
+	fn version(self) -> (u8, u8) {
+		return match self {
+			Self::Fly | Self::Push | Self::Cover | Self::Uncover | Self::Fade => (1, 5),
+			_ => (1, 1),
+		};
+	}
+}
+
+impl Page<'_> {
+	/// Sets the transition effect shown when moving to the page, lasting the
+	/// given number of seconds. The `Fly`, `Push`, `Cover`, `Uncover`, and
+	/// `Fade` styles need PDF 1.5, so the document is written with it.
+	pub fn set_transition(&mut self, style: TransitionStyle, duration: f64) {
+		let mut trans = Dictionary::new();
+		trans.set("S", PdfObject::name(style.name()));
+		trans.set("D", duration);
+		self.doc.require_version(style.version());
+		self.dict_mut().set("Trans", trans);
+	}
+
+	/// Sets the scale at which the changes of the `Fly` transition of the page
+	/// start or end, and whether the area which flies in or out is opaque.
+	/// Does nothing for pages without a transition. The entries need PDF 1.5,
+	/// so the document is written with it.
+	pub fn set_fly_scale(&mut self, scale: f64, opaque: bool) {
+		let Some(PdfObject::Dictionary(trans)) = self.dict_mut().get_mut("Trans") else {
+			return;
+		};
+		trans.set("SS", scale);
+		trans.set("B", opaque);
+		self.doc.require_version((1, 5));
+	}
+
+	/// Sets the number of seconds the page is displayed for, before a viewer
+	/// in presentation mode advances to the next one.
+	pub fn set_display_duration(&mut self, seconds: f64) {
+		self.dict_mut().set("Dur", seconds);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::*;
+
+	#[test]
+	fn fade_transition() {
+		let mut doc = Document::new();
+		doc.set_version((1, 4));
+		let mut page = doc.add_page(612.0, 792.0);
+		page.set_transition(TransitionStyle::Fade, 1.0);
+		page.set_display_duration(1.0);
+
+		let mut output = Vec::new();
+		doc.write(&mut Writer::new(&mut output)).unwrap();
+		let output = String::from_utf8_lossy(&output);
+		assert!(output.contains("/Trans << /S /Fade /D 1 >>"));
+		assert!(output.contains("/Dur 1\n"));
+		assert!(output.starts_with("%PDF-1.5"));
+	}
+
+	#[test]
+	fn fly_scale() {
+		let mut doc = Document::new();
+		doc.set_version((1, 4));
+		let mut page = doc.add_page(612.0, 792.0);
+		page.set_fly_scale(0.5, true);
+		page.set_transition(TransitionStyle::Wipe, 1.0);
+		assert_eq!(doc.effective_version(), (1, 4));
+
+		let mut page = doc.add_page(612.0, 792.0);
+		page.set_transition(TransitionStyle::Fly, 1.0);
+		page.set_fly_scale(0.5, true);
+		let mut output = Vec::new();
+		doc.write(&mut Writer::new(&mut output)).unwrap();
+		let output = String::from_utf8_lossy(&output);
+		assert!(output.contains("/Trans << /S /Wipe /D 1 >>"));
+		assert!(output.contains("/Trans << /S /Fly /D 1 /SS 0.5 /B true >>"));
+		assert_eq!(doc.effective_version(), (1, 5));
+	}
+}
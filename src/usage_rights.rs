@@ -0,0 +1,116 @@
+//! Usage rights signatures (`/Perms /UR3` of the catalog), which enable
+//! features like saving filled forms in Adobe Reader.
+
+use crate::{Dictionary, Document, ObjectId, PdfObject};
+
+/// Value of the `/ByteRange` placeholder, as wide as the largest offset which
+/// can appear in a cross-reference table, so that the signer can overwrite it
+/// in place, padding the actual ranges with spaces.
+const BYTE_RANGE_PLACEHOLDER: i64 = 9_999_999_999;
+
+/// Features enabled by a usage rights signature, given by the names of the
+/// `/UR3` transform parameters, see section 8.7.2 of the reference.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UsageRights {
+	/// Rights on the whole document, e.g. `FullSave`.
+	pub document: Vec<String>,
+	/// Rights on the form fields, e.g. `FillIn` or `Import`.
+	pub form: Vec<String>,
+	/// Rights on the annotations, e.g. `Create` or `Modify`.
+	pub annotations: Vec<String>,
+	/// Rights on the signature fields, e.g. `Modify`.
+	pub signature: Vec<String>,
+	/// Number of bytes reserved for the signature in `/Contents`.
+	pub signature_size: usize,
+}
+
+impl Default for UsageRights {
+	/// Creates the rights usually granted to forms, i.e. to fill in and save
+	/// them, and to comment on the document.
+	fn default() -> Self {
+		let names = |names: &[&str]| names.iter().map(|name| name.to_string()).collect();
+		return Self {
+			document: names(&["FullSave"]),
+			form: names(&["FillIn", "Import", "Export", "SubmitStandalone", "SpawnTemplate"]),
+			annotations: names(&["Create", "Delete", "Modify", "Copy", "Import", "Export"]),
+			signature: names(&["Modify"]),
+			signature_size: 8192,
+		};
+	}
+}
+
+impl Document {
+	/// Adds the usage rights signature with the given rights to the catalog,
+	/// replacing the previous one. The signature has placeholders for the
+	/// `/ByteRange` and `/Contents`, which have to be filled in by the signer
+	/// after the document is written. Returns the signature dictionary.
+	pub fn set_usage_rights(&mut self, rights: &UsageRights) -> ObjectId {
+		let names = |names: &[String]| names.iter().map(|name| PdfObject::name(name)).collect::<Vec<_>>();
+		let mut params = Dictionary::new();
+		params.set("Type", PdfObject::name("TransformParams"));
+		params.set("V", PdfObject::name("2.2"));
+		for (key, rights) in [("Document", &rights.document), ("Form", &rights.form), ("Annots", &rights.annotations), ("Signature", &rights.signature)] {
+			if !rights.is_empty() {
+				params.set(key, names(rights));
+			}
+		}
+
+		let mut reference = Dictionary::new();
+		reference.set("Type", PdfObject::name("SigRef"));
+		reference.set("TransformMethod", PdfObject::name("UR3"));
+		reference.set("TransformParams", params);
+
+		let mut signature = Dictionary::new();
+		signature.set("Type", PdfObject::name("Sig"));
+		signature.set("Filter", PdfObject::name("Adobe.PPKLite"));
+		signature.set("SubFilter", PdfObject::name("adbe.pkcs7.detached"));
+		signature.set("ByteRange", [0, BYTE_RANGE_PLACEHOLDER, BYTE_RANGE_PLACEHOLDER, BYTE_RANGE_PLACEHOLDER].map(PdfObject::Integer).to_vec());
+		signature.set("Contents", PdfObject::hex_string(std::vec![0; rights.signature_size]));
+		signature.set("Reference", std::vec![PdfObject::Dictionary(reference)]);
+		let signature = self.add_object(signature);
+
+		if let Some(PdfObject::Reference(previous)) = self.catalog.get("Perms").and_then(PdfObject::as_dict).and_then(|perms| perms.get("UR3")) {
+			let previous = *previous;
+			self.remove_object(previous);
+		}
+		let mut perms = Dictionary::new();
+		perms.set("UR3", signature);
+		self.catalog.set("Perms", perms);
+		return signature;
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::*;
+
+	#[test]
+	fn usage_rights() {
+		let mut doc = Document::new();
+		doc.add_page(612.0, 792.0);
+		let first = doc.set_usage_rights(&UsageRights::default());
+		let rights = UsageRights {
+			annotations: Vec::new(),
+			signature_size: 16,
+			..UsageRights::default()
+		};
+		let signature = doc.set_usage_rights(&rights);
+		assert!(doc.object(first).is_none());
+
+		let mut output = Vec::new();
+		doc.write(&mut Writer::new(&mut output)).unwrap();
+		let text = String::from_utf8_lossy(&output);
+		assert!(text.contains(&std::format!("/Perms << /UR3 {} 0 R >>", signature.number)));
+		assert!(text.contains("/ByteRange [0 9999999999 9999999999 9999999999]\n/Contents <00000000000000000000000000000000>\n"));
+
+		let reader = Reader::new(&output).unwrap();
+		let perms = reader.catalog().unwrap().get("Perms").and_then(PdfObject::as_dict).unwrap().clone();
+		let signature = reader.resolve(perms.get("UR3").and_then(PdfObject::as_reference).unwrap()).unwrap();
+		let reference = &signature.as_dict().unwrap().get("Reference").and_then(PdfObject::as_array).unwrap()[0];
+		let reference = reference.as_dict().unwrap();
+		assert_eq!(reference.get_name("TransformMethod"), Some(&b"UR3"[..]));
+		let params = reference.get("TransformParams").and_then(PdfObject::as_dict).unwrap();
+		assert_eq!(params.get("Document"), Some(&PdfObject::Array(std::vec![PdfObject::name("FullSave")])));
+		assert_eq!(params.get("Annots"), None);
+	}
+}
@@ -0,0 +1,99 @@
+//! Initial view of the document, when it's opened in a viewer.
+
+use crate::{Document, PdfObject};
+
+/// Arrangement of the pages (`/PageLayout`).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PageLayout {
+	/// One page at a time.
+	#[default]
+	SinglePage,
+	/// Pages in a single column.
+	OneColumn,
+	/// Pages in two columns, with the odd pages on the left.
+	TwoColumnLeft,
+	/// Pages in two columns, with the odd pages on the right.
+	TwoColumnRight,
+	/// Two pages at a time, with the odd pages on the left.
+	TwoPageLeft,
+	/// Two pages at a time, with the odd pages on the right.
+	TwoPageRight,
+}
+
+impl PageLayout {
+	fn name(self) -> &'static str {
+		return match self {
+			Self::SinglePage => "SinglePage",
+			Self::OneColumn => "OneColumn",
+			Self::TwoColumnLeft => "TwoColumnLeft",
+			Self::TwoColumnRight => "TwoColumnRight",
+			Self::TwoPageLeft => "TwoPageLeft",
+			Self::TwoPageRight => "TwoPageRight",
+		};
+	}
+}
+
+/// Panel shown next to the pages, or the full screen mode (`/PageMode`).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PageMode {
+	/// Neither the outline nor the thumbnails are shown.
+	#[default]
+	UseNone,
+	/// The document outline is shown.
+	UseOutlines,
+	/// The page thumbnails are shown.
+	UseThumbs,
+	/// Pages fill the screen, without any menus or panels.
+	FullScreen,
+	/// The optional content panel is shown.
+	UseOC,
+	/// The attachments panel is shown.
+	UseAttachments,
+}
+
+impl PageMode {
+	fn name(self) -> &'static str {
+		return match self {
+			Self::UseNone => "UseNone",
+			Self::UseOutlines => "UseOutlines",
+			Self::UseThumbs => "UseThumbs",
+			Self::FullScreen => "FullScreen",
+			Self::UseOC => "UseOC",
+			Self::UseAttachments => "UseAttachments",
+		};
+	}
+}
+
+impl Document {
+	/// Sets how the pages are arranged when the document is opened.
+	pub fn set_page_layout(&mut self, layout: PageLayout) {
+		self.catalog.set("PageLayout", PdfObject::name(layout.name()));
+	}
+
+	/// Sets which panel is shown when the document is opened.
+	pub fn set_page_mode(&mut self, mode: PageMode) {
+		self.catalog.set("PageMode", PdfObject::name(mode.name()));
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::*;
+
+	#[test]
+	fn initial_view() {
+		let mut doc = Document::new();
+		doc.add_page(612.0, 792.0);
+		doc.set_page_layout(PageLayout::TwoColumnLeft);
+		doc.set_page_mode(PageMode::UseThumbs);
+		doc.set_page_mode(PageMode::UseOutlines);
+
+		let mut output = Vec::new();
+		doc.write(&mut Writer::new(&mut output)).unwrap();
+		let catalog = Reader::new(&output).unwrap().catalog().unwrap();
+		assert_eq!(catalog.get_name("PageLayout"), Some(&b"TwoColumnLeft"[..]));
+		assert_eq!(catalog.get_name("PageMode"), Some(&b"UseOutlines"[..]));
+		let output = String::from_utf8_lossy(&output);
+		assert!(output.contains("/PageLayout /TwoColumnLeft\n/PageMode /UseOutlines\n"));
+	}
+}